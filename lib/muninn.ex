@@ -15,4 +15,25 @@ defmodule Muninn do
   def hello do
     :world
   end
+
+  @doc """
+  Returns the Tantivy version Muninn was compiled against.
+
+  The result has three keys:
+
+    * `"tantivy_version"` - The Tantivy version, such as `"0.25.0"`
+    * `"index_format_version"` - The index format this build writes
+    * `"compatible"` - Always `true`; present so the map can be compared with the
+      result of `Muninn.Index.format_info/2`
+
+  ## Examples
+
+      %{"tantivy_version" => "0.25.0", "index_format_version" => 7} = Muninn.info()
+
+  """
+  @spec info() :: %{String.t() => String.t() | non_neg_integer() | boolean()}
+  def info do
+    {:ok, info} = Muninn.Native.muninn_info()
+    info
+  end
 end
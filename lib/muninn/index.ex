@@ -15,6 +15,23 @@ defmodule Muninn.Index do
       # Create an index
       {:ok, index} = Muninn.Index.create("/tmp/my_index", schema)
 
+  ## Paths
+
+  `create/3`, `open/2` and `format_info/2` expand a leading `~` to the home
+  directory and resolve relative paths against a base directory before the index
+  is touched, so error messages always show the absolute path that was used. The
+  base directory is the `:base_dir` option, then the `:base_dir` application
+  setting, then the current working directory:
+
+      # config/runtime.exs
+      config :muninn, base_dir: "/var/lib/my_app/indexes"
+
+      # Opens /var/lib/my_app/indexes/products
+      {:ok, index} = Muninn.Index.open("products")
+
+  Configuring a base directory avoids paths that work in development but not in
+  a release, which may be started from another working directory.
+
   """
 
   alias Muninn.{Native, Schema}
@@ -25,17 +42,67 @@ defmodule Muninn.Index do
   Creates a new index at the specified path with the given schema.
 
   The index directory will be created if it doesn't exist. If the directory
-  already contains an index, an error will be returned.
+  already contains an index, `{:error, :already_exists, message}` is returned,
+  unless `force: true` is given.
 
   ## Parameters
 
     * `path` - The directory path where the index will be stored
     * `schema` - A `Muninn.Schema` defining the index structure
+    * `opts` - Keyword list of options:
+      - `:max_limit` - Largest `:limit` a search on this index may request
+        (default: 100_000). Searches asking for more return
+        `{:error, :invalid_argument, message}` instead of allocating the results
+      - `:positions` - Whether text fields index term positions by default
+        (default: `true`). See "Positions" below
+      - `:base_dir` - Directory a relative `path` is resolved against. See
+        "Paths" in the module documentation
+      - `:force` - Delete an index already in the directory and create a new
+        one in its place (default: `false`). The schema is checked first, so an
+        invalid schema leaves the existing index untouched. Only the index's own
+        files are deleted, other files in the directory are kept. An index whose
+        writer is still open, here or in another process, is refused with
+        `:index_locked`; close it first with `Muninn.IndexWriter.close/2`. Its
+        documents are lost, and readers still open on it must not be used again
+      - `:durability` - `:durable` (default) or `:fast`. See `set_durability/2`
+
+  ## Positions
+
+  Text fields normally index where each term occurs, which phrase queries need.
+  A write-once analytical index that never runs phrase queries can pass
+  `positions: false` to index only which documents contain each term. The index
+  is smaller and builds faster, but on those fields:
+
+    * phrase queries such as `"error rate"` fail with `:parse_error`
+    * snippets can only highlight single terms, never phrases
+    * scoring ignores how often a term occurs in a document
+
+  A text field added with `positions: true` keeps its positions either way.
+
+  ## Document order
+
+  Documents are stored in the order they were added, and merged segments keep
+  that order. Index-level sorting, such as a time-series index stored newest
+  first, is not available: Tantivy removed its `sort_by_field` index setting in
+  0.24, and Muninn builds against 0.25. Order results by a field at query time
+  instead.
 
   ## Returns
 
     * `{:ok, index}` - Successfully created index
-    * `{:error, reason}` - Failed to create index
+    * `{:error, :already_exists, message}` - The directory already contains an
+      index
+    * `{:error, :index_locked, message}` - With `force: true`, a writer still
+      holds the existing index's lock
+    * `{:error, :invalid_schema, message}` - The schema was rejected, such as a
+      field with an unknown tokenizer
+    * `{:error, :invalid_argument, message}` - An invalid option, such as a
+      `:max_limit` of 0
+    * `{:error, :io_error, message}` - The directory could not be created, or
+      the existing index deleted
+    * `{:error, :index_error, message}` - Tantivy failed to create the index
+    * `{:error, reason}` - `Muninn.Schema.validate/1` rejected the schema, such
+      as `:no_fields`
 
   ## Examples
 
@@ -45,25 +112,76 @@ defmodule Muninn.Index do
       {:ok, index} = Muninn.Index.create("/tmp/my_index", schema)
 
   """
-  @spec create(String.t(), Schema.t()) :: {:ok, t()} | {:error, atom()}
-  def create(path, %Schema{} = schema) do
+  @spec create(String.t(), Schema.t(), keyword()) ::
+          {:ok, t()}
+          | {:error,
+             :already_exists
+             | :index_locked
+             | :invalid_schema
+             | :invalid_argument
+             | :io_error
+             | :index_error
+             | :internal_panic, String.t()}
+          | {:error, atom()}
+  def create(path, %Schema{} = schema, opts \\ []) do
     with :ok <- Schema.validate(schema) do
-      # Convert schema to list of tuples {name, type, stored, indexed}
+      # Convert schema to list of tuples {name, type, stored, indexed, options}
       fields =
-        Enum.map(schema.fields, fn field ->
-          {field.name, Atom.to_string(field.type), field.stored, field.indexed}
-        end)
+        schema.fields
+        |> Enum.map(&default_positions(&1, Keyword.get(opts, :positions, true)))
+        |> Enum.map(&Schema.Field.to_native/1)
 
-      Native.index_create(path, fields)
+      path
+      |> resolve_path(opts)
+      |> Native.index_create(
+        fields,
+        Keyword.get(opts, :max_limit),
+        Keyword.get(opts, :force, false)
+      )
+      |> apply_durability(opts)
     end
   end
 
+  defp apply_durability({:ok, index} = result, opts) do
+    case Keyword.get(opts, :durability, :durable) do
+      :durable -> result
+      durability -> with :ok <- set_durability(index, durability), do: result
+    end
+  end
+
+  defp apply_durability(error, _opts), do: error
+
+  defp default_positions(%Schema.Field{type: :text, positions: nil} = field, positions),
+    do: %{field | positions: positions}
+
+  defp default_positions(field, _positions), do: field
+
+  # Expands "~" and resolves a relative path against the configured base directory
+  defp resolve_path(path, opts) do
+    base_dir =
+      Keyword.get_lazy(opts, :base_dir, fn ->
+        Application.get_env(:muninn, :base_dir, File.cwd!())
+      end)
+
+    Path.expand(path, base_dir)
+  end
+
   @doc """
   Opens an existing index at the specified path.
 
   ## Parameters
 
     * `path` - The directory path where the index is stored
+    * `opts` - Keyword list of options:
+      - `:max_limit` - Largest `:limit` a search on this index may request
+        (default: 100_000). See `create/3`
+      - `:read_only` - Open the index for searching only (default: `false`). No
+        writer is ever created, so the process never takes Tantivy's writer lock
+        and `Muninn.IndexWriter` functions return `{:error, :read_only, message}`.
+        Use it in every process except the single one that writes
+      - `:base_dir` - Directory a relative `path` is resolved against. See
+        "Paths" in the module documentation
+      - `:durability` - `:durable` (default) or `:fast`. See `set_durability/2`
 
   ## Returns
 
@@ -74,9 +192,574 @@ defmodule Muninn.Index do
 
       {:ok, index} = Muninn.Index.open("/tmp/my_index")
 
+      # Allow larger result pages for batch jobs
+      {:ok, index} = Muninn.Index.open("/tmp/my_index", max_limit: 1_000_000)
+
+      # Search an index that another process writes to
+      {:ok, index} = Muninn.Index.open("/tmp/my_index", read_only: true)
+
+  """
+  @spec open(String.t(), keyword()) :: {:ok, t()} | {:error, atom()}
+  def open(path, opts \\ []) do
+    path = resolve_path(path, opts)
+    max_limit = Keyword.get(opts, :max_limit)
+
+    if Keyword.get(opts, :read_only, false) do
+      Native.index_open_read_only(path, max_limit)
+    else
+      path
+      |> Native.index_open(max_limit)
+      |> apply_durability(opts)
+    end
+  end
+
+  @doc """
+  Compares a schema with the schema the index was created with.
+
+  Opening an index only reads its on-disk schema, so an application whose schema
+  definition has drifted still opens it and then writes or searches it subtly
+  wrong. Call this after `open/2` to fail fast at boot instead.
+
+  Each difference is a map with these keys:
+
+    * `"field"` - The field name
+    * `"difference"` - What differs: `"missing"` (in `schema` but not in the
+      index), `"unexpected"` (in the index but not in `schema`), `"type"`,
+      `"stored"`, `"indexed"`, `"fast"`, `"tokenizer"` or `"id"`
+    * `"expected"` - The value in `schema` as a string, `nil` for `"unexpected"`
+    * `"actual"` - The value in the index as a string, `nil` for `"missing"`
+
+  Options are only compared for fields whose type matches.
+
+  ## Returns
+
+    * `{:ok, []}` - The schema matches the index
+    * `{:ok, differences}` - The list of differences
+    * `{:error, reason}` - The schema failed `Muninn.Schema.validate/1`
+    * `{:error, kind, message}` - The schema is invalid or the index could not be read
+
+  ## Examples
+
+      {:ok, index} = Muninn.Index.open("/path/to/index")
+
+      case Muninn.Index.check_schema(index, MyApp.Search.schema()) do
+        {:ok, []} -> :ok
+        {:ok, differences} -> raise "Search index schema drifted: #{inspect(differences)}"
+      end
+
+  """
+  @spec check_schema(t(), Schema.t()) ::
+          {:ok, [map()]}
+          | {:error, atom()}
+          | {:error, :invalid_argument | :index_error, String.t()}
+  def check_schema(index, %Schema{} = schema) do
+    with :ok <- Schema.validate(schema) do
+      fields = Enum.map(schema.fields, &Schema.Field.to_native/1)
+      Native.index_check_schema(index, fields)
+    end
+  end
+
+  @doc """
+  Returns where the index keeps its files, for diagnostics.
+
+  The result has two keys:
+
+    * `"directory"` - `"mmap"` for an index on disk, `"ram"` for one held in memory
+    * `"path"` - The canonical path of an index on disk, with symlinks and relative
+      segments resolved, or `nil` for an index in memory
+
+  ## Examples
+
+      {:ok, index} = Muninn.Index.create("/tmp/my_index", schema)
+
+      %{"directory" => "mmap", "path" => "/tmp/my_index"} = Muninn.Index.info(index)
+
+  """
+  @spec info(t()) :: %{String.t() => String.t() | nil}
+  def info(index) do
+    Native.index_info(index)
+  end
+
+  @doc """
+  Checks that the index can be read and queried, for liveness and readiness probes.
+
+  Opens a fresh reader on the last commit and runs a query matching every
+  document, so a missing or corrupt segment fails the check rather than the
+  next search. The reader is discarded afterwards.
+
+  ## Returns
+
+    * `{:ok, %{"num_docs" => num_docs, "latency_ms" => latency_ms}}` - The
+      number of committed documents and how long the check took, in milliseconds
+    * `{:error, :index_error, message}` - The index could not be read
+    * `{:error, :search_error, message}` - The query failed
+    * `{:error, :internal_panic, message}` - Tantivy panicked while checking
+
+  ## Examples
+
+      case Muninn.Index.health_check(index) do
+        {:ok, %{"latency_ms" => latency_ms}} when latency_ms < 500 -> :ready
+        _ -> :not_ready
+      end
+
   """
-  @spec open(String.t()) :: {:ok, t()} | {:error, atom()}
-  def open(path) do
-    Native.index_open(path)
+  @spec health_check(t()) ::
+          {:ok, %{String.t() => non_neg_integer() | float()}}
+          | {:error, :index_error | :search_error | :internal_panic, String.t()}
+  def health_check(index) do
+    Native.index_health_check(index)
+  end
+
+  @doc """
+  Returns the Tantivy version and index format that wrote the index at `path`.
+
+  The index is not opened: the versions are read from the footer of a segment
+  file. Use it during upgrades to check whether an index on disk can be read by
+  this build before calling `open/2`. The result has the same keys as
+  `Muninn.info/0`, with `"compatible"` telling whether this build can read the
+  index format. Like `open/2`, it accepts a `:base_dir` option to resolve a
+  relative `path` against.
+
+  ## Returns
+
+    * `{:ok, info}` - The versions that wrote the index
+    * `{:ok, nil}` - The index has no segments yet, so no format is recorded
+    * `{:error, :not_found, message}` - No index exists at `path`
+    * `{:error, :index_error, message}` - A segment file could not be read
+
+  ## Examples
+
+      {:ok, %{"compatible" => true, "tantivy_version" => "0.25.0"}} =
+        Muninn.Index.format_info("/tmp/my_index")
+
+  """
+  @spec format_info(String.t(), keyword()) ::
+          {:ok, %{String.t() => String.t() | non_neg_integer() | boolean()} | nil}
+          | {:error, :not_found | :index_error, String.t()}
+  def format_info(path, opts \\ []) when is_binary(path) do
+    Native.index_format_info(resolve_path(path, opts))
+  end
+
+  @doc """
+  Registers a tokenizer for Chinese, Japanese and Korean text on the index.
+
+  CJK text has no spaces between words, so the default tokenizer indexes a whole
+  sentence as a single term. This tokenizer splits runs of CJK characters into
+  overlapping character bigrams (`"北京大学"` becomes `"北京"`, `"京大"` and
+  `"大学"`) and indexes any other word like the default tokenizer does. Query
+  strings are tokenized the same way, so a phrase matches wherever its bigrams
+  appear next to each other.
+
+  The tokenizer is registered as `"cjk"`, the name a text field references it
+  by with `tokenizer: "cjk"`, and the name to pass where a registered tokenizer
+  is picked at search time, such as the `:query_tokenizers` option of
+  `Muninn.Searcher.search_query/4`. Tokenizers are not saved with the index:
+  register it after every `create/3` or `open/2`, before adding documents or
+  searching.
+
+  ## Parameters
+
+    * `index` - The index to register the tokenizer on
+
+  ## Returns
+
+    * `:ok` - Tokenizer registered
+    * `{:error, kind, message}` - Registration failed
+
+  ## Examples
+
+      schema = Muninn.Schema.new()
+        |> Muninn.Schema.add_text_field("body", stored: true, tokenizer: "cjk")
+
+      {:ok, index} = Muninn.Index.create("/tmp/my_index", schema)
+      :ok = Muninn.Index.register_cjk_tokenizer(index)
+
+  """
+  @spec register_cjk_tokenizer(t()) :: :ok | {:error, :index_error, String.t()}
+  def register_cjk_tokenizer(index) do
+    case Native.index_register_cjk_tokenizer(index) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Sets the synonyms expanded in query strings searched on the index.
+
+  Each key is a word or phrase mapped to its synonyms. When a query string
+  contains a key as a plain word or a quoted phrase, the parser searches for it
+  or any of its synonyms in the same field. Multi-word synonyms are searched as
+  phrases, so `%{"nyc" => ["new york city"]}` makes `nyc` also match the phrase
+  "New York City". Keys match case-insensitively, and mappings only apply in the
+  direction given: add the reverse entry to expand both ways. Fuzzy, prefix and
+  slop clauses are not expanded.
+
+  Synonyms apply to `Muninn.Searcher.search_query/4` and the other functions
+  taking a query string, including searchers that already exist. Each call
+  replaces the previous synonyms, and `%{}` removes them. Like tokenizers,
+  synonyms are not saved with the index: set them after every `create/3` or
+  `open/2`.
+
+  ## Returns
+
+    * `:ok` - Synonyms set
+    * `{:error, kind, message}` - A key is empty or the index could not be locked
+
+  ## Examples
+
+      :ok =
+        Muninn.Index.set_synonyms(index, %{
+          "nyc" => ["new york city"],
+          "new york city" => ["nyc"]
+        })
+
+  """
+  @spec set_synonyms(t(), %{String.t() => [String.t()]}) ::
+          :ok | {:error, :invalid_argument | :index_error, String.t()}
+  def set_synonyms(index, synonyms) when is_map(synonyms) do
+    case Native.index_set_synonyms(index, synonyms) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Switches commits on the index between durable and fast.
+
+  By default every commit waits until the new segment files and `meta.json` are
+  synced to disk, so a committed document survives a power loss or an OS crash.
+  Bulk loads that commit often spend much of their time waiting for those syncs.
+  With `:fast`, commits hand their files to the operating system and return
+  without syncing.
+
+  ## Data-loss window
+
+  While an index is `:fast`, a committed document is only as safe as the OS page
+  cache. If the BEAM crashes, nothing is lost: the files are already written.
+  If the machine loses power or the kernel crashes before the OS flushes its
+  cache, which typically takes up to about 30 seconds on Linux, commits made
+  since the last sync can be lost, and the index can fall back to an earlier
+  commit or fail to open. Only use `:fast` for data that can be loaded again.
+
+  Switching back to `:durable` syncs every file in the index directory before
+  returning. Once `set_durability(index, :durable)` returns `:ok`, everything
+  committed while the index was `:fast` is on disk, and later commits are
+  durable again.
+
+  The setting is not saved with the index. `create/3` and `open/2` accept it as
+  the `:durability` option, defaulting to `:durable`.
+
+  ## Examples
+
+      :ok = Muninn.Index.set_durability(index, :fast)
+      # ... add and commit the bulk load ...
+      :ok = Muninn.Index.set_durability(index, :durable)
+
+  """
+  @spec set_durability(t(), :durable | :fast) ::
+          :ok | {:error, :invalid_argument | :index_error, String.t()}
+  def set_durability(index, durability) when durability in [:durable, :fast] do
+    case Native.index_set_durability(index, durability == :durable) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Lists the names of the tokenizers registered on the index.
+
+  Includes Tantivy's built-in tokenizers (`"default"`, `"en_stem"`, `"raw"` and
+  `"whitespace"`), Muninn's `"raw_lowercase"`, registered on every index for
+  case-insensitive keyword fields, and any tokenizer registered through Muninn,
+  such as `register_cjk_tokenizer/1`. Use it to check that every tokenizer referenced by
+  the schema is registered before adding documents or searching.
+
+  ## Returns
+
+    * `{:ok, names}` - Sorted list of tokenizer names
+    * `{:error, kind, message}` - The index could not be read
+
+  ## Examples
+
+      {:ok, index} = Muninn.Index.open("/tmp/my_index")
+      :ok = Muninn.Index.register_cjk_tokenizer(index)
+
+      {:ok, ["cjk", "default", "en_stem", "raw", "raw_lowercase", "whitespace"]} =
+        Muninn.Index.list_tokenizers(index)
+
+  """
+  @spec list_tokenizers(t()) :: {:ok, [String.t()]} | {:error, :index_error, String.t()}
+  def list_tokenizers(index) do
+    Native.index_list_tokenizers(index)
+  end
+
+  @doc """
+  Runs a text through the analyzer of an indexed text field.
+
+  Shows the terms the field indexes for the text, as when a document is added,
+  along with the name of the tokenizer the field resolved to and the components
+  of its analyzer: the tokenizer, then each filter in order. Use it to confirm
+  that a schema's `:tokenizer` setting took effect, or to see why a search does
+  not match.
+
+  Tantivy's analyzers cannot describe themselves, so `"components"` is only
+  known for the built-in tokenizers and those registered through Muninn, and is
+  empty otherwise.
+
+  ## Parameters
+
+    * `index` - The index
+    * `field_name` - An indexed text or keyword field
+    * `text` - The text to analyze
+
+  ## Returns
+
+    * `{:ok, result}` - A map with the `"tokenizer"` name, its `"components"`,
+      and the `"tokens"`, each a map of `"text"`, `"position"`, and the
+      `"offset_from"` and `"offset_to"` byte offsets into `text`
+    * `{:error, :not_found, message}` - The field does not exist
+    * `{:error, :invalid_argument, message}` - The field is not an indexed text
+      field, or its tokenizer is not registered on the index
+
+  ## Examples
+
+      {:ok, result} = Muninn.Index.tokenize(index, "body", "Running fast")
+
+      %{
+        "tokenizer" => "en_stem",
+        "components" => ["SimpleTokenizer", "RemoveLongFilter(40)", "LowerCaser",
+                         "Stemmer(English)"],
+        "tokens" => [
+          %{"text" => "run", "position" => 0, "offset_from" => 0, "offset_to" => 7},
+          %{"text" => "fast", "position" => 1, "offset_from" => 8, "offset_to" => 12}
+        ]
+      } = result
+
+  """
+  @spec tokenize(t(), String.t(), String.t()) :: {:ok, map()} | {:error, atom(), String.t()}
+  def tokenize(index, field_name, text) when is_binary(field_name) and is_binary(text) do
+    Native.index_tokenize(index, field_name, text)
+  end
+
+  @doc """
+  Returns the payload stored with the last commit.
+
+  See `Muninn.IndexWriter.commit_with_payload/2`. The payload is read from the
+  index metadata on disk, so it is also available after reopening the index.
+
+  ## Returns
+
+    * `{:ok, payload}` - The payload of the last commit
+    * `{:ok, nil}` - The last commit has no payload
+    * `{:error, kind, message}` - The index metadata could not be read
+
+  ## Examples
+
+      :ok = Muninn.IndexWriter.commit_with_payload(index, "lsn:0/16B3748")
+      {:ok, "lsn:0/16B3748"} = Muninn.Index.commit_payload(index)
+
+  """
+  @spec commit_payload(t()) :: {:ok, String.t() | nil} | {:error, :index_error, String.t()}
+  def commit_payload(index) do
+    Native.index_commit_payload(index)
+  end
+
+  @doc """
+  Copies every document of one index into another.
+
+  Use this to migrate documents after changing analysis settings: create the
+  destination index with the new schema, then reindex into it. Documents are
+  rebuilt from their stored values, so every field of the source index must be
+  stored. Source fields missing from the destination schema are dropped, and a
+  field present in both must hold the same kind of value (text, u64, i64, f64
+  or bool). Exact companions, see "Exact matches" in `Muninn.Schema`, need not
+  be stored: the destination fills its own from their source fields.
+
+  The destination writer commits after every `:batch_size` documents and once
+  more at the end, which also commits anything already pending on it.
+
+  ## Parameters
+
+    * `source` - The index to copy documents from
+    * `dest` - The index to add documents to
+    * `opts` - Keyword list of options
+
+  ## Options
+
+    * `:batch_size` - Number of documents added per commit (default: `1000`)
+
+  ## Returns
+
+    * `{:ok, count}` - The number of documents copied
+    * `{:error, :invalid_argument, message}` - A source field is not stored or
+      has a different type in the destination, or both indexes are the same
+    * `{:error, kind, message}` - The destination could not be written to
+
+  ## Examples
+
+      {:ok, source} = Muninn.Index.open("/path/to/index")
+      {:ok, dest} = Muninn.Index.create("/path/to/index_v2", new_schema)
+      {:ok, 12_345} = Muninn.Index.reindex(source, dest, batch_size: 5_000)
+
+  """
+  @spec reindex(t(), t(), keyword()) ::
+          {:ok, non_neg_integer()} | Muninn.IndexWriter.error()
+  def reindex(source, dest, opts \\ []) do
+    batch_size = Keyword.get(opts, :batch_size, 1000)
+    Native.index_reindex(source, dest, batch_size)
+  end
+
+  @doc """
+  Adds the documents of other index directories to an index.
+
+  Use this to consolidate shards, such as the daily indexes of a month, into
+  one index. The live documents of all sources are merged into a single new
+  segment, which is added to the index and committed. Unlike `reindex/3`,
+  nothing is re-analyzed and fields do not need to be stored, but every source
+  must have been created with the same schema as the index.
+
+  The sources are only read. They should not be written to during the merge,
+  since documents committed to them meanwhile may be missed. The merged
+  segment is built in the system's temporary directory first, which needs
+  room for it. The commit also covers documents already pending on the index.
+
+  ## Parameters
+
+    * `index` - The index to add documents to
+    * `source_paths` - Directories of the indexes to merge in
+    * `opts` - Keyword list of options:
+      - `:base_dir` - Directory relative `source_paths` are resolved against.
+        See "Paths" in the module documentation
+
+  ## Returns
+
+    * `{:ok, count}` - The number of documents added
+    * `{:error, :invalid_argument, message}` - A source has a different schema,
+      or is the index itself
+    * `{:error, kind, message}` - A source could not be opened or the index
+      could not be written to
+
+  ## Examples
+
+      {:ok, month} = Muninn.Index.create("/data/logs-2026-09", schema)
+      {:ok, _count} = Muninn.Index.merge_from(month, Path.wildcard("/data/logs-2026-09-*"))
+
+  """
+  @spec merge_from(t(), [String.t()], keyword()) ::
+          {:ok, non_neg_integer()} | Muninn.IndexWriter.error()
+  def merge_from(index, source_paths, opts \\ []) when is_list(source_paths) do
+    Native.index_merge_from(index, Enum.map(source_paths, &resolve_path(&1, opts)))
+  end
+
+  @doc """
+  Points the index at another index directory and returns a reader on it.
+
+  This completes a rebuild without downtime: build a new index next to the live
+  one, for example with `reindex/3` after a schema change, then swap the live
+  index over to it. From then on, every reader, searcher and write made through
+  `index` uses the new directory, with its schema, id field, required fields,
+  value limits, copied fields and multi-valued fields. Readers and searchers
+  created before the swap keep serving the old directory, so in-flight searches
+  finish on it; replace them with the returned reader, or with readers created
+  from `index`.
+
+  Tokenizers registered on `index` and its durability setting carry over to the
+  new directory. The old directory is left on disk; delete it once the readers
+  still using it are gone.
+
+  The swap is refused with `:invalid_argument` while documents added to `index`,
+  or deletes queued on it, are waiting to be committed, since they would be
+  lost. Any writer of the old
+  directory is closed, releasing its lock. The index that built the new
+  directory must have closed its writer, see `Muninn.IndexWriter.close/2`,
+  otherwise writing after the swap fails with `:index_locked`.
+
+  ## Parameters
+
+    * `index` - The live index
+    * `new_path` - Directory of the index to switch to
+    * `opts` - Keyword list of options:
+      - `:base_dir` - Directory a relative `new_path` is resolved against. See
+        "Paths" in the module documentation
+
+  ## Returns
+
+    * `{:ok, reader}` - A reader on the new directory
+    * `{:error, :invalid_argument, message}` - Documents or deletes are pending,
+      or `new_path` is the directory the index already uses
+    * `{:error, :index_error, message}` - The new directory could not be opened
+
+  ## Examples
+
+      {:ok, next} = Muninn.Index.create("/data/products_v2", new_schema)
+      {:ok, _count} = Muninn.Index.reindex(live, next)
+      :ok = Muninn.IndexWriter.close(next)
+
+      {:ok, reader} = Muninn.Index.swap(live, "/data/products_v2")
+
+  """
+  @spec swap(t(), String.t(), keyword()) ::
+          {:ok, reference()} | {:error, :invalid_argument | :index_error, String.t()}
+  def swap(index, new_path, opts \\ []) when is_binary(new_path) do
+    Native.index_swap(index, resolve_path(new_path, opts))
+  end
+
+  @doc """
+  Returns which of many saved queries match a single document.
+
+  This is the reverse of a search, as used for alerting: instead of finding the
+  documents matching a query, find the queries matching an incoming document.
+  The document is indexed alone in a throwaway in-memory index built from
+  `schema`, then every query is run against it. Nothing is written to disk.
+
+  Only Tantivy's built-in tokenizers are available, so a field using one
+  registered on an index, such as `Muninn.Index.register_cjk_tokenizer/1`,
+  fails with `:invalid_argument`. Synonyms are not expanded.
+
+  ## Parameters
+
+    * `schema` - A `Muninn.Schema` describing the document
+    * `document` - The document, as given to `Muninn.IndexWriter.add_document/2`
+    * `queries` - A list of `{id, query_string}` pairs, or a map of ids to
+      query strings. Ids can be any term
+    * `opts` - Keyword list of options:
+      - `:default_fields` - Fields searched by terms without a field (default:
+        every indexed text and keyword field of the schema)
+
+  ## Returns
+
+    * `{:ok, ids}` - The ids of the matching queries, in the order given
+    * `{:error, reason}` - The schema failed `Muninn.Schema.validate/1`
+    * `{:error, kind, message}` - The schema or document was rejected, or a
+      query failed to parse. The message names the position of the query
+
+  ## Examples
+
+      alerts = [{:outage, "error AND datacenter"}, {:billing, "invoice OR refund"}]
+
+      {:ok, [:outage]} =
+        Muninn.Index.percolate(schema, %{"body" => "error in datacenter 3"}, alerts)
+
+  """
+  @spec percolate(Schema.t(), map(), [{term(), String.t()}] | map(), keyword()) ::
+          {:ok, [term()]}
+          | {:error, atom()}
+          | {:error, :not_found | :parse_error | :invalid_argument | :index_error, String.t()}
+  def percolate(%Schema{} = schema, document, queries, opts \\ []) when is_map(document) do
+    with :ok <- Schema.validate(schema) do
+      default_fields =
+        Keyword.get_lazy(opts, :default_fields, fn ->
+          for %{type: type, indexed: true, name: name} <- schema.fields,
+              type in [:text, :keyword],
+              do: name
+        end)
+
+      Native.index_percolate(
+        Enum.map(schema.fields, &Schema.Field.to_native/1),
+        document,
+        Enum.to_list(queries),
+        default_fields
+      )
+    end
   end
 end
@@ -16,6 +16,43 @@ defmodule Muninn.IndexReader do
   Readers are automatically managed and cleaned up by the BEAM garbage collector.
   No explicit close is required.
 
+  ## Result window
+
+  Ranking page 10_000 of a result list means ranking every hit before it, so a
+  single request with a huge `:offset` can keep a scheduler busy for a long
+  time. Like Elasticsearch's `index.max_result_window`, a reader created with
+  `:max_result_window` caps `offset + limit` in the paginated functions of its
+  searchers, `Muninn.Searcher.search_paged/4` and `Muninn.Searcher.search_ids/4`.
+  A page past the window returns `{:error, :result_window_exceeded, message}`:
+
+      {:ok, reader} = Muninn.IndexReader.new(index, max_result_window: 10_000)
+      {:ok, searcher} = Muninn.Searcher.new(reader)
+
+      {:error, :result_window_exceeded, _} =
+        Muninn.Searcher.search_paged(searcher, "elixir", ["title"], offset: 10_000)
+
+  ## Snapshots
+
+  A searcher always sees the commit its reader served when the searcher was
+  created, even while writes continue. A reader normally moves to every new
+  commit shortly after it happens, so searchers created from it later see
+  newer data. For reports that run a batch of queries against one stable
+  state, create the reader with `reload: :manual`: it stays on its commit,
+  across any number of searchers, until `reload/1` moves it to the latest one.
+
+      {:ok, reader} = Muninn.IndexReader.new(index, reload: :manual)
+      {:ok, opstamp} = Muninn.IndexReader.opstamp(reader)
+
+      # Every searcher of this reader sees the commit `opstamp`
+      {:ok, searcher} = Muninn.Searcher.new(reader)
+
+  `opstamp/1` names the commit, matching the opstamps returned by
+  `Muninn.IndexWriter.add_and_commit/2` and
+  `Muninn.IndexWriter.delete_and_commit/3`. Tantivy only keeps the latest
+  commit on disk, so a reader can hold on to a commit but never go back to an
+  older one: to query the index as of a commit, create the manual reader when
+  that commit is the latest.
+
   """
 
   alias Muninn.Native
@@ -30,11 +67,19 @@ defmodule Muninn.IndexReader do
   ## Parameters
 
     * `index` - The index to create a reader for
+    * `opts` - Keyword list of options:
+      - `:max_result_window` - Largest `offset + limit` a paginated search may
+        reach (default: `nil`, unlimited beyond the index's `:max_limit`). See
+        "Result window" in the module documentation
+      - `:reload` - `:on_commit` (default) to follow new commits, or `:manual`
+        to stay on the current commit until `reload/1`. See "Snapshots" in the
+        module documentation
 
   ## Returns
 
     * `{:ok, reader}` - Successfully created reader
-    * `{:error, reason}` - Failed to create reader
+    * `{:error, :invalid_argument, message}` - A `:max_result_window` of 0
+    * `{:error, :index_error, message}` - The reader could not be created
 
   ## Examples
 
@@ -42,8 +87,62 @@ defmodule Muninn.IndexReader do
       {:ok, reader} = Muninn.IndexReader.new(index)
 
   """
-  @spec new(reference()) :: {:ok, t()} | {:error, String.t()}
-  def new(index) do
-    Native.reader_new(index)
+  @spec new(reference(), keyword()) ::
+          {:ok, t()} | {:error, :invalid_argument | :index_error, String.t()}
+  def new(index, opts \\ []) do
+    manual_reload =
+      case Keyword.get(opts, :reload, :on_commit) do
+        :manual -> true
+        :on_commit -> false
+      end
+
+    Native.reader_new(index, Keyword.get(opts, :max_result_window), manual_reload)
+  end
+
+  @doc """
+  Moves a reader created with `reload: :manual` to the latest commit.
+
+  Searchers created before the reload keep seeing the previous commit.
+
+  ## Returns
+
+    * `{:ok, opstamp}` - The opstamp of the commit the reader now serves
+    * `{:error, :invalid_argument, message}` - The reader follows commits on
+      its own
+    * `{:error, :index_error, message}` - The index could not be reloaded
+
+  ## Examples
+
+      {:ok, reader} = Muninn.IndexReader.new(index, reload: :manual)
+      :ok = Muninn.IndexWriter.commit_with_payload(index, "batch-42")
+      {:ok, opstamp} = Muninn.IndexReader.reload(reader)
+
+  """
+  @spec reload(t()) ::
+          {:ok, non_neg_integer()} | {:error, :invalid_argument | :index_error, String.t()}
+  def reload(reader) do
+    Native.reader_reload(reader)
+  end
+
+  @doc """
+  Returns the opstamp of the commit a reader created with `reload: :manual`
+  serves.
+
+  ## Returns
+
+    * `{:ok, opstamp}` - The commit's opstamp
+    * `{:error, :invalid_argument, message}` - The reader follows commits on
+      its own, so it has no fixed commit
+
+  ## Examples
+
+      {:ok, reader} = Muninn.IndexReader.new(index, reload: :manual)
+      {:ok, opstamp} = Muninn.IndexReader.opstamp(reader)
+
+  """
+  @spec opstamp(t()) ::
+          {:ok, non_neg_integer()} | {:error, :invalid_argument | :index_error, String.t()}
+  def opstamp(reader) do
+    Native.reader_opstamp(reader)
   end
 end
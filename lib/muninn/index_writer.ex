@@ -23,25 +23,68 @@ defmodule Muninn.IndexWriter do
       # Commit changes
       :ok = Muninn.IndexWriter.commit(index)
 
+  ## Concurrency
+
+  Any number of processes may write to the same index at once. Writes share
+  the index's single writer and run one at a time, so a long commit delays
+  other writes but never readers: `Muninn.IndexReader.new/2` and searches
+  proceed while documents are added or committed, and see each commit with
+  all of its documents or none of them.
+
+  ## Errors
+
+  Failures are returned as `{:error, kind, message}`, where `kind` is one of:
+
+    * `:invalid_argument` - The document cannot be decoded or lacks a required field
+    * `:read_only` - The index was opened with `read_only: true`
+    * `:index_locked` - Another writer, possibly on another node, holds the
+      index lock. The writer is created on the first write, so back off and
+      retry the call
+    * `:index_error` - The index or its writer failed
+    * `:internal_panic` - Tantivy panicked while adding, deleting or
+      committing. A panic while the writer was locked leaves the lock
+      poisoned, so later writes fail with `:index_error` until the index is
+      reopened
+
   """
 
   alias Muninn.Native
 
+  @type error ::
+          {:error,
+           :invalid_argument | :read_only | :index_locked | :index_error | :internal_panic,
+           String.t()}
+
   @doc """
   Adds a single document to the index.
 
   The document should be a map where keys match the field names defined
   in the schema. Field values are automatically converted to the correct types.
 
+  Numeric fields (`u64`, `i64` and `f64`) also accept a list of values, such as
+  `%{"cat_ids" => [1, 5, 9]}`. The document matches queries on any of them, and
+  search hits return the field as a list when it holds more than one value, or
+  always for a field added with `multi: true`.
+
+  A `nil` value, like a missing key, leaves the field without a value, so `0`
+  and "missing" stay distinct for exists queries such as `rating:*`. A numeric
+  or bool value that does not fit the field's type, such as `"12"` or `-1` for
+  a `u64` field, or an integer above the `i64` range, rejects the document with
+  `:invalid_argument` instead of being dropped. Integers are accepted by `f64`
+  fields.
+
   ## Parameters
 
     * `index` - The index to add the document to
     * `document` - A map with field names as keys
 
+  Use `add_document_detailed/2` to learn whether the add triggered an automatic
+  commit or truncated a value.
+
   ## Returns
 
     * `:ok` - Document added successfully
-    * `{:error, reason}` - Failed to add document
+    * `{:error, kind, message}` - Failed to add document
 
   ## Examples
 
@@ -55,10 +98,43 @@ defmodule Muninn.IndexWriter do
       :ok = Muninn.IndexWriter.add_document(index, doc)
 
   """
-  @spec add_document(reference(), map()) :: :ok | {:error, String.t()}
+  @spec add_document(reference(), map()) :: :ok | error()
   def add_document(index, document) when is_map(document) do
+    case add_document_detailed(index, document) do
+      {:ok, _details} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Adds a single document to the index, like `add_document/2`, and reports what
+  the add did besides.
+
+  ## Parameters
+
+    * `index` - The index to add the document to
+    * `document` - A map with field names as keys
+
+  ## Returns
+
+    * `{:ok, %{opstamp: opstamp, truncated: fields}}` - Document added.
+      `opstamp` identifies the automatic commit the add triggered (see
+      `set_autocommit/2`), or is `nil` when it triggered none. `fields` names
+      the fields whose values were truncated to their `:max_length` (see
+      "Value limits" in `Muninn.Schema`), and is empty when none were
+    * `{:error, kind, message}` - Failed to add document
+
+  ## Examples
+
+      {:ok, %{opstamp: nil, truncated: ["body"]}} =
+        Muninn.IndexWriter.add_document_detailed(index, %{"body" => long_text})
+
+  """
+  @spec add_document_detailed(reference(), map()) ::
+          {:ok, %{opstamp: non_neg_integer() | nil, truncated: [String.t()]}} | error()
+  def add_document_detailed(index, document) when is_map(document) do
     case Native.writer_add_document(index, document) do
-      {:ok, _} -> :ok
+      {:ok, {opstamp, truncated}} -> {:ok, %{opstamp: opstamp, truncated: truncated}}
       error -> error
     end
   end
@@ -76,7 +152,7 @@ defmodule Muninn.IndexWriter do
   ## Returns
 
     * `:ok` - All documents added successfully
-    * `{:error, reason}` - Failed to add documents
+    * `{:error, kind, message}` - Failed to add documents
 
   ## Examples
 
@@ -89,7 +165,7 @@ defmodule Muninn.IndexWriter do
       :ok = Muninn.IndexWriter.add_documents(index, docs)
 
   """
-  @spec add_documents(reference(), [map()]) :: :ok | {:error, String.t()}
+  @spec add_documents(reference(), [map()]) :: :ok | error()
   def add_documents(index, documents) when is_list(documents) do
     # For now, add one by one. Can be optimized later
     Enum.reduce_while(documents, :ok, fn doc, :ok ->
@@ -100,6 +176,70 @@ defmodule Muninn.IndexWriter do
     end)
   end
 
+  @doc """
+  Commits automatically every `every_n_docs` added documents.
+
+  Once enabled, the add that brings the number of uncommitted documents to
+  `every_n_docs` also commits them, so ingestion loops never accumulate
+  unbounded uncommitted work. Pass `0` to turn auto-commit off. Explicit
+  `commit/1`, `commit_with_payload/2` and `rollback/1` calls reset the count.
+
+  ## Parameters
+
+    * `index` - The index to configure
+    * `every_n_docs` - Number of added documents per automatic commit, or `0`
+
+  ## Returns
+
+    * `:ok` - Auto-commit configured
+    * `{:error, kind, message}` - The index is read-only or could not be locked
+
+  ## Examples
+
+      :ok = Muninn.IndexWriter.set_autocommit(index, 1_000)
+
+      Enum.each(stream_of_docs, fn doc ->
+        case Muninn.IndexWriter.add_document_detailed(index, doc) do
+          {:ok, %{opstamp: nil}} -> :ok
+          {:ok, %{opstamp: opstamp}} -> Logger.debug("committed up to opstamp #{opstamp}")
+        end
+      end)
+
+  """
+  @spec set_autocommit(reference(), non_neg_integer()) :: :ok | error()
+  def set_autocommit(index, every_n_docs) when is_integer(every_n_docs) and every_n_docs >= 0 do
+    case Native.writer_set_autocommit(index, every_n_docs) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Returns the number of documents added but not yet committed.
+
+  The count grows with every `add_document/2` and goes back to `0` on any
+  commit, including automatic ones from `set_autocommit/2`, and on
+  `rollback/1`. Ingestion pipelines can use it to decide when to commit or to
+  report progress. Deletes are not counted.
+
+  ## Returns
+
+    * `{:ok, count}` - The number of uncommitted documents
+    * `{:error, kind, message}` - The index could not be locked
+
+  ## Examples
+
+      :ok = Muninn.IndexWriter.add_documents(index, batch)
+
+      {:ok, pending} = Muninn.IndexWriter.pending_docs(index)
+      if pending >= 10_000, do: Muninn.IndexWriter.commit(index)
+
+  """
+  @spec pending_docs(reference()) :: {:ok, non_neg_integer()} | error()
+  def pending_docs(index) do
+    Native.writer_pending_docs(index)
+  end
+
   @doc """
   Commits all pending changes to the index.
 
@@ -113,7 +253,7 @@ defmodule Muninn.IndexWriter do
   ## Returns
 
     * `:ok` - Commit successful
-    * `{:error, reason}` - Failed to commit
+    * `{:error, kind, message}` - Failed to commit
 
   ## Examples
 
@@ -121,7 +261,7 @@ defmodule Muninn.IndexWriter do
       :ok = Muninn.IndexWriter.commit(index)
 
   """
-  @spec commit(reference()) :: :ok | {:error, String.t()}
+  @spec commit(reference()) :: :ok | error()
   def commit(index) do
     case Native.writer_commit(index) do
       {:ok, _} -> :ok
@@ -129,6 +269,237 @@ defmodule Muninn.IndexWriter do
     end
   end
 
+  @doc """
+  Commits the pending changes of several indexes together.
+
+  For data kept in parallel indexes, such as a main index and an autocomplete
+  index, that must stay in sync. A commit is first prepared on every index,
+  which flushes its pending documents and does most of the work, and only then
+  is each index committed. When preparing fails for any index, the pending
+  changes of all of them are rolled back, so none is committed.
+
+  Tantivy commits each index on its own, so this is not a true cross-index
+  transaction: committing a prepared index only writes its metadata, but if
+  that fails, for example on a full disk, the indexes committed before it keep
+  their commit while the rest are rolled back.
+
+  The indexes' writers are locked, in a fixed order, for the whole call, so
+  other writes to these indexes wait for it. An index listed twice is committed
+  once, and an index without pending changes is left as it is.
+
+  ## Parameters
+
+    * `indexes` - The indexes to commit
+
+  ## Returns
+
+    * `:ok` - Every index was committed
+    * `{:error, kind, message}` - Failed to commit, and the pending changes were
+      rolled back
+
+  ## Examples
+
+      :ok = Muninn.IndexWriter.add_documents(products, docs)
+      :ok = Muninn.IndexWriter.add_documents(suggestions, Enum.map(docs, &suggestion/1))
+      :ok = Muninn.IndexWriter.multi_commit([products, suggestions])
+
+  """
+  @spec multi_commit([reference()]) :: :ok | error()
+  def multi_commit(indexes) when is_list(indexes) do
+    case Native.writer_multi_commit(indexes) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Commits all pending changes, then merges the index down to a few segments.
+
+  Each commit writes at least one new segment, and searches visit every
+  segment, so a bulk load committed in batches leaves many small ones behind
+  until background merges catch up. This commits like `commit/1`, then merges
+  the segments into at most `max_segments` before returning: a "load and
+  optimize" step for offline index builds. Segments are grouped so each merged
+  segment holds about the same number of documents, and deleted documents are
+  dropped from the merged segments.
+
+  Merging rewrites the whole index, so it takes time and disk space
+  proportional to its size. Background merges are waited for first, and other
+  writes to the index block until the call returns.
+
+  ## Parameters
+
+    * `index` - The index to commit
+    * `max_segments` - Largest number of segments to leave, a positive integer
+
+  ## Returns
+
+    * `{:ok, num_segments}` - The number of segments the index now has
+    * `{:error, kind, message}` - Failed to commit or merge
+
+  ## Examples
+
+      Enum.each(Stream.chunk_every(docs, 10_000), fn batch ->
+        :ok = Muninn.IndexWriter.add_documents(index, batch)
+        :ok = Muninn.IndexWriter.commit(index)
+      end)
+
+      {:ok, 1} = Muninn.IndexWriter.commit_and_merge(index, 1)
+
+  """
+  @spec commit_and_merge(reference(), pos_integer()) :: {:ok, non_neg_integer()} | error()
+  def commit_and_merge(index, max_segments) when is_integer(max_segments) and max_segments > 0 do
+    Native.writer_commit_and_merge(index, max_segments)
+  end
+
+  @doc """
+  Commits all pending changes, then merges the whole index into one segment.
+
+  This is the "optimize" or force merge of Lucene and Elasticsearch. After many
+  deletes and small commits, deleted documents linger in their segments until
+  merged away, and still count in the term statistics that BM25 scoring uses.
+  Optimizing rewrites the index into a single segment holding only live
+  documents, which restores search speed and scoring accuracy. Unlike
+  `commit_and_merge/2`, an index already down to one segment is rewritten too
+  when it holds deleted documents.
+
+  Merging rewrites the whole index, so it takes time and disk space
+  proportional to its size. Background merges are waited for first, and other
+  writes to the index block until the call returns.
+
+  ## Returns
+
+    * `:ok` - The index now has at most one segment, without deleted documents
+    * `{:error, kind, message}` - Failed to commit or merge
+
+  ## Examples
+
+      {:ok, _count} = Muninn.IndexWriter.delete_terms(index, "id", stale_ids)
+      :ok = Muninn.IndexWriter.optimize(index)
+
+  """
+  @spec optimize(reference()) :: :ok | error()
+  def optimize(index) do
+    case Native.writer_optimize(index) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Commits all pending changes and stamps the commit with a payload.
+
+  The payload is an opaque string stored in the index metadata, such as the
+  log sequence number or version of the upstream data the commit reflects. Read
+  it back with `Muninn.Index.commit_payload/1` to find where to resume syncing.
+  A payload can be stamped even when no documents are pending. Each commit
+  replaces the payload, and a plain `commit/1` clears it.
+
+  ## Parameters
+
+    * `index` - The index to commit
+    * `payload` - The string to store with the commit
+
+  ## Returns
+
+    * `:ok` - Commit successful
+    * `{:error, kind, message}` - Failed to commit
+
+  ## Examples
+
+      :ok = Muninn.IndexWriter.add_documents(index, docs)
+      :ok = Muninn.IndexWriter.commit_with_payload(index, "lsn:0/16B3748")
+
+  """
+  @spec commit_with_payload(reference(), String.t()) :: :ok | error()
+  def commit_with_payload(index, payload) when is_binary(payload) do
+    case Native.writer_commit_with_payload(index, payload) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Adds a single document and commits it in one call.
+
+  The add and the commit happen while holding the writer lock, so no other
+  write can slip in between them. Meant for one-document-per-request writes.
+  Like `commit/1`, the commit also covers documents added earlier and not yet
+  committed.
+
+  Value limits apply as in `add_document/2`, but truncated fields are not
+  reported.
+
+  ## Returns
+
+    * `{:ok, opstamp}` - The opstamp of the commit
+    * `{:error, kind, message}` - The document was rejected or the commit failed
+
+  ## Examples
+
+      {:ok, _opstamp} = Muninn.IndexWriter.add_and_commit(index, %{"sku" => "sku-1"})
+
+  """
+  @spec add_and_commit(reference(), map()) :: {:ok, non_neg_integer()} | error()
+  def add_and_commit(index, document) when is_map(document) do
+    Native.writer_add_and_commit(index, document)
+  end
+
+  @doc """
+  Deletes every document holding a value in a field and commits in one call.
+
+  The delete and the commit happen while holding the writer lock, so no other
+  write can slip in between them. The value is matched as a single, untokenized
+  term, so use a keyword or numeric field that identifies documents. Like
+  `commit/1`, the commit also covers documents added earlier and not yet
+  committed.
+
+  ## Returns
+
+    * `{:ok, opstamp}` - The opstamp of the commit, also when nothing matched
+    * `{:error, kind, message}` - The field does not exist, the value does not
+      match its type or the commit failed
+
+  ## Examples
+
+      {:ok, _opstamp} = Muninn.IndexWriter.delete_and_commit(index, "sku", "sku-1")
+
+  """
+  @spec delete_and_commit(reference(), String.t(), String.t() | number() | boolean()) ::
+          {:ok, non_neg_integer()} | error()
+  def delete_and_commit(index, field_name, value) when is_binary(field_name) do
+    Native.writer_delete_and_commit(index, field_name, value)
+  end
+
+  @doc """
+  Deletes every document holding any of the values in a field.
+
+  Meant for syncing a batch of deletions from another store: the deletes are
+  queued under a single writer lock instead of one call per id, and take effect
+  with the next `commit/1`. Like `delete_and_commit/3`, each value is matched as
+  a single, untokenized term, so use a keyword or numeric field that identifies
+  documents. Every value is checked against the field type before anything is
+  deleted, so a bad value rejects the whole batch.
+
+  ## Returns
+
+    * `{:ok, count}` - The number of values processed, whether or not they
+      matched a document
+    * `{:error, kind, message}` - The field does not exist or a value does not
+      match its type
+
+  ## Examples
+
+      {:ok, 3} = Muninn.IndexWriter.delete_terms(index, "sku", ["sku-1", "sku-2", "sku-3"])
+      :ok = Muninn.IndexWriter.commit(index)
+
+  """
+  @spec delete_terms(reference(), String.t(), [String.t() | number() | boolean()]) ::
+          {:ok, non_neg_integer()} | error()
+  def delete_terms(index, field_name, values) when is_binary(field_name) and is_list(values) do
+    Native.writer_delete_terms(index, field_name, values)
+  end
+
   @doc """
   Rolls back all uncommitted changes.
 
@@ -142,7 +513,7 @@ defmodule Muninn.IndexWriter do
   ## Returns
 
     * `:ok` - Rollback successful
-    * `{:error, reason}` - Failed to rollback
+    * `{:error, kind, message}` - Failed to rollback
 
   ## Examples
 
@@ -151,11 +522,52 @@ defmodule Muninn.IndexWriter do
       # Document is not searchable
 
   """
-  @spec rollback(reference()) :: :ok | {:error, String.t()}
+  @spec rollback(reference()) :: :ok | error()
   def rollback(index) do
     case Native.writer_rollback(index) do
       {:ok, _} -> :ok
       error -> error
     end
   end
+
+  @doc """
+  Commits or rolls back pending changes and closes the writer.
+
+  Closing releases the index lock immediately, so another process can start
+  writing to the same directory without waiting for the index reference to be
+  garbage collected. Call it from a GenServer's `terminate/2` to avoid
+  `:index_locked` errors on quick restarts. The next write on the index opens
+  a new writer.
+
+  ## Parameters
+
+    * `index` - The index whose writer to close
+    * `opts` - Keyword list of options
+
+  ## Options
+
+    * `:commit` - Commit pending changes before closing, or roll them back
+      when `false` (default: `true`)
+
+  ## Returns
+
+    * `:ok` - Writer closed, or no writer was open
+    * `{:error, kind, message}` - Failed to commit, roll back or close
+
+  ## Examples
+
+      :ok = Muninn.IndexWriter.add_document(index, doc)
+      :ok = Muninn.IndexWriter.close(index)
+
+      # Discard pending changes instead
+      :ok = Muninn.IndexWriter.close(index, commit: false)
+
+  """
+  @spec close(reference(), keyword()) :: :ok | error()
+  def close(index, opts \\ []) do
+    case Native.writer_close(index, Keyword.get(opts, :commit, true)) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
 end
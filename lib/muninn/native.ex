@@ -65,26 +65,124 @@ defmodule Muninn.Native do
   ## Index functions
 
   @doc false
-  def index_create(_path, _fields_list), do: :erlang.nif_error(:nif_not_loaded)
+  def index_create(_path, _fields_list, _max_limit, _force),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_create_with_schema(_path, _schema, _max_limit, _force),
+    do: :erlang.nif_error(:nif_not_loaded)
 
   @doc false
-  def index_open(_path), do: :erlang.nif_error(:nif_not_loaded)
+  def index_open(_path, _max_limit), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_open_read_only(_path, _max_limit), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_schema(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_num_fields(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_check_schema(_index, _fields), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_info(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_health_check(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def muninn_info, do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_format_info(_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_register_cjk_tokenizer(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_set_synonyms(_index, _synonyms), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_swap(_index, _new_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_set_durability(_index, _durable), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_list_tokenizers(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_tokenize(_index, _field_name, _text), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_commit_payload(_index), do: :erlang.nif_error(:nif_not_loaded)
 
   ## Writer functions
 
   @doc false
   def writer_add_document(_index, _document), do: :erlang.nif_error(:nif_not_loaded)
 
+  @doc false
+  def writer_set_autocommit(_index, _every_n_docs), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_pending_docs(_index), do: :erlang.nif_error(:nif_not_loaded)
+
   @doc false
   def writer_commit(_index), do: :erlang.nif_error(:nif_not_loaded)
 
+  @doc false
+  def writer_multi_commit(_indexes), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_commit_and_merge(_index, _max_segments), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_optimize(_index), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_commit_with_payload(_index, _payload), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_add_and_commit(_index, _document), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_delete_and_commit(_index, _field_name, _value),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def writer_delete_terms(_index, _field_name, _values), do: :erlang.nif_error(:nif_not_loaded)
+
   @doc false
   def writer_rollback(_index), do: :erlang.nif_error(:nif_not_loaded)
 
+  @doc false
+  def writer_close(_index, _commit), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_reindex(_source, _dest, _batch_size), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_merge_from(_index, _source_paths), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def index_percolate(_fields_list, _document, _queries, _default_fields),
+    do: :erlang.nif_error(:nif_not_loaded)
+
   ## Reader functions
 
   @doc false
-  def reader_new(_index), do: :erlang.nif_error(:nif_not_loaded)
+  def reader_new(_index, _max_result_window, _manual_reload),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def reader_reload(_reader), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def reader_opstamp(_reader), do: :erlang.nif_error(:nif_not_loaded)
 
   ## Searcher functions
 
@@ -92,10 +190,114 @@ defmodule Muninn.Native do
   def searcher_new(_reader), do: :erlang.nif_error(:nif_not_loaded)
 
   @doc false
-  def searcher_search_term(_searcher, _query, _limit), do: :erlang.nif_error(:nif_not_loaded)
+  def searcher_search_term(_searcher, _query, _scoring, _limit),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_query(_searcher, _query_string, _default_fields, _options, _limit),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_set_default_fields(_searcher, _field_names),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_set_field_aliases(_searcher, _aliases), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_set_query_defaults(_searcher, _default_operator, _lenient, _fuzzy_distance),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_get_doc(_searcher, _doc_address), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_score_docs(_searcher, _query_string, _default_fields, _doc_addresses, _explain),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_doc_term_freqs(_searcher, _doc_address, _field_name, _top_n),
+    do: :erlang.nif_error(:nif_not_loaded)
 
   @doc false
-  def searcher_search_query(_searcher, _query_string, _default_fields, _limit),
+  def searcher_term_dict(_searcher, _field_name, _prefix, _limit),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_paged(
+        _searcher,
+        _query_string,
+        _default_fields,
+        _tiebreak,
+        _limit,
+        _offset
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_grouped(
+        _searcher,
+        _query_string,
+        _default_fields,
+        _group_field,
+        _hits_per_group,
+        _num_groups
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_sorted(
+        _searcher,
+        _query_string,
+        _default_fields,
+        _sort_field,
+        _order,
+        _limit
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_with_decay(
+        _searcher,
+        _query_string,
+        _default_fields,
+        _decay_field,
+        _scale,
+        _function,
+        _origin,
+        _limit
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_bbox(
+        _searcher,
+        _lat_field,
+        _lon_field,
+        _min_lat,
+        _max_lat,
+        _min_lon,
+        _max_lon,
+        _query_string,
+        _default_fields,
+        _limit
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_aggregate(_searcher, _query_string, _default_fields, _aggs_json),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_cardinality(_searcher, _query_string, _default_fields, _field_name),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_multi_field(_searcher, _query_string, _field_boosts, _limit),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_min_should_match(_searcher, _field_names, _terms, _min_match, _limit),
     do: :erlang.nif_error(:nif_not_loaded)
 
   @doc false
@@ -105,14 +307,51 @@ defmodule Muninn.Native do
         _default_fields,
         _snippet_fields,
         _max_snippet_chars,
+        _full_text_threshold,
+        _context_words,
+        _snippet_tokenizers,
+        _best_snippet,
+        _scoring,
+        _limit
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_fast_fields(
+        _searcher,
+        _query_string,
+        _default_fields,
+        _fast_fields,
+        _scoring,
         _limit
       ),
       do: :erlang.nif_error(:nif_not_loaded)
 
   @doc false
-  def searcher_search_prefix(_searcher, _field_name, _prefix, _limit),
+  def searcher_search_ids(
+        _searcher,
+        _query_string,
+        _default_fields,
+        _id_field,
+        _limit,
+        _offset
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_prefix(_searcher, _field_name, _prefix, _lowercase, _limit),
     do: :erlang.nif_error(:nif_not_loaded)
 
+  @doc false
+  def searcher_search_wildcard(
+        _searcher,
+        _field_name,
+        _pattern,
+        _allow_leading_wildcard,
+        _limit
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
   @doc false
   def searcher_search_range_u64(
         _searcher,
@@ -149,6 +388,30 @@ defmodule Muninn.Native do
       ),
       do: :erlang.nif_error(:nif_not_loaded)
 
+  @doc false
+  def searcher_search_range_text(
+        _searcher,
+        _field_name,
+        _lower,
+        _upper,
+        _lower_inclusive,
+        _upper_inclusive,
+        _limit
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_doc_exists(_searcher, _field_name, _value),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_count_multi(_searcher, _queries, _default_fields),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_estimate_hits(_searcher, _query_string, _default_fields),
+    do: :erlang.nif_error(:nif_not_loaded)
+
   @doc false
   def searcher_search_fuzzy(
         _searcher,
@@ -183,4 +446,24 @@ defmodule Muninn.Native do
         _limit
       ),
       do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_search_stream(_searcher, _query_string, _default_fields, _batch_size),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_stream_next(_cursor), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def query_compile(_searcher, _query_string, _default_fields),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_run(_searcher, _compiled, _limit), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def regex_compile(_pattern, _field_name), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def searcher_run_regex(_searcher, _compiled, _limit), do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -39,7 +39,11 @@ defmodule Muninn.Query do
   Creates a term query that matches documents containing a specific term.
 
   Term queries search for exact term matches in the specified field.
-  For text fields, the term should match a single token after tokenization.
+  On text fields, the value is analyzed with the field's tokenizer first, so
+  `"Running"` matches a field indexed with `tokenizer: "en_stem"`, which stores
+  it as `"run"`. The value must analyze to exactly one term, or the search
+  fails with `:invalid_argument`. Keyword fields match the value as given.
+  Bool fields match `true` or `false`, given as a boolean or as a string.
 
   ## Parameters
 
@@ -58,9 +62,16 @@ defmodule Muninn.Query do
       # Search for a product by SKU
       query = Muninn.Query.term("sku", "PROD-123")
 
+      # Search for products in stock
+      query = Muninn.Query.term("in_stock", true)
+
   """
-  @spec term(String.t(), String.t()) :: Query.Term.t()
+  @spec term(String.t(), String.t() | boolean()) :: Query.Term.t()
   def term(field, value) when is_binary(field) and is_binary(value) do
     %Query.Term{field: field, value: value}
   end
+
+  def term(field, value) when is_binary(field) and is_boolean(value) do
+    %Query.Term{field: field, value: Atom.to_string(value)}
+  end
 end
@@ -11,6 +11,91 @@ defmodule Muninn.Schema do
         |> Muninn.Schema.add_text_field("title", stored: true)
         |> Muninn.Schema.add_text_field("body", stored: true)
 
+  ## Stored-only fields
+
+  A field added with `stored: true, indexed: false` is a payload, such as an
+  image URL or pre-rendered HTML: it is returned in every hit's `"doc"` but never
+  searched. Queries naming it fail with `:parse_error`, passing it as a default
+  field fails with `:invalid_argument`, and a text field that is not indexed
+  cannot have a `:tokenizer`.
+
+  ## Id field
+
+  Tantivy's internal document addresses change when segments merge, so they
+  cannot be used to identify documents. Instead, one stored text, keyword, u64
+  or i64 field can be marked with `id: true`. Every search hit then carries that
+  field's value under the `"_id"` key, next to `"score"` and `"doc"`:
+
+      schema = Muninn.Schema.new()
+        |> Muninn.Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Muninn.Schema.add_text_field("title", stored: true)
+
+      %{"_id" => "sku-123", "score" => _, "doc" => _} = hit
+
+  The designation is saved with the index and restored by `Muninn.Index.open/2`.
+  A search fails with `:not_found` if a matching document has no value for the
+  id field.
+
+  ## Required fields
+
+  Fields added with `required: true` must have a value in every document.
+  `Muninn.IndexWriter.add_document/2` rejects a document without one, or whose
+  value cannot be converted to the field's type, with `:invalid_argument` and
+  a message listing the missing fields. Like the id field, required fields are
+  saved with the index.
+
+  ## Value limits
+
+  A huge text value, such as a scraped page or an uploaded file, makes indexing
+  slow and memory hungry. Text and keyword fields accept a `:max_length` in bytes:
+
+      schema = Muninn.Schema.new()
+        |> Muninn.Schema.add_text_field("title", stored: true, max_length: 200)
+        |> Muninn.Schema.add_text_field("body",
+          stored: true,
+          max_length: 100_000,
+          on_too_long: :truncate
+        )
+
+  With `on_too_long: :reject`, the default, `Muninn.IndexWriter.add_document/2`
+  rejects a document whose value is longer with `:invalid_argument`. With
+  `:truncate`, the value is cut to the limit, keeping whole UTF-8 characters,
+  and the document is added; `Muninn.IndexWriter.add_document_detailed/2`
+  reports the truncated fields. The `"_source"` copy of `add_source_field/1` keeps the original
+  value. Limits are saved with the index.
+
+  ## Multi-valued fields
+
+  Numeric fields accept a list of values, such as `%{"cat_ids" => [1, 5, 9]}`.
+  By default hits return a field holding several values as a list and a field
+  holding one value as that value, so `[7]` comes back as `7`. Adding the field
+  with `multi: true` always returns a list, whatever the number of values:
+
+      schema = Muninn.Schema.new()
+        |> Muninn.Schema.add_u64_field("cat_ids", stored: true, multi: true)
+
+  Like required fields, multi-valued fields are saved with the index.
+
+  ## Exact matches
+
+  With a stemming tokenizer such as `"en_stem"`, a search for "running" also
+  matches "run" and "runs", and all of them score the same. Adding the field with
+  `exact: true` also indexes its values, unstemmed, into a companion field named
+  `"<name>_exact"`:
+
+      schema = Muninn.Schema.new()
+        |> Muninn.Schema.add_text_field("title",
+          stored: true,
+          tokenizer: "en_stem",
+          exact: true
+        )
+
+  The companion uses the `"default"` tokenizer, which only lowercases, and is not
+  stored. Documents only give a value for `"title"`: it is copied into
+  `"title_exact"` when they are added. `Muninn.Searcher.search_exact_boosted/4`
+  then searches both fields, so exact-form matches rank above stemmed ones. The
+  pairing is saved with the index.
+
   """
 
   alias Muninn.Schema.Field
@@ -43,6 +128,25 @@ defmodule Muninn.Schema do
 
     * `:stored` - Whether to store the field value (default: `false`)
     * `:indexed` - Whether to index the field for searching (default: `true`)
+    * `:tokenizer` - Name of the tokenizer used to split the text into terms
+      (default: `"default"`). One of Tantivy's built-in `"default"`, `"en_stem"`,
+      `"raw"` and `"whitespace"`, Muninn's `"raw_lowercase"`, which keeps the
+      whole value as one lowercased term, or `"cjk"`, which must be registered
+      with `Muninn.Index.register_cjk_tokenizer/1` before documents are added or
+      searched. Any other name fails when the index is created.
+    * `:id` - Whether this field identifies documents (default: `false`). See
+      "Id field" in the module documentation.
+    * `:required` - Whether every document must have a value for this field
+      (default: `false`). See "Required fields" in the module documentation.
+    * `:max_length` - Largest value in bytes (default: `nil`, unlimited). See
+      "Value limits" in the module documentation.
+    * `:on_too_long` - `:reject` (default) or `:truncate` values above `:max_length`
+    * `:positions` - Whether to index term positions, which phrase queries need
+      (default: the `:positions` option of `Muninn.Index.create/3`, itself `true`).
+      Pass `true` to keep phrase queries on this field in an index created with
+      `positions: false`.
+    * `:exact` - Whether to also index the values unstemmed into a `"<name>_exact"`
+      field (default: `false`). See "Exact matches" in the module documentation.
 
   ## Examples
 
@@ -51,10 +155,72 @@ defmodule Muninn.Schema do
       iex> length(schema.fields)
       1
 
+      iex> schema = Muninn.Schema.new()
+      iex> schema = Muninn.Schema.add_text_field(schema, "title", exact: true)
+      iex> Enum.map(schema.fields, & &1.name)
+      ["title", "title_exact"]
+
   """
   @spec add_text_field(t(), String.t(), keyword()) :: t()
   def add_text_field(%__MODULE__{fields: fields} = schema, name, opts \\ []) do
     field = Field.new(:text, name, opts)
+
+    exact_fields =
+      if Keyword.get(opts, :exact, false) do
+        exact_opts = [positions: Keyword.get(opts, :positions), copy_from: name]
+        [Field.new(:text, name <> "_exact", exact_opts)]
+      else
+        []
+      end
+
+    %{schema | fields: fields ++ [field | exact_fields]}
+  end
+
+  @doc """
+  Adds a keyword field to the schema.
+
+  A keyword field is a text field whose whole value is indexed as a single,
+  untokenized term. It is matched exactly and case-sensitively, which makes it
+  the right choice for ids, slugs, enum values and tags.
+
+  With `case_insensitive: true`, the value is lowercased before it is indexed,
+  and so are the values searched for it, so `"Apple Pie"` and `"apple pie"`
+  match each other while `"apple"` alone still does not. Query strings must
+  quote values holding spaces, such as `brand:"Apple Pie"`. The field keeps
+  its stored value as it was added.
+
+  ## Options
+
+    * `:stored` - Whether to store the field value (default: `false`)
+    * `:indexed` - Whether to index the field for exact matching (default: `true`)
+    * `:id` - Whether this field identifies documents (default: `false`). See
+      "Id field" in the module documentation.
+    * `:required` - Whether every document must have a value for this field
+      (default: `false`). See "Required fields" in the module documentation.
+    * `:max_length` - Largest value in bytes (default: `nil`, unlimited). See
+      "Value limits" in the module documentation.
+    * `:on_too_long` - `:reject` (default) or `:truncate` values above `:max_length`
+    * `:case_insensitive` - Whether values match regardless of case (default:
+      `false`), using the `"raw_lowercase"` tokenizer
+
+  ## Examples
+
+      iex> schema = Muninn.Schema.new()
+      iex> schema = Muninn.Schema.add_keyword_field(schema, "slug", stored: true)
+      iex> hd(schema.fields).type
+      :keyword
+
+  """
+  @spec add_keyword_field(t(), String.t(), keyword()) :: t()
+  def add_keyword_field(%__MODULE__{fields: fields} = schema, name, opts \\ []) do
+    opts =
+      if Keyword.get(opts, :case_insensitive, false) do
+        Keyword.put(opts, :tokenizer, "raw_lowercase")
+      else
+        opts
+      end
+
+    field = Field.new(:keyword, name, opts)
     %{schema | fields: fields ++ [field]}
   end
 
@@ -65,6 +231,14 @@ defmodule Muninn.Schema do
 
     * `:stored` - Whether to store the field value (default: `false`)
     * `:indexed` - Whether to index the field (default: `true`)
+    * `:fast` - Whether to store the value in a columnar fast field (default: `false`).
+      Fast fields can be read with `Muninn.Searcher.search_fast_fields/5`.
+    * `:id` - Whether this field identifies documents (default: `false`). See
+      "Id field" in the module documentation.
+    * `:required` - Whether every document must have a value for this field
+      (default: `false`). See "Required fields" in the module documentation.
+    * `:multi` - Whether hits return the field's values as a list, even a single
+      one (default: `false`). See "Multi-valued fields" in the module documentation.
 
   ## Examples
 
@@ -87,6 +261,14 @@ defmodule Muninn.Schema do
 
     * `:stored` - Whether to store the field value (default: `false`)
     * `:indexed` - Whether to index the field (default: `true`)
+    * `:fast` - Whether to store the value in a columnar fast field (default: `false`).
+      Fast fields can be read with `Muninn.Searcher.search_fast_fields/5`.
+    * `:id` - Whether this field identifies documents (default: `false`). See
+      "Id field" in the module documentation.
+    * `:required` - Whether every document must have a value for this field
+      (default: `false`). See "Required fields" in the module documentation.
+    * `:multi` - Whether hits return the field's values as a list, even a single
+      one (default: `false`). See "Multi-valued fields" in the module documentation.
 
   ## Examples
 
@@ -109,6 +291,12 @@ defmodule Muninn.Schema do
 
     * `:stored` - Whether to store the field value (default: `false`)
     * `:indexed` - Whether to index the field (default: `true`)
+    * `:fast` - Whether to store the value in a columnar fast field (default: `false`).
+      Fast fields can be read with `Muninn.Searcher.search_fast_fields/5`.
+    * `:required` - Whether every document must have a value for this field
+      (default: `false`). See "Required fields" in the module documentation.
+    * `:multi` - Whether hits return the field's values as a list, even a single
+      one (default: `false`). See "Multi-valued fields" in the module documentation.
 
   ## Examples
 
@@ -131,6 +319,10 @@ defmodule Muninn.Schema do
 
     * `:stored` - Whether to store the field value (default: `false`)
     * `:indexed` - Whether to index the field (default: `true`)
+    * `:fast` - Whether to store the value in a columnar fast field (default: `false`).
+      Fast fields can be read with `Muninn.Searcher.search_fast_fields/5`.
+    * `:required` - Whether every document must have a value for this field
+      (default: `false`). See "Required fields" in the module documentation.
 
   ## Examples
 
@@ -146,6 +338,33 @@ defmodule Muninn.Schema do
     %{schema | fields: fields ++ [field]}
   end
 
+  @doc """
+  Stores the original of every added document as JSON.
+
+  Hits only return the stored values Muninn knows how to encode, converted to
+  each field's type. With this hidden `"_source"` field, the whole document map
+  passed to `Muninn.IndexWriter.add_document/2` is also stored as JSON, including
+  keys that are not schema fields, and `Muninn.Searcher.search_query/4` returns
+  it with `source: true`. Use it for lossless round trips when the index is a
+  cache of another store.
+
+  The JSON is encoded like `Jason.encode/1`: `nil` becomes `null` and other
+  atoms become strings. A document holding a value with no JSON representation,
+  such as a tuple, is rejected with `:invalid_argument`.
+
+  ## Examples
+
+      iex> schema = Muninn.Schema.new() |> Muninn.Schema.add_source_field()
+      iex> hd(schema.fields).name
+      "_source"
+
+  """
+  @spec add_source_field(t()) :: t()
+  def add_source_field(%__MODULE__{fields: fields} = schema) do
+    field = Field.new(:source, "_source", stored: true, indexed: false)
+    %{schema | fields: fields ++ [field]}
+  end
+
   @doc """
   Validates the schema.
 
@@ -171,10 +390,10 @@ defmodule Muninn.Schema do
     field_names = Enum.map(fields, & &1.name)
     unique_names = Enum.uniq(field_names)
 
-    if length(field_names) == length(unique_names) do
-      :ok
-    else
-      {:error, :duplicate_field_names}
+    cond do
+      length(field_names) != length(unique_names) -> {:error, :duplicate_field_names}
+      Enum.count(fields, & &1.id) > 1 -> {:error, :multiple_id_fields}
+      true -> :ok
     end
   end
 
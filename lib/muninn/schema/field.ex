@@ -3,16 +3,50 @@ defmodule Muninn.Schema.Field do
   Represents a field in a Muninn schema.
   """
 
-  @type field_type :: :text | :u64 | :i64 | :f64 | :bool | :date | :facet | :bytes | :json
+  @type field_type ::
+          :text
+          | :keyword
+          | :u64
+          | :i64
+          | :f64
+          | :bool
+          | :date
+          | :facet
+          | :bytes
+          | :json
+          | :source
 
   @type t :: %__MODULE__{
           type: field_type(),
           name: String.t(),
           stored: boolean(),
-          indexed: boolean()
+          indexed: boolean(),
+          fast: boolean(),
+          tokenizer: String.t() | nil,
+          id: boolean(),
+          required: boolean(),
+          positions: boolean() | nil,
+          max_length: pos_integer() | nil,
+          on_too_long: :reject | :truncate,
+          copy_from: String.t() | nil,
+          multi: boolean()
         }
 
-  defstruct [:type, :name, stored: false, indexed: true]
+  defstruct [
+    :type,
+    :name,
+    stored: false,
+    indexed: true,
+    fast: false,
+    tokenizer: nil,
+    id: false,
+    required: false,
+    positions: nil,
+    max_length: nil,
+    on_too_long: :reject,
+    copy_from: nil,
+    multi: false
+  ]
 
   @doc """
   Creates a new field.
@@ -21,6 +55,23 @@ defmodule Muninn.Schema.Field do
 
     * `:stored` - Whether to store the field value (default: `false`)
     * `:indexed` - Whether to index the field (default: `true`)
+    * `:fast` - Whether to store the field in a columnar fast field (default: `false`)
+    * `:tokenizer` - Name of the tokenizer used to index a text field (default: `nil`,
+      which uses Tantivy's `"default"` tokenizer). See `Muninn.Schema.add_text_field/3`
+      for the accepted names
+    * `:id` - Whether the field identifies documents, returned under `"_id"` in every
+      search hit (default: `false`)
+    * `:required` - Whether every added document must have a value for the field
+      (default: `false`)
+    * `:positions` - Whether a text field indexes term positions (default: `nil`, which
+      follows the `:positions` option of `Muninn.Index.create/3`)
+    * `:max_length` - Largest value, in bytes, accepted for a text or keyword field
+      (default: `nil`, unlimited)
+    * `:on_too_long` - `:reject` (default) or `:truncate` a value above `:max_length`
+    * `:copy_from` - Name of a text or keyword field whose values are also indexed
+      into this one when documents are added (default: `nil`)
+    * `:multi` - Whether search hits return the values of a numeric field as a list,
+      even when a document holds a single value (default: `false`)
 
   """
   @spec new(field_type(), String.t(), keyword()) :: t()
@@ -29,7 +80,16 @@ defmodule Muninn.Schema.Field do
       type: type,
       name: name,
       stored: Keyword.get(opts, :stored, false),
-      indexed: Keyword.get(opts, :indexed, true)
+      indexed: Keyword.get(opts, :indexed, true),
+      fast: Keyword.get(opts, :fast, false),
+      tokenizer: Keyword.get(opts, :tokenizer),
+      id: Keyword.get(opts, :id, false),
+      required: Keyword.get(opts, :required, false),
+      positions: Keyword.get(opts, :positions),
+      max_length: Keyword.get(opts, :max_length),
+      on_too_long: Keyword.get(opts, :on_too_long, :reject),
+      copy_from: Keyword.get(opts, :copy_from),
+      multi: Keyword.get(opts, :multi, false)
     }
   end
 
@@ -42,7 +102,35 @@ defmodule Muninn.Schema.Field do
       type: Atom.to_string(field.type),
       name: field.name,
       stored: field.stored,
-      indexed: field.indexed
+      indexed: field.indexed,
+      fast: field.fast,
+      tokenizer: field.tokenizer,
+      id: field.id,
+      required: field.required,
+      positions: field.positions,
+      max_length: field.max_length,
+      on_too_long: field.on_too_long,
+      copy_from: field.copy_from,
+      multi: field.multi
     }
   end
+
+  @doc """
+  Converts a field to the tuple representation expected by the NIF layer.
+  """
+  @spec to_native(t()) :: {String.t(), String.t(), boolean(), boolean(), map()}
+  def to_native(%__MODULE__{} = field) do
+    {field.name, Atom.to_string(field.type), field.stored, field.indexed,
+     %{
+       "fast" => field.fast,
+       "tokenizer" => field.tokenizer,
+       "id" => field.id,
+       "required" => field.required,
+       "positions" => field.positions,
+       "max_length" => field.max_length,
+       "on_too_long" => Atom.to_string(field.on_too_long),
+       "copy_from" => field.copy_from,
+       "multi" => field.multi
+     }}
+  end
 end
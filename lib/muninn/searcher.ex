@@ -20,11 +20,42 @@ defmodule Muninn.Searcher do
   Results are returned as a `Muninn.SearchResult` struct containing:
   - `total_hits`: The number of matching documents
   - `hits`: A list of `Muninn.SearchHit` structs, each with:
-    - `score`: Relevance score (higher is better)
+    - `score`: Relevance score (higher is better). Tantivy computes scores as
+      32-bit floats; they are converted through their shortest decimal form,
+      so a score of `0.3` reads `0.3` rather than `0.30000001192092896`
     - `doc`: Map of stored field values
+    - `_id`: Value of the schema's id field, when one is designated (see
+      "Id field" in `Muninn.Schema`)
+    - `_unsupported_fields`: Names of stored fields left out of `doc` because
+      their values have a type Muninn cannot return yet, such as dates, facets
+      or bytes in an index created outside Muninn. The key is only present when
+      a field was left out
 
   Only fields marked as `stored: true` in the schema will be included
-  in the returned documents.
+  in the returned documents. `set_default_fields/2` narrows them further.
+
+  ## Errors
+
+  Search functions return `{:error, kind, message}` on failure, where `kind`
+  is one of:
+
+    * `:not_found` - A referenced field does not exist in the schema, or a
+      matching document has no value for the schema's id field
+    * `:parse_error` - The query string could not be parsed
+    * `:invalid_argument` - An option or field type was rejected, including a
+      `:limit` above the index's `:max_limit` (see `Muninn.Index.create/3`)
+    * `:search_error` - Tantivy failed while executing the search
+    * `:result_window_exceeded` - A page of a paginated search reached past the
+      reader's `:max_result_window` (see `Muninn.IndexReader.new/2`)
+    * `:internal_panic` - Tantivy panicked, for example on a corrupt segment. The
+      panic is caught instead of raising in the calling process; the message
+      holds the panic message
+
+      case Muninn.Searcher.search_query(searcher, user_input, ["title"]) do
+        {:ok, results} -> results
+        {:error, :parse_error, _message} -> show_syntax_help()
+        {:error, _kind, message} -> raise message
+      end
 
   """
 
@@ -33,6 +64,15 @@ defmodule Muninn.Searcher do
 
   @type t :: reference()
 
+  @type error ::
+          {:error,
+           :not_found
+           | :parse_error
+           | :invalid_argument
+           | :search_error
+           | :result_window_exceeded
+           | :internal_panic, String.t()}
+
   @doc """
   Creates a new Searcher from an IndexReader.
 
@@ -43,7 +83,7 @@ defmodule Muninn.Searcher do
   ## Returns
 
     * `{:ok, searcher}` - Successfully created searcher
-    * `{:error, reason}` - Failed to create searcher
+    * `{:error, kind, message}` - Failed to create searcher
 
   ## Examples
 
@@ -51,7 +91,7 @@ defmodule Muninn.Searcher do
       {:ok, searcher} = Muninn.Searcher.new(reader)
 
   """
-  @spec new(reference()) :: {:ok, t()} | {:error, String.t()}
+  @spec new(reference()) :: {:ok, t()} | error()
   def new(reader) do
     Native.searcher_new(reader)
   end
@@ -65,11 +105,13 @@ defmodule Muninn.Searcher do
     * `query` - The query to execute (e.g., from `Muninn.Query.term/2`)
     * `opts` - Keyword list of options:
       - `:limit` - Maximum number of results to return (default: 10)
+      - `:scoring` - `:bm25` for relevance scoring (default) or `:const` to give
+        every match a score of `1.0`, which skips relevance computation for pure filtering
 
   ## Returns
 
     * `{:ok, results}` - Search results as a `Muninn.SearchResult` struct
-    * `{:error, reason}` - Search failed
+    * `{:error, kind, message}` - Search failed
 
   ## Examples
 
@@ -82,11 +124,11 @@ defmodule Muninn.Searcher do
       end
 
   """
-  @spec search(t(), Query.Term.t(), keyword()) :: {:ok, map()} | {:error, String.t()}
+  @spec search(t(), Query.Term.t(), keyword()) :: {:ok, map()} | error()
   def search(searcher, %Query.Term{} = query, opts \\ []) do
     limit = Keyword.get(opts, :limit, 10)
 
-    Native.searcher_search_term(searcher, query, limit)
+    Native.searcher_search_term(searcher, query, scoring(opts), limit)
   end
 
   @doc """
@@ -95,24 +137,99 @@ defmodule Muninn.Searcher do
   This function uses Tantivy's QueryParser to support advanced query syntax:
 
   - **Field-specific search**: `title:elixir` or `author:alice`
+  - **Bool fields**: `in_stock:true` or `in_stock:false`; any other value is a
+    `:parse_error`
   - **Boolean operators**: `elixir AND phoenix`, `rust OR go`
   - **Phrase queries**: `"exact phrase match"`
   - **Required terms**: `+elixir phoenix` (elixir is required)
   - **Excluded terms**: `elixir -draft` (exclude draft)
+  - **Boosts**: `title:elixir^2 content:elixir`, `(rust OR go)^0.5` (scale the
+    score of a term, phrase or group by a non-negative number)
+  - **Exists**: `rating:*` matches documents with a value in a fast field, or in
+    an indexed numeric or bool field; `* -rating:*` matches those without one
   - **Combining**: `title:elixir AND (content:phoenix OR content:otp)`
 
   ## Parameters
 
     * `searcher` - The searcher to use
     * `query_string` - The query string with natural syntax
-    * `default_fields` - List of field names to search when no field is specified
+    * `default_fields` - List of field names to search when no field is specified.
+      May be empty when every clause of the query names its field.
     * `opts` - Keyword list of options:
       - `:limit` - Maximum number of results to return (default: 10)
+      - `:scoring` - `:bm25` for relevance scoring (default) or `:const` to give
+        every match a score of `1.0`, which skips relevance computation for pure filtering
+      - `:matched_terms` - When `true`, each hit gets a `"matched_terms"` list of
+        `%{"field" => field, "term" => term}` maps naming the query terms found in
+        that document (default: `false`). Only term and phrase clauses are reported;
+        fuzzy, regex and range clauses are not
+      - `:segment_ords` - When `true`, each hit gets a `"segment_ord"` integer naming
+        the index segment the document lives in, for spotting a large unmerged segment
+        that dominates results (default: `false`)
+      - `:doc_addresses` - When `true`, each hit gets a `"doc_address"` tuple of
+        `{segment_ord, doc_id}` that `get_doc/2` accepts to fetch the document again
+        without re-running the query (default: `false`)
+      - `:normalize_scores` - When `true`, every `"score"` is divided by the top hit's
+        score, so the best hit scores `1.0` and the others fall between 0 and 1, for
+        "87% match" displays and thresholds that hold across queries. The original
+        BM25 score is kept under `"raw_score"` (default: `false`)
+      - `:source` - When `true`, each hit gets a `"_source"` string holding the
+        original document as JSON, for indexes built with
+        `Muninn.Schema.add_source_field/1` (default: `false`). Decode it with
+        `Jason.decode!/1`
+      - `:match_offsets` - When `true`, each hit gets a `"match_offsets"` map from
+        stored text field names to the `{start, end}` byte ranges where query terms
+        occur in that field's value under `"doc"`, for highlighting the full text
+        inline (default: `false`). Ranges follow the field's tokenizer, so
+        `"Running"` is found by a query for `"running"`, and can be cut out with
+        `binary_part(text, start, end - start)`. Fields without a match are left
+        out
+      - `:ordered_fields` - When `true`, each hit also gets a `"fields"` list of
+        `{name, value}` tuples holding the fields of `"doc"` in the order they were
+        added to the schema, for deterministic rendering and snapshot tests
+        (default: `false`)
+      - `:doc_bytes` - When `true`, each hit gets a `"_doc_bytes"` integer holding
+        the size of its stored document as serialized in the doc store, before
+        compression (default: `false`). Every stored field counts, including
+        fields left out of `"doc"`, so comparing hits with and without a large
+        field shows what storing it costs
+      - `:tiebreak` - `{field, :asc | :desc}` ordering hits with equal scores by
+        the value of a u64, i64 or bool fast field, such as `{"published_at", :desc}`
+        for newest first (default: `nil`). Without it, ties are ordered by internal
+        document address, which changes when segments merge. Documents without a
+        value come after tied documents that have one
+      - `:fields` - Stored fields included in each hit's `"doc"` map, overriding
+        the searcher's default from `set_default_fields/2` for this call (default:
+        `nil`, the searcher's default)
+      - `:exclude_terms` - List of `{field, value}` pairs. Documents containing any
+        of them are dropped from the results, regardless of the query string
+        (default: `[]`). Text values are matched as a single indexed term, like
+        `Muninn.Query.term/2`, so use keyword fields for exact values; numeric and
+        bool values must match the field type
+      - `:filters` - List of query strings every hit must also match, such as
+        `"tenant_id:42"` or `"published:true"` (default: `[]`). Unlike adding them
+        to the query string with `AND`, filters add nothing to the score, so the
+        ranking stays driven by the query string alone. They are parsed with the
+        same operator, leniency and fuzzy distance as the query string
+      - `:query_tokenizers` - Map of text field names to the tokenizer used to split
+        the query text for that field, instead of the field's indexing tokenizer
+        (default: `%{}`). Use it for fields indexed as n-grams, such as the
+        `Muninn.Index.register_cjk_tokenizer/1` bigrams, so a query term is looked
+        up whole (`"raw"`) rather than exploded into n-grams
+      - `:default_operator` - `:or` or `:and`, joining the clauses of the query
+        string that name no operator, overriding the searcher's default from
+        `set_query_defaults/2` for this call
+      - `:lenient` - When `true`, parts of the query string that cannot be parsed
+        or searched match nothing instead of failing the search, overriding the
+        searcher's default for this call
+      - `:fuzzy_distance` - Levenshtein distance from 0 to 2 within which terms
+        match tokenized text fields, overriding the searcher's default for this
+        call. Phrases and keyword fields are always matched exactly
 
   ## Returns
 
     * `{:ok, results}` - Search results with total_hits and hits
-    * `{:error, reason}` - Search or parse failed
+    * `{:error, kind, message}` - Search or parse failed
 
   ## Examples
 
@@ -152,14 +269,844 @@ defmodule Muninn.Searcher do
         ["title", "content"]
       )
 
+      # Report which terms matched each hit
+      {:ok, results} = Muninn.Searcher.search_query(
+        searcher,
+        "elixir phoenix",
+        ["title", "tags"],
+        matched_terms: true
+      )
+
+      # Highlight matches in the full field text
+      {:ok, %{"hits" => [hit | _]}} = Muninn.Searcher.search_query(
+        searcher,
+        "elixir",
+        ["title"],
+        match_offsets: true
+      )
+
+      %{"match_offsets" => %{"title" => [{0, 6}]}} = hit
+
+      # Hide archived documents without editing the query string
+      {:ok, results} = Muninn.Searcher.search_query(
+        searcher,
+        "elixir",
+        ["title", "content"],
+        exclude_terms: [{"status", "archived"}]
+      )
+
+      # Restrict to one tenant without changing the ranking
+      {:ok, results} = Muninn.Searcher.search_query(
+        searcher,
+        "elixir",
+        ["title", "content"],
+        filters: ["tenant_id:42", "published:true"]
+      )
+
+      # Fully field-qualified query without default fields
+      {:ok, results} = Muninn.Searcher.search_query(
+        searcher,
+        "title:rust AND author:steve",
+        []
+      )
+
   """
   @spec search_query(t(), String.t(), list(String.t()), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_query(searcher, query_string, default_fields, opts \\ [])
       when is_binary(query_string) and is_list(default_fields) do
     limit = Keyword.get(opts, :limit, 10)
 
-    Native.searcher_search_query(searcher, query_string, default_fields, limit)
+    options = %{
+      "exclude_terms" => Keyword.get(opts, :exclude_terms, []),
+      "filters" => Keyword.get(opts, :filters, []),
+      "query_tokenizers" => opts |> Keyword.get(:query_tokenizers, %{}) |> Enum.to_list(),
+      "scoring" => scoring(opts),
+      "matched_terms" => Keyword.get(opts, :matched_terms, false),
+      "segment_ords" => Keyword.get(opts, :segment_ords, false),
+      "doc_addresses" => Keyword.get(opts, :doc_addresses, false),
+      "normalize_scores" => Keyword.get(opts, :normalize_scores, false),
+      "source" => Keyword.get(opts, :source, false),
+      "match_offsets" => Keyword.get(opts, :match_offsets, false),
+      "ordered_fields" => Keyword.get(opts, :ordered_fields, false),
+      "doc_bytes" => Keyword.get(opts, :doc_bytes, false),
+      "tiebreak" => tiebreak(opts),
+      "fields" => Keyword.get(opts, :fields),
+      "default_operator" => default_operator(opts),
+      "lenient" => Keyword.get(opts, :lenient),
+      "fuzzy_distance" => Keyword.get(opts, :fuzzy_distance)
+    }
+
+    Native.searcher_search_query(searcher, query_string, default_fields, options, limit)
+  end
+
+  @doc """
+  Sets the stored fields returned in each hit's `"doc"` map.
+
+  Hits normally carry every stored field. Apps that always display the same few
+  fields of a wide schema can set that projection once instead of trimming
+  every result, which also keeps large fields, such as a full page body, from
+  being decoded and sent to clients by accident. These are the fields
+  returned, not the `default_fields` searched by a query string.
+
+  The projection applies to every later search on this searcher and to
+  `get_doc/2`, and `search_query/4` can override it with its `:fields` option.
+  `"_id"` is returned either way. Each searcher has its own projection, so set
+  it again on a searcher created after a commit. Pass `nil` to return every
+  stored field again.
+
+  ## Parameters
+
+    * `searcher` - The searcher to configure
+    * `field_names` - List of stored field names, or `nil`
+
+  ## Returns
+
+    * `:ok` - The projection was set
+    * `{:error, :not_found, message}` - A field does not exist
+    * `{:error, :invalid_argument, message}` - A field is not stored
+
+  ## Examples
+
+      :ok = Muninn.Searcher.set_default_fields(searcher, ["title", "url"])
+
+      {:ok, %{"hits" => [%{"doc" => %{"title" => _, "url" => _}} | _]}} =
+        Muninn.Searcher.search_query(searcher, "elixir", ["title", "body"])
+
+  """
+  @spec set_default_fields(t(), [String.t()] | nil) ::
+          :ok | {:error, :not_found | :invalid_argument | :search_error, String.t()}
+  def set_default_fields(searcher, field_names)
+      when is_list(field_names) or is_nil(field_names) do
+    case Native.searcher_set_default_fields(searcher, field_names) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Sets old names under which renamed fields can still be searched.
+
+  Saved queries and clients keep working across a schema rename when the
+  searcher maps each old field name to its new one. Aliases are rewritten to
+  the field they point to in query strings, including filters, and in the
+  field arguments of every search, such as `default_fields`, snippet, fast and
+  group fields, and the `"field"` of `aggregate/4` requests. Names that are not
+  aliases are left alone, so a misspelled field still fails with `:not_found`
+  or `:parse_error`. Results always use the current field names.
+
+  Each searcher has its own aliases, so set them again on a searcher created
+  after a commit. Pass an empty map to remove them.
+
+  ## Parameters
+
+    * `searcher` - The searcher to configure
+    * `aliases` - Map of old field name to current field name
+
+  ## Returns
+
+    * `:ok` - The aliases were set
+    * `{:error, :not_found, message}` - An alias points to a missing field
+    * `{:error, :invalid_argument, message}` - An alias is itself a field of
+      the schema, which could then no longer be searched
+
+  ## Examples
+
+      # "name" was renamed to "title"
+      :ok = Muninn.Searcher.set_field_aliases(searcher, %{"name" => "title"})
+
+      {:ok, results} = Muninn.Searcher.search_query(searcher, "name:elixir", [])
+
+  """
+  @spec set_field_aliases(t(), %{String.t() => String.t()}) ::
+          :ok | {:error, :not_found | :invalid_argument | :search_error, String.t()}
+  def set_field_aliases(searcher, aliases) when is_map(aliases) do
+    case Native.searcher_set_field_aliases(searcher, aliases) do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Sets how `search_query/4` parses query strings on this searcher.
+
+  Apps that search with the same operator, leniency and typo tolerance from
+  many call sites can set them once here instead of on every call. Each
+  search can still override a setting with the option of the same name.
+  Options left out go back to their defaults. The `:filters` of a search are
+  parsed with the same settings as its query string, so `tag:a tag:b` means the
+  same in both.
+
+  Each searcher has its own settings, so set them again on a searcher created
+  after a commit.
+
+  ## Parameters
+
+    * `searcher` - The searcher to configure
+    * `opts` - Keyword list of options:
+      - `:default_operator` - `:or` or `:and`, joining the clauses of a query
+        string that name no operator, so `"elixir phoenix"` matches documents
+        with either or both terms (default: `:or`)
+      - `:lenient` - When `true`, parts of a query string that cannot be parsed
+        or searched, such as an unclosed quote or an unknown field, match
+        nothing instead of failing with `:parse_error` (default: `false`)
+      - `:fuzzy_distance` - Levenshtein distance from 0 to 2 within which the
+        terms of a query string match tokenized text fields (default: `0`,
+        exact). Phrases and keyword fields are always matched exactly
+
+  ## Returns
+
+    * `:ok` - The settings were set
+    * `{:error, :invalid_argument, message}` - An option is out of range
+
+  ## Examples
+
+      :ok = Muninn.Searcher.set_query_defaults(searcher,
+        default_operator: :and,
+        fuzzy_distance: 1
+      )
+
+      # Matches documents containing both "elixir" and "phoenix", or near misses
+      {:ok, results} = Muninn.Searcher.search_query(searcher, "elixr phoenix", ["title"])
+
+      # Any of the terms, for this call only
+      {:ok, results} =
+        Muninn.Searcher.search_query(searcher, "elixir phoenix", ["title"],
+          default_operator: :or
+        )
+
+  """
+  @spec set_query_defaults(t(), keyword()) ::
+          :ok | {:error, :invalid_argument | :search_error, String.t()}
+  def set_query_defaults(searcher, opts \\ []) when is_list(opts) do
+    result =
+      Native.searcher_set_query_defaults(
+        searcher,
+        opts |> Keyword.put_new(:default_operator, :or) |> default_operator(),
+        Keyword.get(opts, :lenient, false),
+        Keyword.get(opts, :fuzzy_distance, 0)
+      )
+
+    case result do
+      {:ok, _} -> :ok
+      error -> error
+    end
+  end
+
+  @doc """
+  Fetches the document at a `"doc_address"` returned by `search_query/4`.
+
+  Supports "open this result" flows without running the query again. The hit has
+  the same `"doc"`, and `"_id"` when the schema has an id field, as search hits,
+  but no `"score"`.
+
+  Addresses are internal to the index segments a searcher sees and change when
+  segments merge, so only pass an address to the searcher whose search returned
+  it. Do not store addresses as document ids; use the schema's id field instead.
+
+  ## Returns
+
+    * `{:ok, hit}` - The document's hit map
+    * `{:error, :not_found, message}` - No live document exists at the address
+
+  ## Examples
+
+      {:ok, %{"hits" => [%{"doc_address" => address} | _]}} =
+        Muninn.Searcher.search_query(searcher, "elixir", ["title"], doc_addresses: true)
+
+      {:ok, %{"doc" => doc}} = Muninn.Searcher.get_doc(searcher, address)
+
+  """
+  @spec get_doc(t(), {non_neg_integer(), non_neg_integer()}) :: {:ok, map()} | error()
+  def get_doc(searcher, {segment_ord, doc_id} = doc_address)
+      when is_integer(segment_ord) and is_integer(doc_id) do
+    Native.searcher_get_doc(searcher, doc_address)
+  end
+
+  @doc """
+  Scores the documents at the given addresses under a query.
+
+  For offline relevance evaluation, such as NDCG over a labeled set of
+  documents, which needs the score of specific known documents rather than the
+  top hits. A document the query does not match scores `0.0`. Scores are the
+  same as the `"score"` of the hits of `search_query/4` for the same query.
+
+  Get the addresses from the `"doc_address"` of search hits, see
+  `search_query/4`. Like `get_doc/2`, only pass an address to the searcher whose
+  search returned it.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `doc_addresses` - `{segment_ord, doc_id}` addresses from search hits
+    * `opts` - Keyword list of options:
+      - `:explain` - When `true`, each result also gets an `"explanation"`: a
+        map of the `"value"`, `"description"` and nested `"details"` that
+        add up to the score, or `nil` when the document does not match
+        (default: `false`)
+
+  ## Returns
+
+    * `{:ok, results}` - One map per address, in the order given, with the
+      `"doc_address"` and its `"score"`
+    * `{:error, :not_found, message}` - No live document exists at an address
+    * `{:error, kind, message}` - The query failed to parse
+
+  ## Examples
+
+      {:ok, %{"hits" => hits}} =
+        Muninn.Searcher.search_query(searcher, "*", [], limit: 100, doc_addresses: true)
+
+      labeled = Enum.map(hits, & &1["doc_address"])
+
+      {:ok, [%{"doc_address" => _, "score" => score} | _]} =
+        Muninn.Searcher.score_docs(searcher, "elixir phoenix", ["title"], labeled)
+
+  """
+  @spec score_docs(
+          t(),
+          String.t(),
+          list(String.t()),
+          [{non_neg_integer(), non_neg_integer()}],
+          keyword()
+        ) :: {:ok, [map()]} | error()
+  def score_docs(searcher, query_string, default_fields, doc_addresses, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) and is_list(doc_addresses) do
+    explain = Keyword.get(opts, :explain, false)
+
+    Native.searcher_score_docs(searcher, query_string, default_fields, doc_addresses, explain)
+  end
+
+  @doc """
+  Returns the most frequent terms of a text field within one document.
+
+  Reads the term frequencies Tantivy already indexed for the document at a
+  `"doc_address"` returned by `search_query/4`, for tag clouds, keyword
+  extraction or "most prominent terms in this document" features. Terms are
+  the field's analyzed terms, so they are lowercased, and stemmed with a
+  stemming tokenizer. The field need not be stored.
+
+  Every term the field has in the document's segment is looked up, so the cost
+  grows with the vocabulary of the field rather than the size of the document.
+  Like `get_doc/2`, only pass an address to the searcher whose search returned it.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `doc_address` - A `{segment_ord, doc_id}` address from a search hit
+    * `field_name` - A text field; keyword fields index no frequencies
+    * `opts` - Keyword list of options:
+      - `:top_n` - Number of terms to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, terms}` - Maps with `"term"` and `"freq"` keys, by descending
+      frequency and then by term
+    * `{:error, :not_found, message}` - The field does not exist or no live
+      document exists at the address
+    * `{:error, :invalid_argument, message}` - The field is not a text field
+      indexing term frequencies
+
+  ## Examples
+
+      {:ok, %{"hits" => [%{"doc_address" => address} | _]}} =
+        Muninn.Searcher.search_query(searcher, "elixir", ["body"], doc_addresses: true)
+
+      {:ok, [%{"term" => "elixir", "freq" => 4} | _]} =
+        Muninn.Searcher.doc_term_freqs(searcher, address, "body", top_n: 5)
+
+  """
+  @spec doc_term_freqs(t(), {non_neg_integer(), non_neg_integer()}, String.t(), keyword()) ::
+          {:ok, [map()]} | error()
+  def doc_term_freqs(searcher, {segment_ord, doc_id} = doc_address, field_name, opts \\ [])
+      when is_integer(segment_ord) and is_integer(doc_id) and is_binary(field_name) do
+    top_n = Keyword.get(opts, :top_n, 10)
+
+    Native.searcher_doc_term_freqs(searcher, doc_address, field_name, top_n)
+  end
+
+  @doc """
+  Lists the terms of a field with the number of documents holding each.
+
+  Walks the term dictionary Tantivy keeps per segment, for building facet
+  lists or autocomplete dictionaries offline and for content analysis. Terms
+  are the field's indexed terms, so a text field's are lowercased (and
+  stemmed with a stemming tokenizer) and `:prefix` is matched against those.
+  Use `:prefix` and `:limit` to keep the walk bounded on large dictionaries.
+
+  Document frequencies are summed over the segments and, like the statistics
+  used for scoring, still count deleted documents until their segment is merged.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `field_name` - An indexed text or keyword field
+    * `opts` - Keyword list of options:
+      - `:prefix` - Only list terms starting with it (default: `""`, every term)
+      - `:limit` - Maximum number of terms (default: 100)
+
+  ## Returns
+
+    * `{:ok, terms}` - `{term, doc_freq}` tuples in ascending term order
+    * `{:error, :not_found, message}` - The field does not exist
+    * `{:error, :invalid_argument, message}` - The field is not an indexed text
+      or keyword field, or the limit is 0 or above the reader's maximum
+
+  ## Examples
+
+      {:ok, [{"elixir", 12}, {"elm", 3}]} =
+        Muninn.Searcher.term_dict(searcher, "tags", prefix: "el", limit: 50)
+
+  """
+  @spec term_dict(t(), String.t(), keyword()) ::
+          {:ok, [{String.t(), non_neg_integer()}]} | error()
+  def term_dict(searcher, field_name, opts \\ []) when is_binary(field_name) do
+    prefix = Keyword.get(opts, :prefix, "")
+    limit = Keyword.get(opts, :limit, 100)
+
+    Native.searcher_term_dict(searcher, field_name, prefix, limit)
+  end
+
+  @doc """
+  Executes a search and returns one page of hits with the exact number of matches.
+
+  Where the `"total_hits"` of `search_query/4` only counts the returned hits,
+  `"total"` counts every document matching the query. The page and the count
+  are gathered in a single pass over the same searcher, so they always agree,
+  which is what a paginated result list needs.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of hits in the page (default: 10)
+      - `:offset` - Number of top hits to skip (default: 0)
+      - `:tiebreak` - `{field, :asc | :desc}` ordering hits with equal scores, as
+        in `search_query/4`. Use it to keep pages stable across merges
+
+  ## Returns
+
+    * `{:ok, %{"total" => total, "hits" => hits}}` - Hits in the same format as
+      `search_query/4`
+    * `{:error, kind, message}` - Search or parse failed
+
+  ## Examples
+
+      {:ok, %{"total" => total, "hits" => hits}} =
+        Muninn.Searcher.search_paged(searcher, "elixir", ["title"], limit: 20, offset: 40)
+
+      pages = div(total + 19, 20)
+
+  """
+  @spec search_paged(t(), String.t(), list(String.t()), keyword()) :: {:ok, map()} | error()
+  def search_paged(searcher, query_string, default_fields, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) do
+    Native.searcher_search_paged(
+      searcher,
+      query_string,
+      default_fields,
+      tiebreak(opts),
+      Keyword.get(opts, :limit, 10),
+      Keyword.get(opts, :offset, 0)
+    )
+  end
+
+  @doc """
+  Executes a search and returns the top hits of each value of a field.
+
+  For "top 3 per category" result pages: matching documents are partitioned
+  by the value of `group_field`, and each group keeps its best
+  `:hits_per_group` hits. Groups are ordered by the score of their best hit and
+  only the first `:num_groups` are returned. The group field must be a u64, i64
+  or bool field declared with `fast: true`. Documents without a value for it
+  belong to no group and are left out.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `group_field` - The fast field whose values define the groups
+    * `opts` - Keyword list of options:
+      - `:hits_per_group` - Maximum number of hits per group (default: 3)
+      - `:num_groups` - Maximum number of groups to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, %{"groups" => groups}}` - Each group is a map with the group
+      `"value"` and its `"hits"`, in the same format as `search_query/4`
+    * `{:error, kind, message}` - Search or parse failed, or the group field is
+      missing or not a u64, i64 or bool fast field
+
+  ## Examples
+
+      {:ok, %{"groups" => groups}} =
+        Muninn.Searcher.search_grouped(searcher, "laptop", ["title"], "category_id",
+          hits_per_group: 3
+        )
+
+      for %{"value" => category_id, "hits" => hits} <- groups do
+        {category_id, Enum.map(hits, & &1["doc"]["title"])}
+      end
+
+  """
+  @spec search_grouped(t(), String.t(), list(String.t()), String.t(), keyword()) ::
+          {:ok, map()} | error()
+  def search_grouped(searcher, query_string, default_fields, group_field, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) and is_binary(group_field) do
+    Native.searcher_search_grouped(
+      searcher,
+      query_string,
+      default_fields,
+      group_field,
+      Keyword.get(opts, :hits_per_group, 3),
+      Keyword.get(opts, :num_groups, 10)
+    )
+  end
+
+  @doc """
+  Executes a search and returns the hits with the largest or smallest values of
+  a field, instead of the most relevant ones.
+
+  For "latest N" listings such as a feed ordered by timestamp. Segments are
+  searched starting from the one holding the best value, and once `:limit` hits
+  are collected, a segment whose best value cannot beat the worst of them is
+  skipped without reading its postings. Tantivy 0.25 cannot sort the documents
+  of an index, so the skipping relies on segments covering distinct value
+  ranges, which is the case for values that grow as documents are added, like
+  creation times. Otherwise every segment is searched and the results are the
+  same, only slower.
+
+  The sort field must be a u64, i64 or bool field declared with `fast: true`.
+  Documents without a value for it come after every document with one. Hits
+  with equal values are ordered by internal document address.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `sort_field` - The fast field to order hits by
+    * `opts` - Keyword list of options:
+      - `:order` - `:desc` (default) for the largest values first, or `:asc`
+      - `:limit` - Maximum number of results (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - A map with `"total_hits"`, the number of returned hits,
+      `"hits"` in the same format as `search_query/4` with an extra
+      `"sort_value"`, `nil` when the document has no value, and the
+      `"segments_searched"` and `"segments_skipped"` counts. Hits are not scored,
+      so every `"score"` is `1.0`
+    * `{:error, kind, message}` - Search or parse failed, or the sort field is
+      missing or not a u64, i64 or bool fast field
+
+  ## Examples
+
+      {:ok, %{"hits" => hits}} =
+        Muninn.Searcher.search_sorted(searcher, "*", ["title"], "created_at", limit: 20)
+
+  """
+  @spec search_sorted(t(), String.t(), list(String.t()), String.t(), keyword()) ::
+          {:ok, map()} | error()
+  def search_sorted(searcher, query_string, default_fields, sort_field, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) and is_binary(sort_field) do
+    Native.searcher_search_sorted(
+      searcher,
+      query_string,
+      default_fields,
+      sort_field,
+      to_string(Keyword.get(opts, :order, :desc)),
+      Keyword.get(opts, :limit, 10)
+    )
+  end
+
+  @doc """
+  Executes a search that favors documents whose field value is recent.
+
+  Each hit's score is multiplied by a decay factor computed from how far the
+  value of `decay_field` lies before `:origin`, by default the current Unix
+  time in seconds. The factor is `1.0` at the origin and `0.5` at `scale`
+  before it, so with timestamps in seconds and a scale of one day, a day-old
+  article needs twice the relevance of a fresh one to rank level with it.
+  Values after the origin are not decayed, and documents without a value get
+  a factor of `0.0`.
+
+  The decay is applied while collecting hits, so the results are the top hits
+  of the decayed scores. Decaying the scores of a regular search afterwards
+  would only reorder its top hits and miss recent documents ranked below them.
+
+  The decay field must be a u64 or i64 field declared with `fast: true`.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `decay_field` - The fast field holding the value to decay on, such as a
+      publication time in Unix seconds
+    * `scale` - Distance from the origin at which scores are halved, in the
+      field's unit
+    * `opts` - Keyword list of options:
+      - `:function` - `:exp` (default) halves the score again every `scale`, while
+        `:linear` falls in a straight line to `0.0` at twice the `scale`
+      - `:origin` - Value at which documents keep their whole score (default:
+        the current Unix time in seconds). Pass it for fields in another unit,
+        such as milliseconds, and for reproducible rankings
+      - `:limit` - Maximum number of results to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - Results in the same format as `search_query/4`, with
+      the decayed scores
+    * `{:error, kind, message}` - Search or parse failed, or the decay field or
+      scale was rejected
+
+  ## Examples
+
+      # News search where relevance halves every 12 hours
+      {:ok, results} =
+        Muninn.Searcher.search_with_decay(searcher, "election", ["title", "body"],
+          "published_at", 12 * 3600
+        )
+
+  """
+  @spec search_with_decay(t(), String.t(), list(String.t()), String.t(), number(), keyword()) ::
+          {:ok, map()} | error()
+  def search_with_decay(searcher, query_string, default_fields, decay_field, scale, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) and is_binary(decay_field) and
+             is_number(scale) do
+    Native.searcher_search_with_decay(
+      searcher,
+      query_string,
+      default_fields,
+      decay_field,
+      scale / 1,
+      opts |> Keyword.get(:function, :exp) |> to_string(),
+      Keyword.get(opts, :origin),
+      Keyword.get(opts, :limit, 10)
+    )
+  end
+
+  @doc """
+  Executes a search restricted to documents located within a bounding box.
+
+  Muninn has no geo field type, but latitude and longitude can be stored as two
+  f64 fields declared with `fast: true`. The box becomes a range filter on each
+  of them, combined with the query in a boolean query. The filters only drop
+  documents outside the box and leave the ranking of the query unchanged. Use
+  `"*"` as the query to return every document in the box.
+
+  Coordinates are in degrees. A box whose `:min_lon` is greater than its
+  `:max_lon` crosses the antimeridian, so `min_lon: 170, max_lon: -170` covers
+  the 20 degrees around longitude 180. Documents missing either coordinate never
+  match.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `{lat_field, lon_field}` - The f64 fast fields holding latitude and longitude
+    * `bbox` - Map with the `:min_lat`, `:max_lat`, `:min_lon` and `:max_lon`
+      bounds, all inclusive. Latitudes must lie between -90 and 90 and longitudes
+      between -180 and 180
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - Results in the same format as `search_query/4`
+    * `{:error, kind, message}` - Search or parse failed, or a field or bound was
+      rejected
+
+  ## Examples
+
+      # Coffee shops in central London
+      {:ok, results} =
+        Muninn.Searcher.search_bbox(searcher, "coffee", ["name"], {"lat", "lon"}, %{
+          min_lat: 51.49,
+          max_lat: 51.53,
+          min_lon: -0.16,
+          max_lon: -0.07
+        })
+
+  """
+  @spec search_bbox(
+          t(),
+          String.t(),
+          list(String.t()),
+          {String.t(), String.t()},
+          %{min_lat: number(), max_lat: number(), min_lon: number(), max_lon: number()},
+          keyword()
+        ) :: {:ok, map()} | error()
+  def search_bbox(
+        searcher,
+        query_string,
+        default_fields,
+        {lat_field, lon_field},
+        %{min_lat: min_lat, max_lat: max_lat, min_lon: min_lon, max_lon: max_lon},
+        opts \\ []
+      )
+      when is_binary(query_string) and is_list(default_fields) and is_binary(lat_field) and
+             is_binary(lon_field) do
+    Native.searcher_search_bbox(
+      searcher,
+      lat_field,
+      lon_field,
+      min_lat / 1,
+      max_lat / 1,
+      min_lon / 1,
+      max_lon / 1,
+      query_string,
+      default_fields,
+      Keyword.get(opts, :limit, 10)
+    )
+  end
+
+  @doc """
+  Searches one query string across several fields, weighting each field.
+
+  This is the usual site search box: unqualified terms are searched in every
+  field of `field_boosts`, and a match in a field has its score multiplied by
+  that field's boost. The query string supports the same syntax as
+  `search_query/4`, and clauses naming a field such as `tags:elixir` still work.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `field_boosts` - Map of field name to boost, a non-negative number
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - Search results in the same format as `search_query/4`
+    * `{:error, kind, message}` - Search or parse failed, a field does not exist,
+      a boost is negative or `field_boosts` is empty
+
+  ## Examples
+
+      {:ok, results} =
+        Muninn.Searcher.search_multi_field(searcher, "elixir phoenix", %{
+          "title" => 3,
+          "tags" => 2,
+          "body" => 1
+        })
+
+  """
+  @spec search_multi_field(t(), String.t(), %{String.t() => number()}, keyword()) ::
+          {:ok, map()} | error()
+  def search_multi_field(searcher, query_string, field_boosts, opts \\ [])
+      when is_binary(query_string) and is_map(field_boosts) do
+    field_boosts = Enum.map(field_boosts, fn {field, boost} -> {field, boost / 1} end)
+    limit = Keyword.get(opts, :limit, 10)
+
+    Native.searcher_search_multi_field(searcher, query_string, field_boosts, limit)
+  end
+
+  @doc """
+  Searches terms, matching documents that hold at least `min_match` of them.
+
+  Like Solr's `mm` parameter: out of M optional terms, a document must match K.
+  This sits between an OR of the terms, which matches any single one and can
+  flood the results, and an AND, which drops documents missing just one term.
+  Documents matching more terms still score higher.
+
+  Each term counts once, whichever of `field_names` it is found in. Terms are
+  analyzed with each field's tokenizer, so "Running" matches a stemmed field's
+  "run", and must analyze into a single term.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `field_names` - Indexed text or keyword fields to search the terms in
+    * `terms` - The terms, one word each
+    * `min_match` - How many of the terms a document must hold, from 1 to the
+      number of terms
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - Search results in the same format as `search_query/4`
+    * `{:error, kind, message}` - A field does not exist or is not an indexed
+      text field, a term does not analyze into a single term, or `min_match` is
+      out of range
+
+  ## Examples
+
+      # At least 2 of the 3 terms, in the title or the body
+      {:ok, results} =
+        Muninn.Searcher.search_min_should_match(
+          searcher,
+          ["title", "body"],
+          ["elixir", "phoenix", "liveview"],
+          2
+        )
+
+  """
+  @spec search_min_should_match(t(), [String.t()], [String.t()], pos_integer(), keyword()) ::
+          {:ok, map()} | error()
+  def search_min_should_match(searcher, field_names, terms, min_match, opts \\ [])
+      when is_list(field_names) and is_list(terms) and is_integer(min_match) and
+             min_match > 0 do
+    limit = Keyword.get(opts, :limit, 10)
+
+    Native.searcher_search_min_should_match(searcher, field_names, terms, min_match, limit)
+  end
+
+  @doc """
+  Searches text fields and their exact companions, ranking exact-form matches first.
+
+  Each field of `fields` must have been added with `exact: true`, see "Exact
+  matches" in `Muninn.Schema`. The query string is searched in both the field and
+  its `"<name>_exact"` companion, like `search_multi_field/4`. A document matching
+  a term in its exact form matches both fields and adds up both scores, while a
+  document only matching a stemmed form, such as "run" for "running", is only
+  scored by the stemmed field.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `fields` - List of text fields added with `exact: true`
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+      - `:exact_boost` - Boost of the exact companions, relative to a boost of 1
+        for the stemmed fields (default: 2)
+
+  ## Returns
+
+    * `{:ok, results}` - Search results in the same format as `search_query/4`
+    * `{:error, kind, message}` - Search or parse failed, `fields` is empty, or a
+      field or its exact companion does not exist
+
+  ## Examples
+
+      {:ok, results} =
+        Muninn.Searcher.search_exact_boosted(searcher, "running shoes", ["title"],
+          exact_boost: 3
+        )
+
+  """
+  @spec search_exact_boosted(t(), String.t(), [String.t()], keyword()) ::
+          {:ok, map()} | error()
+  def search_exact_boosted(searcher, query_string, fields, opts \\ [])
+      when is_binary(query_string) and is_list(fields) do
+    exact_boost = Keyword.get(opts, :exact_boost, 2)
+
+    field_boosts =
+      fields
+      |> Enum.flat_map(fn field -> [{field, 1}, {field <> "_exact", exact_boost}] end)
+      |> Map.new()
+
+    search_multi_field(searcher, query_string, field_boosts, opts)
   end
 
   @doc """
@@ -178,11 +1125,30 @@ defmodule Muninn.Searcher do
     * `opts` - Keyword list of options:
       - `:limit` - Maximum number of results to return (default: 10)
       - `:max_snippet_chars` - Maximum characters per snippet (default: 150)
+      - `:full_text_threshold` - Snippet fields whose value has at most this many
+        characters are returned whole, with every matching term highlighted,
+        instead of as a single best fragment (default: `0`, disabled). Useful for
+        titles and short messages that are displayed in full anyway
+      - `:context_words` - Number of whole words to show on each side of the best
+        match, instead of cutting the snippet at `:max_snippet_chars` characters
+        (default: `nil`, disabled). The snippet is centered on the match whose
+        surrounding words hold the most distinct query terms, rarer terms counting
+        more, and never ends in the middle of a word. Words are separated by
+        whitespace
+      - `:snippet_tokenizers` - Map of snippet field name to the name of a tokenizer
+        registered on the index, used instead of the field's own tokenizer to find
+        highlight boundaries (default: `%{}`). With `"default"` on an `"en_stem"`
+        field, a search for "run" still matches "running" but only highlights "run"
+      - `:best_snippet` - Whether to return only the most relevant snippet of each
+        hit, across all snippet fields, instead of one snippet per field (default:
+        `false`). See "Best snippet" below
+      - `:scoring` - `:bm25` for relevance scoring (default) or `:const` to give
+        every match a score of `1.0`, which skips relevance computation for pure filtering
 
   ## Returns
 
     * `{:ok, results}` - Search results with snippets
-    * `{:error, reason}` - Search or parse failed
+    * `{:error, kind, message}` - Search or parse failed
 
   Result format includes an additional `"snippets"` map with HTML-highlighted snippets:
 
@@ -200,6 +1166,26 @@ defmodule Muninn.Searcher do
         ]
       }
 
+  ## Best snippet
+
+  A results list usually shows a single snippet per hit. With `best_snippet: true`,
+  the best fragment of every snippet field is scored by the query terms it holds,
+  rarer terms counting more, and only the highest scoring one is highlighted. It
+  is returned under `"best_snippet"`, in place of the `"snippets"` map, along with
+  the field it came from. Ties go to the field listed first, and `"best_snippet"`
+  is `nil` when no snippet field holds a query term:
+
+      %{
+        "score" => 3.14,
+        "doc" => %{"title" => "...", "content" => "..."},
+        "best_snippet" => %{
+          "field" => "content",
+          "snippet" => "Learn about <b>elixir</b> and <b>phoenix</b>..."
+        }
+      }
+
+  `:full_text_threshold` and `:context_words` still shape the returned snippet.
+
   ## Examples
 
       # Search with content snippets
@@ -215,44 +1201,293 @@ defmodule Muninn.Searcher do
         IO.puts("Snippet: \#{hit["snippets"]["content"]}")
       end
 
-      # Search with custom snippet length
-      {:ok, results} = Muninn.Searcher.search_with_snippets(
-        searcher,
-        "functional programming",
-        ["content"],
-        ["content"],
-        max_snippet_chars: 200
-      )
+      # Search with custom snippet length
+      {:ok, results} = Muninn.Searcher.search_with_snippets(
+        searcher,
+        "functional programming",
+        ["content"],
+        ["content"],
+        max_snippet_chars: 200
+      )
+
+      # Multiple snippet fields
+      {:ok, results} = Muninn.Searcher.search_with_snippets(
+        searcher,
+        "elixir web",
+        ["title", "content"],
+        ["title", "content"]
+      )
+
+      # Show five words on each side of the match
+      {:ok, results} = Muninn.Searcher.search_with_snippets(
+        searcher,
+        "phoenix",
+        ["content"],
+        ["content"],
+        context_words: 5
+      )
+
+      # Show short titles in full, highlighting every match
+      {:ok, results} = Muninn.Searcher.search_with_snippets(
+        searcher,
+        "elixir",
+        ["title"],
+        ["title"],
+        full_text_threshold: 200
+      )
+
+      # One snippet per hit, from whichever field matches best
+      {:ok, results} = Muninn.Searcher.search_with_snippets(
+        searcher,
+        "elixir web",
+        ["title", "content"],
+        ["title", "content"],
+        best_snippet: true
+      )
+
+  """
+  @spec search_with_snippets(t(), String.t(), list(String.t()), list(String.t()), keyword()) ::
+          {:ok, map()} | error()
+  def search_with_snippets(
+        searcher,
+        query_string,
+        default_fields,
+        snippet_fields,
+        opts \\ []
+      )
+      when is_binary(query_string) and is_list(default_fields) and is_list(snippet_fields) do
+    limit = Keyword.get(opts, :limit, 10)
+    max_snippet_chars = Keyword.get(opts, :max_snippet_chars, 150)
+    full_text_threshold = Keyword.get(opts, :full_text_threshold, 0)
+    context_words = Keyword.get(opts, :context_words) || 0
+    snippet_tokenizers = opts |> Keyword.get(:snippet_tokenizers, %{}) |> Enum.to_list()
+    best_snippet = Keyword.get(opts, :best_snippet, false)
+
+    Native.searcher_search_with_snippets(
+      searcher,
+      query_string,
+      default_fields,
+      snippet_fields,
+      max_snippet_chars,
+      full_text_threshold,
+      context_words,
+      snippet_tokenizers,
+      best_snippet,
+      scoring(opts),
+      limit
+    )
+  end
+
+  @doc """
+  Executes a search and returns fast field values instead of stored documents.
+
+  Values are read straight from the columnar fast fields of each segment, so no
+  stored document is loaded for the hits. This is much cheaper than
+  `search_query/4` when only an identifier is needed, for example to hydrate
+  records from a database.
+
+  Requested fields must be numeric or bool fields declared with `fast: true`.
+  Documents without a value for a field simply omit it from `"fields"`.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `fast_fields` - List of fast field names to return for each hit
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+      - `:scoring` - `:bm25` for relevance scoring (default) or `:const` to give
+        every match a score of `1.0`, which skips relevance computation for pure filtering
+
+  ## Returns
+
+    * `{:ok, results}` - Results where each hit has `"score"` and `"fields"`
+    * `{:error, kind, message}` - Search failed or a field is not a fast field
+
+  ## Examples
+
+      schema =
+        Muninn.Schema.new()
+        |> Muninn.Schema.add_text_field("title")
+        |> Muninn.Schema.add_u64_field("id", fast: true)
+
+      {:ok, results} = Muninn.Searcher.search_fast_fields(searcher, "elixir", ["title"], ["id"])
+
+      ids = Enum.map(results["hits"], & &1["fields"]["id"])
+
+  """
+  @spec search_fast_fields(t(), String.t(), list(String.t()), list(String.t()), keyword()) ::
+          {:ok, map()} | error()
+  def search_fast_fields(searcher, query_string, default_fields, fast_fields, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) and is_list(fast_fields) do
+    limit = Keyword.get(opts, :limit, 10)
+
+    Native.searcher_search_fast_fields(
+      searcher,
+      query_string,
+      default_fields,
+      fast_fields,
+      scoring(opts),
+      limit
+    )
+  end
+
+  @doc """
+  Executes a search and runs several named aggregations over the matching documents.
+
+  The aggregations are collected together in a single pass over the matches,
+  which is much cheaper than one search per aggregation. `aggs` maps each
+  aggregation name to a request in Elasticsearch's shape, such as
+  `%{"terms" => %{"field" => "brand_id", "size" => 5}}`. The supported kinds are
+  the bucket aggregations `"terms"`, `"histogram"`, `"date_histogram"` and
+  `"range"`, and the metric aggregations `"avg"`, `"min"`, `"max"`, `"sum"`,
+  `"value_count"`, `"stats"`, `"extended_stats"`, `"percentiles"`,
+  `"cardinality"` and `"top_hits"`. Every field they read must be a u64, i64,
+  f64 or bool field declared with `fast: true`.
+
+  A bucket aggregation can nest sub-aggregations under `"aggs"`, which are
+  computed over the documents of each of its buckets and returned in each
+  bucket under their name. Metric aggregations cannot have sub-aggregations.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax, `"*"` for all documents
+    * `default_fields` - List of field names to search when no field is specified
+    * `aggs` - Map of aggregation name to aggregation request
+
+  ## Returns
+
+    * `{:ok, results}` - Map of aggregation name to its result, as Elasticsearch
+      returns it: bucket aggregations have a `"buckets"` list of maps with
+      `"key"` and `"doc_count"`, metric aggregations a `"value"` or, for
+      `"stats"`, `"count"`, `"sum"`, `"min"`, `"max"` and `"avg"`
+    * `{:error, kind, message}` - Search or parse failed, a request is
+      malformed, or a field is missing or not a fast field
+
+  ## Examples
+
+      {:ok, %{"brands" => brands, "price" => price}} =
+        Muninn.Searcher.aggregate(searcher, "laptop", ["title"], %{
+          "brands" => %{"terms" => %{"field" => "brand_id", "size" => 5}},
+          "price" => %{"stats" => %{"field" => "price"}}
+        })
+
+      for %{"key" => brand_id, "doc_count" => count} <- brands["buckets"] do
+        {brand_id, count}
+      end
+
+      price["avg"]
+
+      # Top 5 brands, with the average price of each
+      {:ok, %{"brands" => %{"buckets" => buckets}}} =
+        Muninn.Searcher.aggregate(searcher, "*", [], %{
+          "brands" => %{
+            "terms" => %{"field" => "brand_id", "size" => 5},
+            "aggs" => %{"avg_price" => %{"avg" => %{"field" => "price"}}}
+          }
+        })
+
+      for %{"key" => brand_id, "avg_price" => %{"value" => avg}} <- buckets do
+        {brand_id, avg}
+      end
+
+  """
+  @spec aggregate(t(), String.t(), list(String.t()), map()) :: {:ok, map()} | error()
+  def aggregate(searcher, query_string, default_fields, aggs)
+      when is_binary(query_string) and is_list(default_fields) and is_map(aggs) do
+    case Jason.encode(aggs) do
+      {:ok, aggs_json} ->
+        Native.searcher_aggregate(searcher, query_string, default_fields, aggs_json)
+
+      {:error, error} ->
+        {:error, :invalid_argument, "Invalid aggregation request: #{Exception.message(error)}"}
+    end
+  end
+
+  @doc """
+  Estimates the number of distinct values of a field among the matching documents.
+
+  Counting distinct values exactly needs memory proportional to their number,
+  so the count is estimated with HyperLogLog, like Elasticsearch's
+  `cardinality` aggregation: it is exact for small counts and typically within
+  a few percent above that. The field must be a u64, i64, f64 or bool field
+  declared with `fast: true`. Use `aggregate/4` to compute it next to other
+  aggregations in one pass.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax, `"*"` for all documents
+    * `default_fields` - List of field names to search when no field is specified
+    * `field_name` - The fast field whose distinct values are counted
+
+  ## Returns
+
+    * `{:ok, count}` - The estimated number of distinct values, `0` when nothing matches
+    * `{:error, kind, message}` - Search or parse failed, or the field is missing
+      or not a fast field
+
+  ## Examples
+
+      # Distinct users who triggered a checkout event
+      {:ok, users} = Muninn.Searcher.cardinality(searcher, "event:checkout", [], "user_id")
+
+  """
+  @spec cardinality(t(), String.t(), list(String.t()), String.t()) ::
+          {:ok, non_neg_integer()} | error()
+  def cardinality(searcher, query_string, default_fields, field_name)
+      when is_binary(query_string) and is_list(default_fields) and is_binary(field_name) do
+    Native.searcher_cardinality(searcher, query_string, default_fields, field_name)
+  end
+
+  @doc """
+  Executes a search and returns only the id and score of each hit.
+
+  Meant for search-then-hydrate setups where the documents live in a database:
+  no `"doc"` map is built for the hits. The id is read from the field's fast
+  column when it is a u64 or i64 field declared with `fast: true`, which skips
+  the stored documents entirely. Any other id field must be stored.
+
+  Hits are ordered by score. A hit whose document has no value for the id field
+  fails the search with `:not_found`.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `opts` - Keyword list of options:
+      - `:id_field` - Field holding the ids (default: the schema's id field, see
+        "Id field" in `Muninn.Schema`)
+      - `:limit` - Maximum number of results to return (default: 10)
+      - `:offset` - Number of top hits to skip, for pagination (default: 0)
+
+  ## Returns
+
+    * `{:ok, results}` - Results where each hit has `"_id"` and `"score"`
+    * `{:error, kind, message}` - Search failed, or the id field is missing or
+      neither stored nor fast
 
-      # Multiple snippet fields
-      {:ok, results} = Muninn.Searcher.search_with_snippets(
-        searcher,
-        "elixir web",
-        ["title", "content"],
-        ["title", "content"]
-      )
+  ## Examples
 
-  """
-  @spec search_with_snippets(t(), String.t(), list(String.t()), list(String.t()), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
-  def search_with_snippets(
-        searcher,
-        query_string,
-        default_fields,
-        snippet_fields,
-        opts \\ []
-      )
-      when is_binary(query_string) and is_list(default_fields) and is_list(snippet_fields) do
-    limit = Keyword.get(opts, :limit, 10)
-    max_snippet_chars = Keyword.get(opts, :max_snippet_chars, 150)
+      {:ok, results} =
+        Muninn.Searcher.search_ids(searcher, "elixir", ["title"], id_field: "post_id")
 
-    Native.searcher_search_with_snippets(
+      ids = Enum.map(results["hits"], & &1["_id"])
+
+  """
+  @spec search_ids(t(), String.t(), list(String.t()), keyword()) :: {:ok, map()} | error()
+  def search_ids(searcher, query_string, default_fields, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) do
+    Native.searcher_search_ids(
       searcher,
       query_string,
       default_fields,
-      snippet_fields,
-      max_snippet_chars,
-      limit
+      Keyword.get(opts, :id_field),
+      Keyword.get(opts, :limit, 10),
+      Keyword.get(opts, :offset, 0)
     )
   end
 
@@ -269,11 +1504,14 @@ defmodule Muninn.Searcher do
     * `prefix` - The prefix string to search for
     * `opts` - Keyword list of options:
       - `:limit` - Maximum number of results to return (default: 10)
+      - `:lowercase` - Whether to lowercase the prefix before matching. By default
+        this follows the field's tokenizer: prefixes are lowercased for lowercasing
+        tokenizers such as `default`, and left untouched for `raw` and `whitespace`.
 
   ## Returns
 
     * `{:ok, results}` - Search results with total_hits and hits
-    * `{:error, reason}` - Search failed
+    * `{:error, kind, message}` - Search failed
 
   ## Examples
 
@@ -307,12 +1545,68 @@ defmodule Muninn.Searcher do
 
   """
   @spec search_prefix(t(), String.t(), String.t(), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_prefix(searcher, field_name, prefix, opts \\ [])
       when is_binary(field_name) and is_binary(prefix) do
     limit = Keyword.get(opts, :limit, 10)
+    lowercase = Keyword.get(opts, :lowercase)
+
+    Native.searcher_search_prefix(searcher, field_name, prefix, lowercase, limit)
+  end
+
+  @doc """
+  Performs a wildcard search on the terms of a text field.
+
+  The pattern uses glob syntax: `*` matches any number of characters and `?`
+  matches exactly one. Every other character, including regex syntax such as
+  `.` or `+`, is matched literally. The pattern must match a whole term, so
+  `elix*` finds "elixir" but `lix*` does not. Since terms are single words,
+  a pattern containing whitespace is rejected.
+
+  As with `search_prefix/4`, the pattern is lowercased for fields whose
+  tokenizer lowercases terms, and left untouched for `raw` and `whitespace`.
+
+  ## Leading wildcards
+
+  A pattern such as `*bar` or `?oo` cannot use the term dictionary's sort
+  order, so it visits every term of the field and can be slow on a large
+  index. It is rejected with `:invalid_argument` unless
+  `allow_leading_wildcard: true` is given. A pattern made only of wildcards
+  is always rejected; use `*` in `search_query/4` to match every document.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `field_name` - The text field to search
+    * `pattern` - The glob pattern a term must match
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+      - `:allow_leading_wildcard` - Accept patterns starting with `*` or `?`
+        (default: `false`)
+
+  ## Examples
 
-    Native.searcher_search_prefix(searcher, field_name, prefix, limit)
+      {:ok, results} = Muninn.Searcher.search_wildcard(searcher, "title", "elix*")
+
+      {:ok, results} = Muninn.Searcher.search_wildcard(searcher, "sku", "AB-??-7*")
+
+      {:ok, results} =
+        Muninn.Searcher.search_wildcard(searcher, "title", "*script",
+          allow_leading_wildcard: true
+        )
+
+  """
+  @spec search_wildcard(t(), String.t(), String.t(), keyword()) ::
+          {:ok, map()} | error()
+  def search_wildcard(searcher, field_name, pattern, opts \\ [])
+      when is_binary(field_name) and is_binary(pattern) do
+    Native.searcher_search_wildcard(
+      searcher,
+      field_name,
+      pattern,
+      Keyword.get(opts, :allow_leading_wildcard, false),
+      Keyword.get(opts, :limit, 10)
+    )
   end
 
   @doc """
@@ -337,7 +1631,7 @@ defmodule Muninn.Searcher do
   ## Returns
 
     * `{:ok, results}` - Search results
-    * `{:error, reason}` - Search failed
+    * `{:error, kind, message}` - Search failed
 
   ## Examples
 
@@ -361,7 +1655,7 @@ defmodule Muninn.Searcher do
 
   """
   @spec search_range_u64(t(), String.t(), non_neg_integer(), non_neg_integer(), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_range_u64(searcher, field_name, lower, upper, opts \\ [])
       when is_binary(field_name) and is_integer(lower) and is_integer(upper) do
     limit = Keyword.get(opts, :limit, 10)
@@ -411,7 +1705,7 @@ defmodule Muninn.Searcher do
 
   """
   @spec search_range_i64(t(), String.t(), integer(), integer(), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_range_i64(searcher, field_name, lower, upper, opts \\ [])
       when is_binary(field_name) and is_integer(lower) and is_integer(upper) do
     limit = Keyword.get(opts, :limit, 10)
@@ -470,7 +1764,7 @@ defmodule Muninn.Searcher do
 
   """
   @spec search_range_f64(t(), String.t(), float(), float(), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_range_f64(searcher, field_name, lower, upper, opts \\ [])
       when is_binary(field_name) and is_float(lower) and is_float(upper) do
     limit = Keyword.get(opts, :limit, 10)
@@ -495,6 +1789,175 @@ defmodule Muninn.Searcher do
     )
   end
 
+  @doc """
+  Performs a lexicographic range query on a text field.
+
+  Searches for documents with an indexed term that sorts between `lower` and
+  `upper`, comparing bytes. This suits keyword fields holding ids, bucketed keys
+  or version strings, where the whole value is a single term. On tokenized text
+  fields the range is matched against individual (lowercased) tokens instead.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `field_name` - The indexed text or keyword field name to search
+    * `lower` - Lower bound string
+    * `upper` - Upper bound string
+    * `opts` - Keyword list of options (see `search_range_u64/5`)
+
+  ## Examples
+
+      # Find ids from "a000" to "a999"
+      {:ok, results} = Searcher.search_range_text(
+        searcher,
+        "sku",
+        "a000",
+        "a999"
+      )
+
+      # Find every 1.x version ("1.0" included, "2.0" excluded)
+      {:ok, results} = Searcher.search_range_text(
+        searcher,
+        "version",
+        "1.0",
+        "2.0",
+        inclusive: :lower
+      )
+
+  """
+  @spec search_range_text(t(), String.t(), String.t(), String.t(), keyword()) ::
+          {:ok, map()} | error()
+  def search_range_text(searcher, field_name, lower, upper, opts \\ [])
+      when is_binary(field_name) and is_binary(lower) and is_binary(upper) do
+    limit = Keyword.get(opts, :limit, 10)
+    inclusive = Keyword.get(opts, :inclusive, :both)
+
+    {lower_inclusive, upper_inclusive} =
+      case inclusive do
+        :both -> {true, true}
+        :lower -> {true, false}
+        :upper -> {false, true}
+        :neither -> {false, false}
+      end
+
+    Native.searcher_search_range_text(
+      searcher,
+      field_name,
+      lower,
+      upper,
+      lower_inclusive,
+      upper_inclusive,
+      limit
+    )
+  end
+
+  @doc """
+  Checks whether any document has `value` in `field_name`.
+
+  Matches are counted without loading any document, which makes this a cheap
+  check before an idempotent insert. The value is matched as a single indexed
+  term, like `Muninn.Query.term/2`, so keyword fields are the natural choice
+  for unique keys. Numeric and bool values must match the field type.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `field_name` - The field holding the key
+    * `value` - The key to look up
+
+  ## Returns
+
+    * `{:ok, true}` - At least one document has the value
+    * `{:ok, false}` - No document has the value
+    * `{:error, kind, message}` - Unknown field or value of the wrong type
+
+  ## Examples
+
+      case Muninn.Searcher.doc_exists(searcher, "id", "post-42") do
+        {:ok, false} -> Muninn.IndexWriter.add_document(index, post)
+        {:ok, true} -> :ok
+      end
+
+  """
+  @spec doc_exists(t(), String.t(), String.t() | number() | boolean()) ::
+          {:ok, boolean()} | error()
+  def doc_exists(searcher, field_name, value) when is_binary(field_name) do
+    Native.searcher_doc_exists(searcher, field_name, value)
+  end
+
+  @doc """
+  Counts the matches of several query strings in one call.
+
+  For comparing the coverage of query variants, such as when A/B testing
+  relevance. Every query runs against the same searcher, and so the same index
+  snapshot, which keeps the counts comparable even while documents are being
+  committed. Matches are counted without scoring or loading any document.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `queries` - List of query strings with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+
+  ## Returns
+
+    * `{:ok, counts}` - A map of each query string to its number of matches
+    * `{:error, kind, message}` - A query failed to parse or run
+
+  ## Examples
+
+      {:ok, counts} =
+        Muninn.Searcher.count_multi(searcher, ["laptop", "laptop~1", "notebook"], ["title"])
+
+      counts["laptop~1"] - counts["laptop"]
+
+  """
+  @spec count_multi(t(), [String.t()], [String.t()]) ::
+          {:ok, %{String.t() => non_neg_integer()}} | error()
+  def count_multi(searcher, queries, default_fields)
+      when is_list(queries) and is_list(default_fields) do
+    Native.searcher_count_multi(searcher, queries, default_fields)
+  end
+
+  @doc """
+  Estimates the number of documents matching a query without counting them all.
+
+  For guarding against expensive queries: at most 10,000 matches are visited
+  in each segment, so the cost stays bounded however broad the query is. Check
+  the estimate before running a query, to warn the user or require more
+  selective filters.
+
+  A segment with fewer matches is counted exactly, so small results are exact.
+  Otherwise the matches seen are extrapolated over the rest of the segment,
+  assuming they are spread evenly, which is close for broad queries but can be
+  off for matches clustered among recent or old documents. Use `count_multi/3`
+  for exact counts.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+
+  ## Returns
+
+    * `{:ok, estimate}` - The approximate number of matching documents
+    * `{:error, kind, message}` - The query failed to parse or prepare
+
+  ## Examples
+
+      case Muninn.Searcher.estimate_hits(searcher, query, ["title", "body"]) do
+        {:ok, estimate} when estimate > 1_000_000 -> {:error, :too_broad}
+        {:ok, _estimate} -> Muninn.Searcher.search_query(searcher, query, ["title", "body"])
+      end
+
+  """
+  @spec estimate_hits(t(), String.t(), [String.t()]) :: {:ok, non_neg_integer()} | error()
+  def estimate_hits(searcher, query_string, default_fields)
+      when is_binary(query_string) and is_list(default_fields) do
+    Native.searcher_estimate_hits(searcher, query_string, default_fields)
+  end
+
   @doc """
   Performs fuzzy search for terms within a specified Levenshtein distance.
 
@@ -520,7 +1983,7 @@ defmodule Muninn.Searcher do
   ## Returns
 
   - `{:ok, results}` - Map with "total_hits" and "hits" array
-  - `{:error, reason}` - Error string if search fails
+  - `{:error, kind, message}` - Search failed, see the Errors section
 
   ## Examples
 
@@ -555,7 +2018,7 @@ defmodule Muninn.Searcher do
   - Transposition=true is slightly faster than false
   """
   @spec search_fuzzy(t(), String.t(), String.t(), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_fuzzy(searcher, field_name, term, opts \\ [])
       when is_binary(field_name) and is_binary(term) do
     distance = Keyword.get(opts, :distance, 1)
@@ -564,7 +2027,7 @@ defmodule Muninn.Searcher do
 
     # Validate distance
     unless distance in 0..2 do
-      {:error, "Distance must be between 0 and 2"}
+      {:error, :invalid_argument, "Distance must be between 0 and 2"}
     else
       Native.searcher_search_fuzzy(
         searcher,
@@ -602,7 +2065,7 @@ defmodule Muninn.Searcher do
       )
   """
   @spec search_fuzzy_prefix(t(), String.t(), String.t(), keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_fuzzy_prefix(searcher, field_name, prefix, opts \\ [])
       when is_binary(field_name) and is_binary(prefix) do
     distance = Keyword.get(opts, :distance, 1)
@@ -610,7 +2073,7 @@ defmodule Muninn.Searcher do
     limit = Keyword.get(opts, :limit, 10)
 
     unless distance in 0..2 do
-      {:error, "Distance must be between 0 and 2"}
+      {:error, :invalid_argument, "Distance must be between 0 and 2"}
     else
       Native.searcher_search_fuzzy_prefix(
         searcher,
@@ -627,7 +2090,9 @@ defmodule Muninn.Searcher do
   Performs fuzzy search with highlighted snippets showing matched terms.
 
   Combines `search_fuzzy/4` with snippet generation for displaying context
-  around fuzzy matches.
+  around fuzzy matches. Every snippet field highlights its own words within
+  `:distance` of `term`, so a snippet field other than `field_name` is
+  highlighted too. A field with no such word gets an empty snippet.
 
   ## Parameters
 
@@ -659,7 +2124,7 @@ defmodule Muninn.Searcher do
       end
   """
   @spec search_fuzzy_with_snippets(t(), String.t(), String.t(), [String.t()], keyword()) ::
-          {:ok, map()} | {:error, String.t()}
+          {:ok, map()} | error()
   def search_fuzzy_with_snippets(searcher, field_name, term, snippet_fields, opts \\ [])
       when is_binary(field_name) and is_binary(term) and is_list(snippet_fields) do
     distance = Keyword.get(opts, :distance, 1)
@@ -668,7 +2133,7 @@ defmodule Muninn.Searcher do
     limit = Keyword.get(opts, :limit, 10)
 
     unless distance in 0..2 do
-      {:error, "Distance must be between 0 and 2"}
+      {:error, :invalid_argument, "Distance must be between 0 and 2"}
     else
       Native.searcher_search_fuzzy_with_snippets(
         searcher,
@@ -682,4 +2147,206 @@ defmodule Muninn.Searcher do
       )
     end
   end
+
+  @doc """
+  Streams every hit of a query in batches instead of materializing one large result.
+
+  The query is executed once to collect the matching document addresses. Stored
+  documents are then loaded lazily, `:batch_size` at a time, as the stream is
+  consumed. This is intended for exports and reindexing jobs that need to walk
+  an entire result set without holding it in memory.
+
+  Hits are emitted one by one, ordered by score, with the same shape as the
+  hits returned by `search_query/4`.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+    * `opts` - Keyword list of options:
+      - `:batch_size` - Number of documents loaded per round trip (default: 500),
+        at most the index's `:max_limit`
+
+  ## Returns
+
+    * `{:ok, stream}` - A lazy `Stream` of hit maps
+    * `{:error, kind, message}` - Search or parse failed
+
+  ## Examples
+
+      {:ok, stream} = Muninn.Searcher.search_stream(searcher, "status:published", ["title"])
+
+      stream
+      |> Stream.map(& &1["doc"])
+      |> Stream.each(&export_row/1)
+      |> Stream.run()
+
+  """
+  @spec search_stream(t(), String.t(), list(String.t()), keyword()) ::
+          {:ok, Enumerable.t()} | error()
+  def search_stream(searcher, query_string, default_fields, opts \\ [])
+      when is_binary(query_string) and is_list(default_fields) do
+    batch_size = Keyword.get(opts, :batch_size, 500)
+
+    with {:ok, cursor} <-
+           Native.searcher_search_stream(searcher, query_string, default_fields, batch_size) do
+      stream =
+        Stream.resource(
+          fn -> cursor end,
+          fn cursor ->
+            case Native.searcher_stream_next(cursor) do
+              {:ok, []} -> {:halt, cursor}
+              {:ok, hits} -> {hits, cursor}
+              {:error, _kind, message} -> raise "search stream failed: #{message}"
+            end
+          end,
+          fn _cursor -> :ok end
+        )
+
+      {:ok, stream}
+    end
+  end
+
+  @doc """
+  Parses a query string once so it can be executed many times with `run/3`.
+
+  Saved searches and alerts run the same query over and over. Compiling it
+  skips building the query parser and parsing the string on every run. The
+  index's synonyms are expanded when compiling, so synonyms set afterwards
+  only apply to queries compiled afterwards.
+
+  The compiled query can be run with any searcher of an index with the same
+  schema, including searchers created after later commits.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `query_string` - The query string with natural syntax
+    * `default_fields` - List of field names to search when no field is specified
+
+  ## Returns
+
+    * `{:ok, compiled}` - An opaque compiled query
+    * `{:error, kind, message}` - Parsing failed, see the Errors section
+
+  ## Examples
+
+      {:ok, compiled} = Muninn.Searcher.compile_query(searcher, "status:error", ["body"])
+
+      # Later, with a fresh searcher
+      {:ok, results} = Muninn.Searcher.run(searcher, compiled, limit: 50)
+
+  """
+  @spec compile_query(t(), String.t(), list(String.t())) :: {:ok, reference()} | error()
+  def compile_query(searcher, query_string, default_fields)
+      when is_binary(query_string) and is_list(default_fields) do
+    Native.query_compile(searcher, query_string, default_fields)
+  end
+
+  @doc """
+  Executes a query compiled with `compile_query/3`.
+
+  Results have the same shape as those of `search_query/4` without options.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `compiled` - A query returned by `compile_query/3`
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - Map with "total_hits" and "hits"
+    * `{:error, kind, message}` - Search failed. `:invalid_argument` when the
+      searcher's index has a different schema than the one the query was
+      compiled for
+
+  ## Examples
+
+      {:ok, results} = Muninn.Searcher.run(searcher, compiled, limit: 10)
+
+  """
+  @spec run(t(), reference(), keyword()) :: {:ok, map()} | error()
+  def run(searcher, compiled, opts \\ []) do
+    Native.searcher_run(searcher, compiled, Keyword.get(opts, :limit, 10))
+  end
+
+  @doc """
+  Compiles a regular expression once so it can be run many times with `run_regex/3`.
+
+  Tantivy matches a regex by turning it into an automaton walked over the
+  field's term dictionary. Building that automaton is the costly part of a
+  regex search, so validation pipelines running the same pattern over and
+  over should compile it once.
+
+  The regex must match a whole indexed term, not a substring of it, and terms
+  are matched as the field's tokenizer indexed them, usually lowercased. The
+  field is looked up when running, so the compiled regex works with any index
+  that has a text field of that name.
+
+  ## Parameters
+
+    * `pattern` - The regular expression, such as `"err[0-9]+"`
+    * `field_name` - Name of the text field whose terms are matched
+
+  ## Returns
+
+    * `{:ok, compiled}` - An opaque compiled regex
+    * `{:error, :invalid_argument, message}` - The pattern is not a valid regex
+
+  ## Examples
+
+      {:ok, compiled} = Muninn.Searcher.compile_regex("inv-[0-9]{4}", "reference")
+      {:ok, results} = Muninn.Searcher.run_regex(searcher, compiled, limit: 100)
+
+  """
+  @spec compile_regex(String.t(), String.t()) ::
+          {:ok, reference()} | {:error, :invalid_argument, String.t()}
+  def compile_regex(pattern, field_name) when is_binary(pattern) and is_binary(field_name) do
+    Native.regex_compile(pattern, field_name)
+  end
+
+  @doc """
+  Executes a regex compiled with `compile_regex/2`.
+
+  ## Parameters
+
+    * `searcher` - The searcher to use
+    * `compiled` - A regex returned by `compile_regex/2`
+    * `opts` - Keyword list of options:
+      - `:limit` - Maximum number of results to return (default: 10)
+
+  ## Returns
+
+    * `{:ok, results}` - Map with "total_hits" and "hits"
+    * `{:error, kind, message}` - The field is missing or not a text field, or
+      the search failed
+
+  ## Examples
+
+      {:ok, results} = Muninn.Searcher.run_regex(searcher, compiled)
+
+  """
+  @spec run_regex(t(), reference(), keyword()) :: {:ok, map()} | error()
+  def run_regex(searcher, compiled, opts \\ []) do
+    Native.searcher_run_regex(searcher, compiled, Keyword.get(opts, :limit, 10))
+  end
+
+  defp scoring(opts), do: opts |> Keyword.get(:scoring, :bm25) |> to_string()
+
+  defp default_operator(opts) do
+    case Keyword.get(opts, :default_operator) do
+      nil -> nil
+      operator -> to_string(operator)
+    end
+  end
+
+  defp tiebreak(opts) do
+    case Keyword.get(opts, :tiebreak) do
+      {field, order} -> {field, to_string(order)}
+      nil -> nil
+    end
+  end
 end
@@ -0,0 +1,187 @@
+use rustler::types::map;
+use rustler::{Encoder, ResourceArc};
+use serde_json::Value as Json;
+use tantivy::aggregation::agg_req::{AggregationVariants, Aggregations};
+use tantivy::aggregation::{AggregationCollector, AggregationLimitsGuard};
+use tantivy::schema::Schema;
+
+use crate::aliases::{resolve_alias, FieldAliases};
+use crate::error::MuninnError;
+use crate::searcher::{parse_query, SearcherResource};
+
+/// Performs a query and runs several named aggregations over the matching documents
+/// The aggregations are given as JSON in Elasticsearch's request shape and collected in
+/// a single pass. Bucket aggregations may nest sub-aggregations under "aggs", which are
+/// computed for each bucket. Returns a map of aggregation name to result
+pub fn searcher_aggregate<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    aggs_json: String,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    let aggs_json: Json = serde_json::from_str(&aggs_json).map_err(|e| {
+        MuninnError::invalid_argument(format!("Invalid aggregation request: {}", e))
+    })?;
+
+    let results = collect_aggregations(&searcher_res, &query_string, &default_fields, aggs_json)?;
+
+    Ok(json_to_term(env, &results))
+}
+
+/// Performs a query and estimates the number of distinct values of a fast field among
+/// the matching documents, with Tantivy's HyperLogLog based cardinality aggregation
+pub fn searcher_cardinality(
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    field_name: String,
+) -> Result<u64, MuninnError> {
+    let aggs_json = serde_json::json!({
+        "cardinality": { "cardinality": { "field": field_name } }
+    });
+
+    let results = collect_aggregations(&searcher_res, &query_string, &default_fields, aggs_json)?;
+
+    // The estimate is a float, and null when no document matched
+    let estimate = results["cardinality"]["value"].as_f64().unwrap_or(0.0);
+
+    Ok(estimate.round() as u64)
+}
+
+/// Runs aggregations given as JSON over the documents matching a query
+/// Returns the results as Tantivy serializes them
+fn collect_aggregations(
+    searcher_res: &SearcherResource,
+    query_string: &str,
+    default_fields: &[String],
+    mut aggs_json: Json,
+) -> Result<Json, MuninnError> {
+    let aliases = searcher_res.field_aliases()?;
+    rename_aliased_fields(&mut aggs_json, &aliases);
+
+    let aggs: Aggregations = serde_json::from_value(aggs_json).map_err(|e| {
+        MuninnError::invalid_argument(format!("Invalid aggregation request: {}", e))
+    })?;
+
+    if aggs.is_empty() {
+        return Err(MuninnError::invalid_argument(
+            "At least one aggregation is required",
+        ));
+    }
+
+    let searcher = &searcher_res.searcher;
+    let schema = searcher.index().schema();
+
+    validate_aggregations(&schema, &aggs)?;
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        query_string,
+        default_fields,
+        &[],
+    )?;
+
+    let collector = AggregationCollector::from_aggs(aggs, AggregationLimitsGuard::default());
+    let results = searcher
+        .search(&*query, &collector)
+        .map_err(|e| MuninnError::search_error(format!("Aggregation failed: {}", e)))?;
+
+    serde_json::to_value(&results).map_err(|e| {
+        MuninnError::search_error(format!("Failed to encode aggregation results: {}", e))
+    })
+}
+
+/// Replaces aliases given as the "field" of an aggregation with the current field name
+fn rename_aliased_fields(json: &mut Json, aliases: &FieldAliases) {
+    match json {
+        Json::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Json::String(field_name) if key == "field" => {
+                        *field_name = resolve_alias(aliases, field_name);
+                    }
+                    value => rename_aliased_fields(value, aliases),
+                }
+            }
+        }
+        Json::Array(elements) => {
+            for element in elements {
+                rename_aliased_fields(element, aliases);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks that every field read by the aggregations and their sub-aggregations is a
+/// fast field, since Tantivy returns empty results for missing fields. Only bucket
+/// aggregations can have sub-aggregations, which Tantivy would otherwise ignore
+fn validate_aggregations(schema: &Schema, aggs: &Aggregations) -> Result<(), MuninnError> {
+    for (name, agg) in aggs {
+        for field_name in agg.agg.get_fast_field_names() {
+            let (field, _) = schema
+                .find_field(field_name)
+                .ok_or_else(|| MuninnError::field_not_found(field_name))?;
+
+            if !schema.get_field_entry(field).is_fast() {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Field '{}' of aggregation '{}' is not a fast field",
+                    field_name, name
+                )));
+            }
+        }
+
+        if agg.sub_aggregation.is_empty() {
+            continue;
+        }
+
+        let is_bucket = matches!(
+            agg.agg,
+            AggregationVariants::Terms(_)
+                | AggregationVariants::Range(_)
+                | AggregationVariants::Histogram(_)
+                | AggregationVariants::DateHistogram(_)
+        );
+        if !is_bucket {
+            return Err(MuninnError::invalid_argument(format!(
+                "Aggregation '{}' is a metric aggregation and cannot have sub-aggregations",
+                name
+            )));
+        }
+
+        validate_aggregations(schema, &agg.sub_aggregation)?;
+    }
+
+    Ok(())
+}
+
+/// Converts JSON to the term Jason would decode it to, with string map keys
+pub(crate) fn json_to_term<'a>(env: rustler::Env<'a>, json: &Json) -> rustler::Term<'a> {
+    match json {
+        Json::Null => rustler::types::atom::nil().encode(env),
+        Json::Bool(bool_val) => bool_val.encode(env),
+        Json::Number(number) => {
+            if let Some(int_val) = number.as_i64() {
+                int_val.encode(env)
+            } else if let Some(uint_val) = number.as_u64() {
+                uint_val.encode(env)
+            } else {
+                number.as_f64().unwrap_or_default().encode(env)
+            }
+        }
+        Json::String(string) => string.encode(env),
+        Json::Array(elements) => elements
+            .iter()
+            .map(|element| json_to_term(env, element))
+            .collect::<Vec<_>>()
+            .encode(env),
+        Json::Object(object) => object.iter().fold(map::map_new(env), |acc, (key, value)| {
+            acc.map_put(key.encode(env), json_to_term(env, value))
+                .ok()
+                .unwrap()
+        }),
+    }
+}
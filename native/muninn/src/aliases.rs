@@ -0,0 +1,98 @@
+use rustler::ResourceArc;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tantivy::query_grammar::{UserInputAst, UserInputLeaf};
+
+use crate::error::MuninnError;
+use crate::searcher::SearcherResource;
+
+/// Current field name of every renamed field, keyed by its old name
+pub type FieldAliases = HashMap<String, String>;
+
+/// Replaces the field aliases of a searcher
+/// Every alias must point to a field of the schema and must not itself be a field name,
+/// since that field could then no longer be searched
+pub fn searcher_set_field_aliases(
+    searcher_res: ResourceArc<SearcherResource>,
+    aliases: FieldAliases,
+) -> Result<(), MuninnError> {
+    let schema = searcher_res.searcher.schema();
+
+    for (alias, field_name) in &aliases {
+        schema
+            .get_field(field_name)
+            .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+        if schema.get_field(alias).is_ok() {
+            return Err(MuninnError::invalid_argument(format!(
+                "Alias '{}' is already a field of the schema",
+                alias
+            )));
+        }
+    }
+
+    *searcher_res
+        .field_aliases
+        .write()
+        .map_err(|_| MuninnError::search_error("Failed to acquire field aliases lock"))? =
+        Arc::new(aliases);
+
+    Ok(())
+}
+
+/// Returns the current name of a field, or the name itself when it is not an alias
+pub fn resolve_alias(aliases: &FieldAliases, field_name: &str) -> String {
+    aliases
+        .get(field_name)
+        .cloned()
+        .unwrap_or_else(|| field_name.to_string())
+}
+
+/// Returns the current names of fields, keeping names that are not aliases
+pub fn resolve_aliases(aliases: &FieldAliases, field_names: &[String]) -> Vec<String> {
+    field_names
+        .iter()
+        .map(|field_name| resolve_alias(aliases, field_name))
+        .collect()
+}
+
+/// Renames the aliased fields named in a parsed query, leaving other names alone so
+/// the query parser still reports unknown fields
+pub fn rewrite_field_aliases(ast: UserInputAst, aliases: &FieldAliases) -> UserInputAst {
+    let rename = |field: Option<String>| field.map(|field| resolve_alias(aliases, &field));
+
+    match ast {
+        UserInputAst::Clause(clauses) => UserInputAst::Clause(
+            clauses
+                .into_iter()
+                .map(|(occur, clause)| (occur, rewrite_field_aliases(clause, aliases)))
+                .collect(),
+        ),
+        UserInputAst::Boost(ast, boost) => {
+            UserInputAst::Boost(Box::new(rewrite_field_aliases(*ast, aliases)), boost)
+        }
+        UserInputAst::Leaf(leaf) => UserInputAst::Leaf(Box::new(match *leaf {
+            UserInputLeaf::Literal(mut literal) => {
+                literal.field_name = rename(literal.field_name);
+                UserInputLeaf::Literal(literal)
+            }
+            UserInputLeaf::All => UserInputLeaf::All,
+            UserInputLeaf::Range {
+                field,
+                lower,
+                upper,
+            } => UserInputLeaf::Range {
+                field: rename(field),
+                lower,
+                upper,
+            },
+            UserInputLeaf::Set { field, elements } => UserInputLeaf::Set {
+                field: rename(field),
+                elements,
+            },
+            UserInputLeaf::Exists { field } => UserInputLeaf::Exists {
+                field: resolve_alias(aliases, &field),
+            },
+        })),
+    }
+}
@@ -0,0 +1,135 @@
+use rustler::{Env, ResourceArc};
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+use tantivy::query::{Query, RegexQuery};
+use tantivy::schema::{FieldType, Schema};
+use tantivy_fst::Regex;
+
+use crate::error::MuninnError;
+use crate::searcher::{execute_query, parse_query, SearcherResource};
+
+/// Resource wrapper for a parsed query string
+/// Parsing and synonym expansion happen once, so a saved search run over and over
+/// only pays for executing the query
+pub struct CompiledQueryResource {
+    pub query: Box<dyn Query>,
+    /// Schema the query was parsed against, since its fields are only valid there
+    pub schema: Schema,
+}
+
+unsafe impl Send for CompiledQueryResource {}
+unsafe impl Sync for CompiledQueryResource {}
+impl RefUnwindSafe for CompiledQueryResource {}
+
+/// Parses a query string once for repeated execution with `searcher_run`
+/// Synonyms are expanded with the index's synonyms at compile time
+pub fn query_compile(
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+) -> Result<ResourceArc<CompiledQueryResource>, MuninnError> {
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    Ok(ResourceArc::new(CompiledQueryResource {
+        query,
+        schema: searcher.schema().clone(),
+    }))
+}
+
+/// Executes a compiled query, returning results like a plain query search
+/// The searcher must belong to an index with the schema the query was compiled for
+pub fn searcher_run<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    compiled: ResourceArc<CompiledQueryResource>,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let schema = searcher.schema();
+
+    if *schema != compiled.schema {
+        return Err(MuninnError::invalid_argument(
+            "Compiled query was built for an index with a different schema",
+        ));
+    }
+
+    execute_query(env, &searcher_res, schema, &*compiled.query, limit)
+}
+
+/// Resource wrapper for a regex compiled to the automaton Tantivy matches terms with
+/// Building the automaton is the expensive part of a regex search, so a pattern run
+/// over and over is only compiled once
+pub struct CompiledRegexResource {
+    pub regex: Arc<Regex>,
+    pub field_name: String,
+}
+
+unsafe impl Send for CompiledRegexResource {}
+unsafe impl Sync for CompiledRegexResource {}
+impl RefUnwindSafe for CompiledRegexResource {}
+
+/// Compiles a regex for repeated execution on a text field with `searcher_run_regex`
+/// The field is only looked up when running, so one compiled regex serves any index
+/// with a text field of that name
+pub fn regex_compile(
+    pattern: String,
+    field_name: String,
+) -> Result<ResourceArc<CompiledRegexResource>, MuninnError> {
+    let regex = Regex::new(&pattern).map_err(|e| {
+        MuninnError::invalid_argument(format!("Invalid regex '{}': {}", pattern, e))
+    })?;
+
+    Ok(ResourceArc::new(CompiledRegexResource {
+        regex: Arc::new(regex),
+        field_name,
+    }))
+}
+
+/// Executes a compiled regex, returning the documents with a term of the field
+/// matching it as a whole
+pub fn searcher_run_regex<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    compiled: ResourceArc<CompiledRegexResource>,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let schema = searcher_res.searcher.schema();
+    let field_name = &compiled.field_name;
+
+    let field = schema
+        .get_field(field_name)
+        .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+    if !matches!(
+        schema.get_field_entry(field).field_type(),
+        FieldType::Str(_)
+    ) {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not a text field. Regex search only works on text fields.",
+            field_name
+        )));
+    }
+
+    let regex_query = RegexQuery::from_regex(compiled.regex.clone(), field);
+
+    execute_query(env, &searcher_res, schema, &regex_query, limit)
+}
+
+pub fn load(env: Env) -> bool {
+    rustler::resource!(CompiledQueryResource, env);
+    rustler::resource!(CompiledRegexResource, env);
+    true
+}
@@ -0,0 +1,187 @@
+use rustler::{Encoder, ResourceArc};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tantivy::collector::{ScoreSegmentTweaker, ScoreTweaker, TopDocs};
+use tantivy::schema::{FieldType, Schema};
+use tantivy::{DocId, Score, SegmentReader};
+
+use crate::aliases::resolve_alias;
+use crate::error::MuninnError;
+use crate::grouping::KeyColumn;
+use crate::searcher::{document_to_hit_map, fetch_documents, parse_query, SearcherResource};
+
+/// Multiplies scores by a factor that shrinks with the distance between a fast field's
+/// value and an origin, such as the age of a document's timestamp
+#[derive(Clone)]
+struct Decay {
+    field_name: String,
+    field_type: FieldType,
+    origin: f64,
+    scale: f64,
+    linear: bool,
+}
+
+struct DecaySegment {
+    column: KeyColumn,
+    decay: Decay,
+}
+
+impl Decay {
+    /// Checks that the field is a u64 or i64 fast field, the scale is positive and
+    /// the function is "exp" or "linear"
+    fn new(
+        schema: &Schema,
+        field_name: String,
+        scale: f64,
+        function: &str,
+        origin: f64,
+    ) -> Result<Self, MuninnError> {
+        let field = schema
+            .get_field(&field_name)
+            .map_err(|_| MuninnError::field_not_found(&field_name))?;
+        let field_entry = schema.get_field_entry(field);
+
+        if !field_entry.is_fast()
+            || !matches!(
+                field_entry.field_type(),
+                FieldType::U64(_) | FieldType::I64(_)
+            )
+        {
+            return Err(MuninnError::invalid_argument(format!(
+                "Decay field '{}' must be a u64 or i64 fast field",
+                field_name
+            )));
+        }
+
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(MuninnError::invalid_argument(
+                "Decay scale must be greater than 0",
+            ));
+        }
+
+        let linear = match function {
+            "exp" => false,
+            "linear" => true,
+            _ => {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Invalid decay function '{}': expected exp or linear",
+                    function
+                )))
+            }
+        };
+
+        Ok(Decay {
+            field_name,
+            field_type: field_entry.field_type().clone(),
+            origin,
+            scale,
+            linear,
+        })
+    }
+
+    /// Factor applied to the score of a document with the given value
+    /// Both functions give 1 at the origin and 0.5 at `scale` before it. The linear one
+    /// reaches 0 at twice the scale. Values past the origin are not decayed
+    fn factor(&self, value: Option<i128>) -> f64 {
+        // Documents without a value rank after every document with one
+        let Some(value) = value else {
+            return 0.0;
+        };
+
+        let distance = (self.origin - value as f64).max(0.0);
+        if self.linear {
+            (1.0 - distance / (2.0 * self.scale)).max(0.0)
+        } else {
+            0.5f64.powf(distance / self.scale)
+        }
+    }
+}
+
+impl ScoreTweaker<Score> for Decay {
+    type Child = DecaySegment;
+
+    fn segment_tweaker(&self, segment_reader: &SegmentReader) -> tantivy::Result<DecaySegment> {
+        Ok(DecaySegment {
+            column: KeyColumn::open(segment_reader, &self.field_name, &self.field_type)?,
+            decay: self.clone(),
+        })
+    }
+}
+
+impl ScoreSegmentTweaker<Score> for DecaySegment {
+    fn score(&mut self, doc: DocId, score: Score) -> Score {
+        score * self.decay.factor(self.column.first_key(doc)) as Score
+    }
+}
+
+/// Performs a query and multiplies each hit's score by a decay of a fast field's value
+/// With Unix timestamps in seconds and no origin, documents lose half their score
+/// every `scale` seconds of age, which favors recent documents without reordering
+/// hits of similar age. The decay is applied while collecting, so the top hits are the
+/// top hits of the decayed scores
+pub fn searcher_search_with_decay<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    decay_field: String,
+    scale: f64,
+    function: String,
+    origin: Option<i64>,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::types::map;
+
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let decay_field = resolve_alias(&aliases, &decay_field);
+    let schema = searcher.index().schema();
+
+    let origin = match origin {
+        Some(origin) => origin as f64,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |now| now.as_secs() as f64),
+    };
+    let decay = Decay::new(&schema, decay_field, scale, &function, origin)?;
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let top_docs = searcher
+        .search(&*query, &TopDocs::with_limit(limit).tweak_score(decay))
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    let mut hits = Vec::new();
+    for ((score, _), doc) in top_docs.iter().zip(docs) {
+        hits.push(document_to_hit_map(
+            env,
+            &schema,
+            &doc,
+            *score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?);
+    }
+
+    let result_map = map::map_new(env)
+        .map_put("total_hits".encode(env), top_docs.len().encode(env))
+        .ok()
+        .unwrap()
+        .map_put("hits".encode(env), hits.encode(env))
+        .ok()
+        .unwrap();
+
+    Ok(result_map)
+}
@@ -0,0 +1,223 @@
+use rustler::ResourceArc;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tantivy::directory::error::{
+    DeleteError, LockError, OpenDirectoryError, OpenReadError, OpenWriteError,
+};
+use tantivy::directory::{
+    AntiCallToken, Directory, DirectoryLock, FileHandle, Lock, MmapDirectory, TerminatingWrite,
+    WatchCallback, WatchHandle, WritePtr,
+};
+
+use crate::error::MuninnError;
+use crate::index::IndexResource;
+
+/// Whether commits on an index wait for the disk, shared by the index and its writer
+pub type SharedDurability = Arc<Durability>;
+
+#[derive(Debug)]
+pub struct Durability {
+    durable: AtomicBool,
+    /// Held while meta.json is replaced and while the flag is switched, so a commit
+    /// point is written either durably or fast, never half of each
+    switch_lock: RwLock<()>,
+}
+
+impl Durability {
+    pub fn new(durable: bool) -> Self {
+        Durability {
+            durable: AtomicBool::new(durable),
+            switch_lock: RwLock::new(()),
+        }
+    }
+
+    fn is_durable(&self) -> bool {
+        self.durable.load(Ordering::SeqCst)
+    }
+
+    /// Sets the flag once no meta.json is being replaced, returning the previous one
+    fn swap(&self, durable: bool) -> bool {
+        let _guard = self
+            .switch_lock
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.durable.swap(durable, Ordering::SeqCst)
+    }
+}
+
+/// Directory on disk whose fsyncs can be switched off
+/// When durable it behaves exactly like `MmapDirectory`. Otherwise new files, the
+/// atomically replaced meta.json and the directory entries are left in the page cache,
+/// so a commit returns as soon as the data is handed to the operating system
+#[derive(Clone, Debug)]
+pub struct DurabilityDirectory {
+    inner: MmapDirectory,
+    root: PathBuf,
+    durable: SharedDurability,
+}
+
+impl DurabilityDirectory {
+    pub fn open(root: &Path, durable: SharedDurability) -> Result<Self, OpenDirectoryError> {
+        Ok(DurabilityDirectory {
+            inner: MmapDirectory::open(root)?,
+            root: root.to_path_buf(),
+            durable,
+        })
+    }
+
+    fn is_durable(&self) -> bool {
+        self.durable.is_durable()
+    }
+}
+
+/// File writer that flushes to the operating system, and only syncs to disk if the
+/// directory became durable while the file was written, such as by a background merge
+struct UnsyncedFileWriter {
+    file: File,
+    durable: SharedDurability,
+}
+
+impl Write for UnsyncedFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl TerminatingWrite for UnsyncedFileWriter {
+    fn terminate_ref(&mut self, _: AntiCallToken) -> io::Result<()> {
+        self.file.flush()?;
+        if self.durable.is_durable() {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+impl Directory for DurabilityDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.inner.get_file_handle(path)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        if self.is_durable() {
+            return self.inner.open_write(path);
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.root.join(path))
+            .map_err(|io_error| {
+                if io_error.kind() == io::ErrorKind::AlreadyExists {
+                    OpenWriteError::FileAlreadyExists(path.to_path_buf())
+                } else {
+                    OpenWriteError::wrap_io_error(io_error, path.to_path_buf())
+                }
+            })?;
+
+        Ok(BufWriter::new(Box::new(UnsyncedFileWriter {
+            file,
+            durable: self.durable.clone(),
+        })))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.inner.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        // A switch to durable waits for a fast write, and the files it syncs include it
+        let _guard = self
+            .durable
+            .switch_lock
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if self.is_durable() {
+            return self.inner.atomic_write(path, data);
+        }
+
+        // Still written aside and renamed, so a reader never sees a partial meta.json
+        let full_path = self.root.join(path);
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp_path = full_path.with_file_name(format!(".{}.muninn_tmp", file_name));
+
+        fs::write(&temp_path, data)?;
+        fs::rename(&temp_path, &full_path)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        if self.is_durable() {
+            self.inner.sync_directory()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.inner.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        self.inner.watch(watch_callback)
+    }
+}
+
+/// Switches commits on the index between durable and fast
+/// Switching back to durable syncs every file of the index directory, so the commits
+/// made while fast are on disk once this returns. The flag is set before the files are
+/// synced: a file finished afterwards, by a commit or a background merge, syncs itself
+/// A meta.json being replaced fast is finished before the flag changes
+pub fn index_set_durability(
+    index_res: ResourceArc<IndexResource>,
+    durable: bool,
+) -> Result<(), MuninnError> {
+    let Some(path) = index_res.path() else {
+        return Err(MuninnError::invalid_argument(
+            "Durability only applies to an index on disk",
+        ));
+    };
+
+    let was_durable = index_res.durable.swap(durable);
+
+    if durable && !was_durable {
+        sync_index_files(&path)
+            .map_err(|e| MuninnError::index_error(format!("Failed to sync index files: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+/// Syncs every file of a directory, then the directory entries themselves
+fn sync_index_files(path: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        // A merge may have garbage collected the file since the directory was listed
+        match File::open(entry.path()) {
+            Ok(file) => file.sync_all()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    File::open(path)?.sync_all()
+}
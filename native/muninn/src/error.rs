@@ -0,0 +1,168 @@
+use rustler::{Encoder, Env, Term};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::atoms;
+
+/// Category of an error, encoded as an atom so Elixir can pattern-match on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A field, document or resource does not exist
+    NotFound,
+    /// The query string could not be parsed
+    ParseError,
+    /// An argument was rejected before running the operation
+    InvalidArgument,
+    /// A schema definition was rejected, for example a field with an unknown tokenizer
+    InvalidSchema,
+    /// Reading or writing a file of the index directory failed
+    IoError,
+    /// Tantivy failed while executing a search or loading results
+    SearchError,
+    /// The index or its writer failed
+    IndexError,
+    /// A write was attempted on an index opened read-only
+    ReadOnly,
+    /// Another writer, possibly in another process, holds the index lock
+    IndexLocked,
+    /// The directory already holds an index
+    AlreadyExists,
+    /// A paginated search reached past the reader's maximum result window
+    ResultWindowExceeded,
+    /// Tantivy panicked, for example on a corrupt segment
+    InternalPanic,
+}
+
+/// Error returned by the search and writer NIFs as `{:error, kind, message}`
+#[derive(Debug, Clone)]
+pub struct MuninnError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl MuninnError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        MuninnError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn parse_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::ParseError, message)
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidArgument, message)
+    }
+
+    pub fn invalid_schema(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidSchema, message)
+    }
+
+    pub fn io_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::IoError, message)
+    }
+
+    pub fn search_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::SearchError, message)
+    }
+
+    pub fn index_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::IndexError, message)
+    }
+
+    pub fn read_only(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::ReadOnly, message)
+    }
+
+    pub fn index_locked(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::IndexLocked, message)
+    }
+
+    pub fn already_exists(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::AlreadyExists, message)
+    }
+
+    pub fn result_window_exceeded(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::ResultWindowExceeded, message)
+    }
+
+    pub fn internal_panic(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InternalPanic, message)
+    }
+
+    /// Error for a field name that is missing from the schema
+    pub fn field_not_found(field_name: &str) -> Self {
+        Self::not_found(format!("Field '{}' not found in schema", field_name))
+    }
+}
+
+impl Encoder for ErrorKind {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let atom = match self {
+            ErrorKind::NotFound => atoms::not_found(),
+            ErrorKind::ParseError => atoms::parse_error(),
+            ErrorKind::InvalidArgument => atoms::invalid_argument(),
+            ErrorKind::InvalidSchema => atoms::invalid_schema(),
+            ErrorKind::IoError => atoms::io_error(),
+            ErrorKind::SearchError => atoms::search_error(),
+            ErrorKind::IndexError => atoms::index_error(),
+            ErrorKind::ReadOnly => atoms::read_only(),
+            ErrorKind::IndexLocked => atoms::index_locked(),
+            ErrorKind::AlreadyExists => atoms::already_exists(),
+            ErrorKind::ResultWindowExceeded => atoms::result_window_exceeded(),
+            ErrorKind::InternalPanic => atoms::internal_panic(),
+        };
+        atom.encode(env)
+    }
+}
+
+/// Runs the body of a NIF, returning a panic as an `internal_panic` error
+/// Rustler would otherwise raise an opaque exception in the calling process. Locks
+/// held by the panicking code are poisoned, so later calls on the same resource fail
+/// with a lock error instead of seeing half-updated state
+pub fn catch_panic<T>(body: impl FnOnce() -> Result<T, MuninnError>) -> Reply<T> {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => result.into(),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic payload".to_string());
+
+            Reply::Error(MuninnError::internal_panic(format!(
+                "Tantivy panicked: {}",
+                message
+            )))
+        }
+    }
+}
+
+/// NIF return value encoded as `{:ok, value}` or `{:error, kind, message}`
+pub enum Reply<T> {
+    Ok(T),
+    Error(MuninnError),
+}
+
+impl<T> From<Result<T, MuninnError>> for Reply<T> {
+    fn from(result: Result<T, MuninnError>) -> Self {
+        match result {
+            Ok(value) => Reply::Ok(value),
+            Err(error) => Reply::Error(error),
+        }
+    }
+}
+
+impl<T: Encoder> Encoder for Reply<T> {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            Reply::Ok(value) => (atoms::ok(), value).encode(env),
+            Reply::Error(error) => (atoms::error(), error.kind, error.message.as_str()).encode(env),
+        }
+    }
+}
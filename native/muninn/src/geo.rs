@@ -0,0 +1,96 @@
+use rustler::ResourceArc;
+use std::ops::Bound;
+use tantivy::query::{BooleanQuery, Occur, Query, RangeQuery};
+use tantivy::schema::{Field, FieldType, Schema};
+use tantivy::Term;
+
+use crate::aliases::resolve_alias;
+use crate::error::MuninnError;
+use crate::searcher::{apply_filters, execute_query, parse_query, SearcherResource};
+
+/// Performs a query and keeps the hits whose coordinates fall within a bounding box
+/// Latitude and longitude are read from two f64 fast fields and filtered with range
+/// queries, which do not change the ranking. A box whose minimum longitude is above
+/// its maximum crosses the antimeridian and matches either side of it
+pub fn searcher_search_bbox<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    lat_field: String,
+    lon_field: String,
+    min_lat: f64,
+    max_lat: f64,
+    min_lon: f64,
+    max_lon: f64,
+    query_string: String,
+    default_fields: Vec<String>,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let schema = searcher.index().schema();
+
+    let lat_field = coordinate_field(&schema, &resolve_alias(&aliases, &lat_field))?;
+    let lon_field = coordinate_field(&schema, &resolve_alias(&aliases, &lon_field))?;
+
+    let in_range = |value: f64, limit: f64| value.is_finite() && value.abs() <= limit;
+    if !(in_range(min_lat, 90.0) && in_range(max_lat, 90.0)) || min_lat > max_lat {
+        return Err(MuninnError::invalid_argument(
+            "Latitudes must be between -90 and 90, with the minimum at most the maximum",
+        ));
+    }
+    if !(in_range(min_lon, 180.0) && in_range(max_lon, 180.0)) {
+        return Err(MuninnError::invalid_argument(
+            "Longitudes must be between -180 and 180",
+        ));
+    }
+
+    let lon_filter: Box<dyn Query> = if min_lon <= max_lon {
+        coordinate_range(lon_field, min_lon, max_lon)
+    } else {
+        Box::new(BooleanQuery::new(vec![
+            (Occur::Should, coordinate_range(lon_field, min_lon, 180.0)),
+            (Occur::Should, coordinate_range(lon_field, -180.0, max_lon)),
+        ]))
+    };
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+    let query = apply_filters(
+        query,
+        vec![coordinate_range(lat_field, min_lat, max_lat), lon_filter],
+    );
+
+    execute_query(env, &searcher_res, &schema, &*query, limit)
+}
+
+/// Looks up a coordinate field, which must be an f64 fast field
+fn coordinate_field(schema: &Schema, field_name: &str) -> Result<Field, MuninnError> {
+    let field = schema
+        .get_field(field_name)
+        .map_err(|_| MuninnError::field_not_found(field_name))?;
+    let field_entry = schema.get_field_entry(field);
+
+    if !field_entry.is_fast() || !matches!(field_entry.field_type(), FieldType::F64(_)) {
+        return Err(MuninnError::invalid_argument(format!(
+            "Coordinate field '{}' must be an f64 fast field",
+            field_name
+        )));
+    }
+
+    Ok(field)
+}
+
+fn coordinate_range(field: Field, lower: f64, upper: f64) -> Box<dyn Query> {
+    Box::new(RangeQuery::new(
+        Bound::Included(Term::from_field_f64(field, lower)),
+        Bound::Included(Term::from_field_f64(field, upper)),
+    ))
+}
@@ -0,0 +1,276 @@
+use rustler::types::map;
+use rustler::{Encoder, ResourceArc};
+use std::collections::HashMap;
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::columnar::Column;
+use tantivy::schema::{FieldEntry, FieldType};
+use tantivy::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
+
+use crate::aliases::resolve_alias;
+use crate::error::MuninnError;
+use crate::searcher::{document_to_hit_map, fetch_documents, parse_query, SearcherResource};
+
+/// Top hits of every group, keyed by the group value widened to an integer
+type Groups = HashMap<i128, Vec<(Score, DocAddress)>>;
+
+/// Fast field column whose values are compared as integers, such as group values
+/// or score tiebreakers. Every supported type widens to i128 without loss
+#[derive(Clone)]
+pub(crate) enum KeyColumn {
+    U64(Column<u64>),
+    I64(Column<i64>),
+    Bool(Column<bool>),
+}
+
+impl KeyColumn {
+    /// Whether a field is a u64, i64 or bool fast field
+    pub(crate) fn supports(field_entry: &FieldEntry) -> bool {
+        field_entry.is_fast()
+            && matches!(
+                field_entry.field_type(),
+                FieldType::U64(_) | FieldType::I64(_) | FieldType::Bool(_)
+            )
+    }
+
+    pub(crate) fn open(
+        segment_reader: &SegmentReader,
+        field_name: &str,
+        field_type: &FieldType,
+    ) -> tantivy::Result<Self> {
+        let fast_fields = segment_reader.fast_fields();
+        Ok(match field_type {
+            FieldType::U64(_) => KeyColumn::U64(fast_fields.u64(field_name)?),
+            FieldType::I64(_) => KeyColumn::I64(fast_fields.i64(field_name)?),
+            _ => KeyColumn::Bool(fast_fields.bool(field_name)?),
+        })
+    }
+
+    pub(crate) fn first_key(&self, doc_id: DocId) -> Option<i128> {
+        match self {
+            KeyColumn::U64(column) => column.first(doc_id).map(i128::from),
+            KeyColumn::I64(column) => column.first(doc_id).map(i128::from),
+            KeyColumn::Bool(column) => column.first(doc_id).map(i128::from),
+        }
+    }
+
+    /// Smallest and largest value of the segment, or `None` when no document has one
+    pub(crate) fn bounds(&self) -> Option<(i128, i128)> {
+        let (num_vals, min_value, max_value) = match self {
+            KeyColumn::U64(column) => (
+                column.values.num_vals(),
+                i128::from(column.min_value()),
+                i128::from(column.max_value()),
+            ),
+            KeyColumn::I64(column) => (
+                column.values.num_vals(),
+                i128::from(column.min_value()),
+                i128::from(column.max_value()),
+            ),
+            KeyColumn::Bool(column) => (
+                column.values.num_vals(),
+                i128::from(column.min_value()),
+                i128::from(column.max_value()),
+            ),
+        };
+
+        (num_vals > 0).then_some((min_value, max_value))
+    }
+}
+
+/// Collects the top `hits_per_group` documents of every value of a fast field
+struct GroupedTopDocs {
+    field_name: String,
+    field_type: FieldType,
+    hits_per_group: usize,
+}
+
+struct GroupedSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    column: KeyColumn,
+    hits_per_group: usize,
+    groups: Groups,
+}
+
+impl Collector for GroupedTopDocs {
+    type Fruit = Groups;
+    type Child = GroupedSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<GroupedSegmentCollector> {
+        let column = KeyColumn::open(segment_reader, &self.field_name, &self.field_type)?;
+
+        Ok(GroupedSegmentCollector {
+            segment_ord,
+            column,
+            hits_per_group: self.hits_per_group,
+            groups: Groups::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(&self, segment_groups: Vec<Groups>) -> tantivy::Result<Groups> {
+        let mut groups = Groups::new();
+        for segment_group in segment_groups {
+            for (key, hits) in segment_group {
+                groups.entry(key).or_default().extend(hits);
+            }
+        }
+
+        for hits in groups.values_mut() {
+            keep_top_hits(hits, self.hits_per_group);
+        }
+
+        Ok(groups)
+    }
+}
+
+impl SegmentCollector for GroupedSegmentCollector {
+    type Fruit = Groups;
+
+    fn collect(&mut self, doc_id: DocId, score: Score) {
+        // Documents without a group value belong to no group
+        let Some(key) = self.column.first_key(doc_id) else {
+            return;
+        };
+
+        let hits = self.groups.entry(key).or_default();
+        hits.push((score, DocAddress::new(self.segment_ord, doc_id)));
+
+        // Trim in batches rather than on every hit
+        if hits.len() >= self.hits_per_group * 2 {
+            keep_top_hits(hits, self.hits_per_group);
+        }
+    }
+
+    fn harvest(mut self) -> Groups {
+        for hits in self.groups.values_mut() {
+            keep_top_hits(hits, self.hits_per_group);
+        }
+        self.groups
+    }
+}
+
+/// Sorts hits by descending score, then by address for stable ties, and keeps the first `n`
+fn keep_top_hits(hits: &mut Vec<(Score, DocAddress)>, n: usize) {
+    hits.sort_by(|(score_a, address_a), (score_b, address_b)| {
+        score_b
+            .total_cmp(score_a)
+            .then_with(|| address_a.cmp(address_b))
+    });
+    hits.truncate(n);
+}
+
+/// Performs a query and returns the top hits of each value of a fast field
+/// Groups are ordered by the score of their best hit, and only the first `num_groups`
+/// are returned. The group field must be a u64, i64 or bool fast field
+pub fn searcher_search_grouped<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    group_field: String,
+    hits_per_group: usize,
+    num_groups: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    if hits_per_group == 0 || num_groups == 0 {
+        return Err(MuninnError::invalid_argument(
+            "Hits per group and number of groups must be greater than 0",
+        ));
+    }
+
+    searcher_res.check_limit(hits_per_group.saturating_mul(num_groups))?;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let group_field = resolve_alias(&aliases, &group_field);
+    let schema = searcher.index().schema();
+
+    let field = schema
+        .get_field(&group_field)
+        .map_err(|_| MuninnError::field_not_found(&group_field))?;
+    let field_entry = schema.get_field_entry(field);
+
+    if !KeyColumn::supports(field_entry) {
+        return Err(MuninnError::invalid_argument(format!(
+            "Group field '{}' must be a u64, i64 or bool fast field",
+            group_field
+        )));
+    }
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let collector = GroupedTopDocs {
+        field_name: group_field,
+        field_type: field_entry.field_type().clone(),
+        hits_per_group,
+    };
+
+    let groups = searcher
+        .search(&*query, &collector)
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    // Order groups by their best hit, which is first after keep_top_hits
+    let mut groups: Vec<(i128, Vec<(Score, DocAddress)>)> = groups.into_iter().collect();
+    groups.sort_by(|(key_a, hits_a), (key_b, hits_b)| {
+        hits_b[0]
+            .0
+            .total_cmp(&hits_a[0].0)
+            .then_with(|| key_a.cmp(key_b))
+    });
+    groups.truncate(num_groups);
+
+    let returned_fields = searcher_res.returned_fields()?;
+
+    let mut group_terms = Vec::new();
+    for (key, hits) in groups {
+        let docs = fetch_documents(searcher, &hits)?;
+
+        let mut hit_terms = Vec::new();
+        for ((score, _), doc) in hits.into_iter().zip(docs) {
+            hit_terms.push(document_to_hit_map(
+                env,
+                &schema,
+                &doc,
+                score,
+                searcher_res.id_field,
+                returned_fields.as_deref(),
+                &searcher_res.multi_fields,
+            )?);
+        }
+
+        let value = match field_entry.field_type() {
+            FieldType::U64(_) => (key as u64).encode(env),
+            FieldType::I64(_) => (key as i64).encode(env),
+            _ => (key != 0).encode(env),
+        };
+
+        let group_map = map::map_new(env)
+            .map_put("value".encode(env), value)
+            .ok()
+            .unwrap()
+            .map_put("hits".encode(env), hit_terms.encode(env))
+            .ok()
+            .unwrap();
+        group_terms.push(group_map);
+    }
+
+    let result_map = map::map_new(env)
+        .map_put("groups".encode(env), group_terms.encode(env))
+        .ok()
+        .unwrap();
+
+    Ok(result_map)
+}
@@ -1,10 +1,60 @@
-use rustler::{Env, ResourceArc};
+use rustler::{Encoder, Env, ResourceArc, Term};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tantivy::{Index, IndexWriter, TantivyDocument};
+use std::time::Instant;
+use tantivy::collector::Count;
+use tantivy::directory::error::LockError;
+use tantivy::directory::footer::Footer;
+use tantivy::directory::{Directory, MmapDirectory, INDEX_WRITER_LOCK};
+use tantivy::query::AllQuery;
+use tantivy::schema::{Field, Schema};
+use tantivy::{
+    Index, IndexReader, IndexSettings, IndexWriter, ReloadPolicy, TantivyDocument, Version,
+    INDEX_FORMAT_OLDEST_SUPPORTED_VERSION, INDEX_FORMAT_VERSION,
+};
 
-use crate::schema::{build_schema, SchemaDef};
+use crate::durability::{Durability, DurabilityDirectory, SharedDurability};
+use crate::error::MuninnError;
+use crate::reader::{reader_new, ReaderResource};
+use crate::schema::{
+    build_schema, id_field_name, schema_differences, CopyField, SchemaDef, SchemaDefSettings,
+    SchemaDifference, SchemaResource, ValueLimit,
+};
+use crate::synonyms::SharedSynonyms;
+use crate::tokenizer::{builtin_tokenizers, register_builtin_tokenizers};
+use crate::writer::AutoCommit;
+
+/// Largest `limit` a search may request unless the index is opened with another maximum.
+/// TopDocs allocates its heap up front, so an unchecked limit of a few million can
+/// exhaust the node's memory.
+pub const DEFAULT_MAX_LIMIT: usize = 100_000;
+
+/// Tantivy's file in the index directory listing the segments of the last commit
+const META_FILE: &str = "meta.json";
+
+/// Tantivy's file in the index directory listing every file it created and still tracks
+const MANAGED_FILE: &str = ".managed.json";
+
+/// File in the index directory holding the name of the id field
+/// Tantivy's schema has no room for custom metadata, so the designation is kept next to it
+const ID_FIELD_FILE: &str = "muninn_id_field";
+
+/// File in the index directory listing the required fields, one name per line
+const REQUIRED_FIELDS_FILE: &str = "muninn_required_fields";
+
+/// File in the index directory listing the value limits, one
+/// `name<TAB>max_length<TAB>truncate|reject` line per limited field
+const VALUE_LIMITS_FILE: &str = "muninn_value_limits";
+
+/// File in the index directory listing the copied fields, one `source<TAB>target` line
+/// per field copied into another
+const COPY_FIELDS_FILE: &str = "muninn_copy_fields";
+
+/// File in the index directory listing the fields always returned as a list, one name
+/// per line
+const MULTI_FIELDS_FILE: &str = "muninn_multi_fields";
 
 /// Resource wrapper for Tantivy Index
 /// We use Arc<Mutex<>> to ensure thread safety and RefUnwindSafe
@@ -12,44 +62,870 @@ use crate::schema::{build_schema, SchemaDef};
 pub struct IndexResource {
     pub index: Arc<Mutex<Index>>,
     pub writer: Arc<Mutex<Option<IndexWriter<TantivyDocument>>>>,
+    /// Tokenizers registered on the index's TokenizerManager, which cannot list them
+    /// itself, with the components of their analyzers
+    pub tokenizers: Arc<Mutex<BTreeMap<String, Vec<String>>>>,
+    /// Largest `limit` accepted by searches on this index
+    pub max_limit: usize,
+    /// Opened with `index_open_read_only`: the writer is never created
+    pub read_only: bool,
+    pub autocommit: Arc<Mutex<AutoCommit>>,
+    /// Settings saved next to the schema, replaced with it by `index_swap`. Read them
+    /// with `schema_settings()` while holding the index or the writer lock
+    pub schema_settings: Mutex<SchemaSettings>,
+    /// Synonyms expanded when parsing query strings, see `index_set_synonyms`
+    pub synonyms: SharedSynonyms,
+    /// Canonical path of the index directory, None for an index held in RAM
+    /// Changed by `index_swap`, read it with `path()`
+    pub path: Mutex<Option<PathBuf>>,
+    /// Whether commits sync to disk, see `index_set_durability`
+    pub durable: SharedDurability,
+}
+
+/// Muninn's settings of an index that Tantivy's schema has no room for
+#[derive(Clone)]
+pub struct SchemaSettings {
+    /// Field whose value is returned as "_id" in every search hit
+    pub id_field: Option<Field>,
+    /// Fields every added document must have a value for
+    pub required_fields: Vec<Field>,
+    /// Size limits on text values, enforced when documents are added
+    pub value_limits: Vec<ValueLimit>,
+    /// Source and target of every field whose values are copied into another field
+    pub copy_fields: Vec<(Field, Field)>,
+    /// Numeric fields whose values are returned as a list, even a single one
+    pub multi_fields: Vec<Field>,
+}
+
+impl IndexResource {
+    /// Canonical path of the index directory, None for an index held in RAM
+    pub fn path(&self) -> Option<PathBuf> {
+        match self.path.lock() {
+            Ok(path) => path.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    pub fn schema_settings(&self) -> SchemaSettings {
+        match self.schema_settings.lock() {
+            Ok(schema_settings) => schema_settings.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+/// Where an index keeps its files, returned by `index_info`
+pub struct IndexInfo {
+    /// "mmap" for an index on disk, "ram" for one held in memory
+    pub directory: &'static str,
+    pub path: Option<String>,
+}
+
+impl Encoder for IndexInfo {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        rustler::types::map::map_new(env)
+            .map_put("directory".encode(env), self.directory.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("path".encode(env), self.path.encode(env))
+            .ok()
+            .unwrap()
+    }
+}
+
+/// Outcome of a successful `index_health_check`
+pub struct HealthCheck {
+    pub num_docs: usize,
+    /// Time taken to open a reader and run the query, in milliseconds
+    pub latency_ms: f64,
+}
+
+impl Encoder for HealthCheck {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        rustler::types::map::map_new(env)
+            .map_put("num_docs".encode(env), self.num_docs.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("latency_ms".encode(env), self.latency_ms.encode(env))
+            .ok()
+            .unwrap()
+    }
+}
+
+/// Tantivy and index format versions, returned by `muninn_info` and `index_format_info`
+pub struct VersionInfo {
+    /// Tantivy version as "major.minor.patch"
+    pub tantivy_version: String,
+    pub index_format_version: u32,
+    /// Whether this build of Muninn can read the index format
+    pub compatible: bool,
+}
+
+impl Encoder for VersionInfo {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        rustler::types::map::map_new(env)
+            .map_put(
+                "tantivy_version".encode(env),
+                self.tantivy_version.encode(env),
+            )
+            .ok()
+            .unwrap()
+            .map_put(
+                "index_format_version".encode(env),
+                self.index_format_version.encode(env),
+            )
+            .ok()
+            .unwrap()
+            .map_put("compatible".encode(env), self.compatible.encode(env))
+            .ok()
+            .unwrap()
+    }
+}
+
+impl VersionInfo {
+    /// Reads a Tantivy version, whose fields are only exposed through serde
+    fn from_version(version: &Version) -> Result<Self, MuninnError> {
+        let invalid = || MuninnError::index_error("Failed to read index version");
+        let json = serde_json::to_value(version).map_err(|_| invalid())?;
+        let number = |key: &str| json[key].as_u64().ok_or_else(invalid);
+
+        let index_format_version = number("index_format_version")? as u32;
+
+        Ok(VersionInfo {
+            tantivy_version: format!(
+                "{}.{}.{}",
+                number("major")?,
+                number("minor")?,
+                number("patch")?
+            ),
+            index_format_version,
+            compatible: (INDEX_FORMAT_OLDEST_SUPPORTED_VERSION..=INDEX_FORMAT_VERSION)
+                .contains(&index_format_version),
+        })
+    }
 }
 
 /// Creates a new index at the specified path with the given schema
+/// Fails with `already_exists` if the directory already holds an index, unless `force`
+/// is set, in which case that index is deleted first
 pub fn create_index(
     path: String,
     schema_def: SchemaDef,
-) -> Result<ResourceArc<IndexResource>, String> {
-    // Build the schema first
-    let schema = build_schema(schema_def)?;
+    max_limit: Option<usize>,
+    force: bool,
+) -> Result<ResourceArc<IndexResource>, MuninnError> {
+    // Check the arguments before looking at the directory, so an invalid definition is
+    // reported as such and never costs an existing index its documents
+    resolve_max_limit(max_limit).map_err(MuninnError::invalid_argument)?;
+    let settings = SchemaDefSettings::from_def(&schema_def).map_err(MuninnError::invalid_schema)?;
+    let schema = build_schema(schema_def).map_err(MuninnError::invalid_schema)?;
+
+    clear_index_dir(&path, force)?;
+    create_new_index(path, schema, settings, max_limit)
+}
+
+/// Creates a new index with a schema built by `schema_build` or read with `index_schema`,
+/// so several indexes can share one schema and its settings, like `create_index`
+pub fn create_index_with_schema(
+    path: String,
+    schema_res: ResourceArc<SchemaResource>,
+    max_limit: Option<usize>,
+    force: bool,
+) -> Result<ResourceArc<IndexResource>, MuninnError> {
+    resolve_max_limit(max_limit).map_err(MuninnError::invalid_argument)?;
+
+    clear_index_dir(&path, force)?;
+    create_new_index(
+        path,
+        schema_res.schema.clone(),
+        schema_res.settings.clone(),
+        max_limit,
+    )
+}
+
+/// Refuses a directory already holding an index with `already_exists`, or deletes that
+/// index when `force` is set
+/// Only the files Tantivy manages and Muninn's own settings files are deleted, so other
+/// files in the directory survive. Tantivy's writer lock is held meanwhile, and an
+/// index with an open writer, in this or another process, is refused as `index_locked`
+fn clear_index_dir(path: &str, force: bool) -> Result<(), MuninnError> {
+    let index_path = Path::new(path);
+    if !index_path.join(META_FILE).exists() {
+        return Ok(());
+    }
+
+    if !force {
+        return Err(MuninnError::already_exists(format!(
+            "Directory '{}' already holds an index",
+            path
+        )));
+    }
+
+    let delete_error = |e: String| {
+        MuninnError::io_error(format!(
+            "Failed to delete existing index at '{}': {}",
+            path, e
+        ))
+    };
+    let directory = MmapDirectory::open(index_path).map_err(|e| delete_error(e.to_string()))?;
+    let _writer_lock = directory
+        .acquire_lock(&INDEX_WRITER_LOCK)
+        .map_err(|e| match e {
+            LockError::LockBusy => MuninnError::index_locked(format!(
+                "Cannot replace the index at '{}' while a writer holds its lock",
+                path
+            )),
+            LockError::IoError(e) => delete_error(e.to_string()),
+        })?;
+
+    // The managed files list every segment file of the index, as Tantivy tracks them
+    let managed_files: Vec<PathBuf> = match fs::read(index_path.join(MANAGED_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| delete_error(e.to_string()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(delete_error(e.to_string())),
+    };
+
+    let own_files = [
+        META_FILE,
+        MANAGED_FILE,
+        ID_FIELD_FILE,
+        REQUIRED_FIELDS_FILE,
+        VALUE_LIMITS_FILE,
+        COPY_FIELDS_FILE,
+        MULTI_FIELDS_FILE,
+    ];
+    let files = managed_files
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(own_files.iter().map(Path::new));
+
+    for file in files {
+        match fs::remove_file(index_path.join(file)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(delete_error(e.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn create_new_index(
+    path: String,
+    schema: Schema,
+    settings: SchemaDefSettings,
+    max_limit: Option<usize>,
+) -> Result<ResourceArc<IndexResource>, MuninnError> {
+    let max_limit = resolve_max_limit(max_limit).map_err(MuninnError::invalid_argument)?;
+    let SchemaDefSettings {
+        id_field: id_field_name,
+        required_fields: required_field_names,
+        value_limits,
+        copy_fields,
+        multi_fields: multi_field_names,
+    } = settings;
 
     // Create the directory if it doesn't exist
     let index_path = Path::new(&path);
-    fs::create_dir_all(index_path)
-        .map_err(|e| format!("Failed to create index directory: {}", e))?;
+    fs::create_dir_all(index_path).map_err(|e| {
+        MuninnError::io_error(format!(
+            "Failed to create index directory '{}': {}",
+            path, e
+        ))
+    })?;
 
     // Create index
-    let index = Index::create_in_dir(index_path, schema)
-        .map_err(|e| format!("Failed to create index: {}", e))?;
+    let durable = SharedDurability::new(Durability::new(true));
+    let create_error = |e: String| format!("Failed to create index at '{}': {}", path, e);
+    let directory = DurabilityDirectory::open(index_path, durable.clone())
+        .map_err(|e| MuninnError::io_error(create_error(e.to_string())))?;
+    let index = Index::create(directory, schema, IndexSettings::default())
+        .map_err(|e| MuninnError::index_error(create_error(e.to_string())))?;
+    register_builtin_tokenizers(&index);
+    let path = canonical_path(index_path).map_err(MuninnError::io_error)?;
+
+    let id_field = match id_field_name {
+        Some(name) => {
+            fs::write(index_path.join(ID_FIELD_FILE), &name)
+                .map_err(|e| MuninnError::io_error(format!("Failed to save id field: {}", e)))?;
+            index.schema().get_field(&name).ok()
+        }
+        None => None,
+    };
+
+    if !required_field_names.is_empty() {
+        fs::write(
+            index_path.join(REQUIRED_FIELDS_FILE),
+            required_field_names.join("\n"),
+        )
+        .map_err(|e| MuninnError::io_error(format!("Failed to save required fields: {}", e)))?;
+    }
+
+    if !value_limits.is_empty() {
+        let lines: Vec<String> = value_limits
+            .iter()
+            .map(|limit| {
+                let on_too_long = if limit.truncate { "truncate" } else { "reject" };
+                format!(
+                    "{}\t{}\t{}",
+                    limit.field_name, limit.max_length, on_too_long
+                )
+            })
+            .collect();
+
+        fs::write(index_path.join(VALUE_LIMITS_FILE), lines.join("\n"))
+            .map_err(|e| MuninnError::io_error(format!("Failed to save value limits: {}", e)))?;
+    }
+
+    if !copy_fields.is_empty() {
+        let lines: Vec<String> = copy_fields
+            .iter()
+            .map(|copy| format!("{}\t{}", copy.source, copy.target))
+            .collect();
+
+        fs::write(index_path.join(COPY_FIELDS_FILE), lines.join("\n"))
+            .map_err(|e| MuninnError::io_error(format!("Failed to save copied fields: {}", e)))?;
+    }
+
+    if !multi_field_names.is_empty() {
+        fs::write(
+            index_path.join(MULTI_FIELDS_FILE),
+            multi_field_names.join("\n"),
+        )
+        .map_err(|e| MuninnError::io_error(format!("Failed to save multi-valued fields: {}", e)))?;
+    }
+
+    let required_fields = required_field_names
+        .iter()
+        .filter_map(|name| index.schema().get_field(name).ok())
+        .collect();
+    let multi_fields = multi_field_names
+        .iter()
+        .filter_map(|name| index.schema().get_field(name).ok())
+        .collect();
+    let copy_fields =
+        resolve_copy_fields(&index, &copy_fields).map_err(MuninnError::invalid_schema)?;
 
     Ok(ResourceArc::new(IndexResource {
         index: Arc::new(Mutex::new(index)),
         writer: Arc::new(Mutex::new(None)),
+        tokenizers: Arc::new(Mutex::new(builtin_tokenizers())),
+        max_limit,
+        read_only: false,
+        autocommit: Arc::new(Mutex::new(AutoCommit::default())),
+        schema_settings: Mutex::new(SchemaSettings {
+            id_field,
+            required_fields,
+            value_limits,
+            copy_fields,
+            multi_fields,
+        }),
+        synonyms: SharedSynonyms::default(),
+        path: Mutex::new(Some(path)),
+        durable,
     }))
 }
 
+fn canonical_path(index_path: &Path) -> Result<PathBuf, String> {
+    fs::canonicalize(index_path).map_err(|e| format!("Failed to resolve index path: {}", e))
+}
+
+/// Falls back to the default maximum limit and rejects a maximum of 0
+fn resolve_max_limit(max_limit: Option<usize>) -> Result<usize, String> {
+    match max_limit {
+        None => Ok(DEFAULT_MAX_LIMIT),
+        Some(0) => Err("Max limit must be greater than 0".to_string()),
+        Some(max_limit) => Ok(max_limit),
+    }
+}
+
 /// Opens an existing index at the specified path
-pub fn open_index(path: String) -> Result<ResourceArc<IndexResource>, String> {
+pub fn open_index(
+    path: String,
+    max_limit: Option<usize>,
+) -> Result<ResourceArc<IndexResource>, String> {
+    open_index_with_mode(path, max_limit, false)
+}
+
+/// Opens an existing index for searching only
+/// The writer, and with it Tantivy's writer lock, is never acquired, so any number
+/// of processes can open the same directory this way next to a single writer
+pub fn open_index_read_only(
+    path: String,
+    max_limit: Option<usize>,
+) -> Result<ResourceArc<IndexResource>, String> {
+    open_index_with_mode(path, max_limit, true)
+}
+
+fn open_index_with_mode(
+    path: String,
+    max_limit: Option<usize>,
+    read_only: bool,
+) -> Result<ResourceArc<IndexResource>, String> {
+    let max_limit = resolve_max_limit(max_limit)?;
     let index_path = Path::new(&path);
 
+    let durable = SharedDurability::new(Durability::new(true));
+    let directory = DurabilityDirectory::open(index_path, durable.clone())
+        .map_err(|e| format!("Failed to open index at '{}': {}", path, e))?;
     let index =
-        Index::open_in_dir(index_path).map_err(|e| format!("Failed to open index: {}", e))?;
+        Index::open(directory).map_err(|e| format!("Failed to open index at '{}': {}", path, e))?;
+    register_builtin_tokenizers(&index);
+    let id_field = load_id_field(index_path, &index)?;
+    let required_fields = load_required_fields(index_path, &index)?;
+    let value_limits = load_value_limits(index_path, &index)?;
+    let copy_fields = load_copy_fields(index_path, &index)?;
+    let multi_fields = load_multi_fields(index_path, &index)?;
+    let path = canonical_path(index_path)?;
 
     Ok(ResourceArc::new(IndexResource {
         index: Arc::new(Mutex::new(index)),
         writer: Arc::new(Mutex::new(None)),
+        tokenizers: Arc::new(Mutex::new(builtin_tokenizers())),
+        max_limit,
+        read_only,
+        autocommit: Arc::new(Mutex::new(AutoCommit::default())),
+        schema_settings: Mutex::new(SchemaSettings {
+            id_field,
+            required_fields,
+            value_limits,
+            copy_fields,
+            multi_fields,
+        }),
+        synonyms: SharedSynonyms::default(),
+        path: Mutex::new(Some(path)),
+        durable,
     }))
 }
 
+/// Reads the id field saved by `create_index`, if the index has one
+fn load_id_field(index_path: &Path, index: &Index) -> Result<Option<Field>, String> {
+    let id_field_path = index_path.join(ID_FIELD_FILE);
+    if !id_field_path.exists() {
+        return Ok(None);
+    }
+
+    let name = fs::read_to_string(&id_field_path)
+        .map_err(|e| format!("Failed to read id field: {}", e))?;
+
+    index
+        .schema()
+        .get_field(&name)
+        .map(Some)
+        .map_err(|_| format!("Id field '{}' is not in the index schema", name))
+}
+
+/// Reads the required fields saved by `create_index`
+fn load_required_fields(index_path: &Path, index: &Index) -> Result<Vec<Field>, String> {
+    let required_fields_path = index_path.join(REQUIRED_FIELDS_FILE);
+    if !required_fields_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let names = fs::read_to_string(&required_fields_path)
+        .map_err(|e| format!("Failed to read required fields: {}", e))?;
+
+    names
+        .lines()
+        .map(|name| {
+            index
+                .schema()
+                .get_field(name)
+                .map_err(|_| format!("Required field '{}' is not in the index schema", name))
+        })
+        .collect()
+}
+
+/// Reads the value limits saved by `create_index`
+fn load_value_limits(index_path: &Path, index: &Index) -> Result<Vec<ValueLimit>, String> {
+    let value_limits_path = index_path.join(VALUE_LIMITS_FILE);
+    if !value_limits_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&value_limits_path)
+        .map_err(|e| format!("Failed to read value limits: {}", e))?;
+
+    contents
+        .lines()
+        .map(|line| {
+            let invalid = || format!("Invalid value limit '{}'", line);
+            let mut parts = line.split('\t');
+
+            let field_name = parts.next().ok_or_else(invalid)?;
+            let max_length = parts
+                .next()
+                .and_then(|max_length| max_length.parse().ok())
+                .ok_or_else(invalid)?;
+            let truncate = match parts.next() {
+                Some("truncate") => true,
+                Some("reject") => false,
+                _ => return Err(invalid()),
+            };
+
+            if index.schema().get_field(field_name).is_err() {
+                return Err(format!(
+                    "Value limit field '{}' is not in the index schema",
+                    field_name
+                ));
+            }
+
+            Ok(ValueLimit {
+                field_name: field_name.to_string(),
+                max_length,
+                truncate,
+            })
+        })
+        .collect()
+}
+
+/// Reads the copied fields saved by `create_index`
+fn load_copy_fields(index_path: &Path, index: &Index) -> Result<Vec<(Field, Field)>, String> {
+    let copy_fields_path = index_path.join(COPY_FIELDS_FILE);
+    if !copy_fields_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&copy_fields_path)
+        .map_err(|e| format!("Failed to read copied fields: {}", e))?;
+
+    let copy_fields: Vec<CopyField> = contents
+        .lines()
+        .map(|line| match line.split_once('\t') {
+            Some((source, target)) => Ok(CopyField {
+                source: source.to_string(),
+                target: target.to_string(),
+            }),
+            None => Err(format!("Invalid copied field '{}'", line)),
+        })
+        .collect::<Result<_, _>>()?;
+
+    resolve_copy_fields(index, &copy_fields)
+}
+
+/// Reads the multi-valued fields saved by `create_index`
+fn load_multi_fields(index_path: &Path, index: &Index) -> Result<Vec<Field>, String> {
+    let multi_fields_path = index_path.join(MULTI_FIELDS_FILE);
+    if !multi_fields_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let names = fs::read_to_string(&multi_fields_path)
+        .map_err(|e| format!("Failed to read multi-valued fields: {}", e))?;
+
+    names
+        .lines()
+        .map(|name| {
+            index
+                .schema()
+                .get_field(name)
+                .map_err(|_| format!("Multi-valued field '{}' is not in the index schema", name))
+        })
+        .collect()
+}
+
+/// Looks up the source and target field of every copied field in the index schema
+fn resolve_copy_fields(
+    index: &Index,
+    copy_fields: &[CopyField],
+) -> Result<Vec<(Field, Field)>, String> {
+    let schema = index.schema();
+    let get_field = |name: &str| {
+        schema
+            .get_field(name)
+            .map_err(|_| format!("Copied field '{}' is not in the index schema", name))
+    };
+
+    copy_fields
+        .iter()
+        .map(|copy| Ok((get_field(&copy.source)?, get_field(&copy.target)?)))
+        .collect()
+}
+
+/// Points the index at another index directory, such as one rebuilt with `reindex`,
+/// and returns a reader on it
+/// Readers and searchers created earlier keep serving the old directory until they are
+/// dropped, while everything created from the index afterwards uses the new one and its
+/// schema, id field, required fields, value limits, copied and multi-valued fields
+/// The registered tokenizers and the durability setting carry over. The swap is refused
+/// while added documents or queued deletes are waiting to be committed, since they
+/// would be lost with the old writer
+pub fn index_swap(
+    index_res: ResourceArc<IndexResource>,
+    new_path: String,
+) -> Result<ResourceArc<ReaderResource>, MuninnError> {
+    let new_dir = Path::new(&new_path);
+    let canonical_new_path = canonical_path(new_dir).map_err(MuninnError::index_error)?;
+    if index_res.path().as_ref() == Some(&canonical_new_path) {
+        return Err(MuninnError::invalid_argument(
+            "An index cannot be swapped with itself",
+        ));
+    }
+
+    let open_error = |e: String| {
+        MuninnError::index_error(format!("Failed to open index at '{}': {}", new_path, e))
+    };
+    let directory = DurabilityDirectory::open(new_dir, index_res.durable.clone())
+        .map_err(|e| open_error(e.to_string()))?;
+    let new_index = Index::open(directory).map_err(|e| open_error(e.to_string()))?;
+
+    let new_schema_settings = SchemaSettings {
+        id_field: load_id_field(new_dir, &new_index).map_err(open_error)?,
+        required_fields: load_required_fields(new_dir, &new_index).map_err(open_error)?,
+        value_limits: load_value_limits(new_dir, &new_index).map_err(open_error)?,
+        copy_fields: load_copy_fields(new_dir, &new_index).map_err(open_error)?,
+        multi_fields: load_multi_fields(new_dir, &new_index).map_err(open_error)?,
+    };
+
+    // The writer lock comes before the index lock, as for every write
+    let mut writer = index_res
+        .writer
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire writer lock"))?;
+
+    let autocommit = index_res
+        .autocommit
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire autocommit lock"))?;
+    if autocommit.pending_docs > 0 || autocommit.pending_deletes > 0 {
+        return Err(MuninnError::invalid_argument(format!(
+            "Commit or roll back the {} pending documents and {} pending deletes before swapping the index",
+            autocommit.pending_docs, autocommit.pending_deletes
+        )));
+    }
+    drop(autocommit);
+
+    // Closing the old writer releases its lock on the old directory
+    if let Some(old_writer) = writer.take() {
+        old_writer.wait_merging_threads().map_err(|e| {
+            MuninnError::index_error(format!("Failed to close the old writer: {}", e))
+        })?;
+    }
+
+    let mut index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let tokenizers = index_res
+        .tokenizers
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire tokenizers lock"))?;
+
+    // Tokenizers are registered on each Tantivy index, so they are copied over
+    for name in tokenizers.keys() {
+        if let Some(analyzer) = index.tokenizers().get(name) {
+            new_index.tokenizers().register(name, analyzer);
+        }
+    }
+    drop(tokenizers);
+
+    *index = new_index;
+    *index_res
+        .schema_settings
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire schema settings lock"))? =
+        new_schema_settings;
+    *index_res
+        .path
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire path lock"))? =
+        Some(canonical_new_path);
+
+    drop(writer);
+    drop(index);
+
+    reader_new(index_res, None, false)
+}
+
+/// Returns the payload stored with the last commit, if any
+/// Reads the metadata from disk, so commits made by other writers are visible
+pub fn index_commit_payload(
+    index_res: ResourceArc<IndexResource>,
+) -> Result<Option<String>, MuninnError> {
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let metas = index
+        .load_metas()
+        .map_err(|e| MuninnError::index_error(format!("Failed to load index metadata: {}", e)))?;
+
+    Ok(metas.payload)
+}
+
+/// Returns the schema of the index, so an opened index can be introspected like a built one
+pub fn index_schema(
+    index_res: ResourceArc<IndexResource>,
+) -> Result<ResourceArc<SchemaResource>, MuninnError> {
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let schema = index.schema();
+    let schema_settings = index_res.schema_settings();
+    let field_name = |field: Field| schema.get_field_name(field).to_string();
+
+    let settings = SchemaDefSettings {
+        id_field: schema_settings.id_field.map(field_name),
+        required_fields: schema_settings
+            .required_fields
+            .into_iter()
+            .map(field_name)
+            .collect(),
+        value_limits: schema_settings.value_limits,
+        copy_fields: schema_settings
+            .copy_fields
+            .into_iter()
+            .map(|(source, target)| CopyField {
+                source: field_name(source),
+                target: field_name(target),
+            })
+            .collect(),
+        multi_fields: schema_settings
+            .multi_fields
+            .into_iter()
+            .map(field_name)
+            .collect(),
+    };
+
+    Ok(ResourceArc::new(SchemaResource { schema, settings }))
+}
+
+/// Compares a schema definition with the index's actual schema
+/// Returns the differences, an empty list when the definition matches the index
+pub fn index_check_schema(
+    index_res: ResourceArc<IndexResource>,
+    schema_def: SchemaDef,
+) -> Result<Vec<SchemaDifference>, MuninnError> {
+    let expected_id_field = id_field_name(&schema_def).map_err(MuninnError::invalid_argument)?;
+    let expected = build_schema(schema_def).map_err(MuninnError::invalid_argument)?;
+
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let actual = index.schema();
+    let actual_id_field = index_res
+        .schema_settings()
+        .id_field
+        .map(|field| actual.get_field_name(field).to_string());
+
+    Ok(schema_differences(
+        &expected,
+        expected_id_field.as_deref(),
+        &actual,
+        actual_id_field.as_deref(),
+    ))
+}
+
+/// Returns the number of fields in the index's schema, like `schema_num_fields`
+pub fn index_num_fields(index_res: ResourceArc<IndexResource>) -> usize {
+    let index = match index_res.index.lock() {
+        Ok(index) => index,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    index.schema().fields().count()
+}
+
+/// Returns the kind of directory the index lives in and, for one on disk, its path
+pub fn index_info(index_res: ResourceArc<IndexResource>) -> IndexInfo {
+    match index_res.path() {
+        Some(path) => IndexInfo {
+            directory: "mmap",
+            path: Some(path.to_string_lossy().into_owned()),
+        },
+        None => IndexInfo {
+            directory: "ram",
+            path: None,
+        },
+    }
+}
+
+/// Checks that the index can be read and queried, for liveness and readiness probes
+/// Opens a fresh reader on the last commit and counts every document with a query,
+/// so a corrupt or missing segment fails the check instead of a later search
+pub fn index_health_check(
+    index_res: ResourceArc<IndexResource>,
+) -> Result<HealthCheck, MuninnError> {
+    let started = Instant::now();
+
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?
+        .clone();
+
+    let reader: IndexReader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()
+        .map_err(|e| MuninnError::index_error(format!("Index is not readable: {}", e)))?;
+
+    let num_docs = reader
+        .searcher()
+        .search(&AllQuery, &Count)
+        .map_err(|e| MuninnError::search_error(format!("Index is not queryable: {}", e)))?;
+
+    Ok(HealthCheck {
+        num_docs,
+        latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+/// Returns the Tantivy version Muninn was compiled against and the index format it writes
+pub fn muninn_info() -> Result<VersionInfo, MuninnError> {
+    VersionInfo::from_version(tantivy::version())
+}
+
+/// Returns the Tantivy version and index format that wrote the index at a path
+/// Reads the footer of a segment file instead of opening the index, so it also works
+/// on indexes this build cannot open. Returns None for an index without segments
+pub fn index_format_info(path: String) -> Result<Option<VersionInfo>, MuninnError> {
+    let index_path = Path::new(&path);
+    if !index_path.join(META_FILE).exists() {
+        return Err(MuninnError::not_found(format!(
+            "No index found at '{}'",
+            path
+        )));
+    }
+
+    let entries = fs::read_dir(index_path)
+        .map_err(|e| MuninnError::index_error(format!("Failed to list index files: {}", e)))?;
+
+    // Every segment has a doc store file, and every file ends with the same footer
+    let mut store_files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| {
+            file.extension()
+                .is_some_and(|extension| extension == "store")
+        })
+        .collect();
+    store_files.sort();
+
+    let Some(store_file) = store_files.first() else {
+        return Ok(None);
+    };
+
+    let directory = MmapDirectory::open(index_path)
+        .map_err(|e| MuninnError::index_error(format!("Failed to open directory: {}", e)))?;
+
+    let file_name = store_file.file_name().map(Path::new).unwrap_or(store_file);
+    let file = directory
+        .open_read(file_name)
+        .map_err(|e| MuninnError::index_error(format!("Failed to read segment file: {}", e)))?;
+
+    let (footer, _) = Footer::extract_footer(file)
+        .map_err(|e| MuninnError::index_error(format!("Failed to read file footer: {}", e)))?;
+
+    VersionInfo::from_version(&footer.version).map(Some)
+}
+
 pub fn load(env: Env) -> bool {
     rustler::resource!(IndexResource, env);
     true
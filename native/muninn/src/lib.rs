@@ -10,13 +10,36 @@ mod atoms {
         invalid_schema,
         io_error,
         index_error,
+        parse_error,
+        invalid_argument,
+        search_error,
+        read_only,
+        index_locked,
+        result_window_exceeded,
+        already_exists,
+        internal_panic,
     }
 }
 
+mod aggregation;
+mod aliases;
+mod compiled;
+mod decay;
+mod durability;
+mod error;
+mod geo;
+mod grouping;
 mod index;
+mod percolate;
 mod reader;
 mod schema;
 mod searcher;
+mod sorted;
+mod stream;
+mod synonyms;
+mod terms;
+mod tiebreak;
+mod tokenizer;
 mod writer;
 
 // NIF entry point
@@ -36,45 +59,269 @@ fn schema_num_fields(schema: rustler::ResourceArc<schema::SchemaResource>) -> us
 fn index_create(
     path: String,
     schema_def: schema::SchemaDef,
+    max_limit: Option<usize>,
+    force: bool,
+) -> error::Reply<rustler::ResourceArc<index::IndexResource>> {
+    index::create_index(path, schema_def, max_limit, force).into()
+}
+
+#[rustler::nif]
+fn index_create_with_schema(
+    path: String,
+    schema: rustler::ResourceArc<schema::SchemaResource>,
+    max_limit: Option<usize>,
+    force: bool,
+) -> error::Reply<rustler::ResourceArc<index::IndexResource>> {
+    index::create_index_with_schema(path, schema, max_limit, force).into()
+}
+
+#[rustler::nif]
+fn index_open(
+    path: String,
+    max_limit: Option<usize>,
 ) -> Result<rustler::ResourceArc<index::IndexResource>, String> {
-    index::create_index(path, schema_def)
+    index::open_index(path, max_limit)
+}
+
+#[rustler::nif]
+fn index_open_read_only(
+    path: String,
+    max_limit: Option<usize>,
+) -> Result<rustler::ResourceArc<index::IndexResource>, String> {
+    index::open_index_read_only(path, max_limit)
+}
+
+#[rustler::nif]
+fn index_schema(
+    index: rustler::ResourceArc<index::IndexResource>,
+) -> error::Reply<rustler::ResourceArc<schema::SchemaResource>> {
+    index::index_schema(index).into()
+}
+
+#[rustler::nif]
+fn index_check_schema(
+    index: rustler::ResourceArc<index::IndexResource>,
+    schema_def: schema::SchemaDef,
+) -> error::Reply<Vec<schema::SchemaDifference>> {
+    index::index_check_schema(index, schema_def).into()
+}
+
+#[rustler::nif]
+fn index_num_fields(index: rustler::ResourceArc<index::IndexResource>) -> usize {
+    index::index_num_fields(index)
 }
 
 #[rustler::nif]
-fn index_open(path: String) -> Result<rustler::ResourceArc<index::IndexResource>, String> {
-    index::open_index(path)
+fn index_info(index: rustler::ResourceArc<index::IndexResource>) -> index::IndexInfo {
+    index::index_info(index)
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn index_health_check(
+    index: rustler::ResourceArc<index::IndexResource>,
+) -> error::Reply<index::HealthCheck> {
+    error::catch_panic(|| index::index_health_check(index))
 }
 
 #[rustler::nif]
+fn muninn_info() -> error::Reply<index::VersionInfo> {
+    index::muninn_info().into()
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn index_format_info(path: String) -> error::Reply<Option<index::VersionInfo>> {
+    index::index_format_info(path).into()
+}
+
+#[rustler::nif]
+fn index_register_cjk_tokenizer(
+    index: rustler::ResourceArc<index::IndexResource>,
+) -> error::Reply<()> {
+    tokenizer::index_register_cjk_tokenizer(index).into()
+}
+
+#[rustler::nif]
+fn index_set_synonyms(
+    index: rustler::ResourceArc<index::IndexResource>,
+    synonyms: std::collections::HashMap<String, Vec<String>>,
+) -> error::Reply<()> {
+    synonyms::index_set_synonyms(index, synonyms).into()
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn index_set_durability(
+    index: rustler::ResourceArc<index::IndexResource>,
+    durable: bool,
+) -> error::Reply<()> {
+    error::catch_panic(|| durability::index_set_durability(index, durable))
+}
+
+#[rustler::nif]
+fn index_list_tokenizers(
+    index: rustler::ResourceArc<index::IndexResource>,
+) -> error::Reply<Vec<String>> {
+    tokenizer::index_list_tokenizers(index).into()
+}
+
+#[rustler::nif]
+fn index_tokenize(
+    index: rustler::ResourceArc<index::IndexResource>,
+    field_name: String,
+    text: String,
+) -> error::Reply<tokenizer::FieldTokens> {
+    error::catch_panic(|| tokenizer::index_tokenize(index, field_name, text))
+}
+
+#[rustler::nif]
+fn index_commit_payload(
+    index: rustler::ResourceArc<index::IndexResource>,
+) -> error::Reply<Option<String>> {
+    index::index_commit_payload(index).into()
+}
+
+// Scheduled on a dirty IO scheduler since the add may trigger an auto-commit
+#[rustler::nif(schedule = "DirtyIo")]
 fn writer_add_document(
     index: rustler::ResourceArc<index::IndexResource>,
     document: rustler::Term,
-) -> Result<(), String> {
-    writer::writer_add_document(index, document)
+) -> error::Reply<(Option<u64>, Vec<String>)> {
+    error::catch_panic(|| writer::writer_add_document(index, document))
+}
+
+#[rustler::nif]
+fn writer_set_autocommit(
+    index: rustler::ResourceArc<index::IndexResource>,
+    every_n_docs: usize,
+) -> error::Reply<()> {
+    writer::writer_set_autocommit(index, every_n_docs).into()
+}
+
+#[rustler::nif]
+fn writer_pending_docs(index: rustler::ResourceArc<index::IndexResource>) -> error::Reply<usize> {
+    writer::writer_pending_docs(index).into()
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_commit(index: rustler::ResourceArc<index::IndexResource>) -> error::Reply<()> {
+    error::catch_panic(|| writer::writer_commit(index))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_multi_commit(
+    indexes: Vec<rustler::ResourceArc<index::IndexResource>>,
+) -> error::Reply<()> {
+    error::catch_panic(|| writer::writer_multi_commit(indexes))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_commit_and_merge(
+    index: rustler::ResourceArc<index::IndexResource>,
+    max_segments: usize,
+) -> error::Reply<usize> {
+    error::catch_panic(|| writer::writer_commit_and_merge(index, max_segments))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_optimize(index: rustler::ResourceArc<index::IndexResource>) -> error::Reply<()> {
+    error::catch_panic(|| writer::writer_optimize(index))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_commit_with_payload(
+    index: rustler::ResourceArc<index::IndexResource>,
+    payload: String,
+) -> error::Reply<()> {
+    error::catch_panic(|| writer::writer_commit_with_payload(index, payload))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_add_and_commit(
+    index: rustler::ResourceArc<index::IndexResource>,
+    document: rustler::Term,
+) -> error::Reply<u64> {
+    error::catch_panic(|| writer::writer_add_and_commit(index, document))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_delete_and_commit(
+    index: rustler::ResourceArc<index::IndexResource>,
+    field_name: String,
+    value: rustler::Term,
+) -> error::Reply<u64> {
+    error::catch_panic(|| writer::writer_delete_and_commit(index, field_name, value))
 }
 
 #[rustler::nif(schedule = "DirtyIo")]
-fn writer_commit(index: rustler::ResourceArc<index::IndexResource>) -> Result<(), String> {
-    writer::writer_commit(index)
+fn writer_delete_terms(
+    index: rustler::ResourceArc<index::IndexResource>,
+    field_name: String,
+    values: Vec<rustler::Term>,
+) -> error::Reply<usize> {
+    error::catch_panic(|| writer::writer_delete_terms(index, field_name, values))
 }
 
 #[rustler::nif]
-fn writer_rollback(index: rustler::ResourceArc<index::IndexResource>) -> Result<(), String> {
-    writer::writer_rollback(index)
+fn writer_rollback(index: rustler::ResourceArc<index::IndexResource>) -> error::Reply<()> {
+    error::catch_panic(|| writer::writer_rollback(index))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn writer_close(
+    index: rustler::ResourceArc<index::IndexResource>,
+    commit: bool,
+) -> error::Reply<()> {
+    error::catch_panic(|| writer::writer_close(index, commit))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn index_reindex(
+    source: rustler::ResourceArc<index::IndexResource>,
+    dest: rustler::ResourceArc<index::IndexResource>,
+    batch_size: usize,
+) -> error::Reply<u64> {
+    error::catch_panic(|| writer::index_reindex(source, dest, batch_size))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn index_merge_from(
+    index: rustler::ResourceArc<index::IndexResource>,
+    source_paths: Vec<String>,
+) -> error::Reply<u64> {
+    error::catch_panic(|| writer::index_merge_from(index, source_paths))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn index_swap(
+    index: rustler::ResourceArc<index::IndexResource>,
+    new_path: String,
+) -> error::Reply<rustler::ResourceArc<reader::ReaderResource>> {
+    error::catch_panic(|| index::index_swap(index, new_path))
 }
 
 #[rustler::nif]
 fn reader_new(
     index: rustler::ResourceArc<index::IndexResource>,
-) -> Result<rustler::ResourceArc<reader::ReaderResource>, String> {
-    reader::reader_new(index)
+    max_result_window: Option<usize>,
+    manual_reload: bool,
+) -> error::Reply<rustler::ResourceArc<reader::ReaderResource>> {
+    reader::reader_new(index, max_result_window, manual_reload).into()
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn reader_reload(reader: rustler::ResourceArc<reader::ReaderResource>) -> error::Reply<u64> {
+    reader::reader_reload(reader).into()
+}
+
+#[rustler::nif]
+fn reader_opstamp(reader: rustler::ResourceArc<reader::ReaderResource>) -> error::Reply<u64> {
+    reader::reader_opstamp(reader).into()
 }
 
 #[rustler::nif]
 fn searcher_new(
     reader: rustler::ResourceArc<reader::ReaderResource>,
-) -> Result<rustler::ResourceArc<searcher::SearcherResource>, String> {
-    searcher::searcher_new(reader)
+) -> error::Reply<rustler::ResourceArc<searcher::SearcherResource>> {
+    searcher::searcher_new(reader).into()
 }
 
 #[rustler::nif]
@@ -82,9 +329,10 @@ fn searcher_search_term<'a>(
     env: rustler::Env<'a>,
     searcher: rustler::ResourceArc<searcher::SearcherResource>,
     query: searcher::TermQueryDef,
+    scoring: String,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_term(env, searcher, query, limit)
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| searcher::searcher_search_term(env, searcher, query, scoring, limit))
 }
 
 #[rustler::nif]
@@ -93,9 +341,247 @@ fn searcher_search_query<'a>(
     searcher: rustler::ResourceArc<searcher::SearcherResource>,
     query_string: String,
     default_fields: Vec<String>,
+    options: searcher::SearchQueryOptions<'a>,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_query(env, searcher, query_string, default_fields, limit)
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_query(env, searcher, query_string, default_fields, options, limit)
+    })
+}
+
+#[rustler::nif]
+fn searcher_set_default_fields(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    field_names: Option<Vec<String>>,
+) -> error::Reply<()> {
+    searcher::searcher_set_default_fields(searcher, field_names).into()
+}
+
+#[rustler::nif]
+fn searcher_set_field_aliases(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    aliases: aliases::FieldAliases,
+) -> error::Reply<()> {
+    aliases::searcher_set_field_aliases(searcher, aliases).into()
+}
+
+#[rustler::nif]
+fn searcher_set_query_defaults(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    default_operator: String,
+    lenient: bool,
+    fuzzy_distance: u32,
+) -> error::Reply<()> {
+    searcher::searcher_set_query_defaults(searcher, default_operator, lenient, fuzzy_distance)
+        .into()
+}
+
+#[rustler::nif]
+fn searcher_get_doc<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    doc_address: (u32, u32),
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| searcher::searcher_get_doc(env, searcher, doc_address))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn searcher_term_dict(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    field_name: String,
+    prefix: String,
+    limit: usize,
+) -> error::Reply<Vec<(String, u64)>> {
+    error::catch_panic(|| terms::searcher_term_dict(searcher, field_name, prefix, limit))
+}
+
+#[rustler::nif]
+fn searcher_score_docs<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    doc_addresses: Vec<(u32, u32)>,
+    explain: bool,
+) -> error::Reply<Vec<rustler::Term<'a>>> {
+    error::catch_panic(|| {
+        searcher::searcher_score_docs(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            doc_addresses,
+            explain,
+        )
+    })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn searcher_doc_term_freqs<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    doc_address: (u32, u32),
+    field_name: String,
+    top_n: usize,
+) -> error::Reply<Vec<rustler::Term<'a>>> {
+    error::catch_panic(|| {
+        terms::searcher_doc_term_freqs(env, searcher, doc_address, field_name, top_n)
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_paged<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    tiebreak: Option<(String, String)>,
+    limit: usize,
+    offset: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_paged(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            tiebreak,
+            limit,
+            offset,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_grouped<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    group_field: String,
+    hits_per_group: usize,
+    num_groups: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        grouping::searcher_search_grouped(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            group_field,
+            hits_per_group,
+            num_groups,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_sorted<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    sort_field: String,
+    order: String,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        sorted::searcher_search_sorted(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            sort_field,
+            order,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_with_decay<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    decay_field: String,
+    scale: f64,
+    function: String,
+    origin: Option<i64>,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        decay::searcher_search_with_decay(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            decay_field,
+            scale,
+            function,
+            origin,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_aggregate<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    aggs_json: String,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        aggregation::searcher_aggregate(env, searcher, query_string, default_fields, aggs_json)
+    })
+}
+
+#[rustler::nif]
+fn searcher_cardinality(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    field_name: String,
+) -> error::Reply<u64> {
+    error::catch_panic(|| {
+        aggregation::searcher_cardinality(searcher, query_string, default_fields, field_name)
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_multi_field<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    field_boosts: Vec<(String, f64)>,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_multi_field(env, searcher, query_string, field_boosts, limit)
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_min_should_match<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    field_names: Vec<String>,
+    terms: Vec<String>,
+    min_match: usize,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_min_should_match(
+            env,
+            searcher,
+            field_names,
+            terms,
+            min_match,
+            limit,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -106,17 +592,106 @@ fn searcher_search_with_snippets<'a>(
     default_fields: Vec<String>,
     snippet_fields: Vec<String>,
     max_snippet_chars: usize,
+    full_text_threshold: usize,
+    context_words: usize,
+    snippet_tokenizers: Vec<(String, String)>,
+    best_snippet: bool,
+    scoring: String,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_with_snippets(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            snippet_fields,
+            max_snippet_chars,
+            full_text_threshold,
+            context_words,
+            snippet_tokenizers,
+            best_snippet,
+            scoring,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_bbox<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    lat_field: String,
+    lon_field: String,
+    min_lat: f64,
+    max_lat: f64,
+    min_lon: f64,
+    max_lon: f64,
+    query_string: String,
+    default_fields: Vec<String>,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_with_snippets(
-        env,
-        searcher,
-        query_string,
-        default_fields,
-        snippet_fields,
-        max_snippet_chars,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        geo::searcher_search_bbox(
+            env,
+            searcher,
+            lat_field,
+            lon_field,
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+            query_string,
+            default_fields,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_fast_fields<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    fast_fields: Vec<String>,
+    scoring: String,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_fast_fields(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            fast_fields,
+            scoring,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_ids<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    id_field: Option<String>,
+    limit: usize,
+    offset: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_ids(
+            env,
+            searcher,
+            query_string,
+            default_fields,
+            id_field,
+            limit,
+            offset,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -125,9 +700,33 @@ fn searcher_search_prefix<'a>(
     searcher: rustler::ResourceArc<searcher::SearcherResource>,
     field_name: String,
     prefix: String,
+    lowercase: Option<bool>,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_prefix(env, searcher, field_name, prefix, limit)
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_prefix(env, searcher, field_name, prefix, lowercase, limit)
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_wildcard<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    field_name: String,
+    pattern: String,
+    allow_leading_wildcard: bool,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_wildcard(
+            env,
+            searcher,
+            field_name,
+            pattern,
+            allow_leading_wildcard,
+            limit,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -140,17 +739,19 @@ fn searcher_search_range_u64<'a>(
     lower_inclusive: bool,
     upper_inclusive: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_range_u64(
-        env,
-        searcher,
-        field_name,
-        lower,
-        upper,
-        lower_inclusive,
-        upper_inclusive,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_range_u64(
+            env,
+            searcher,
+            field_name,
+            lower,
+            upper,
+            lower_inclusive,
+            upper_inclusive,
+            limit,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -163,17 +764,19 @@ fn searcher_search_range_i64<'a>(
     lower_inclusive: bool,
     upper_inclusive: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_range_i64(
-        env,
-        searcher,
-        field_name,
-        lower,
-        upper,
-        lower_inclusive,
-        upper_inclusive,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_range_i64(
+            env,
+            searcher,
+            field_name,
+            lower,
+            upper,
+            lower_inclusive,
+            upper_inclusive,
+            limit,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -186,17 +789,71 @@ fn searcher_search_range_f64<'a>(
     lower_inclusive: bool,
     upper_inclusive: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_range_f64(
-        env,
-        searcher,
-        field_name,
-        lower,
-        upper,
-        lower_inclusive,
-        upper_inclusive,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_range_f64(
+            env,
+            searcher,
+            field_name,
+            lower,
+            upper,
+            lower_inclusive,
+            upper_inclusive,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_search_range_text<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    field_name: String,
+    lower: String,
+    upper: String,
+    lower_inclusive: bool,
+    upper_inclusive: bool,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_range_text(
+            env,
+            searcher,
+            field_name,
+            lower,
+            upper,
+            lower_inclusive,
+            upper_inclusive,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif]
+fn searcher_doc_exists(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    field_name: String,
+    value: rustler::Term,
+) -> error::Reply<bool> {
+    error::catch_panic(|| searcher::searcher_doc_exists(searcher, field_name, value))
+}
+
+#[rustler::nif]
+fn searcher_count_multi(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    queries: Vec<String>,
+    default_fields: Vec<String>,
+) -> error::Reply<std::collections::HashMap<String, usize>> {
+    error::catch_panic(|| searcher::searcher_count_multi(searcher, queries, default_fields))
+}
+
+#[rustler::nif]
+fn searcher_estimate_hits(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+) -> error::Reply<u64> {
+    error::catch_panic(|| searcher::searcher_estimate_hits(searcher, query_string, default_fields))
 }
 
 #[rustler::nif]
@@ -205,19 +862,21 @@ fn searcher_search_fuzzy<'a>(
     searcher: rustler::ResourceArc<searcher::SearcherResource>,
     field_name: String,
     term: String,
-    distance: u8,
+    distance: u32,
     transposition_cost_one: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_fuzzy(
-        env,
-        searcher,
-        field_name,
-        term,
-        distance,
-        transposition_cost_one,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_fuzzy(
+            env,
+            searcher,
+            field_name,
+            term,
+            distance,
+            transposition_cost_one,
+            limit,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -226,19 +885,21 @@ fn searcher_search_fuzzy_prefix<'a>(
     searcher: rustler::ResourceArc<searcher::SearcherResource>,
     field_name: String,
     prefix: String,
-    distance: u8,
+    distance: u32,
     transposition_cost_one: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_fuzzy_prefix(
-        env,
-        searcher,
-        field_name,
-        prefix,
-        distance,
-        transposition_cost_one,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_fuzzy_prefix(
+            env,
+            searcher,
+            field_name,
+            prefix,
+            distance,
+            transposition_cost_one,
+            limit,
+        )
+    })
 }
 
 #[rustler::nif]
@@ -248,22 +909,91 @@ fn searcher_search_fuzzy_with_snippets<'a>(
     field_name: String,
     term: String,
     snippet_fields: Vec<String>,
-    distance: u8,
+    distance: u32,
     transposition_cost_one: bool,
     max_snippet_chars: usize,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    searcher::searcher_search_fuzzy_with_snippets(
-        env,
-        searcher,
-        field_name,
-        term,
-        snippet_fields,
-        distance,
-        transposition_cost_one,
-        max_snippet_chars,
-        limit,
-    )
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| {
+        searcher::searcher_search_fuzzy_with_snippets(
+            env,
+            searcher,
+            field_name,
+            term,
+            snippet_fields,
+            distance,
+            transposition_cost_one,
+            max_snippet_chars,
+            limit,
+        )
+    })
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn searcher_search_stream(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    batch_size: usize,
+) -> error::Reply<rustler::ResourceArc<stream::StreamCursorResource>> {
+    error::catch_panic(|| {
+        stream::searcher_search_stream(searcher, query_string, default_fields, batch_size)
+    })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+fn searcher_stream_next<'a>(
+    env: rustler::Env<'a>,
+    cursor: rustler::ResourceArc<stream::StreamCursorResource>,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| stream::searcher_stream_next(env, cursor))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_compile(
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+) -> error::Reply<rustler::ResourceArc<compiled::CompiledQueryResource>> {
+    error::catch_panic(|| compiled::query_compile(searcher, query_string, default_fields))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn searcher_run<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    compiled: rustler::ResourceArc<compiled::CompiledQueryResource>,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| compiled::searcher_run(env, searcher, compiled, limit))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn regex_compile(
+    pattern: String,
+    field_name: String,
+) -> error::Reply<rustler::ResourceArc<compiled::CompiledRegexResource>> {
+    error::catch_panic(|| compiled::regex_compile(pattern, field_name))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn searcher_run_regex<'a>(
+    env: rustler::Env<'a>,
+    searcher: rustler::ResourceArc<searcher::SearcherResource>,
+    compiled: rustler::ResourceArc<compiled::CompiledRegexResource>,
+    limit: usize,
+) -> error::Reply<rustler::Term<'a>> {
+    error::catch_panic(|| compiled::searcher_run_regex(env, searcher, compiled, limit))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn index_percolate<'a>(
+    schema_def: schema::SchemaDef,
+    document: rustler::Term<'a>,
+    queries: Vec<(rustler::Term<'a>, String)>,
+    default_fields: Vec<String>,
+) -> error::Reply<Vec<rustler::Term<'a>>> {
+    error::catch_panic(|| percolate::index_percolate(schema_def, document, queries, default_fields))
 }
 
 rustler::init!("Elixir.Muninn.Native", load = on_load);
@@ -274,5 +1004,7 @@ fn on_load(env: rustler::Env, _info: rustler::Term) -> bool {
     writer::load(env);
     reader::load(env);
     searcher::load(env);
+    stream::load(env);
+    compiled::load(env);
     true
 }
@@ -0,0 +1,106 @@
+use rustler::Term;
+use tantivy::collector::Count;
+use tantivy::schema::FieldType;
+use tantivy::{Index, IndexWriter, TantivyDocument};
+
+use crate::aliases::FieldAliases;
+use crate::error::MuninnError;
+use crate::schema::{build_schema, copy_fields, SchemaDef};
+use crate::searcher::parse_query;
+use crate::synonyms::SharedSynonyms;
+use crate::tokenizer::register_builtin_tokenizers;
+use crate::writer::{build_document, copy_field_values, decode_document};
+
+/// Smallest memory budget Tantivy accepts for an index writer thread
+const PERCOLATE_WRITER_MEMORY: usize = 15_000_000;
+
+/// Runs every query against a single document and returns the ids of those it matches,
+/// in the order the queries were given
+/// The document is indexed alone in a throwaway in-RAM index built from the schema, so
+/// only the tokenizers built into Tantivy and Muninn are available and no synonyms are
+/// expanded
+pub fn index_percolate<'a>(
+    schema_def: SchemaDef,
+    document: Term<'a>,
+    queries: Vec<(Term<'a>, String)>,
+    default_fields: Vec<String>,
+) -> Result<Vec<Term<'a>>, MuninnError> {
+    let copy_fields = copy_fields(&schema_def).map_err(MuninnError::invalid_argument)?;
+    let schema = build_schema(schema_def).map_err(MuninnError::invalid_argument)?;
+    let index = Index::create_in_ram(schema.clone());
+    register_builtin_tokenizers(&index);
+
+    // A missing tokenizer would only surface once the writer indexes the document
+    for (_, field_entry) in schema.fields() {
+        if let FieldType::Str(options) = field_entry.field_type() {
+            if let Some(indexing) = options.get_indexing_options() {
+                if index.tokenizers().get(indexing.tokenizer()).is_none() {
+                    return Err(MuninnError::invalid_argument(format!(
+                        "Tokenizer '{}' of field '{}' is not available when percolating",
+                        indexing.tokenizer(),
+                        field_entry.name()
+                    )));
+                }
+            }
+        }
+    }
+
+    let mut writer: IndexWriter<TantivyDocument> = index
+        .writer_with_num_threads(1, PERCOLATE_WRITER_MEMORY)
+        .map_err(|e| MuninnError::index_error(format!("Failed to create writer: {}", e)))?;
+
+    let copy_fields: Vec<_> = copy_fields
+        .iter()
+        .filter_map(|copy| {
+            Some((
+                schema.get_field(&copy.source).ok()?,
+                schema.get_field(&copy.target).ok()?,
+            ))
+        })
+        .collect();
+
+    let doc_map = decode_document(document)?;
+    let mut tantivy_doc = build_document(&schema, doc_map)?;
+    copy_field_values(&copy_fields, &mut tantivy_doc);
+
+    writer
+        .add_document(tantivy_doc)
+        .map_err(|e| MuninnError::index_error(format!("Failed to add document: {}", e)))?;
+    writer
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+
+    let searcher = index
+        .reader()
+        .map_err(|e| MuninnError::index_error(format!("Failed to create reader: {}", e)))?
+        .searcher();
+    let synonyms = SharedSynonyms::default();
+
+    let mut matching_ids = Vec::new();
+    for (position, (id, query_string)) in queries.into_iter().enumerate() {
+        let query = parse_query(
+            &searcher,
+            &synonyms,
+            &FieldAliases::new(),
+            &query_string,
+            &default_fields,
+            &[],
+        )
+        .map_err(|e| {
+            MuninnError::new(
+                e.kind,
+                format!("Query at position {}: {}", position, e.message),
+            )
+        })?;
+
+        let count = searcher
+            .search(&*query, &Count)
+            .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+        if count > 0 {
+            matching_ids.push(id);
+        }
+    }
+
+    Ok(matching_ids)
+}
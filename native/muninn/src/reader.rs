@@ -1,12 +1,34 @@
 use rustler::{Env, ResourceArc};
+use std::collections::BTreeSet;
 use std::panic::RefUnwindSafe;
-use tantivy::IndexReader;
+use std::sync::Mutex;
+use tantivy::schema::Field;
+use tantivy::{Index, IndexReader, Opstamp, ReloadPolicy};
 
+use crate::error::MuninnError;
 use crate::index::IndexResource;
+use crate::synonyms::SharedSynonyms;
+
+/// Number of times a manual reader is reloaded when commits keep landing while the
+/// opstamp of the commit it serves is looked up
+const PIN_ATTEMPTS: usize = 5;
 
 /// Resource wrapper for Tantivy IndexReader
 pub struct ReaderResource {
     pub reader: IndexReader,
+    /// Largest `limit` accepted by searchers created from this reader
+    pub max_limit: usize,
+    /// Field whose value is returned as "_id" in every search hit
+    pub id_field: Option<Field>,
+    /// Numeric fields whose values are returned as a list in every hit
+    pub multi_fields: Vec<Field>,
+    pub synonyms: SharedSynonyms,
+    /// Largest `offset + limit` a paginated search may reach, unlimited when None
+    pub max_result_window: Option<usize>,
+    pub index: Index,
+    /// Opstamp of the commit a manually reloaded reader serves, None for readers that
+    /// follow commits on their own
+    pub pinned_opstamp: Mutex<Option<Opstamp>>,
 }
 
 unsafe impl Send for ReaderResource {}
@@ -14,19 +36,127 @@ unsafe impl Sync for ReaderResource {}
 impl RefUnwindSafe for ReaderResource {}
 
 /// Creates a new IndexReader for the given index
+/// A manual reader stays on the commit it was created at until `reader_reload`,
+/// while other readers move to every new commit shortly after it happens
 pub fn reader_new(
     index_res: ResourceArc<IndexResource>,
-) -> Result<ResourceArc<ReaderResource>, String> {
+    max_result_window: Option<usize>,
+    manual_reload: bool,
+) -> Result<ResourceArc<ReaderResource>, MuninnError> {
+    if max_result_window == Some(0) {
+        return Err(MuninnError::invalid_argument(
+            "Max result window must be greater than 0",
+        ));
+    }
+
     let index = index_res
         .index
         .lock()
-        .map_err(|_| "Failed to acquire index lock".to_string())?;
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let reload_policy = if manual_reload {
+        ReloadPolicy::Manual
+    } else {
+        ReloadPolicy::OnCommitWithDelay
+    };
 
     let reader = index
-        .reader()
-        .map_err(|e| format!("Failed to create reader: {}", e))?;
+        .reader_builder()
+        .reload_policy(reload_policy)
+        .try_into()
+        .map_err(|e| MuninnError::index_error(format!("Failed to create reader: {}", e)))?;
+
+    let pinned_opstamp = if manual_reload {
+        Some(pin(&index, &reader)?)
+    } else {
+        None
+    };
+
+    let schema_settings = index_res.schema_settings();
+
+    Ok(ResourceArc::new(ReaderResource {
+        reader,
+        max_limit: index_res.max_limit,
+        id_field: schema_settings.id_field,
+        multi_fields: schema_settings.multi_fields,
+        synonyms: index_res.synonyms.clone(),
+        max_result_window,
+        index: index.clone(),
+        pinned_opstamp: Mutex::new(pinned_opstamp),
+    }))
+}
+
+/// Moves a manual reader to the latest commit and returns that commit's opstamp
+pub fn reader_reload(reader_res: ResourceArc<ReaderResource>) -> Result<Opstamp, MuninnError> {
+    let mut pinned_opstamp = reader_res
+        .pinned_opstamp
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire reader lock"))?;
+
+    if pinned_opstamp.is_none() {
+        return Err(MuninnError::invalid_argument(
+            "Only readers created with manual reloading can be reloaded",
+        ));
+    }
+
+    reader_res
+        .reader
+        .reload()
+        .map_err(|e| MuninnError::index_error(format!("Failed to reload reader: {}", e)))?;
+
+    let opstamp = pin(&reader_res.index, &reader_res.reader)?;
+    *pinned_opstamp = Some(opstamp);
+
+    Ok(opstamp)
+}
+
+/// Returns the opstamp of the commit a manual reader serves
+pub fn reader_opstamp(reader_res: ResourceArc<ReaderResource>) -> Result<Opstamp, MuninnError> {
+    reader_res
+        .pinned_opstamp
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire reader lock"))?
+        .ok_or_else(|| {
+            MuninnError::invalid_argument(
+                "Only readers created with manual reloading serve a fixed commit",
+            )
+        })
+}
+
+/// Looks up the opstamp of the commit a manual reader serves
+/// Only the latest commit's metadata is on disk, so it is read and compared with the
+/// reader's segments. A commit landing in between makes them differ, in which case the
+/// reader is moved to the newer commit and compared again
+fn pin(index: &Index, reader: &IndexReader) -> Result<Opstamp, MuninnError> {
+    for _ in 0..PIN_ATTEMPTS {
+        let metas = index
+            .load_metas()
+            .map_err(|e| MuninnError::index_error(format!("Failed to load metas: {}", e)))?;
+
+        let committed: BTreeSet<_> = metas
+            .segments
+            .iter()
+            .map(|segment| (segment.id(), segment.delete_opstamp()))
+            .collect();
+        let served: BTreeSet<_> = reader
+            .searcher()
+            .segment_readers()
+            .iter()
+            .map(|segment| (segment.segment_id(), segment.delete_opstamp()))
+            .collect();
+
+        if committed == served {
+            return Ok(metas.opstamp);
+        }
+
+        reader
+            .reload()
+            .map_err(|e| MuninnError::index_error(format!("Failed to reload reader: {}", e)))?;
+    }
 
-    Ok(ResourceArc::new(ReaderResource { reader }))
+    Err(MuninnError::index_error(
+        "Index kept changing while looking up the reader's commit",
+    ))
 }
 
 pub fn load(env: Env) -> bool {
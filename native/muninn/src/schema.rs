@@ -1,22 +1,317 @@
-use rustler::{Env, ResourceArc};
-use tantivy::schema::{NumericOptions, Schema, SchemaBuilder, TextFieldIndexing, TextOptions};
+use rustler::{Decoder, Encoder, Env, NifResult, ResourceArc, Term};
+use std::collections::HashMap;
+use tantivy::schema::{
+    FieldEntry, FieldType, IndexRecordOption, NumericOptions, Schema, SchemaBuilder,
+    TextFieldIndexing, TextOptions,
+};
+
+use crate::tokenizer::{field_tokenizer_names, RAW_LOWERCASE_TOKENIZER};
+
+/// Name of the hidden stored field holding each document's original JSON
+/// Added by a "source" field definition, see `SchemaDef`
+pub const SOURCE_FIELD: &str = "_source";
 
 /// Resource wrapper for Tantivy Schema
 pub struct SchemaResource {
     pub schema: Schema,
+    /// Muninn's settings of the schema, saved next to it by an index created from it
+    pub settings: SchemaDefSettings,
+}
+
+/// Muninn's settings of a schema definition, by field name
+/// Tantivy's schema has no room for them, so an index keeps them in files of its own
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDefSettings {
+    pub id_field: Option<String>,
+    pub required_fields: Vec<String>,
+    pub value_limits: Vec<ValueLimit>,
+    pub copy_fields: Vec<CopyField>,
+    pub multi_fields: Vec<String>,
+}
+
+impl SchemaDefSettings {
+    /// Reads and checks the settings of a schema definition
+    pub fn from_def(schema_def: &SchemaDef) -> Result<Self, String> {
+        Ok(SchemaDefSettings {
+            id_field: id_field_name(schema_def)?,
+            required_fields: required_field_names(schema_def),
+            value_limits: value_limits(schema_def)?,
+            copy_fields: copy_fields(schema_def)?,
+            multi_fields: multi_field_names(schema_def)?,
+        })
+    }
+}
+
+/// Field definition from Elixir - Using tuple (name, type, stored, indexed),
+/// optionally followed by a map of extra field options
+#[derive(Clone)]
+pub struct FieldDef {
+    pub name: String,
+    pub field_type: String,
+    pub stored: bool,
+    pub indexed: bool,
+    pub options: FieldOptions,
+}
+
+/// Extra field options from Elixir, keyed by option name
+/// Options missing from the map keep their default value
+#[derive(Debug, Default, Clone)]
+pub struct FieldOptions {
+    /// Store the values in a columnar fast field
+    pub fast: bool,
+    /// Name of a built-in tokenizer, or "cjk", for text fields, "default" when unset
+    pub tokenizer: Option<String>,
+    /// Identifies documents: its value is returned as "_id" in every search hit
+    pub id: bool,
+    /// Documents without a value for the field are rejected when added
+    pub required: bool,
+    /// Whether a text field indexes term positions, true when unset
+    /// Without positions only document ids are indexed, like a keyword field
+    pub positions: Option<bool>,
+    /// Largest value, in bytes, accepted for a text or keyword field
+    pub max_length: Option<usize>,
+    /// Truncate values above `max_length` instead of rejecting the document
+    pub truncate: bool,
+    /// Text field whose values are also indexed into this field when documents are added
+    pub copy_from: Option<String>,
+    /// Search hits return the values of a numeric field as a list, even a single one
+    pub multi: bool,
+}
+
+impl<'a> Decoder<'a> for FieldDef {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let elements = rustler::types::tuple::get_tuple(term)?;
+
+        let options = match elements.len() {
+            4 => FieldOptions::default(),
+            5 => FieldOptions::decode(elements[4])?,
+            _ => return Err(rustler::Error::BadArg),
+        };
+
+        Ok(FieldDef {
+            name: elements[0].decode()?,
+            field_type: elements[1].decode()?,
+            stored: elements[2].decode()?,
+            indexed: elements[3].decode()?,
+            options,
+        })
+    }
 }
 
-/// Field definition from Elixir - Using tuple (name, type, stored, indexed)
-pub type FieldDef = (String, String, bool, bool);
+impl<'a> Decoder<'a> for FieldOptions {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let option_map: HashMap<String, Term<'a>> = term.decode()?;
+        let mut options = FieldOptions::default();
+
+        for (key, value) in option_map {
+            match key.as_str() {
+                "fast" => options.fast = value.decode()?,
+                "tokenizer" => options.tokenizer = value.decode()?,
+                "id" => options.id = value.decode()?,
+                "required" => options.required = value.decode()?,
+                "positions" => options.positions = value.decode()?,
+                "max_length" => options.max_length = value.decode()?,
+                "copy_from" => options.copy_from = value.decode()?,
+                "multi" => options.multi = value.decode()?,
+                "on_too_long" => {
+                    options.truncate = match value.decode::<String>()?.as_str() {
+                        "truncate" => true,
+                        "reject" => false,
+                        _ => return Err(rustler::Error::BadArg),
+                    }
+                }
+                _ => return Err(rustler::Error::BadArg),
+            }
+        }
+
+        Ok(options)
+    }
+}
 
 /// Schema definition from Elixir - Using list of field definitions
 pub type SchemaDef = Vec<FieldDef>;
 
+/// Returns the name of the field designated as the id field, if any
+/// Fails if more than one field is designated, or if the id field is not a stored
+/// text, keyword or integer field
+pub fn id_field_name(schema_def: &SchemaDef) -> Result<Option<String>, String> {
+    let mut id_fields = schema_def.iter().filter(|field_def| field_def.options.id);
+
+    let Some(id_field) = id_fields.next() else {
+        return Ok(None);
+    };
+
+    if id_fields.next().is_some() {
+        return Err("Only one field can be the id field".to_string());
+    }
+
+    if !matches!(
+        id_field.field_type.as_str(),
+        "text" | "keyword" | "u64" | "i64"
+    ) {
+        return Err(format!(
+            "Id field '{}' must be a text, keyword, u64 or i64 field",
+            id_field.name
+        ));
+    }
+
+    if !id_field.stored {
+        return Err(format!("Id field '{}' must be stored", id_field.name));
+    }
+
+    Ok(Some(id_field.name.clone()))
+}
+
+/// Returns the names of the fields marked as required, in schema order
+pub fn required_field_names(schema_def: &SchemaDef) -> Vec<String> {
+    schema_def
+        .iter()
+        .filter(|field_def| field_def.options.required)
+        .map(|field_def| field_def.name.clone())
+        .collect()
+}
+
+/// Returns the names of the fields whose values are always returned as a list, in
+/// schema order
+/// Fails if one of them is not a u64, i64 or f64 field, the only types holding
+/// several values
+pub fn multi_field_names(schema_def: &SchemaDef) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+
+    for field_def in schema_def
+        .iter()
+        .filter(|field_def| field_def.options.multi)
+    {
+        if !matches!(field_def.field_type.as_str(), "u64" | "i64" | "f64") {
+            return Err(format!(
+                "Field '{}' must be a u64, i64 or f64 field to be multi-valued",
+                field_def.name
+            ));
+        }
+
+        names.push(field_def.name.clone());
+    }
+
+    Ok(names)
+}
+
+/// Size limit on the values of a text or keyword field, enforced when documents are added
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueLimit {
+    pub field_name: String,
+    /// Largest accepted value in bytes
+    pub max_length: usize,
+    /// Truncate longer values instead of rejecting the document
+    pub truncate: bool,
+}
+
+/// Returns the value limits of the fields with a max length, in schema order
+/// Fails if a limit is set on a field that is not a text or keyword field, or is 0
+pub fn value_limits(schema_def: &SchemaDef) -> Result<Vec<ValueLimit>, String> {
+    let mut limits = Vec::new();
+
+    for field_def in schema_def {
+        let Some(max_length) = field_def.options.max_length else {
+            continue;
+        };
+
+        if !matches!(field_def.field_type.as_str(), "text" | "keyword") {
+            return Err(format!(
+                "Max length of field '{}' is only supported on text and keyword fields",
+                field_def.name
+            ));
+        }
+
+        if max_length == 0 {
+            return Err(format!(
+                "Max length of field '{}' must be greater than 0",
+                field_def.name
+            ));
+        }
+
+        limits.push(ValueLimit {
+            field_name: field_def.name.clone(),
+            max_length,
+            truncate: field_def.options.truncate,
+        });
+    }
+
+    Ok(limits)
+}
+
+/// Field whose values are indexed a second time into another field, such as the exact
+/// companion of a stemmed text field
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyField {
+    pub source: String,
+    pub target: String,
+}
+
+/// Returns the fields copied into other fields, in schema order
+/// Both fields must be text or keyword fields, the target must be indexed, and a field
+/// cannot both copy from and be copied into another, so copies never chain
+pub fn copy_fields(schema_def: &SchemaDef) -> Result<Vec<CopyField>, String> {
+    let is_text =
+        |field_def: &FieldDef| matches!(field_def.field_type.as_str(), "text" | "keyword");
+    let mut copies = Vec::new();
+
+    for field_def in schema_def {
+        let Some(source) = &field_def.options.copy_from else {
+            continue;
+        };
+
+        if !is_text(field_def) || !field_def.indexed {
+            return Err(format!(
+                "Field '{}' copies from another field and must be an indexed text or keyword field",
+                field_def.name
+            ));
+        }
+
+        let Some(source_def) = schema_def
+            .iter()
+            .find(|source_def| &source_def.name == source)
+        else {
+            return Err(format!(
+                "Field '{}' copies from '{}', which is not in the schema",
+                field_def.name, source
+            ));
+        };
+
+        if !is_text(source_def) || source_def.options.copy_from.is_some() {
+            return Err(format!(
+                "Field '{}' cannot copy from '{}': the source must be a text or keyword field \
+                 that does not copy from another field",
+                field_def.name, source
+            ));
+        }
+
+        copies.push(CopyField {
+            source: source.clone(),
+            target: field_def.name.clone(),
+        });
+    }
+
+    Ok(copies)
+}
+
 /// Creates a Tantivy schema from the Elixir schema definition
 pub fn build_schema(schema_def: SchemaDef) -> Result<Schema, String> {
+    id_field_name(&schema_def)?;
+    value_limits(&schema_def)?;
+    copy_fields(&schema_def)?;
+    multi_field_names(&schema_def)?;
+
     let mut schema_builder = SchemaBuilder::new();
 
-    for (name, field_type, stored, indexed) in schema_def {
+    for field_def in schema_def {
+        let FieldDef {
+            name,
+            field_type,
+            stored,
+            indexed,
+            options,
+        } = field_def;
+
         match field_type.as_str() {
             "text" => {
                 let mut text_options = TextOptions::default();
@@ -25,12 +320,61 @@ pub fn build_schema(schema_def: SchemaDef) -> Result<Schema, String> {
                     text_options = text_options.set_stored();
                 }
 
+                // A stored-only payload field has no indexing options at all
+                if !indexed && options.tokenizer.is_some() {
+                    return Err(format!(
+                        "Field '{}' is not indexed and cannot have a tokenizer",
+                        name
+                    ));
+                }
+
                 if indexed {
+                    let tokenizer = options.tokenizer.as_deref().unwrap_or("default");
+                    let tokenizer_names = field_tokenizer_names();
+                    if !tokenizer_names.iter().any(|known| known == tokenizer) {
+                        return Err(format!(
+                            "Field '{}' uses unknown tokenizer '{}', expected one of {}",
+                            name,
+                            tokenizer,
+                            tokenizer_names.join(", ")
+                        ));
+                    }
+
+                    let index_option = if options.positions.unwrap_or(true) {
+                        IndexRecordOption::WithFreqsAndPositions
+                    } else {
+                        IndexRecordOption::Basic
+                    };
                     let indexing = TextFieldIndexing::default()
-                        .set_tokenizer("default")
-                        .set_index_option(
-                            tantivy::schema::IndexRecordOption::WithFreqsAndPositions,
-                        );
+                        .set_tokenizer(tokenizer)
+                        .set_index_option(index_option);
+                    text_options = text_options.set_indexing_options(indexing);
+                }
+
+                schema_builder.add_text_field(&name, text_options);
+            }
+            "keyword" => {
+                // Exact-match text: the whole value is a single untokenized term
+                let mut text_options = TextOptions::default();
+
+                if stored {
+                    text_options = text_options.set_stored();
+                }
+
+                if indexed {
+                    let tokenizer = match options.tokenizer.as_deref() {
+                        None | Some("raw") => "raw",
+                        Some(RAW_LOWERCASE_TOKENIZER) => RAW_LOWERCASE_TOKENIZER,
+                        Some(tokenizer) => {
+                            return Err(format!(
+                                "Keyword field '{}' cannot use tokenizer '{}', only raw or {}",
+                                name, tokenizer, RAW_LOWERCASE_TOKENIZER
+                            ));
+                        }
+                    };
+                    let indexing = TextFieldIndexing::default()
+                        .set_tokenizer(tokenizer)
+                        .set_index_option(tantivy::schema::IndexRecordOption::Basic);
                     text_options = text_options.set_indexing_options(indexing);
                 }
 
@@ -47,6 +391,10 @@ pub fn build_schema(schema_def: SchemaDef) -> Result<Schema, String> {
                     numeric_options = numeric_options.set_indexed();
                 }
 
+                if options.fast {
+                    numeric_options = numeric_options.set_fast();
+                }
+
                 match field_type.as_str() {
                     "u64" => schema_builder.add_u64_field(&name, numeric_options),
                     "i64" => schema_builder.add_i64_field(&name, numeric_options),
@@ -65,8 +413,19 @@ pub fn build_schema(schema_def: SchemaDef) -> Result<Schema, String> {
                     bool_options = bool_options.set_indexed();
                 }
 
+                if options.fast {
+                    bool_options = bool_options.set_fast();
+                }
+
                 schema_builder.add_bool_field(&name, bool_options);
             }
+            "source" => {
+                if name != SOURCE_FIELD {
+                    return Err(format!("Source field must be named '{}'", SOURCE_FIELD));
+                }
+
+                schema_builder.add_text_field(&name, TextOptions::default().set_stored());
+            }
             _ => {
                 return Err(format!("Unsupported field type: {}", field_type));
             }
@@ -77,10 +436,175 @@ pub fn build_schema(schema_def: SchemaDef) -> Result<Schema, String> {
     Ok(schema)
 }
 
+/// One way a schema definition differs from an index's actual schema
+#[derive(Debug)]
+pub struct SchemaDifference {
+    pub field: String,
+    /// "missing", "unexpected", "type", "stored", "indexed", "fast", "tokenizer" or "id"
+    pub difference: &'static str,
+    /// Value in the definition, None for an unexpected field
+    pub expected: Option<String>,
+    /// Value in the index, None for a missing field
+    pub actual: Option<String>,
+}
+
+impl Encoder for SchemaDifference {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        rustler::types::map::map_new(env)
+            .map_put("field".encode(env), self.field.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("difference".encode(env), self.difference.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("expected".encode(env), self.expected.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("actual".encode(env), self.actual.encode(env))
+            .ok()
+            .unwrap()
+    }
+}
+
+/// Returns the Muninn type name of a field, as used in schema definitions
+fn field_type_name(entry: &FieldEntry) -> &'static str {
+    match entry.field_type() {
+        FieldType::Str(options) => match options.get_indexing_options() {
+            Some(indexing)
+                if matches!(indexing.tokenizer(), "raw" | RAW_LOWERCASE_TOKENIZER)
+                    && indexing.index_option() == IndexRecordOption::Basic =>
+            {
+                "keyword"
+            }
+            _ => "text",
+        },
+        FieldType::U64(_) => "u64",
+        FieldType::I64(_) => "i64",
+        FieldType::F64(_) => "f64",
+        FieldType::Bool(_) => "bool",
+        FieldType::Date(_) => "date",
+        FieldType::Facet(_) => "facet",
+        FieldType::Bytes(_) => "bytes",
+        FieldType::JsonObject(_) => "json",
+        FieldType::IpAddr(_) => "ip_addr",
+    }
+}
+
+fn field_tokenizer(entry: &FieldEntry) -> Option<String> {
+    match entry.field_type() {
+        FieldType::Str(options) => options
+            .get_indexing_options()
+            .map(|indexing| indexing.tokenizer().to_string()),
+        _ => None,
+    }
+}
+
+/// Compares the schema built from a definition with an index's actual schema
+/// Fields are matched by name. Options are only compared for fields of the same type
+pub fn schema_differences(
+    expected: &Schema,
+    expected_id_field: Option<&str>,
+    actual: &Schema,
+    actual_id_field: Option<&str>,
+) -> Vec<SchemaDifference> {
+    let mut differences = Vec::new();
+
+    let mut push = |field: &str, difference, expected: Option<String>, actual: Option<String>| {
+        differences.push(SchemaDifference {
+            field: field.to_string(),
+            difference,
+            expected,
+            actual,
+        })
+    };
+
+    for (_, expected_entry) in expected.fields() {
+        let name = expected_entry.name();
+        let expected_type = field_type_name(expected_entry);
+
+        let Ok(actual_field) = actual.get_field(name) else {
+            push(name, "missing", Some(expected_type.to_string()), None);
+            continue;
+        };
+
+        let actual_entry = actual.get_field_entry(actual_field);
+        let actual_type = field_type_name(actual_entry);
+
+        if expected_type != actual_type {
+            push(
+                name,
+                "type",
+                Some(expected_type.to_string()),
+                Some(actual_type.to_string()),
+            );
+            continue;
+        }
+
+        let flags = [
+            (
+                "stored",
+                expected_entry.is_stored(),
+                actual_entry.is_stored(),
+            ),
+            (
+                "indexed",
+                expected_entry.is_indexed(),
+                actual_entry.is_indexed(),
+            ),
+            ("fast", expected_entry.is_fast(), actual_entry.is_fast()),
+        ];
+        for (difference, expected_flag, actual_flag) in flags {
+            if expected_flag != actual_flag {
+                push(
+                    name,
+                    difference,
+                    Some(expected_flag.to_string()),
+                    Some(actual_flag.to_string()),
+                );
+            }
+        }
+
+        let expected_tokenizer = field_tokenizer(expected_entry);
+        let actual_tokenizer = field_tokenizer(actual_entry);
+        if expected_tokenizer.is_some()
+            && actual_tokenizer.is_some()
+            && expected_tokenizer != actual_tokenizer
+        {
+            push(name, "tokenizer", expected_tokenizer, actual_tokenizer);
+        }
+    }
+
+    for (_, actual_entry) in actual.fields() {
+        if expected.get_field(actual_entry.name()).is_err() {
+            let actual_type = field_type_name(actual_entry);
+            push(
+                actual_entry.name(),
+                "unexpected",
+                None,
+                Some(actual_type.to_string()),
+            );
+        }
+    }
+
+    if expected_id_field != actual_id_field {
+        let field = expected_id_field.or(actual_id_field).unwrap_or_default();
+        push(
+            field,
+            "id",
+            expected_id_field.map(str::to_string),
+            actual_id_field.map(str::to_string),
+        );
+    }
+
+    differences
+}
+
 /// Builds a schema resource from definition
 pub fn schema_build(schema_def: SchemaDef) -> Result<ResourceArc<SchemaResource>, rustler::Error> {
-    let schema = build_schema(schema_def).map_err(|e| rustler::Error::Term(Box::new(e)))?;
-    Ok(ResourceArc::new(SchemaResource { schema }))
+    let error = |e: String| rustler::Error::Term(Box::new(e));
+    let settings = SchemaDefSettings::from_def(&schema_def).map_err(error)?;
+    let schema = build_schema(schema_def).map_err(error)?;
+    Ok(ResourceArc::new(SchemaResource { schema, settings }))
 }
 
 pub fn load(env: Env) -> bool {
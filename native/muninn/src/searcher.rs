@@ -1,24 +1,247 @@
-use rustler::{Env, ResourceArc};
-use std::collections::HashMap;
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA, SINK_STATE};
+use rustler::{Decoder, Env, NifResult, ResourceArc};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ops::Bound;
 use std::panic::RefUnwindSafe;
-use tantivy::collector::TopDocs;
-use tantivy::query::{FuzzyTermQuery, Query, QueryParser, RangeQuery, RegexQuery, TermQuery};
-use tantivy::schema::FieldType;
-use tantivy::snippet::SnippetGenerator;
-use tantivy::{Searcher, TantivyDocument, Term};
-
+use std::sync::{Arc, RwLock};
+use tantivy::collector::{Count, TopDocs};
+use tantivy::columnar::Column;
+use tantivy::query::{
+    BooleanQuery, BoostQuery, ConstScoreQuery, EmptyQuery, EnableScoring, ExistsQuery,
+    FuzzyTermQuery, Occur, Query, QueryParser, QueryParserError, RangeQuery, RegexQuery, TermQuery,
+};
+use tantivy::query_grammar::{UserInputAst, UserInputLeaf};
+use tantivy::schema::{Field, FieldType, IndexRecordOption, Schema, SchemaBuilder, Value};
+use tantivy::snippet::{Snippet, SnippetGenerator};
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::{
+    DocAddress, DocId, DocSet, Score, Searcher, SegmentReader, TantivyDocument, Term, TERMINATED,
+};
+use tantivy_fst::Automaton;
+
+use crate::aggregation::json_to_term;
+use crate::aliases::{resolve_alias, resolve_aliases, rewrite_field_aliases, FieldAliases};
+use crate::error::MuninnError;
 use crate::reader::ReaderResource;
+use crate::schema::SOURCE_FIELD;
+use crate::synonyms::{expand_synonyms, SharedSynonyms};
+use crate::tiebreak::{strip_tiebreak_keys, Tiebreak};
+use crate::tokenizer::RAW_LOWERCASE_TOKENIZER;
 
 /// Resource wrapper for Tantivy Searcher
 pub struct SearcherResource {
     pub searcher: Searcher,
+    /// Largest `limit` a search may request
+    pub max_limit: usize,
+    /// Field whose value is returned as "_id" in every hit
+    pub id_field: Option<Field>,
+    /// Numeric fields whose values are returned as a list in every hit
+    pub multi_fields: Vec<Field>,
+    /// Synonyms of the index, expanded when parsing query strings
+    pub synonyms: SharedSynonyms,
+    /// Largest `offset + limit` a paginated search may reach, unlimited when None
+    pub max_result_window: Option<usize>,
+    /// Stored fields returned in every hit's "doc" map, all of them when None
+    pub returned_fields: RwLock<Option<Vec<Field>>>,
+    /// Renamed fields, rewritten in query strings and field arguments before lookup
+    pub field_aliases: RwLock<Arc<FieldAliases>>,
+    /// Parsing settings of `searcher_search_query` query strings
+    pub query_defaults: RwLock<QueryDefaults>,
 }
 
 unsafe impl Send for SearcherResource {}
 unsafe impl Sync for SearcherResource {}
 impl RefUnwindSafe for SearcherResource {}
 
+impl SearcherResource {
+    /// Fields returned in hits unless a search asks for others
+    pub(crate) fn returned_fields(&self) -> Result<Option<Vec<Field>>, MuninnError> {
+        self.returned_fields
+            .read()
+            .map(|fields| fields.clone())
+            .map_err(|_| MuninnError::search_error("Failed to acquire returned fields lock"))
+    }
+
+    /// Aliases of renamed fields, see `searcher_set_field_aliases`
+    pub(crate) fn field_aliases(&self) -> Result<Arc<FieldAliases>, MuninnError> {
+        self.field_aliases
+            .read()
+            .map(|aliases| aliases.clone())
+            .map_err(|_| MuninnError::search_error("Failed to acquire field aliases lock"))
+    }
+
+    /// Parsing settings of query strings, see `searcher_set_query_defaults`
+    pub(crate) fn query_defaults(&self) -> Result<QueryDefaults, MuninnError> {
+        self.query_defaults
+            .read()
+            .map(|defaults| *defaults)
+            .map_err(|_| MuninnError::search_error("Failed to acquire query defaults lock"))
+    }
+
+    /// Current name of a field passed by its alias, see `searcher_set_field_aliases`
+    pub(crate) fn resolve_field_name(&self, field_name: &str) -> Result<String, MuninnError> {
+        let aliases = self.field_aliases()?;
+        Ok(resolve_alias(&aliases, field_name))
+    }
+
+    /// Rejects a limit above the index's maximum before TopDocs allocates for it
+    pub(crate) fn check_limit(&self, limit: usize) -> Result<(), MuninnError> {
+        if limit > self.max_limit {
+            return Err(MuninnError::invalid_argument(format!(
+                "Limit {} exceeds the maximum of {}",
+                limit, self.max_limit
+            )));
+        }
+        Ok(())
+    }
+
+    /// Rejects a page reaching past the reader's maximum result window, since ranking
+    /// a deep page means ranking every hit before it
+    pub(crate) fn check_result_window(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(), MuninnError> {
+        let Some(max_result_window) = self.max_result_window else {
+            return Ok(());
+        };
+
+        if offset.saturating_add(limit) > max_result_window {
+            return Err(MuninnError::result_window_exceeded(format!(
+                "Offset {} plus limit {} exceeds the maximum result window of {}",
+                offset, limit, max_result_window
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Largest number of matches `searcher_estimate_hits` visits in each segment
+const ESTIMATE_SAMPLE_DOCS: u32 = 10_000;
+
+/// Largest Levenshtein distance accepted for fuzzy queries. The automaton
+/// Tantivy builds grows quickly with the distance, so anything above 2 can
+/// stall the scheduler without producing useful matches.
+const MAX_FUZZY_DISTANCE: u32 = 2;
+
+/// How the query parser reads a query string
+/// The default is Tantivy's: optional clauses, strict parsing and exact terms
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryDefaults {
+    /// Clauses without an operator are required (AND) rather than optional (OR)
+    pub conjunction: bool,
+    /// Invalid parts of a query match nothing instead of failing the search
+    pub lenient: bool,
+    /// Levenshtein distance of the terms matched against tokenized text fields,
+    /// exact matching when 0
+    pub fuzzy_distance: u8,
+}
+
+impl QueryDefaults {
+    /// Returns these settings with the ones given for a single search replaced
+    fn with_overrides(
+        self,
+        default_operator: Option<String>,
+        lenient: Option<bool>,
+        fuzzy_distance: Option<u32>,
+    ) -> Result<Self, MuninnError> {
+        Ok(QueryDefaults {
+            conjunction: match default_operator {
+                Some(operator) => parse_default_operator(&operator)?,
+                None => self.conjunction,
+            },
+            lenient: lenient.unwrap_or(self.lenient),
+            fuzzy_distance: match fuzzy_distance {
+                Some(distance) => validate_fuzzy_distance(distance)?,
+                None => self.fuzzy_distance,
+            },
+        })
+    }
+
+    /// Operator joining clauses that do not name one
+    fn default_occur(&self) -> Occur {
+        if self.conjunction {
+            Occur::Must
+        } else {
+            Occur::Should
+        }
+    }
+}
+
+/// Options of `searcher_search_query` from Elixir, keyed by option name
+/// Options missing from the map keep their default value
+#[derive(Default)]
+pub struct SearchQueryOptions<'a> {
+    /// Field and value pairs whose matching documents are left out
+    pub exclude_terms: Vec<(String, rustler::Term<'a>)>,
+    /// Query strings every hit must also match, without affecting its score
+    pub filters: Vec<String>,
+    /// Field and tokenizer pairs analyzing the query text instead of the field's tokenizer
+    pub query_tokenizers: Vec<(String, String)>,
+    /// "bm25", or "const" to score every hit 1.0
+    pub scoring: String,
+    /// Each hit lists the query terms found in it
+    pub matched_terms: bool,
+    /// Each hit names the segment holding it
+    pub segment_ords: bool,
+    /// Each hit gets its `{segment_ord, doc_id}` address
+    pub doc_addresses: bool,
+    /// Scores are divided by the top score, keeping the original as "raw_score"
+    pub normalize_scores: bool,
+    /// Each hit gets the document's original JSON
+    pub source: bool,
+    /// Each hit gets the byte offsets of the matches in its stored text fields
+    pub match_offsets: bool,
+    /// Each hit gets its stored fields as a list in schema order
+    pub ordered_fields: bool,
+    /// Each hit gets the size of its stored document
+    pub doc_bytes: bool,
+    /// Field and "asc" or "desc" ordering hits with equal scores
+    pub tiebreak: Option<(String, String)>,
+    /// Stored fields returned in each hit, overriding the searcher's projection
+    pub fields: Option<Vec<String>>,
+    /// Overrides of the searcher's query defaults for this search
+    pub default_operator: Option<String>,
+    pub lenient: Option<bool>,
+    pub fuzzy_distance: Option<u32>,
+}
+
+impl<'a> Decoder<'a> for SearchQueryOptions<'a> {
+    fn decode(term: rustler::Term<'a>) -> NifResult<Self> {
+        let option_map: HashMap<String, rustler::Term<'a>> = term.decode()?;
+        let mut options = SearchQueryOptions {
+            scoring: "bm25".to_string(),
+            ..Default::default()
+        };
+
+        for (key, value) in option_map {
+            match key.as_str() {
+                "exclude_terms" => options.exclude_terms = value.decode()?,
+                "filters" => options.filters = value.decode()?,
+                "query_tokenizers" => options.query_tokenizers = value.decode()?,
+                "scoring" => options.scoring = value.decode()?,
+                "matched_terms" => options.matched_terms = value.decode()?,
+                "segment_ords" => options.segment_ords = value.decode()?,
+                "doc_addresses" => options.doc_addresses = value.decode()?,
+                "normalize_scores" => options.normalize_scores = value.decode()?,
+                "source" => options.source = value.decode()?,
+                "match_offsets" => options.match_offsets = value.decode()?,
+                "ordered_fields" => options.ordered_fields = value.decode()?,
+                "doc_bytes" => options.doc_bytes = value.decode()?,
+                "tiebreak" => options.tiebreak = value.decode()?,
+                "fields" => options.fields = value.decode()?,
+                "default_operator" => options.default_operator = value.decode()?,
+                "lenient" => options.lenient = value.decode()?,
+                "fuzzy_distance" => options.fuzzy_distance = value.decode()?,
+                _ => return Err(rustler::Error::BadArg),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
 /// Query definition passed from Elixir
 #[derive(Debug, rustler::NifStruct)]
 #[module = "Muninn.Query.Term"]
@@ -30,10 +253,20 @@ pub struct TermQueryDef {
 /// Creates a new Searcher from an IndexReader
 pub fn searcher_new(
     reader_res: ResourceArc<ReaderResource>,
-) -> Result<ResourceArc<SearcherResource>, String> {
+) -> Result<ResourceArc<SearcherResource>, MuninnError> {
     let searcher = reader_res.reader.searcher();
 
-    Ok(ResourceArc::new(SearcherResource { searcher }))
+    Ok(ResourceArc::new(SearcherResource {
+        searcher,
+        max_limit: reader_res.max_limit,
+        id_field: reader_res.id_field,
+        multi_fields: reader_res.multi_fields.clone(),
+        synonyms: reader_res.synonyms.clone(),
+        max_result_window: reader_res.max_result_window,
+        returned_fields: RwLock::new(None),
+        field_aliases: RwLock::new(Arc::default()),
+        query_defaults: RwLock::new(QueryDefaults::default()),
+    }))
 }
 
 /// Performs a term query search, returns native Elixir terms
@@ -41,49 +274,84 @@ pub fn searcher_search_term<'a>(
     env: rustler::Env<'a>,
     searcher_res: ResourceArc<SearcherResource>,
     query_def: TermQueryDef,
+    scoring: String,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&query_def.field)?;
     let schema = searcher.index().schema();
 
     // Get the field
     let field = schema
-        .get_field(&query_def.field)
-        .map_err(|_| format!("Field '{}' not found in schema", query_def.field))?;
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
     // Build the query based on field type
     let query: Box<dyn Query> = {
         let field_entry = schema.get_field_entry(field);
         match field_entry.field_type() {
-            FieldType::Str(_) => {
-                // For text fields, create a term query
-                let term = tantivy::Term::from_field_text(field, &query_def.value);
+            FieldType::Str(text_options) => {
+                // Analyze the value like indexed text, or a stemmed or lowercased field
+                // would never match it. Keyword fields index their values untouched
+                let tokenizer_name = text_options
+                    .get_indexing_options()
+                    .map(|indexing_options| indexing_options.tokenizer());
+                let value = match tokenizer_name {
+                    Some(tokenizer_name) if tokenizer_name != "raw" => {
+                        analyze_term_value(searcher, field, &field_name, &query_def.value)?
+                    }
+                    _ => query_def.value.clone(),
+                };
+
+                let term = tantivy::Term::from_field_text(field, &value);
                 Box::new(TermQuery::new(term, Default::default()))
             }
+            FieldType::Bool(_) => {
+                let value: bool = query_def.value.parse().map_err(|_| {
+                    MuninnError::invalid_argument(format!(
+                        "Field '{}' is a bool field and only matches \"true\" or \"false\", got \"{}\"",
+                        field_name, query_def.value
+                    ))
+                })?;
+
+                let term = tantivy::Term::from_field_bool(field, value);
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+            }
             _ => {
-                return Err(format!(
-                    "Field '{}' is not a text field. Only text fields are currently supported for term queries.",
-                    query_def.field
-                ));
+                return Err(MuninnError::invalid_argument(format!(
+                    "Field '{}' is not a text or bool field. Only text and bool fields are currently supported for term queries.",
+                    field_name
+                )));
             }
         }
     };
 
+    let query = apply_scoring(query, &scoring)?;
+
     // Execute the search
     let top_docs = searcher
         .search(&*query, &TopDocs::with_limit(limit))
-        .map_err(|e| format!("Search failed: {}", e))?;
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
 
     // Convert results to Elixir format
     let total_hits = top_docs.len();
     let mut hits = Vec::new();
 
-    for (score, doc_address) in top_docs {
-        let doc: TantivyDocument = searcher
-            .doc(doc_address)
-            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
-
-        let hit_map = document_to_hit_map(env, &schema, &doc, score);
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    for ((score, _), doc) in top_docs.into_iter().zip(docs) {
+        let hit_map = document_to_hit_map(
+            env,
+            &schema,
+            &doc,
+            score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?;
         hits.push(hit_map);
     }
 
@@ -109,53 +377,226 @@ pub fn searcher_search_query<'a>(
     searcher_res: ResourceArc<SearcherResource>,
     query_string: String,
     default_fields: Vec<String>,
+    options: SearchQueryOptions<'a>,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::Encoder;
+
+    searcher_res.check_limit(limit)?;
+
+    let SearchQueryOptions {
+        exclude_terms,
+        filters,
+        query_tokenizers,
+        scoring,
+        matched_terms,
+        segment_ords,
+        doc_addresses,
+        normalize_scores,
+        source,
+        match_offsets,
+        ordered_fields,
+        doc_bytes,
+        tiebreak,
+        fields,
+        default_operator,
+        lenient,
+        fuzzy_distance,
+    } = options;
+
     let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let query_defaults =
+        searcher_res
+            .query_defaults()?
+            .with_overrides(default_operator, lenient, fuzzy_distance)?;
     let schema = searcher.index().schema();
 
-    // Convert default field names to Field references
-    let mut fields = Vec::new();
-    for field_name in &default_fields {
-        let field = schema
-            .get_field(field_name)
-            .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
-        fields.push(field);
-    }
+    let returned_fields = match fields {
+        Some(field_names) => Some(resolve_returned_fields(
+            &schema,
+            &resolve_aliases(&aliases, &field_names),
+        )?),
+        None => searcher_res.returned_fields()?,
+    };
 
-    if fields.is_empty() {
-        return Err("At least one default field must be provided".to_string());
-    }
+    let tiebreak = tiebreak
+        .map(|(field_name, order)| {
+            Tiebreak::new(&schema, resolve_alias(&aliases, &field_name), &order)
+        })
+        .transpose()?;
+
+    let exclude_terms = exclude_terms
+        .into_iter()
+        .map(|(field_name, value)| (resolve_alias(&aliases, &field_name), value))
+        .collect();
+
+    let source_field = if source {
+        Some(schema.get_field(SOURCE_FIELD).map_err(|_| {
+            MuninnError::invalid_argument("The index does not store document sources")
+        })?)
+    } else {
+        None
+    };
 
-    // Create QueryParser with default fields
-    let query_parser = QueryParser::for_index(searcher.index(), fields);
+    let query = parse_query_with_defaults(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &query_tokenizers,
+        query_defaults,
+    )?;
+
+    // Collect the query's terms before scoring wraps the query
+    let query_terms = if matched_terms {
+        collect_query_terms(&*query)
+    } else {
+        Vec::new()
+    };
+
+    let match_offset_finder = if match_offsets {
+        Some(MatchOffsetFinder::create(searcher, &*query)?)
+    } else {
+        None
+    };
+
+    // Filters are read with the same operator, leniency and fuzziness as the query
+    let mut filter_queries = Vec::new();
+    for filter in &filters {
+        filter_queries.push(parse_query_with_defaults(
+            searcher,
+            &searcher_res.synonyms,
+            &aliases,
+            filter,
+            &default_fields,
+            &query_tokenizers,
+            query_defaults,
+        )?);
+    }
 
-    // Parse the query string
-    let query = query_parser
-        .parse_query(&query_string)
-        .map_err(|e| format!("Failed to parse query '{}': {}", query_string, e))?;
+    let query = apply_filters(query, filter_queries);
+    let query = apply_exclusions(searcher, query, exclude_terms)?;
+    let query = apply_scoring(query, &scoring)?;
 
     // Execute the search
-    let top_docs = searcher
-        .search(&*query, &TopDocs::with_limit(limit))
-        .map_err(|e| format!("Search failed: {}", e))?;
+    let top_docs = match &tiebreak {
+        Some(tiebreak) => searcher
+            .search(&*query, &tiebreak.top_docs(limit, 0))
+            .map(strip_tiebreak_keys),
+        None => searcher.search(&*query, &TopDocs::with_limit(limit)),
+    }
+    .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
 
     // Convert results to Elixir format
     let total_hits = top_docs.len();
     let mut hits = Vec::new();
 
-    for (score, doc_address) in top_docs {
-        let doc: TantivyDocument = searcher
-            .doc(doc_address)
-            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let doc_sizes = if doc_bytes {
+        stored_doc_bytes(searcher, &top_docs)?
+    } else {
+        Vec::new()
+    };
+
+    // Hits are sorted by score, so the first one holds the maximum
+    let top_score = top_docs.first().map_or(0.0, |(score, _)| *score);
+
+    for (hit_index, ((score, doc_address), doc)) in top_docs.into_iter().zip(docs).enumerate() {
+        let hit_score = if normalize_scores && top_score > 0.0 {
+            score / top_score
+        } else {
+            score
+        };
+
+        let mut hit_map = document_to_hit_map(
+            env,
+            &schema,
+            &doc,
+            hit_score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?;
+
+        if normalize_scores {
+            hit_map = hit_map
+                .map_put("raw_score".encode(env), score_to_f64(score).encode(env))
+                .ok()
+                .unwrap();
+        }
+
+        if matched_terms {
+            let matched = matched_terms_in_doc(env, searcher, doc_address, &query_terms)?;
+            hit_map = hit_map
+                .map_put("matched_terms".encode(env), matched.encode(env))
+                .ok()
+                .unwrap();
+        }
+
+        if let Some(finder) = &match_offset_finder {
+            hit_map = hit_map
+                .map_put("match_offsets".encode(env), finder.offsets(env, &doc))
+                .ok()
+                .unwrap();
+        }
+
+        if segment_ords {
+            hit_map = hit_map
+                .map_put(
+                    "segment_ord".encode(env),
+                    doc_address.segment_ord.encode(env),
+                )
+                .ok()
+                .unwrap();
+        }
+
+        if doc_addresses {
+            hit_map = hit_map
+                .map_put(
+                    "doc_address".encode(env),
+                    (doc_address.segment_ord, doc_address.doc_id).encode(env),
+                )
+                .ok()
+                .unwrap();
+        }
+
+        if ordered_fields {
+            let fields_list = stored_fields_to_list(
+                env,
+                &schema,
+                &doc,
+                returned_fields.as_deref(),
+                &searcher_res.multi_fields,
+            );
+            hit_map = hit_map
+                .map_put("fields".encode(env), fields_list)
+                .ok()
+                .unwrap();
+        }
+
+        if let Some(source_field) = source_field {
+            // Documents copied by reindex from an index without sources have none
+            let json = doc.get_first(source_field).and_then(|value| value.as_str());
+            hit_map = hit_map
+                .map_put("_source".encode(env), json.encode(env))
+                .ok()
+                .unwrap();
+        }
+
+        if let Some(size) = doc_sizes.get(hit_index) {
+            hit_map = hit_map
+                .map_put("_doc_bytes".encode(env), size.encode(env))
+                .ok()
+                .unwrap();
+        }
 
-        let hit_map = document_to_hit_map(env, &schema, &doc, score);
         hits.push(hit_map);
     }
 
     // Build the result map
     use rustler::types::map;
-    use rustler::Encoder;
 
     let result_map = map::map_new(env)
         .map_put("total_hits".encode(env), total_hits.encode(env))
@@ -168,52 +609,299 @@ pub fn searcher_search_query<'a>(
     Ok(result_map)
 }
 
-/// Performs a query with snippet highlighting
-/// Returns matching words highlighted in context
-pub fn searcher_search_with_snippets<'a>(
+/// Performs a query and returns one page of hits with the exact number of matches
+/// The page and the count are collected in a single pass over the same searcher, so
+/// they always agree, unlike a search followed by a separate count
+pub fn searcher_search_paged<'a>(
     env: rustler::Env<'a>,
     searcher_res: ResourceArc<SearcherResource>,
     query_string: String,
     default_fields: Vec<String>,
-    snippet_fields: Vec<String>,
-    max_snippet_chars: usize,
+    tiebreak: Option<(String, String)>,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+    offset: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::types::map;
+    use rustler::Encoder;
+
+    searcher_res.check_result_window(limit, offset)?;
+    searcher_res.check_limit(limit.saturating_add(offset))?;
+
     let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
     let schema = searcher.index().schema();
 
-    // Convert default field names to Field references
-    let mut fields = Vec::new();
-    for field_name in &default_fields {
+    let tiebreak = tiebreak
+        .map(|(field_name, order)| {
+            Tiebreak::new(&schema, resolve_alias(&aliases, &field_name), &order)
+        })
+        .transpose()?;
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let (total, top_docs) = match &tiebreak {
+        Some(tiebreak) => searcher
+            .search(&*query, &(Count, tiebreak.top_docs(limit, offset)))
+            .map(|(total, top_docs)| (total, strip_tiebreak_keys(top_docs))),
+        None => searcher.search(
+            &*query,
+            &(Count, TopDocs::with_limit(limit).and_offset(offset)),
+        ),
+    }
+    .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    let mut hits = Vec::new();
+    for ((score, _), doc) in top_docs.into_iter().zip(docs) {
+        hits.push(document_to_hit_map(
+            env,
+            &schema,
+            &doc,
+            score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?);
+    }
+
+    let result_map = map::map_new(env)
+        .map_put("total".encode(env), total.encode(env))
+        .ok()
+        .unwrap()
+        .map_put("hits".encode(env), hits.encode(env))
+        .ok()
+        .unwrap();
+
+    Ok(result_map)
+}
+
+/// Performs a query over several fields at once, weighting matches in each field
+/// Unqualified terms are searched in every field of `field_boosts`, and a match in a
+/// field has its score multiplied by that field's boost
+pub fn searcher_search_multi_field<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    field_boosts: Vec<(String, f64)>,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    if field_boosts.is_empty() {
+        return Err(MuninnError::invalid_argument(
+            "At least one field boost must be provided",
+        ));
+    }
+
+    let searcher = &searcher_res.searcher;
+    let schema = searcher.index().schema();
+    let aliases = searcher_res.field_aliases()?;
+
+    let mut boosted_fields = Vec::new();
+    for (field_name, boost) in &field_boosts {
+        let field_name = &resolve_alias(&aliases, field_name);
         let field = schema
             .get_field(field_name)
-            .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
-        fields.push(field);
+            .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+        if !schema.get_field_entry(field).is_indexed() {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' is not indexed and cannot be searched",
+                field_name
+            )));
+        }
+
+        if !boost.is_finite() || *boost < 0.0 {
+            return Err(MuninnError::invalid_argument(format!(
+                "Boost for field '{}' must be a non-negative number",
+                field_name
+            )));
+        }
+
+        boosted_fields.push((field, *boost as f32));
     }
 
-    if fields.is_empty() {
-        return Err("At least one default field must be provided".to_string());
+    let fields = boosted_fields.iter().map(|(field, _)| *field).collect();
+    let mut query_parser = QueryParser::new(
+        schema.clone(),
+        fields,
+        searcher.index().tokenizers().clone(),
+    );
+
+    for (field, boost) in boosted_fields {
+        query_parser.set_field_boost(field, boost);
     }
 
-    // Create QueryParser with default fields
-    let query_parser = QueryParser::for_index(searcher.index(), fields);
+    let query = parse_with_parser(
+        &query_parser,
+        &schema,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        QueryDefaults::default(),
+    )?;
+
+    execute_query(env, &searcher_res, &schema, &*query, limit)
+}
+
+/// Searches terms in text fields, matching documents that hold at least `min_match` of them
+/// Each term is one SHOULD clause of a boolean query, matching when any of the fields
+/// holds the term, so a term found in several fields still counts once. Terms are
+/// analyzed with each field's tokenizer and must analyze into a single term
+pub fn searcher_search_min_should_match<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    field_names: Vec<String>,
+    terms: Vec<String>,
+    min_match: usize,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    if field_names.is_empty() {
+        return Err(MuninnError::invalid_argument(
+            "At least one field must be provided",
+        ));
+    }
+
+    if min_match == 0 || min_match > terms.len() {
+        return Err(MuninnError::invalid_argument(format!(
+            "Min match must be between 1 and the number of terms ({}), got {}",
+            terms.len(),
+            min_match
+        )));
+    }
+
+    let searcher = &searcher_res.searcher;
+    let schema = searcher.index().schema();
+
+    let mut fields = Vec::new();
+    for field_name in &field_names {
+        let field_name = searcher_res.resolve_field_name(field_name)?;
+        let field = schema
+            .get_field(&field_name)
+            .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+        let field_entry = schema.get_field_entry(field);
+        if !matches!(field_entry.field_type(), FieldType::Str(_)) || !field_entry.is_indexed() {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' is not an indexed text field",
+                field_name
+            )));
+        }
+
+        fields.push((field_name, field));
+    }
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    for term in &terms {
+        let mut field_clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for (field_name, field) in &fields {
+            let value = analyze_term_value(searcher, *field, field_name, term)?;
+            let term_query = TermQuery::new(
+                Term::from_field_text(*field, &value),
+                IndexRecordOption::WithFreqs,
+            );
+            field_clauses.push((Occur::Should, Box::new(term_query)));
+        }
+
+        let clause: Box<dyn Query> = if field_clauses.len() == 1 {
+            field_clauses.pop().unwrap().1
+        } else {
+            Box::new(BooleanQuery::new(field_clauses))
+        };
+        clauses.push((Occur::Should, clause));
+    }
+
+    let query = BooleanQuery::with_minimum_required_clauses(clauses, min_match);
+
+    execute_query(env, &searcher_res, &schema, &query, limit)
+}
+
+/// Performs a query with snippet highlighting
+/// Returns matching words highlighted in context. A field listed in `snippet_tokenizers`
+/// is split with that tokenizer instead of its own when highlighting. With
+/// `context_words` above 0, snippets are that many whole words on each side of the best
+/// match instead of a fragment of at most `max_snippet_chars` characters. With
+/// `best_snippet`, each hit only gets the snippet of the field whose best fragment
+/// scores highest, in place of one snippet per field
+pub fn searcher_search_with_snippets<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    snippet_fields: Vec<String>,
+    max_snippet_chars: usize,
+    full_text_threshold: usize,
+    context_words: usize,
+    snippet_tokenizers: Vec<(String, String)>,
+    best_snippet: bool,
+    scoring: String,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let schema = searcher.index().schema();
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
 
-    // Parse the query string
-    let query = query_parser
-        .parse_query(&query_string)
-        .map_err(|e| format!("Failed to parse query '{}': {}", query_string, e))?;
+    let query = apply_scoring(query, &scoring)?;
+
+    let mut tokenizer_overrides = HashMap::new();
+    for (field_name, tokenizer) in &snippet_tokenizers {
+        let field_name = &resolve_alias(&aliases, field_name);
+        let field = schema
+            .get_field(field_name)
+            .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+        let analyzer = searcher
+            .index()
+            .tokenizers()
+            .get(tokenizer)
+            .ok_or_else(|| {
+                MuninnError::invalid_argument(format!(
+                    "Tokenizer '{}' is not registered on the index",
+                    tokenizer
+                ))
+            })?;
+        tokenizer_overrides.insert(field, analyzer);
+    }
 
     // Execute the search
     let top_docs = searcher
         .search(&*query, &TopDocs::with_limit(limit))
-        .map_err(|e| format!("Search failed: {}", e))?;
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
 
     // Create snippet generators for requested fields
     let mut snippet_generators = HashMap::new();
-    for field_name in &snippet_fields {
-        let field = schema
-            .get_field(field_name)
-            .map_err(|_| format!("Snippet field '{}' not found in schema", field_name))?;
+    let mut full_text_highlighters = HashMap::new();
+    let mut word_window_highlighters = HashMap::new();
+    let mut fragment_scorers = Vec::new();
+    for field_name in &resolve_aliases(&aliases, &snippet_fields) {
+        let field = schema.get_field(field_name).map_err(|_| {
+            MuninnError::not_found(format!(
+                "Snippet field '{}' not found in schema",
+                field_name
+            ))
+        })?;
 
         // Check if field is a text field
         let field_entry = schema.get_field_entry(field);
@@ -221,24 +909,73 @@ pub fn searcher_search_with_snippets<'a>(
             continue; // Skip non-text fields
         }
 
-        let mut generator = SnippetGenerator::create(searcher, &*query, field)
-            .map_err(|e| format!("Failed to create snippet generator: {}", e))?;
+        let tokenizer_override = tokenizer_overrides.get(&field).cloned();
+
+        let mut generator = match tokenizer_override.clone() {
+            Some(tokenizer) => SnippetGenerator::new(
+                snippet_terms_text(searcher, &*query, field)?,
+                tokenizer,
+                field,
+                max_snippet_chars,
+            ),
+            None => SnippetGenerator::create(searcher, &*query, field).map_err(|e| {
+                MuninnError::search_error(format!("Failed to create snippet generator: {}", e))
+            })?,
+        };
 
         generator.set_max_num_chars(max_snippet_chars);
         snippet_generators.insert(field_name.clone(), generator);
+
+        if best_snippet {
+            let scorer =
+                FragmentScorer::create(searcher, &*query, field, tokenizer_override.clone())?;
+            fragment_scorers.push((field_name.clone(), scorer));
+        }
+
+        if full_text_threshold > 0 {
+            let highlighter = FullTextHighlighter::create(
+                searcher,
+                &*query,
+                field,
+                full_text_threshold,
+                tokenizer_override.clone(),
+            )?;
+            full_text_highlighters.insert(field_name.clone(), highlighter);
+        }
+
+        if context_words > 0 {
+            let highlighter = WordWindowHighlighter::create(
+                searcher,
+                &*query,
+                field,
+                context_words,
+                tokenizer_override,
+            )?;
+            word_window_highlighters.insert(field_name.clone(), highlighter);
+        }
     }
 
     // Convert results to Elixir format with snippets
     let total_hits = top_docs.len();
     let mut hits = Vec::new();
 
-    for (score, doc_address) in top_docs {
-        let doc: TantivyDocument = searcher
-            .doc(doc_address)
-            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
-
-        let hit_map =
-            document_to_hit_map_with_snippets(env, &schema, &doc, score, &snippet_generators);
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    for ((score, _), doc) in top_docs.into_iter().zip(docs) {
+        let hit_map = document_to_hit_map_with_snippets(
+            env,
+            &schema,
+            &doc,
+            score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+            &snippet_generators,
+            &full_text_highlighters,
+            &word_window_highlighters,
+            best_snippet.then_some(fragment_scorers.as_slice()),
+        )?;
         hits.push(hit_map);
     }
 
@@ -257,69 +994,102 @@ pub fn searcher_search_with_snippets<'a>(
     Ok(result_map)
 }
 
-/// Performs a prefix search for autocomplete/typeahead
-/// Searches for terms starting with the given prefix
-pub fn searcher_search_prefix<'a>(
+/// Performs a query and returns fast field values instead of stored documents
+/// Values are read from each segment's columnar storage, skipping the doc store
+pub fn searcher_search_fast_fields<'a>(
     env: rustler::Env<'a>,
     searcher_res: ResourceArc<SearcherResource>,
-    field_name: String,
-    prefix: String,
+    query_string: String,
+    default_fields: Vec<String>,
+    fast_fields: Vec<String>,
+    scoring: String,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
     let schema = searcher.index().schema();
 
-    // Get the field
-    let field = schema
-        .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+    // Validate the requested fast fields up front
+    let mut requested = Vec::new();
+    for field_name in &resolve_aliases(&aliases, &fast_fields) {
+        let field = schema
+            .get_field(field_name)
+            .map_err(|_| MuninnError::field_not_found(field_name))?;
 
-    // Check if field is a text field
-    let field_entry = schema.get_field_entry(field);
-    if !matches!(field_entry.field_type(), FieldType::Str(_)) {
-        return Err(format!(
-            "Field '{}' is not a text field. Prefix search only works on text fields.",
-            field_name
-        ));
-    }
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_fast() {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' is not a fast field",
+                field_name
+            )));
+        }
 
-    // Create a regex pattern for prefix matching
-    // Note: Tantivy's RegexQuery uses tantivy-fst which has limitations
-    // We use a simple approach: ^prefix[a-z]* for lowercase text
-    if prefix.is_empty() {
-        return Err("Prefix cannot be empty".to_string());
+        if !matches!(
+            field_entry.field_type(),
+            FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) | FieldType::Bool(_)
+        ) {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' must be a numeric or bool fast field",
+                field_name
+            )));
+        }
+
+        requested.push((field_name.clone(), field_entry.field_type().clone()));
     }
 
-    let escaped_prefix = regex::escape(&prefix.to_lowercase());
-    // Match the prefix followed by any word characters
-    // [a-z0-9]* allows zero or more alphanumeric chars (matches exact term too)
-    let pattern = format!("{}[a-z0-9]*", escaped_prefix);
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
 
-    let regex_query = RegexQuery::from_pattern(&pattern, field)
-        .map_err(|e| format!("Failed to create prefix query: {}", e))?;
+    let query = apply_scoring(query, &scoring)?;
 
     // Execute the search
     let top_docs = searcher
-        .search(&regex_query, &TopDocs::with_limit(limit))
-        .map_err(|e| format!("Search failed: {}", e))?;
+        .search(&*query, &TopDocs::with_limit(limit))
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    // Column readers are opened once per segment and reused across hits
+    let mut segment_columns = Vec::new();
+    for segment_reader in searcher.segment_readers() {
+        let mut columns = Vec::new();
+        for (field_name, field_type) in &requested {
+            let column = FastFieldColumn::open(segment_reader, field_name, field_type)?;
+            columns.push((field_name.clone(), column));
+        }
+        segment_columns.push(columns);
+    }
 
-    // Convert results to Elixir format
     let total_hits = top_docs.len();
     let mut hits = Vec::new();
 
+    use rustler::types::map;
+    use rustler::Encoder;
+
     for (score, doc_address) in top_docs {
-        let doc: TantivyDocument = searcher
-            .doc(doc_address)
-            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+        let mut field_values: HashMap<String, rustler::Term> = HashMap::new();
+        for (field_name, column) in &segment_columns[doc_address.segment_ord as usize] {
+            if let Some(value) = column.first(env, doc_address.doc_id) {
+                field_values.insert(field_name.clone(), value);
+            }
+        }
 
-        let hit_map = document_to_hit_map(env, &schema, &doc, score);
+        let hit_map = map::map_new(env)
+            .map_put("score".encode(env), score_to_f64(score).encode(env))
+            .ok()
+            .unwrap()
+            .map_put("fields".encode(env), field_values.encode(env))
+            .ok()
+            .unwrap();
         hits.push(hit_map);
     }
 
-    // Build the result map
-    use rustler::types::map;
-    use rustler::Encoder;
-
     let result_map = map::map_new(env)
         .map_put("total_hits".encode(env), total_hits.encode(env))
         .ok()
@@ -331,45 +1101,347 @@ pub fn searcher_search_prefix<'a>(
     Ok(result_map)
 }
 
-/// Performs a range query on a u64 field
-pub fn searcher_search_range_u64<'a>(
+/// Performs a query and returns only the id of each hit with its score
+/// The id is read from the field's fast column when it is a fast u64 or i64 field, and
+/// otherwise from the stored document without building the rest of the hit.
+/// `id_field` defaults to the index's id field
+pub fn searcher_search_ids<'a>(
     env: rustler::Env<'a>,
     searcher_res: ResourceArc<SearcherResource>,
-    field_name: String,
-    lower: u64,
-    upper: u64,
-    lower_inclusive: bool,
-    upper_inclusive: bool,
+    query_string: String,
+    default_fields: Vec<String>,
+    id_field: Option<String>,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    let searcher = &searcher_res.searcher;
-    let schema = searcher.index().schema();
+    offset: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::types::map;
+    use rustler::Encoder;
 
-    let field = schema
-        .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+    searcher_res.check_result_window(limit, offset)?;
+    searcher_res.check_limit(limit.saturating_add(offset))?;
 
-    // Check field type
-    let field_entry = schema.get_field_entry(field);
-    if !matches!(field_entry.field_type(), FieldType::U64(_)) {
-        return Err(format!("Field '{}' is not a u64 field", field_name));
-    }
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let schema = searcher.index().schema();
 
-    let lower_bound = if lower_inclusive {
-        Bound::Included(Term::from_field_u64(field, lower))
-    } else {
-        Bound::Excluded(Term::from_field_u64(field, lower))
+    let id_field = match id_field.map(|field_name| resolve_alias(&aliases, &field_name)) {
+        Some(field_name) => schema
+            .get_field(&field_name)
+            .map_err(|_| MuninnError::field_not_found(&field_name))?,
+        None => searcher_res.id_field.ok_or_else(|| {
+            MuninnError::invalid_argument("The index has no id field, pass one explicitly")
+        })?,
     };
 
-    let upper_bound = if upper_inclusive {
-        Bound::Included(Term::from_field_u64(field, upper))
-    } else {
-        Bound::Excluded(Term::from_field_u64(field, upper))
+    let id_entry = schema.get_field_entry(id_field);
+    let fast_id_type = match id_entry.field_type() {
+        field_type @ (FieldType::U64(_) | FieldType::I64(_)) if id_entry.is_fast() => {
+            Some(field_type.clone())
+        }
+        _ => None,
     };
 
-    let range_query = RangeQuery::new(lower_bound, upper_bound);
+    if fast_id_type.is_none() && !id_entry.is_stored() {
+        return Err(MuninnError::invalid_argument(format!(
+            "Id field '{}' must be stored or a fast u64 or i64 field",
+            id_entry.name()
+        )));
+    }
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let top_docs = searcher
+        .search(&*query, &TopDocs::with_limit(limit).and_offset(offset))
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    let mut id_columns = Vec::new();
+    if let Some(field_type) = &fast_id_type {
+        for segment_reader in searcher.segment_readers() {
+            id_columns.push(FastFieldColumn::open(
+                segment_reader,
+                id_entry.name(),
+                field_type,
+            )?);
+        }
+    }
+
+    let total_hits = top_docs.len();
+    let mut hits = Vec::new();
+
+    for (score, doc_address) in top_docs {
+        let id = if fast_id_type.is_some() {
+            id_columns[doc_address.segment_ord as usize].first(env, doc_address.doc_id)
+        } else {
+            let doc: TantivyDocument = searcher.doc(doc_address).map_err(|e| {
+                MuninnError::search_error(format!("Failed to retrieve document: {}", e))
+            })?;
+            stored_id(env, &doc, id_field)
+        };
+
+        let id = id.ok_or_else(|| missing_id_error(&schema, id_field))?;
+
+        let hit_map = map::map_new(env)
+            .map_put("score".encode(env), score_to_f64(score).encode(env))
+            .ok()
+            .unwrap()
+            .map_put("_id".encode(env), id)
+            .ok()
+            .unwrap();
+        hits.push(hit_map);
+    }
+
+    let result_map = map::map_new(env)
+        .map_put("total_hits".encode(env), total_hits.encode(env))
+        .ok()
+        .unwrap()
+        .map_put("hits".encode(env), hits.encode(env))
+        .ok()
+        .unwrap();
+
+    Ok(result_map)
+}
+
+/// Performs a prefix search for autocomplete/typeahead
+/// Searches for terms starting with the given prefix
+pub fn searcher_search_prefix<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    field_name: String,
+    prefix: String,
+    lowercase: Option<bool>,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
+    let schema = searcher.index().schema();
+
+    // Get the field
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+    // Check if field is a text field
+    let field_entry = schema.get_field_entry(field);
+    let text_options = match field_entry.field_type() {
+        FieldType::Str(options) => options,
+        _ => {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' is not a text field. Prefix search only works on text fields.",
+                field_name
+            )));
+        }
+    };
+
+    // Create a regex pattern for prefix matching
+    // Note: Tantivy's RegexQuery uses tantivy-fst which has limitations
+    if prefix.is_empty() {
+        return Err(MuninnError::invalid_argument("Prefix cannot be empty"));
+    }
+
+    // Only lowercase the prefix when the field's tokenizer lowercased the indexed terms,
+    // unless the caller explicitly overrides it
+    let lowercase = lowercase.unwrap_or_else(|| {
+        text_options
+            .get_indexing_options()
+            .map(|indexing| tokenizer_lowercases(indexing.tokenizer()))
+            .unwrap_or(true)
+    });
+
+    let whole_values = text_options
+        .get_indexing_options()
+        .is_some_and(|indexing| indexes_whole_values(indexing.tokenizer()));
+    let prefix = if lowercase {
+        prefix.to_lowercase()
+    } else {
+        prefix
+    };
+
+    let pattern = if lowercase && !whole_values {
+        // Match the prefix followed by any word characters
+        // [a-z0-9]* allows zero or more alphanumeric chars (matches exact term too)
+        format!("{}[a-z0-9]*", regex::escape(&prefix))
+    } else {
+        // Terms are whole values, so the rest of the term can be anything
+        format!("{}.*", regex::escape(&prefix))
+    };
+
+    let regex_query = RegexQuery::from_pattern(&pattern, field).map_err(|e| {
+        MuninnError::invalid_argument(format!("Failed to create prefix query: {}", e))
+    })?;
+
+    // Execute the search
+    let top_docs = searcher
+        .search(&regex_query, &TopDocs::with_limit(limit))
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    // Convert results to Elixir format
+    let total_hits = top_docs.len();
+    let mut hits = Vec::new();
+
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    for ((score, _), doc) in top_docs.into_iter().zip(docs) {
+        let hit_map = document_to_hit_map(
+            env,
+            &schema,
+            &doc,
+            score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?;
+        hits.push(hit_map);
+    }
+
+    // Build the result map
+    use rustler::types::map;
+    use rustler::Encoder;
+
+    let result_map = map::map_new(env)
+        .map_put("total_hits".encode(env), total_hits.encode(env))
+        .ok()
+        .unwrap()
+        .map_put("hits".encode(env), hits.encode(env))
+        .ok()
+        .unwrap();
+
+    Ok(result_map)
+}
+
+/// Performs a wildcard search on the terms of a text field
+/// The pattern is glob style: `*` matches any run of characters and `?` a single one,
+/// and it must match a whole term. A pattern starting with a wildcard has to walk the
+/// entire term dictionary of the field, so it is rejected unless explicitly allowed
+pub fn searcher_search_wildcard<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    field_name: String,
+    pattern: String,
+    allow_leading_wildcard: bool,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
+    let schema = searcher.index().schema();
+
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+    let text_options = match schema.get_field_entry(field).field_type() {
+        FieldType::Str(options) => options,
+        _ => {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' is not a text field. Wildcard search only works on text fields.",
+                field_name
+            )));
+        }
+    };
+
+    if !pattern.chars().any(|c| c != '*' && c != '?') {
+        return Err(MuninnError::invalid_argument(
+            "Wildcard pattern must contain at least one character besides * and ?",
+        ));
+    }
+
+    if pattern.starts_with(['*', '?']) && !allow_leading_wildcard {
+        return Err(MuninnError::invalid_argument(format!(
+            "Wildcard pattern '{}' starts with a wildcard, which scans every term of the \
+             field; pass allow_leading_wildcard to run it anyway",
+            pattern
+        )));
+    }
+
+    if pattern.chars().any(char::is_whitespace) {
+        return Err(MuninnError::invalid_argument(
+            "Wildcard pattern cannot contain whitespace, since it matches a single term",
+        ));
+    }
+
+    // Lowercase the pattern when the field's tokenizer lowercased the indexed terms
+    let lowercase = text_options
+        .get_indexing_options()
+        .map(|indexing| tokenizer_lowercases(indexing.tokenizer()))
+        .unwrap_or(true);
+    let pattern = if lowercase {
+        pattern.to_lowercase()
+    } else {
+        pattern
+    };
+
+    let regex_pattern: String = pattern
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c => regex::escape(c.encode_utf8(&mut [0; 4])),
+        })
+        .collect();
+
+    let regex_query = RegexQuery::from_pattern(&regex_pattern, field).map_err(|e| {
+        MuninnError::invalid_argument(format!("Failed to create wildcard query: {}", e))
+    })?;
+
+    execute_query(env, &searcher_res, &schema, &regex_query, limit)
+}
+
+/// Performs a range query on a u64 field
+pub fn searcher_search_range_u64<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    field_name: String,
+    lower: u64,
+    upper: u64,
+    lower_inclusive: bool,
+    upper_inclusive: bool,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
+    let schema = searcher.index().schema();
+
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
-    execute_query(env, searcher, &schema, &range_query, limit)
+    // Check field type
+    let field_entry = schema.get_field_entry(field);
+    if !matches!(field_entry.field_type(), FieldType::U64(_)) {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not a u64 field",
+            field_name
+        )));
+    }
+
+    let lower_bound = if lower_inclusive {
+        Bound::Included(Term::from_field_u64(field, lower))
+    } else {
+        Bound::Excluded(Term::from_field_u64(field, lower))
+    };
+
+    let upper_bound = if upper_inclusive {
+        Bound::Included(Term::from_field_u64(field, upper))
+    } else {
+        Bound::Excluded(Term::from_field_u64(field, upper))
+    };
+
+    let range_query = RangeQuery::new(lower_bound, upper_bound);
+
+    execute_query(env, &searcher_res, &schema, &range_query, limit)
 }
 
 /// Performs a range query on an i64 field
@@ -382,17 +1454,23 @@ pub fn searcher_search_range_i64<'a>(
     lower_inclusive: bool,
     upper_inclusive: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
     let schema = searcher.index().schema();
 
     let field = schema
         .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
     let field_entry = schema.get_field_entry(field);
     if !matches!(field_entry.field_type(), FieldType::I64(_)) {
-        return Err(format!("Field '{}' is not an i64 field", field_name));
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not an i64 field",
+            field_name
+        )));
     }
 
     let lower_bound = if lower_inclusive {
@@ -409,7 +1487,7 @@ pub fn searcher_search_range_i64<'a>(
 
     let range_query = RangeQuery::new(lower_bound, upper_bound);
 
-    execute_query(env, searcher, &schema, &range_query, limit)
+    execute_query(env, &searcher_res, &schema, &range_query, limit)
 }
 
 /// Performs a range query on an f64 field
@@ -422,17 +1500,23 @@ pub fn searcher_search_range_f64<'a>(
     lower_inclusive: bool,
     upper_inclusive: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
     let schema = searcher.index().schema();
 
     let field = schema
         .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
     let field_entry = schema.get_field_entry(field);
     if !matches!(field_entry.field_type(), FieldType::F64(_)) {
-        return Err(format!("Field '{}' is not an f64 field", field_name));
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not an f64 field",
+            field_name
+        )));
     }
 
     let lower_bound = if lower_inclusive {
@@ -449,7 +1533,199 @@ pub fn searcher_search_range_f64<'a>(
 
     let range_query = RangeQuery::new(lower_bound, upper_bound);
 
-    execute_query(env, searcher, &schema, &range_query, limit)
+    execute_query(env, &searcher_res, &schema, &range_query, limit)
+}
+
+/// Checks whether any document has the given value in a field
+/// Counts matches of a term query without loading any document
+pub fn searcher_doc_exists(
+    searcher_res: ResourceArc<SearcherResource>,
+    field_name: String,
+    value: rustler::Term,
+) -> Result<bool, MuninnError> {
+    let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
+    let term = term_for_value(searcher.schema(), &field_name, value)?;
+
+    let query = TermQuery::new(term, IndexRecordOption::Basic);
+    let count = searcher
+        .search(&query, &Count)
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    Ok(count > 0)
+}
+
+/// Counts the matches of several query strings against the same searcher, so the counts
+/// of query variants are comparable. Returns a map of query string to count
+pub fn searcher_count_multi(
+    searcher_res: ResourceArc<SearcherResource>,
+    queries: Vec<String>,
+    default_fields: Vec<String>,
+) -> Result<HashMap<String, usize>, MuninnError> {
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+
+    let mut counts = HashMap::new();
+    for query_string in queries {
+        let query = parse_query(
+            searcher,
+            &searcher_res.synonyms,
+            &aliases,
+            &query_string,
+            &default_fields,
+            &[],
+        )?;
+
+        let count = searcher
+            .search(&*query, &Count)
+            .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+        counts.insert(query_string, count);
+    }
+
+    Ok(counts)
+}
+
+/// Estimates the number of documents matching a query by visiting at most
+/// `ESTIMATE_SAMPLE_DOCS` matches per segment
+/// A segment with fewer matches is counted exactly. Otherwise the matches seen are
+/// extrapolated over the rest of the segment, assuming they are spread evenly. Scorer
+/// size hints are not used, since unions report 0 once their first block is buffered
+pub fn searcher_estimate_hits(
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+) -> Result<u64, MuninnError> {
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let search_error =
+        |e: tantivy::TantivyError| MuninnError::search_error(format!("Search failed: {}", e));
+
+    let weight = query
+        .weight(EnableScoring::disabled_from_searcher(searcher))
+        .map_err(search_error)?;
+
+    let mut estimate = 0.0;
+    for segment_reader in searcher.segment_readers() {
+        let mut scorer = weight.scorer(segment_reader, 1.0).map_err(search_error)?;
+        let alive_bitset = segment_reader.alive_bitset();
+
+        let mut visited = 0;
+        let mut matches = 0;
+        let mut doc = scorer.doc();
+        while doc != TERMINATED && visited < ESTIMATE_SAMPLE_DOCS {
+            visited += 1;
+            if alive_bitset.is_none_or(|alive_bitset| alive_bitset.is_alive(doc)) {
+                matches += 1;
+            }
+            doc = scorer.advance();
+        }
+
+        estimate += if doc == TERMINATED {
+            matches as f64
+        } else {
+            // The matches seen all lie below `doc`
+            matches as f64 * segment_reader.max_doc() as f64 / doc as f64
+        };
+    }
+
+    Ok(estimate.round() as u64)
+}
+
+/// Performs a lexicographic range query on a text field
+/// Bounds are compared with the indexed terms byte by byte, so the range applies
+/// to whole values on keyword fields and to individual tokens on tokenized fields
+pub fn searcher_search_range_text<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    field_name: String,
+    lower: String,
+    upper: String,
+    lower_inclusive: bool,
+    upper_inclusive: bool,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
+    let schema = searcher.index().schema();
+
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+    let field_entry = schema.get_field_entry(field);
+    if !matches!(field_entry.field_type(), FieldType::Str(_)) {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not a text field",
+            field_name
+        )));
+    }
+
+    if !field_entry.is_indexed() {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not indexed",
+            field_name
+        )));
+    }
+
+    let lower_bound = if lower_inclusive {
+        Bound::Included(Term::from_field_text(field, &lower))
+    } else {
+        Bound::Excluded(Term::from_field_text(field, &lower))
+    };
+
+    let upper_bound = if upper_inclusive {
+        Bound::Included(Term::from_field_text(field, &upper))
+    } else {
+        Bound::Excluded(Term::from_field_text(field, &upper))
+    };
+
+    let range_query = RangeQuery::new(lower_bound, upper_bound);
+
+    execute_query(env, &searcher_res, &schema, &range_query, limit)
+}
+
+/// Runs a term query value through the tokenizer of its field, which must turn it into
+/// exactly one term, since a term query cannot match several
+fn analyze_term_value(
+    searcher: &Searcher,
+    field: Field,
+    field_name: &str,
+    value: &str,
+) -> Result<String, MuninnError> {
+    let mut tokenizer = searcher
+        .index()
+        .tokenizer_for_field(field)
+        .map_err(|e| MuninnError::search_error(format!("Failed to load field tokenizer: {}", e)))?;
+
+    let mut terms = Vec::new();
+    let mut token_stream = tokenizer.token_stream(value);
+    while let Some(token) = token_stream.next() {
+        terms.push(token.text.clone());
+    }
+
+    match <[String; 1]>::try_from(terms) {
+        Ok([term]) => Ok(term),
+        Err(terms) => Err(MuninnError::invalid_argument(format!(
+            "Term '{}' is analyzed into {} terms by the tokenizer of field '{}', but a term \
+             query matches exactly one. Use search_query/4 to search text",
+            value,
+            terms.len(),
+            field_name
+        ))),
+    }
 }
 
 /// Performs a fuzzy search with Levenshtein distance
@@ -458,38 +1734,39 @@ pub fn searcher_search_fuzzy<'a>(
     searcher_res: ResourceArc<SearcherResource>,
     field_name: String,
     term: String,
-    distance: u8,
+    distance: u32,
     transposition_cost_one: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
     let schema = searcher.index().schema();
 
     // Validate field exists
     let field = schema
         .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
     // Validate field is text type
     let field_entry = schema.get_field_entry(field);
     if !matches!(field_entry.field_type(), FieldType::Str(_)) {
-        return Err(format!(
+        return Err(MuninnError::invalid_argument(format!(
             "Field '{}' must be a text field. Fuzzy search only works on text fields.",
             field_name
-        ));
+        )));
     }
 
     // Validate distance
-    if distance > 2 {
-        return Err("Distance must be between 0 and 2".to_string());
-    }
+    let distance = validate_fuzzy_distance(distance)?;
 
     // Create Tantivy fuzzy query
     let tantivy_term = Term::from_field_text(field, &term);
     let fuzzy_query = FuzzyTermQuery::new(tantivy_term, distance, transposition_cost_one);
 
     // Execute and return results
-    execute_query(env, searcher, &schema, &fuzzy_query, limit)
+    execute_query(env, &searcher_res, &schema, &fuzzy_query, limit)
 }
 
 /// Performs a fuzzy prefix search combining autocomplete with typo tolerance
@@ -498,38 +1775,39 @@ pub fn searcher_search_fuzzy_prefix<'a>(
     searcher_res: ResourceArc<SearcherResource>,
     field_name: String,
     prefix: String,
-    distance: u8,
+    distance: u32,
     transposition_cost_one: bool,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let field_name = searcher_res.resolve_field_name(&field_name)?;
     let schema = searcher.index().schema();
 
     // Validate field exists
     let field = schema
         .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
     // Validate field is text type
     let field_entry = schema.get_field_entry(field);
     if !matches!(field_entry.field_type(), FieldType::Str(_)) {
-        return Err(format!(
+        return Err(MuninnError::invalid_argument(format!(
             "Field '{}' must be a text field. Fuzzy prefix search only works on text fields.",
             field_name
-        ));
+        )));
     }
 
     // Validate distance
-    if distance > 2 {
-        return Err("Distance must be between 0 and 2".to_string());
-    }
+    let distance = validate_fuzzy_distance(distance)?;
 
     // Create Tantivy fuzzy prefix query
     let tantivy_term = Term::from_field_text(field, &prefix);
     let fuzzy_query = FuzzyTermQuery::new_prefix(tantivy_term, distance, transposition_cost_one);
 
     // Execute and return results
-    execute_query(env, searcher, &schema, &fuzzy_query, limit)
+    execute_query(env, &searcher_res, &schema, &fuzzy_query, limit)
 }
 
 /// Performs fuzzy search with highlighted snippets
@@ -539,32 +1817,35 @@ pub fn searcher_search_fuzzy_with_snippets<'a>(
     field_name: String,
     term: String,
     snippet_fields: Vec<String>,
-    distance: u8,
+    distance: u32,
     transposition_cost_one: bool,
-    _max_snippet_chars: usize,
+    max_snippet_chars: usize,
     limit: usize,
-) -> Result<rustler::Term<'a>, String> {
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
     let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let field_name = resolve_alias(&aliases, &field_name);
+    let snippet_fields = resolve_aliases(&aliases, &snippet_fields);
     let schema = searcher.index().schema();
 
     // Validate field exists
     let field = schema
         .get_field(&field_name)
-        .map_err(|_| format!("Field '{}' not found in schema", field_name))?;
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
 
     // Validate field is text type
     let field_entry = schema.get_field_entry(field);
     if !matches!(field_entry.field_type(), FieldType::Str(_)) {
-        return Err(format!(
+        return Err(MuninnError::invalid_argument(format!(
             "Field '{}' must be a text field. Fuzzy search only works on text fields.",
             field_name
-        ));
+        )));
     }
 
     // Validate distance
-    if distance > 2 {
-        return Err("Distance must be between 0 and 2".to_string());
-    }
+    let distance = validate_fuzzy_distance(distance)?;
 
     // Create Tantivy fuzzy query
     let tantivy_term = Term::from_field_text(field, &term);
@@ -573,32 +1854,61 @@ pub fn searcher_search_fuzzy_with_snippets<'a>(
     // Execute search
     let top_docs = searcher
         .search(&fuzzy_query, &TopDocs::with_limit(limit))
-        .map_err(|e| format!("Search failed: {}", e))?;
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
 
     let total_hits = top_docs.len();
     let mut hits = Vec::new();
 
-    // Create snippet generators for requested fields
+    // A fuzzy query exposes no terms to highlight, so every snippet field highlights the
+    // terms of its own dictionary within the distance of the searched term. A field with
+    // none gets an empty snippet
     let mut snippet_generators: HashMap<String, SnippetGenerator> = HashMap::new();
     for snippet_field_name in &snippet_fields {
-        let snippet_field = schema
-            .get_field(snippet_field_name)
-            .map_err(|_| format!("Snippet field '{}' not found in schema", snippet_field_name))?;
-
-        let generator = SnippetGenerator::create(searcher, &fuzzy_query, snippet_field)
-            .map_err(|e| format!("Failed to create snippet generator: {}", e))?;
+        let snippet_field = schema.get_field(snippet_field_name).map_err(|_| {
+            MuninnError::not_found(format!(
+                "Snippet field '{}' not found in schema",
+                snippet_field_name
+            ))
+        })?;
+
+        let terms_text = fuzzy_terms_text(
+            searcher,
+            snippet_field,
+            &term,
+            distance,
+            transposition_cost_one,
+        )?;
+        let analyzer = searcher
+            .index()
+            .tokenizer_for_field(snippet_field)
+            .map_err(|e| {
+                MuninnError::search_error(format!("Failed to create snippet generator: {}", e))
+            })?;
+
+        let generator =
+            SnippetGenerator::new(terms_text, analyzer, snippet_field, max_snippet_chars);
 
         snippet_generators.insert(snippet_field_name.clone(), generator);
     }
 
     // Generate results with snippets
-    for (score, doc_address) in top_docs {
-        let doc: TantivyDocument = searcher
-            .doc(doc_address)
-            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
-
-        let hit_map =
-            document_to_hit_map_with_snippets(env, &schema, &doc, score, &snippet_generators);
+    let docs = fetch_documents(searcher, &top_docs)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    for ((score, _), doc) in top_docs.into_iter().zip(docs) {
+        let hit_map = document_to_hit_map_with_snippets(
+            env,
+            &schema,
+            &doc,
+            score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+            &snippet_generators,
+            &HashMap::new(),
+            &HashMap::new(),
+            None,
+        )?;
         hits.push(hit_map);
     }
 
@@ -616,28 +1926,737 @@ pub fn searcher_search_fuzzy_with_snippets<'a>(
     Ok(result_map)
 }
 
-/// Helper function to execute a query and return results
-fn execute_query<'a>(
-    env: rustler::Env<'a>,
+/// Loads the stored documents of a list of hits, preserving the hit order
+/// Hits come back ordered by score, which scatters them across doc store blocks.
+/// Fetching in doc address order instead reads hits that share a compressed
+/// block back-to-back, so each block is decompressed once and then served from
+/// the store's block cache. When hits are sparse or the limit is small, they rarely
+/// share a block and the order makes no difference
+pub(crate) fn fetch_documents(
     searcher: &Searcher,
-    schema: &tantivy::schema::Schema,
-    query: &dyn Query,
-    limit: usize,
-) -> Result<rustler::Term<'a>, String> {
-    let top_docs = searcher
-        .search(query, &TopDocs::with_limit(limit))
-        .map_err(|e| format!("Search failed: {}", e))?;
-
-    let total_hits = top_docs.len();
-    let mut hits = Vec::new();
+    hits: &[(f32, DocAddress)],
+) -> Result<Vec<TantivyDocument>, MuninnError> {
+    let mut order: Vec<usize> = (0..hits.len()).collect();
+    order.sort_unstable_by_key(|&i| hits[i].1);
+
+    let mut docs: Vec<Option<TantivyDocument>> = (0..hits.len()).map(|_| None).collect();
+    for i in order {
+        let doc: TantivyDocument = searcher.doc(hits[i].1).map_err(|e| {
+            MuninnError::search_error(format!("Failed to retrieve document: {}", e))
+        })?;
+        docs[i] = Some(doc);
+    }
 
-    for (score, doc_address) in top_docs {
-        let doc: TantivyDocument = searcher
-            .doc(doc_address)
-            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+    Ok(docs.into_iter().flatten().collect())
+}
 
-        let hit_map = document_to_hit_map(env, schema, &doc, score);
-        hits.push(hit_map);
+/// Returns the size in bytes of each hit's stored document as serialized in the doc
+/// store, before compression. Every stored field counts, whether or not it is returned
+fn stored_doc_bytes(
+    searcher: &Searcher,
+    hits: &[(f32, DocAddress)],
+) -> Result<Vec<usize>, MuninnError> {
+    let mut store_readers = HashMap::new();
+    let mut sizes = Vec::with_capacity(hits.len());
+
+    for (_, doc_address) in hits {
+        let store_reader = match store_readers.entry(doc_address.segment_ord) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(
+                searcher
+                    .segment_reader(doc_address.segment_ord)
+                    .get_store_reader(1)
+                    .map_err(|e| {
+                        MuninnError::search_error(format!("Failed to open doc store: {}", e))
+                    })?,
+            ),
+        };
+
+        let bytes = store_reader
+            .get_document_bytes(doc_address.doc_id)
+            .map_err(|e| {
+                MuninnError::search_error(format!("Failed to retrieve document: {}", e))
+            })?;
+        sizes.push(bytes.len());
+    }
+
+    Ok(sizes)
+}
+
+/// Returns the distinct terms of a query, in the order the query visits them
+/// Only term-based queries (terms and phrases) expose their terms; fuzzy, regex
+/// and range clauses contribute nothing
+fn collect_query_terms(query: &dyn Query) -> Vec<Term> {
+    let mut terms: Vec<Term> = Vec::new();
+    query.query_terms(&mut |term, _| {
+        if !terms.contains(term) {
+            terms.push(term.clone());
+        }
+    });
+    terms
+}
+
+/// Lists the query terms present in a document's indexed fields as
+/// `%{"field" => name, "term" => text}` maps
+fn matched_terms_in_doc<'a>(
+    env: rustler::Env<'a>,
+    searcher: &Searcher,
+    doc_address: DocAddress,
+    terms: &[Term],
+) -> Result<Vec<rustler::Term<'a>>, MuninnError> {
+    use rustler::types::map;
+    use rustler::Encoder;
+
+    let schema = searcher.schema();
+    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+    let mut matched = Vec::new();
+
+    for term in terms {
+        let inverted_index = segment_reader.inverted_index(term.field()).map_err(|e| {
+            MuninnError::search_error(format!("Failed to open inverted index: {}", e))
+        })?;
+
+        let postings = inverted_index
+            .read_postings(term, IndexRecordOption::Basic)
+            .map_err(|e| MuninnError::search_error(format!("Failed to read postings: {}", e)))?;
+
+        let Some(mut postings) = postings else {
+            continue;
+        };
+
+        if postings.seek(doc_address.doc_id) != doc_address.doc_id {
+            continue;
+        }
+
+        let field_name = schema.get_field_name(term.field());
+        let term_map = map::map_new(env)
+            .map_put("field".encode(env), field_name.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("term".encode(env), term_text(term).encode(env))
+            .ok()
+            .unwrap();
+        matched.push(term_map);
+    }
+
+    Ok(matched)
+}
+
+/// Renders a term's value as text
+fn term_text(term: &Term) -> String {
+    let value = term.value();
+
+    if let Some(text) = value.as_str() {
+        text.to_string()
+    } else if let Some(n) = value.as_u64() {
+        n.to_string()
+    } else if let Some(n) = value.as_i64() {
+        n.to_string()
+    } else if let Some(n) = value.as_f64() {
+        n.to_string()
+    } else if let Some(b) = value.as_bool() {
+        b.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Typed fast field column of a single segment
+enum FastFieldColumn {
+    U64(Column<u64>),
+    I64(Column<i64>),
+    F64(Column<f64>),
+    Bool(Column<bool>),
+}
+
+impl FastFieldColumn {
+    /// Opens the column reader matching the field's type
+    fn open(
+        segment_reader: &SegmentReader,
+        field_name: &str,
+        field_type: &FieldType,
+    ) -> Result<Self, MuninnError> {
+        let fast_fields = segment_reader.fast_fields();
+        let column = match field_type {
+            FieldType::U64(_) => fast_fields.u64(field_name).map(FastFieldColumn::U64),
+            FieldType::I64(_) => fast_fields.i64(field_name).map(FastFieldColumn::I64),
+            FieldType::F64(_) => fast_fields.f64(field_name).map(FastFieldColumn::F64),
+            FieldType::Bool(_) => fast_fields.bool(field_name).map(FastFieldColumn::Bool),
+            _ => {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Field '{}' must be a numeric or bool fast field",
+                    field_name
+                )))
+            }
+        };
+
+        column.map_err(|e| {
+            MuninnError::search_error(format!("Failed to open fast field '{}': {}", field_name, e))
+        })
+    }
+
+    /// Encodes the first value of the document, if it has one
+    fn first<'a>(&self, env: rustler::Env<'a>, doc_id: DocId) -> Option<rustler::Term<'a>> {
+        use rustler::Encoder;
+
+        match self {
+            FastFieldColumn::U64(column) => column.first(doc_id).map(|v| v.encode(env)),
+            FastFieldColumn::I64(column) => column.first(doc_id).map(|v| v.encode(env)),
+            FastFieldColumn::F64(column) => column.first(doc_id).map(|v| v.encode(env)),
+            FastFieldColumn::Bool(column) => column.first(doc_id).map(|v| v.encode(env)),
+        }
+    }
+}
+
+/// Wraps a query according to the requested scoring mode
+/// "bm25" keeps Tantivy's relevance scores, "const" scores every match 1.0
+fn apply_scoring(query: Box<dyn Query>, scoring: &str) -> Result<Box<dyn Query>, MuninnError> {
+    match scoring {
+        "bm25" => Ok(query),
+        "const" => Ok(Box::new(ConstScoreQuery::new(query, 1.0))),
+        _ => Err(MuninnError::invalid_argument(format!(
+            "Unknown scoring mode '{}'. Expected bm25 or const",
+            scoring
+        ))),
+    }
+}
+
+/// Builds the term for a field and an Elixir value decoded according to the field type
+/// Text values are used verbatim as a single term, without tokenization
+pub(crate) fn term_for_value(
+    schema: &tantivy::schema::Schema,
+    field_name: &str,
+    value: rustler::Term,
+) -> Result<Term, MuninnError> {
+    let field = schema
+        .get_field(field_name)
+        .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+    let invalid_value =
+        || MuninnError::invalid_argument(format!("Invalid value for field '{}'", field_name));
+
+    let term = match schema.get_field_entry(field).field_type() {
+        FieldType::Str(options) => {
+            let text: String = value.decode().map_err(|_| invalid_value())?;
+            // Case-insensitive keyword fields index their values lowercased
+            let lowercased = options
+                .get_indexing_options()
+                .is_some_and(|indexing| indexing.tokenizer() == RAW_LOWERCASE_TOKENIZER);
+            if lowercased {
+                Term::from_field_text(field, &text.to_lowercase())
+            } else {
+                Term::from_field_text(field, &text)
+            }
+        }
+        FieldType::U64(_) => {
+            Term::from_field_u64(field, value.decode().map_err(|_| invalid_value())?)
+        }
+        FieldType::I64(_) => {
+            Term::from_field_i64(field, value.decode().map_err(|_| invalid_value())?)
+        }
+        FieldType::F64(_) => {
+            Term::from_field_f64(field, value.decode().map_err(|_| invalid_value())?)
+        }
+        FieldType::Bool(_) => {
+            Term::from_field_bool(field, value.decode().map_err(|_| invalid_value())?)
+        }
+        _ => {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' does not support term lookups",
+                field_name
+            )));
+        }
+    };
+
+    Ok(term)
+}
+
+/// Wraps a query in a BooleanQuery that also requires every filter to match
+/// Filters are scored 0, so they narrow the matches without changing the ranking
+pub(crate) fn apply_filters(query: Box<dyn Query>, filters: Vec<Box<dyn Query>>) -> Box<dyn Query> {
+    if filters.is_empty() {
+        return query;
+    }
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, query)];
+
+    for filter in filters {
+        clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(filter, 0.0))));
+    }
+
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Wraps a query in a BooleanQuery that drops documents containing any of the
+/// given (field, value) terms. Text values are matched verbatim as a single term,
+/// numeric and bool values are decoded according to the field type
+fn apply_exclusions(
+    searcher: &Searcher,
+    query: Box<dyn Query>,
+    exclude_terms: Vec<(String, rustler::Term)>,
+) -> Result<Box<dyn Query>, MuninnError> {
+    if exclude_terms.is_empty() {
+        return Ok(query);
+    }
+
+    let schema = searcher.schema();
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, query)];
+
+    for (field_name, value) in exclude_terms {
+        let term = term_for_value(schema, &field_name, value)?;
+        clauses.push((
+            Occur::MustNot,
+            Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+        ));
+    }
+
+    Ok(Box::new(BooleanQuery::new(clauses)))
+}
+
+/// Returns whether a tokenizer indexes each value as a single term, like keyword fields
+fn indexes_whole_values(tokenizer: &str) -> bool {
+    matches!(tokenizer, "raw" | RAW_LOWERCASE_TOKENIZER)
+}
+
+/// Returns whether a built-in tokenizer lowercases the terms it emits
+/// Unknown (custom) tokenizers are assumed to lowercase, like `default`
+fn tokenizer_lowercases(tokenizer: &str) -> bool {
+    !matches!(tokenizer, "raw" | "whitespace")
+}
+
+/// Parses a query string with Tantivy's QueryParser over the given default fields
+/// Unqualified terms are only rejected when no default field is available
+pub(crate) fn parse_query(
+    searcher: &Searcher,
+    synonyms: &SharedSynonyms,
+    aliases: &FieldAliases,
+    query_string: &str,
+    default_fields: &[String],
+    query_tokenizers: &[(String, String)],
+) -> Result<Box<dyn Query>, MuninnError> {
+    parse_query_with_defaults(
+        searcher,
+        synonyms,
+        aliases,
+        query_string,
+        default_fields,
+        query_tokenizers,
+        QueryDefaults::default(),
+    )
+}
+
+/// Parses a query string like `parse_query`, with the given operator, leniency and
+/// fuzzy distance
+fn parse_query_with_defaults(
+    searcher: &Searcher,
+    synonyms: &SharedSynonyms,
+    aliases: &FieldAliases,
+    query_string: &str,
+    default_fields: &[String],
+    query_tokenizers: &[(String, String)],
+    defaults: QueryDefaults,
+) -> Result<Box<dyn Query>, MuninnError> {
+    let query_tokenizers: Vec<(String, String)> = query_tokenizers
+        .iter()
+        .map(|(field_name, tokenizer)| (resolve_alias(aliases, field_name), tokenizer.clone()))
+        .collect();
+    let schema = schema_with_query_tokenizers(searcher, &query_tokenizers)?;
+
+    // Convert default field names to Field references
+    let mut fields = Vec::new();
+    for field_name in &resolve_aliases(aliases, default_fields) {
+        let field = schema
+            .get_field(field_name)
+            .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+        // Tantivy would only fail once an unqualified term reaches the field
+        if !schema.get_field_entry(field).is_indexed() {
+            return Err(MuninnError::invalid_argument(format!(
+                "Default field '{}' is not indexed and cannot be searched",
+                field_name
+            )));
+        }
+
+        fields.push(field);
+    }
+
+    // Create QueryParser with default fields
+    // An empty list is fine as long as every clause names its field
+    let mut query_parser = QueryParser::new(
+        schema.clone(),
+        fields,
+        searcher.index().tokenizers().clone(),
+    );
+
+    if defaults.conjunction {
+        query_parser.set_conjunction_by_default();
+    }
+
+    // Keyword fields hold exact values such as ids, which a typo must not match
+    if defaults.fuzzy_distance > 0 {
+        for (field, field_entry) in schema.fields() {
+            if let FieldType::Str(text_options) = field_entry.field_type() {
+                let tokenized =
+                    text_options
+                        .get_indexing_options()
+                        .is_some_and(|indexing_options| {
+                            !indexes_whole_values(indexing_options.tokenizer())
+                        });
+                if tokenized {
+                    query_parser.set_field_fuzzy(field, false, defaults.fuzzy_distance, true);
+                }
+            }
+        }
+    }
+
+    parse_with_parser(
+        &query_parser,
+        &schema,
+        synonyms,
+        aliases,
+        query_string,
+        defaults,
+    )
+}
+
+/// Parses a query string with a configured QueryParser, renaming aliased fields and
+/// expanding synonyms first
+fn parse_with_parser(
+    query_parser: &QueryParser,
+    schema: &Schema,
+    synonyms: &SharedSynonyms,
+    aliases: &FieldAliases,
+    query_string: &str,
+    defaults: QueryDefaults,
+) -> Result<Box<dyn Query>, MuninnError> {
+    let synonyms = synonyms
+        .lock()
+        .map_err(|_| MuninnError::search_error("Failed to acquire synonyms lock"))?;
+
+    // A lenient parse keeps whatever part of a malformed query string it can read
+    let ast = if defaults.lenient {
+        Ok(tantivy::query_grammar::parse_query_lenient(query_string).0)
+    } else {
+        tantivy::query_grammar::parse_query(query_string)
+            .map_err(|_| QueryParserError::SyntaxError(query_string.to_string()))
+    };
+
+    // Parse the query string, rewriting the parsed query before it is tokenized per field
+    let query = ast.and_then(|ast| {
+        let ast = if synonyms.is_empty() && aliases.is_empty() {
+            ast
+        } else {
+            expand_synonyms(rewrite_field_aliases(ast, aliases), &synonyms)
+        };
+
+        if all_negative(&ast) && has_exists(&ast) {
+            if !defaults.lenient {
+                return Err(QueryParserError::AllButQueryForbidden);
+            }
+            return build_query_with_exists(query_parser, schema, make_non_negative(ast), defaults);
+        }
+        build_query_with_exists(query_parser, schema, ast, defaults)
+    });
+
+    query.map_err(|e| match e {
+        QueryParserError::NoDefaultFieldDeclared => MuninnError::parse_error(format!(
+            "At least one default field must be provided for unqualified terms in query '{}'",
+            query_string
+        )),
+        e @ QueryParserError::SyntaxError(_) => match invalid_boost(query_string) {
+            Some(boost) => MuninnError::parse_error(format!(
+                "Invalid boost '{}' in query '{}': a boost must directly follow a term, phrase or group and be a non-negative number such as ^2 or ^0.5",
+                boost, query_string
+            )),
+            None => MuninnError::parse_error(format!(
+                "Failed to parse query '{}': {}",
+                query_string, e
+            )),
+        },
+        QueryParserError::ExpectedBool(_) => MuninnError::parse_error(format!(
+            "Failed to parse query '{}': bool fields only match true or false",
+            query_string
+        )),
+        e => MuninnError::parse_error(format!("Failed to parse query '{}': {}", query_string, e)),
+    })
+}
+
+/// Builds a parsed query, turning `field:*` clauses into exists queries
+/// Tantivy's query parser rejects them, so the parts of the query holding one are built
+/// here and every other part is left to the parser. Clauses without an operator use
+/// the parser's default operator
+fn build_query_with_exists(
+    query_parser: &QueryParser,
+    schema: &Schema,
+    ast: UserInputAst,
+    defaults: QueryDefaults,
+) -> Result<Box<dyn Query>, QueryParserError> {
+    if !has_exists(&ast) {
+        return build_parsed_query(query_parser, ast, defaults);
+    }
+
+    match ast {
+        UserInputAst::Clause(clauses) => {
+            let clauses = clauses
+                .into_iter()
+                .map(|(occur, clause)| {
+                    let query = build_query_with_exists(query_parser, schema, clause, defaults)?;
+                    Ok((occur.unwrap_or(defaults.default_occur()), query))
+                })
+                .collect::<Result<Vec<_>, QueryParserError>>()?;
+            Ok(Box::new(BooleanQuery::new(clauses)))
+        }
+        UserInputAst::Boost(ast, boost) => {
+            let query = build_query_with_exists(query_parser, schema, *ast, defaults)?;
+            Ok(Box::new(BoostQuery::new(query, boost as Score)))
+        }
+        UserInputAst::Leaf(leaf) => match *leaf {
+            UserInputLeaf::Exists { field } => match exists_query(schema, &field) {
+                Err(_) if defaults.lenient => Ok(Box::new(EmptyQuery)),
+                query => query,
+            },
+            leaf => build_parsed_query(query_parser, UserInputAst::Leaf(Box::new(leaf)), defaults),
+        },
+    }
+}
+
+/// Builds a parsed query with Tantivy's query parser
+/// A lenient build turns the parts it cannot build into queries matching nothing
+fn build_parsed_query(
+    query_parser: &QueryParser,
+    ast: UserInputAst,
+    defaults: QueryDefaults,
+) -> Result<Box<dyn Query>, QueryParserError> {
+    if defaults.lenient {
+        Ok(query_parser.build_query_from_user_input_ast_lenient(ast).0)
+    } else {
+        query_parser.build_query_from_user_input_ast(ast)
+    }
+}
+
+/// Matches the documents with at least one value in a field
+/// Fast fields are read with Tantivy's exists query. Indexed numeric and bool fields
+/// that are not fast match a range covering every value of their type instead
+fn exists_query(schema: &Schema, field_name: &str) -> Result<Box<dyn Query>, QueryParserError> {
+    let field = schema
+        .get_field(field_name)
+        .map_err(|_| QueryParserError::FieldDoesNotExist(field_name.to_string()))?;
+    let field_entry = schema.get_field_entry(field);
+
+    if field_entry.is_fast() {
+        return Ok(Box::new(ExistsQuery::new(field_name.to_string(), false)));
+    }
+
+    let bounds = match field_entry.field_type() {
+        _ if !field_entry.is_indexed() => None,
+        FieldType::U64(_) => Some((
+            Term::from_field_u64(field, u64::MIN),
+            Term::from_field_u64(field, u64::MAX),
+        )),
+        FieldType::I64(_) => Some((
+            Term::from_field_i64(field, i64::MIN),
+            Term::from_field_i64(field, i64::MAX),
+        )),
+        FieldType::F64(_) => Some((
+            Term::from_field_f64(field, f64::NEG_INFINITY),
+            Term::from_field_f64(field, f64::INFINITY),
+        )),
+        FieldType::Bool(_) => Some((
+            Term::from_field_bool(field, false),
+            Term::from_field_bool(field, true),
+        )),
+        _ => None,
+    };
+
+    let Some((lower, upper)) = bounds else {
+        return Err(QueryParserError::UnsupportedQuery(format!(
+            "'{}:*' needs a fast field, or an indexed numeric or bool field",
+            field_name
+        )));
+    };
+
+    Ok(Box::new(RangeQuery::new(
+        Bound::Included(lower),
+        Bound::Included(upper),
+    )))
+}
+
+/// Whether a parsed query holds a `field:*` clause
+fn has_exists(ast: &UserInputAst) -> bool {
+    match ast {
+        UserInputAst::Clause(clauses) => clauses.iter().any(|(_, clause)| has_exists(clause)),
+        UserInputAst::Boost(ast, _) => has_exists(ast),
+        UserInputAst::Leaf(leaf) => matches!(**leaf, UserInputLeaf::Exists { .. }),
+    }
+}
+
+/// Whether a parsed query only excludes documents, like Tantivy's parser checks
+fn all_negative(ast: &UserInputAst) -> bool {
+    match ast {
+        UserInputAst::Clause(clauses) => {
+            !clauses.is_empty()
+                && clauses
+                    .iter()
+                    .all(|(occur, clause)| *occur == Some(Occur::MustNot) || all_negative(clause))
+        }
+        UserInputAst::Boost(ast, _) => all_negative(ast),
+        UserInputAst::Leaf(_) => false,
+    }
+}
+
+/// Lets a query that only excludes documents match every other document, as Tantivy's
+/// lenient parser does
+fn make_non_negative(ast: UserInputAst) -> UserInputAst {
+    match ast {
+        UserInputAst::Clause(mut clauses) => {
+            clauses.push((
+                Some(Occur::Should),
+                UserInputAst::Leaf(Box::new(UserInputLeaf::All)),
+            ));
+            UserInputAst::Clause(clauses)
+        }
+        UserInputAst::Boost(ast, boost) => {
+            UserInputAst::Boost(Box::new(make_non_negative(*ast)), boost)
+        }
+        leaf => leaf,
+    }
+}
+
+/// Returns the index schema with the given text fields tokenized by another tokenizer
+/// The query parser tokenizes query text with each field's indexing tokenizer, which is
+/// wrong for fields such as n-grams, where the query should stay whole. Field ids are
+/// unchanged, so queries parsed against this schema run on the real index
+fn schema_with_query_tokenizers(
+    searcher: &Searcher,
+    query_tokenizers: &[(String, String)],
+) -> Result<Schema, MuninnError> {
+    let schema = searcher.index().schema();
+    if query_tokenizers.is_empty() {
+        return Ok(schema);
+    }
+
+    for (field_name, tokenizer) in query_tokenizers {
+        schema
+            .get_field(field_name)
+            .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+        if searcher.index().tokenizers().get(tokenizer).is_none() {
+            return Err(MuninnError::invalid_argument(format!(
+                "Tokenizer '{}' is not registered on the index",
+                tokenizer
+            )));
+        }
+    }
+
+    let mut schema_builder = SchemaBuilder::new();
+    for (_, field_entry) in schema.fields() {
+        let query_tokenizer = query_tokenizers
+            .iter()
+            .find(|(field_name, _)| field_name == field_entry.name())
+            .map(|(_, tokenizer)| tokenizer);
+
+        let Some(query_tokenizer) = query_tokenizer else {
+            schema_builder.add_field(field_entry.clone());
+            continue;
+        };
+
+        let indexing = match field_entry.field_type() {
+            FieldType::Str(options) => options
+                .get_indexing_options()
+                .map(|indexing| (options, indexing)),
+            _ => None,
+        };
+
+        let Some((options, indexing)) = indexing else {
+            return Err(MuninnError::invalid_argument(format!(
+                "Field '{}' is not an indexed text field and has no query tokenizer",
+                field_entry.name()
+            )));
+        };
+
+        let indexing = indexing.clone().set_tokenizer(query_tokenizer);
+        let options = options.clone().set_indexing_options(indexing);
+        schema_builder.add_text_field(field_entry.name(), options);
+    }
+
+    Ok(schema_builder.build())
+}
+
+/// Returns the first malformed `^boost` in a query string, outside of phrases
+/// Tantivy only reports these as a generic syntax error over the whole query
+fn invalid_boost(query_string: &str) -> Option<&str> {
+    let mut in_phrase = false;
+    let mut previous = None;
+
+    for (offset, c) in query_string.char_indices() {
+        match c {
+            '"' => in_phrase = !in_phrase,
+            '^' if !in_phrase => {
+                let rest = &query_string[offset + 1..];
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == ')')
+                    .unwrap_or(rest.len());
+                let boost = &query_string[offset..offset + 1 + end];
+
+                let follows_clause = previous.is_some_and(|p: char| !p.is_whitespace());
+                let is_number = !rest[..end].is_empty()
+                    && rest[..end].chars().all(|c| c.is_ascii_digit() || c == '.')
+                    && rest[..end].parse::<f32>().is_ok();
+
+                if !follows_clause || !is_number {
+                    return Some(boost);
+                }
+            }
+            _ => {}
+        }
+        previous = Some(c);
+    }
+
+    None
+}
+
+/// Validates a fuzzy distance coming from Elixir and narrows it for Tantivy
+fn validate_fuzzy_distance(distance: u32) -> Result<u8, MuninnError> {
+    if distance > MAX_FUZZY_DISTANCE {
+        return Err(MuninnError::invalid_argument(format!(
+            "Distance must be between 0 and {}",
+            MAX_FUZZY_DISTANCE
+        )));
+    }
+
+    Ok(distance as u8)
+}
+
+/// Helper function to execute a query and return results
+pub(crate) fn execute_query<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: &SearcherResource,
+    schema: &tantivy::schema::Schema,
+    query: &dyn Query,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    let searcher = &searcher_res.searcher;
+    let returned_fields = searcher_res.returned_fields()?;
+    let top_docs = searcher
+        .search(query, &TopDocs::with_limit(limit))
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    let total_hits = top_docs.len();
+    let mut hits = Vec::new();
+
+    let docs = fetch_documents(searcher, &top_docs)?;
+
+    for ((score, _), doc) in top_docs.into_iter().zip(docs) {
+        let hit_map = document_to_hit_map(
+            env,
+            schema,
+            &doc,
+            score,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?;
+        hits.push(hit_map);
     }
 
     use rustler::types::map;
@@ -654,129 +2673,1029 @@ fn execute_query<'a>(
     Ok(result_map)
 }
 
+/// Widens a score to the f64 Elixir receives, keeping its shortest decimal form
+/// `0.3f32 as f64` is 0.30000001192092896, while going through the decimal gives 0.3
+fn score_to_f64(score: f32) -> f64 {
+    score.to_string().parse().unwrap_or(score as f64)
+}
+
 /// Converts a Tantivy document to an Elixir hit map with score
-fn document_to_hit_map<'a>(
+pub(crate) fn document_to_hit_map<'a>(
     env: rustler::Env<'a>,
     schema: &tantivy::schema::Schema,
     doc: &TantivyDocument,
     score: f32,
-) -> rustler::Term<'a> {
+    id_field: Option<Field>,
+    returned_fields: Option<&[Field]>,
+    multi_fields: &[Field],
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::Encoder;
+
+    let hit_map = document_to_map(env, schema, doc, id_field, returned_fields, multi_fields)?;
+
+    Ok(hit_map
+        .map_put("score".encode(env), score_to_f64(score).encode(env))
+        .ok()
+        .unwrap())
+}
+
+/// Converts a Tantivy document to an Elixir hit map without a score
+fn document_to_map<'a>(
+    env: rustler::Env<'a>,
+    schema: &tantivy::schema::Schema,
+    doc: &TantivyDocument,
+    id_field: Option<Field>,
+    returned_fields: Option<&[Field]>,
+    multi_fields: &[Field],
+) -> Result<rustler::Term<'a>, MuninnError> {
     use rustler::types::map;
     use rustler::Encoder;
 
-    // Create the document map
-    let mut doc_fields: HashMap<String, rustler::Term> = HashMap::new();
+    let (doc_map, unsupported_fields) =
+        stored_fields_to_term(env, schema, doc, returned_fields, multi_fields);
 
-    for field in schema.fields() {
-        let field_name = field.1.name().to_string();
-        let values: Vec<_> = doc.get_all(field.0).collect();
+    let hit_map = map::map_new(env)
+        .map_put("doc".encode(env), doc_map)
+        .ok()
+        .unwrap();
+    let hit_map = put_unsupported_fields(env, hit_map, unsupported_fields);
 
-        // Take the first value (for now, we don't support multi-valued fields)
-        if let Some(value) = values.first() {
-            // Convert CompactDocValue to OwnedValue
-            let owned_value: tantivy::schema::OwnedValue = (*value).into();
-            match owned_value {
-                tantivy::schema::OwnedValue::Str(s) => {
-                    doc_fields.insert(field_name, s.as_str().encode(env));
-                }
-                tantivy::schema::OwnedValue::U64(n) => {
-                    doc_fields.insert(field_name, n.encode(env));
-                }
-                tantivy::schema::OwnedValue::I64(n) => {
-                    doc_fields.insert(field_name, n.encode(env));
-                }
-                tantivy::schema::OwnedValue::F64(n) => {
-                    doc_fields.insert(field_name, n.encode(env));
-                }
-                tantivy::schema::OwnedValue::Bool(b) => {
-                    doc_fields.insert(field_name, b.encode(env));
+    put_hit_id(env, hit_map, schema, doc, id_field)
+}
+
+/// Sets the stored fields returned in the "doc" map of every hit of the searcher's
+/// searches, or every stored field again when None. Searches taking a field list
+/// override it for a single call
+pub fn searcher_set_default_fields(
+    searcher_res: ResourceArc<SearcherResource>,
+    field_names: Option<Vec<String>>,
+) -> Result<(), MuninnError> {
+    let aliases = searcher_res.field_aliases()?;
+    let returned_fields = field_names
+        .map(|field_names| {
+            resolve_returned_fields(
+                searcher_res.searcher.schema(),
+                &resolve_aliases(&aliases, &field_names),
+            )
+        })
+        .transpose()?;
+
+    *searcher_res
+        .returned_fields
+        .write()
+        .map_err(|_| MuninnError::search_error("Failed to acquire returned fields lock"))? =
+        returned_fields;
+
+    Ok(())
+}
+
+/// Replaces the query parsing settings of a searcher
+/// They apply to the query strings of later `searcher_search_query` calls that do not
+/// override them
+pub fn searcher_set_query_defaults(
+    searcher_res: ResourceArc<SearcherResource>,
+    default_operator: String,
+    lenient: bool,
+    fuzzy_distance: u32,
+) -> Result<(), MuninnError> {
+    let query_defaults = QueryDefaults {
+        conjunction: parse_default_operator(&default_operator)?,
+        lenient,
+        fuzzy_distance: validate_fuzzy_distance(fuzzy_distance)?,
+    };
+
+    *searcher_res
+        .query_defaults
+        .write()
+        .map_err(|_| MuninnError::search_error("Failed to acquire query defaults lock"))? =
+        query_defaults;
+
+    Ok(())
+}
+
+/// Reads a default operator name, returning whether it is a conjunction
+fn parse_default_operator(operator: &str) -> Result<bool, MuninnError> {
+    match operator {
+        "or" => Ok(false),
+        "and" => Ok(true),
+        _ => Err(MuninnError::invalid_argument(format!(
+            "Unknown default operator '{}'. Expected and or or",
+            operator
+        ))),
+    }
+}
+
+/// Looks up the fields a hit's "doc" map is restricted to, which must be stored
+fn resolve_returned_fields(
+    schema: &Schema,
+    field_names: &[String],
+) -> Result<Vec<Field>, MuninnError> {
+    field_names
+        .iter()
+        .map(|field_name| {
+            let field = schema
+                .get_field(field_name)
+                .map_err(|_| MuninnError::field_not_found(field_name))?;
+
+            if !schema.get_field_entry(field).is_stored() {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Field '{}' is not stored and cannot be returned",
+                    field_name
+                )));
+            }
+
+            Ok(field)
+        })
+        .collect()
+}
+
+/// Retrieves the document at an address reported by a search on the same searcher
+/// Addresses change when segments merge, so one taken from another searcher may point
+/// to another document or to none at all
+pub fn searcher_get_doc<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    doc_address: (u32, u32),
+) -> Result<rustler::Term<'a>, MuninnError> {
+    let searcher = &searcher_res.searcher;
+    let doc_address = live_doc_address(searcher, doc_address)?;
+
+    let doc: TantivyDocument = searcher
+        .doc(doc_address)
+        .map_err(|e| MuninnError::search_error(format!("Failed to retrieve document: {}", e)))?;
+
+    document_to_map(
+        env,
+        &searcher.index().schema(),
+        &doc,
+        searcher_res.id_field,
+        searcher_res.returned_fields()?.as_deref(),
+        &searcher_res.multi_fields,
+    )
+}
+
+/// Scores the documents at the given addresses under a query, for relevance evaluation
+/// against known documents. Returns a map per address, in the order given, with the
+/// document's score, 0 when it does not match, and with `explain` Tantivy's explanation
+/// of the score, nil when it does not match
+/// Each segment is scored by a single scorer, seeking the addresses in ascending order
+pub fn searcher_score_docs<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    doc_addresses: Vec<(u32, u32)>,
+    explain: bool,
+) -> Result<Vec<rustler::Term<'a>>, MuninnError> {
+    use rustler::types::map;
+    use rustler::Encoder;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+
+    let doc_addresses = doc_addresses
+        .into_iter()
+        .map(|doc_address| live_doc_address(searcher, doc_address))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let search_error = |e: tantivy::TantivyError| {
+        MuninnError::search_error(format!("Failed to score documents: {}", e))
+    };
+    let weight = query
+        .weight(EnableScoring::enabled_from_searcher(searcher))
+        .map_err(search_error)?;
+
+    // Positions of the addresses, by segment and then by doc id
+    let mut order: Vec<usize> = (0..doc_addresses.len()).collect();
+    order.sort_by_key(|&position| doc_addresses[position]);
+
+    let mut scores: Vec<(Score, Option<tantivy::query::Explanation>)> =
+        vec![(0.0, None); doc_addresses.len()];
+    for segment_positions in
+        order.chunk_by(|&a, &b| doc_addresses[a].segment_ord == doc_addresses[b].segment_ord)
+    {
+        let segment_ord = doc_addresses[segment_positions[0]].segment_ord;
+        let segment_reader = searcher.segment_reader(segment_ord);
+        let mut scorer = weight.scorer(segment_reader, 1.0).map_err(search_error)?;
+
+        for &position in segment_positions {
+            let doc_id = doc_addresses[position].doc_id;
+            if scorer.doc() > doc_id || scorer.seek(doc_id) != doc_id {
+                continue;
+            }
+
+            let explanation = if explain {
+                Some(
+                    weight
+                        .explain(segment_reader, doc_id)
+                        .map_err(search_error)?,
+                )
+            } else {
+                None
+            };
+            scores[position] = (scorer.score(), explanation);
+        }
+    }
+
+    doc_addresses
+        .iter()
+        .zip(scores)
+        .map(|(doc_address, (score, explanation))| {
+            let doc_map = map::map_new(env)
+                .map_put(
+                    "doc_address".encode(env),
+                    (doc_address.segment_ord, doc_address.doc_id).encode(env),
+                )
+                .ok()
+                .unwrap()
+                .map_put("score".encode(env), score_to_f64(score).encode(env))
+                .ok()
+                .unwrap();
+
+            if !explain {
+                return Ok(doc_map);
+            }
+
+            let explanation = match explanation {
+                Some(explanation) => {
+                    let json = serde_json::to_value(&explanation).map_err(|e| {
+                        MuninnError::search_error(format!("Failed to encode explanation: {}", e))
+                    })?;
+                    json_to_term(env, &json)
                 }
-                _ => {} // Skip unsupported types
+                None => rustler::types::atom::nil().encode(env),
+            };
+
+            Ok(doc_map
+                .map_put("explanation".encode(env), explanation)
+                .ok()
+                .unwrap())
+        })
+        .collect()
+}
+
+/// Checks that a `{segment_ord, doc_id}` address from a search hit names a live
+/// document of the searcher, failing with `not_found` otherwise
+pub(crate) fn live_doc_address(
+    searcher: &Searcher,
+    (segment_ord, doc_id): (u32, u32),
+) -> Result<DocAddress, MuninnError> {
+    let not_found = || {
+        MuninnError::not_found(format!(
+            "No document at address {{{}, {}}}",
+            segment_ord, doc_id
+        ))
+    };
+
+    let segment_reader = searcher
+        .segment_readers()
+        .get(segment_ord as usize)
+        .ok_or_else(not_found)?;
+
+    if doc_id >= segment_reader.max_doc() || segment_reader.is_deleted(doc_id) {
+        return Err(not_found());
+    }
+
+    Ok(DocAddress::new(segment_ord, doc_id))
+}
+
+/// Encodes the stored values of a document as a hit's "doc" map, restricted to
+/// `returned_fields` when given
+/// Also returns the names of fields that have a stored value of a type Muninn cannot
+/// encode yet, such as dates, facets or bytes, in schema order
+fn stored_fields_to_term<'a>(
+    env: rustler::Env<'a>,
+    schema: &tantivy::schema::Schema,
+    doc: &TantivyDocument,
+    returned_fields: Option<&[Field]>,
+    multi_fields: &[Field],
+) -> (rustler::Term<'a>, Vec<String>) {
+    use rustler::Encoder;
+
+    let (doc_fields, unsupported_fields) =
+        ordered_stored_fields(env, schema, doc, returned_fields, multi_fields);
+    let doc_fields: HashMap<String, rustler::Term> = doc_fields.into_iter().collect();
+
+    (doc_fields.encode(env), unsupported_fields)
+}
+
+/// Encodes the stored values of a document as `{name, value}` pairs in schema order,
+/// for the "fields" list of a hit. Holds the same fields as the "doc" map
+fn stored_fields_to_list<'a>(
+    env: rustler::Env<'a>,
+    schema: &tantivy::schema::Schema,
+    doc: &TantivyDocument,
+    returned_fields: Option<&[Field]>,
+    multi_fields: &[Field],
+) -> rustler::Term<'a> {
+    use rustler::Encoder;
+
+    ordered_stored_fields(env, schema, doc, returned_fields, multi_fields)
+        .0
+        .encode(env)
+}
+
+/// Encodes the stored values of a document in schema order, with the names of the
+/// fields whose values cannot be encoded
+fn ordered_stored_fields<'a>(
+    env: rustler::Env<'a>,
+    schema: &tantivy::schema::Schema,
+    doc: &TantivyDocument,
+    returned_fields: Option<&[Field]>,
+    multi_fields: &[Field],
+) -> (Vec<(String, rustler::Term<'a>)>, Vec<String>) {
+    let mut doc_fields = Vec::new();
+    let mut unsupported_fields = Vec::new();
+
+    for (field, field_entry) in schema.fields() {
+        // The original JSON is only returned on request, under "_source"
+        if field_entry.name() == SOURCE_FIELD {
+            continue;
+        }
+
+        if returned_fields.is_some_and(|returned_fields| !returned_fields.contains(&field)) {
+            continue;
+        }
+
+        match field_values_to_term(env, doc, field, multi_fields.contains(&field)) {
+            Some(value) => {
+                doc_fields.push((field_entry.name().to_string(), value));
+            }
+            None if doc.get_first(field).is_some() => {
+                unsupported_fields.push(field_entry.name().to_string());
             }
+            None => {}
         }
     }
 
-    // Convert HashMap to Elixir map
-    let doc_map = doc_fields.encode(env);
+    (doc_fields, unsupported_fields)
+}
 
-    // Create the hit map with score and doc
-    map::map_new(env)
-        .map_put("score".encode(env), score.encode(env))
-        .ok()
-        .unwrap()
-        .map_put("doc".encode(env), doc_map)
+/// Adds the names of fields dropped from a hit's "doc" map to the hit under
+/// "_unsupported_fields", so missing values are not mistaken for missing data
+/// The key is left out when every stored value could be encoded
+fn put_unsupported_fields<'a>(
+    env: rustler::Env<'a>,
+    hit_map: rustler::Term<'a>,
+    unsupported_fields: Vec<String>,
+) -> rustler::Term<'a> {
+    use rustler::Encoder;
+
+    if unsupported_fields.is_empty() {
+        return hit_map;
+    }
+
+    hit_map
+        .map_put(
+            "_unsupported_fields".encode(env),
+            unsupported_fields.encode(env),
+        )
         .ok()
         .unwrap()
 }
 
-/// Converts a Tantivy document to an Elixir hit map with snippets
-fn document_to_hit_map_with_snippets<'a>(
+/// Encodes the stored values of a field for a hit's "doc" map
+/// Numeric fields holding several values are returned as a list in insertion order,
+/// and a field declared `multi` always is, so a single value comes back as `[value]`.
+/// Other numeric fields return a single value as such, and other types their first
+/// value (multi-valued text is not supported yet)
+fn field_values_to_term<'a>(
     env: rustler::Env<'a>,
+    doc: &TantivyDocument,
+    field: Field,
+    multi: bool,
+) -> Option<rustler::Term<'a>> {
+    use rustler::Encoder;
+    use tantivy::schema::OwnedValue;
+
+    let values: Vec<OwnedValue> = doc.get_all(field).map(OwnedValue::from).collect();
+
+    let encode = |value: &OwnedValue| match value {
+        OwnedValue::Str(s) => Some(s.as_str().encode(env)),
+        OwnedValue::U64(n) => Some(n.encode(env)),
+        OwnedValue::I64(n) => Some(n.encode(env)),
+        OwnedValue::F64(n) => Some(n.encode(env)),
+        OwnedValue::Bool(b) => Some(b.encode(env)),
+        _ => None, // Unsupported types are reported under "_unsupported_fields"
+    };
+
+    let is_numeric = |value: &OwnedValue| {
+        matches!(
+            value,
+            OwnedValue::U64(_) | OwnedValue::I64(_) | OwnedValue::F64(_)
+        )
+    };
+
+    if (multi || values.len() > 1) && !values.is_empty() && values.iter().all(is_numeric) {
+        let list: Vec<rustler::Term> = values.iter().filter_map(encode).collect();
+        return Some(list.encode(env));
+    }
+
+    values.first().and_then(encode)
+}
+
+/// Adds the value of the index's id field to a hit map under "_id"
+/// A document without a value for the id field is an error rather than a hit
+/// without "_id", so callers can rely on the key being present
+fn put_hit_id<'a>(
+    env: rustler::Env<'a>,
+    hit_map: rustler::Term<'a>,
     schema: &tantivy::schema::Schema,
     doc: &TantivyDocument,
-    score: f32,
-    snippet_generators: &HashMap<String, SnippetGenerator>,
-) -> rustler::Term<'a> {
-    use rustler::types::map;
+    id_field: Option<Field>,
+) -> Result<rustler::Term<'a>, MuninnError> {
     use rustler::Encoder;
 
-    // Create the document map
-    let mut doc_fields: HashMap<String, rustler::Term> = HashMap::new();
+    let Some(id_field) = id_field else {
+        return Ok(hit_map);
+    };
 
-    for field in schema.fields() {
-        let field_name = field.1.name().to_string();
-        let values: Vec<_> = doc.get_all(field.0).collect();
+    let id = stored_id(env, doc, id_field).ok_or_else(|| missing_id_error(schema, id_field))?;
 
-        // Take the first value (for now, we don't support multi-valued fields)
-        if let Some(value) = values.first() {
-            // Convert CompactDocValue to OwnedValue
-            let owned_value: tantivy::schema::OwnedValue = (*value).into();
-            match owned_value {
-                tantivy::schema::OwnedValue::Str(s) => {
-                    doc_fields.insert(field_name, s.as_str().encode(env));
-                }
-                tantivy::schema::OwnedValue::U64(n) => {
-                    doc_fields.insert(field_name, n.encode(env));
-                }
-                tantivy::schema::OwnedValue::I64(n) => {
-                    doc_fields.insert(field_name, n.encode(env));
-                }
-                tantivy::schema::OwnedValue::F64(n) => {
-                    doc_fields.insert(field_name, n.encode(env));
+    Ok(hit_map.map_put("_id".encode(env), id).ok().unwrap())
+}
+
+/// Encodes the first stored value of an id field, if the document has one
+fn stored_id<'a>(
+    env: rustler::Env<'a>,
+    doc: &TantivyDocument,
+    id_field: Field,
+) -> Option<rustler::Term<'a>> {
+    use rustler::Encoder;
+    use tantivy::schema::OwnedValue;
+
+    doc.get_first(id_field)
+        .and_then(|value| match OwnedValue::from(value) {
+            OwnedValue::Str(s) => Some(s.as_str().encode(env)),
+            OwnedValue::U64(n) => Some(n.encode(env)),
+            OwnedValue::I64(n) => Some(n.encode(env)),
+            _ => None,
+        })
+}
+
+fn missing_id_error(schema: &Schema, id_field: Field) -> MuninnError {
+    MuninnError::not_found(format!(
+        "Document is missing id field '{}'",
+        schema.get_field_name(id_field)
+    ))
+}
+
+/// Highlights every occurrence of the query terms in a whole field value
+/// Used instead of the snippet generator for values short enough to show in full,
+/// where cutting out a single fragment would only add "..." truncation
+struct FullTextHighlighter {
+    field: Field,
+    terms: BTreeSet<String>,
+    tokenizer: TextAnalyzer,
+    max_num_chars: usize,
+}
+
+impl FullTextHighlighter {
+    fn create(
+        searcher: &Searcher,
+        query: &dyn Query,
+        field: Field,
+        max_num_chars: usize,
+        tokenizer_override: Option<TextAnalyzer>,
+    ) -> Result<Self, MuninnError> {
+        let terms = query_text_terms(query, field);
+
+        let tokenizer = match tokenizer_override {
+            Some(tokenizer) => tokenizer,
+            None => searcher.index().tokenizer_for_field(field).map_err(|e| {
+                MuninnError::search_error(format!("Failed to load field tokenizer: {}", e))
+            })?,
+        };
+
+        Ok(FullTextHighlighter {
+            field,
+            terms,
+            tokenizer,
+            max_num_chars,
+        })
+    }
+
+    /// Returns the whole field value as HTML with matches wrapped in `<b>` tags,
+    /// or None when the value is longer than the threshold
+    fn highlight(&self, doc: &TantivyDocument) -> Option<String> {
+        // Multiple values are joined with a space, like SnippetGenerator::snippet_from_doc
+        let text = doc
+            .get_all(self.field)
+            .filter_map(|value| value.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if text.chars().count() > self.max_num_chars {
+            return None;
+        }
+
+        let mut html = String::with_capacity(text.len());
+        let mut last_offset = 0;
+        let mut tokenizer = self.tokenizer.clone();
+        let mut token_stream = tokenizer.token_stream(&text);
+
+        while let Some(token) = token_stream.next() {
+            if token.offset_from < last_offset || !self.terms.contains(&token.text) {
+                continue;
+            }
+            html.push_str(&escape_html(&text[last_offset..token.offset_from]));
+            html.push_str("<b>");
+            html.push_str(&escape_html(&text[token.offset_from..token.offset_to]));
+            html.push_str("</b>");
+            last_offset = token.offset_to;
+        }
+        html.push_str(&escape_html(&text[last_offset..]));
+
+        Some(html)
+    }
+}
+
+/// Cuts a snippet of whole words around the best match in a field value
+/// Used instead of the snippet generator when a number of context words is requested,
+/// since a fragment cut at a character count can end in the middle of a word
+struct WordWindowHighlighter {
+    field: Field,
+    terms: BTreeMap<String, Score>,
+    tokenizer: TextAnalyzer,
+    context_words: usize,
+}
+
+/// A token of a field value that produced one of the query's terms
+struct WordMatch<'a> {
+    /// Index of the whitespace separated word the token starts in
+    word: usize,
+    offset_from: usize,
+    offset_to: usize,
+    term: &'a str,
+    score: Score,
+}
+
+impl WordWindowHighlighter {
+    fn create(
+        searcher: &Searcher,
+        query: &dyn Query,
+        field: Field,
+        context_words: usize,
+        tokenizer_override: Option<TextAnalyzer>,
+    ) -> Result<Self, MuninnError> {
+        let terms = snippet_terms_text(searcher, query, field)?;
+
+        let tokenizer = match tokenizer_override {
+            Some(tokenizer) => tokenizer,
+            None => searcher.index().tokenizer_for_field(field).map_err(|e| {
+                MuninnError::search_error(format!("Failed to load field tokenizer: {}", e))
+            })?,
+        };
+
+        Ok(WordWindowHighlighter {
+            field,
+            terms,
+            tokenizer,
+            context_words,
+        })
+    }
+
+    /// Returns the words around the best match as HTML with matches wrapped in `<b>`
+    /// tags, or an empty string when no term matches, like an empty Tantivy snippet
+    /// The best match is the one whose window holds the highest total score of distinct
+    /// terms, rarer terms scoring higher, and the first one on ties
+    fn highlight(&self, doc: &TantivyDocument) -> String {
+        // Multiple values are joined with a space, like SnippetGenerator::snippet_from_doc
+        let text = doc
+            .get_all(self.field)
+            .filter_map(|value| value.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let words: Vec<(usize, usize)> = text
+            .split_whitespace()
+            .map(|word| {
+                let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                (start, start + word.len())
+            })
+            .collect();
+
+        let mut matches: Vec<WordMatch> = Vec::new();
+        let mut tokenizer = self.tokenizer.clone();
+        let mut token_stream = tokenizer.token_stream(&text);
+
+        while let Some(token) = token_stream.next() {
+            let overlaps = matches
+                .last()
+                .is_some_and(|last| token.offset_from < last.offset_to);
+            let Some((term, &score)) = self.terms.get_key_value(&token.text) else {
+                continue;
+            };
+            if overlaps {
+                continue;
+            }
+
+            let word = words
+                .partition_point(|&(start, _)| start <= token.offset_from)
+                .saturating_sub(1);
+            matches.push(WordMatch {
+                word,
+                offset_from: token.offset_from,
+                offset_to: token.offset_to,
+                term,
+                score,
+            });
+        }
+
+        if matches.is_empty() {
+            return String::new();
+        }
+
+        // Slide a window over the matches, centered on each one in turn, keeping the
+        // number of matches of every term inside it
+        let mut term_counts: HashMap<&str, usize> = HashMap::new();
+        let (mut first, mut last, mut window_score) = (0, 0, 0.0);
+        let (mut best, mut best_score) = (0, Score::MIN);
+
+        for (center, center_match) in matches.iter().enumerate() {
+            while last < matches.len()
+                && matches[last].word <= center_match.word + self.context_words
+            {
+                let count = term_counts.entry(matches[last].term).or_default();
+                if *count == 0 {
+                    window_score += matches[last].score;
                 }
-                tantivy::schema::OwnedValue::Bool(b) => {
-                    doc_fields.insert(field_name, b.encode(env));
+                *count += 1;
+                last += 1;
+            }
+
+            while matches[first].word + self.context_words < center_match.word {
+                let count = term_counts.entry(matches[first].term).or_default();
+                *count -= 1;
+                if *count == 0 {
+                    window_score -= matches[first].score;
                 }
-                _ => {} // Skip unsupported types
+                first += 1;
+            }
+
+            if window_score > best_score {
+                (best, best_score) = (center, window_score);
+            }
+        }
+
+        let center_match = &matches[best];
+        let first_word = center_match.word.saturating_sub(self.context_words);
+        let last_word = (center_match.word + self.context_words).min(words.len() - 1);
+        let start = words[first_word].0.min(center_match.offset_from);
+        let end = words[last_word].1.max(center_match.offset_to);
+
+        let mut html = String::with_capacity(end - start);
+        let mut last_offset = start;
+        for word_match in &matches {
+            if word_match.offset_from < start || word_match.offset_to > end {
+                continue;
+            }
+            html.push_str(&escape_html(&text[last_offset..word_match.offset_from]));
+            html.push_str("<b>");
+            html.push_str(&escape_html(
+                &text[word_match.offset_from..word_match.offset_to],
+            ));
+            html.push_str("</b>");
+            last_offset = word_match.offset_to;
+        }
+        html.push_str(&escape_html(&text[last_offset..end]));
+
+        html
+    }
+}
+
+/// Scores snippet fragments like Tantivy ranks the fragments of one field: the sum of
+/// the scores of the query terms they hold, rarer terms higher
+/// Makes the best fragments of different fields comparable
+struct FragmentScorer {
+    terms: BTreeMap<String, Score>,
+    tokenizer: TextAnalyzer,
+}
+
+impl FragmentScorer {
+    fn create(
+        searcher: &Searcher,
+        query: &dyn Query,
+        field: Field,
+        tokenizer_override: Option<TextAnalyzer>,
+    ) -> Result<Self, MuninnError> {
+        let terms = snippet_terms_text(searcher, query, field)?;
+
+        let tokenizer = match tokenizer_override {
+            Some(tokenizer) => tokenizer,
+            None => searcher.index().tokenizer_for_field(field).map_err(|e| {
+                MuninnError::search_error(format!("Failed to load field tokenizer: {}", e))
+            })?,
+        };
+
+        Ok(FragmentScorer { terms, tokenizer })
+    }
+
+    fn score(&self, fragment: &str) -> Score {
+        let mut score = 0.0;
+        let mut tokenizer = self.tokenizer.clone();
+        let mut token_stream = tokenizer.token_stream(fragment);
+
+        while let Some(token) = token_stream.next() {
+            if let Some(term_score) = self.terms.get(&token.text.to_lowercase()) {
+                score += term_score;
+            }
+        }
+
+        score
+    }
+}
+
+/// Scores the query's terms on a field like SnippetGenerator::create, rarer terms higher,
+/// for building a generator with another tokenizer
+fn snippet_terms_text(
+    searcher: &Searcher,
+    query: &dyn Query,
+    field: Field,
+) -> Result<BTreeMap<String, Score>, MuninnError> {
+    let mut terms = Vec::new();
+    query.query_terms(&mut |term, _| {
+        if term.field() == field {
+            terms.push(term.clone());
+        }
+    });
+
+    let mut terms_text = BTreeMap::new();
+    for term in terms {
+        let Some(text) = term.value().as_str().map(str::to_string) else {
+            continue;
+        };
+        let doc_freq = searcher
+            .doc_freq(&term)
+            .map_err(|e| MuninnError::search_error(format!("Failed to read term: {}", e)))?;
+        if doc_freq > 0 {
+            terms_text.insert(text, 1.0 / (1.0 + doc_freq as Score));
+        }
+    }
+
+    Ok(terms_text)
+}
+
+/// Scores the terms of a field within a Levenshtein distance of `text` like
+/// SnippetGenerator::create, rarer terms higher
+fn fuzzy_terms_text(
+    searcher: &Searcher,
+    field: Field,
+    text: &str,
+    distance: u8,
+    transposition_cost_one: bool,
+) -> Result<BTreeMap<String, Score>, MuninnError> {
+    let read_error =
+        |e: std::io::Error| MuninnError::search_error(format!("Failed to read terms: {}", e));
+
+    let automaton = LevenshteinAutomaton(
+        LevenshteinAutomatonBuilder::new(distance, transposition_cost_one).build_dfa(text),
+    );
+
+    let mut doc_freqs: BTreeMap<String, u32> = BTreeMap::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader
+            .inverted_index(field)
+            .map_err(|e| MuninnError::search_error(format!("Failed to read terms: {}", e)))?;
+        let mut stream = inverted_index
+            .terms()
+            .search(&automaton)
+            .into_stream()
+            .map_err(read_error)?;
+
+        while stream.advance() {
+            if let Ok(term_text) = std::str::from_utf8(stream.key()) {
+                *doc_freqs.entry(term_text.to_string()).or_default() += stream.value().doc_freq;
+            }
+        }
+    }
+
+    Ok(doc_freqs
+        .into_iter()
+        .map(|(term_text, doc_freq)| (term_text, 1.0 / (1.0 + doc_freq as Score)))
+        .collect())
+}
+
+/// Levenshtein DFA walking a term dictionary, as Tantivy's fuzzy query does
+struct LevenshteinAutomaton(DFA);
+
+impl Automaton for LevenshteinAutomaton {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(self.0.distance(*state), Distance::Exact(_))
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
+/// Returns the text of the query's terms on a field, as the field's tokenizer produced them
+fn query_text_terms(query: &dyn Query, field: Field) -> BTreeSet<String> {
+    let mut terms = BTreeSet::new();
+    query.query_terms(&mut |term, _| {
+        if term.field() == field {
+            if let Some(text) = term.value().as_str() {
+                terms.insert(text.to_string());
             }
         }
+    });
+    terms
+}
+
+/// Finds where the query terms occur in the stored text fields of a hit
+/// Each stored value is tokenized again with its field's tokenizer, so a match is a
+/// token that produced one of the query's terms, like in snippets
+struct MatchOffsetFinder {
+    fields: Vec<(String, Field, BTreeSet<String>, TextAnalyzer)>,
+}
+
+impl MatchOffsetFinder {
+    fn create(searcher: &Searcher, query: &dyn Query) -> Result<Self, MuninnError> {
+        let schema = searcher.schema();
+        let mut fields = Vec::new();
+
+        for (field, field_entry) in schema.fields() {
+            if !field_entry.is_stored() || !matches!(field_entry.field_type(), FieldType::Str(_)) {
+                continue;
+            }
+
+            let terms = query_text_terms(query, field);
+            if terms.is_empty() {
+                continue;
+            }
+
+            let tokenizer = searcher.index().tokenizer_for_field(field).map_err(|e| {
+                MuninnError::search_error(format!("Failed to load field tokenizer: {}", e))
+            })?;
+
+            fields.push((field_entry.name().to_string(), field, terms, tokenizer));
+        }
+
+        Ok(MatchOffsetFinder { fields })
     }
 
-    // Convert HashMap to Elixir map
-    let doc_map = doc_fields.encode(env);
+    /// Returns a map of field names to the `{start, end}` byte ranges of the matches in
+    /// the value returned under "doc". Fields without a match are left out
+    fn offsets<'a>(&self, env: rustler::Env<'a>, doc: &TantivyDocument) -> rustler::Term<'a> {
+        use rustler::Encoder;
+
+        let mut offsets_map: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+
+        for (name, field, terms, tokenizer) in &self.fields {
+            // Hits only return the first value of a text field
+            let Some(text) = doc.get_first(*field).and_then(|value| value.as_str()) else {
+                continue;
+            };
+
+            let mut offsets = Vec::new();
+            let mut tokenizer = tokenizer.clone();
+            let mut token_stream = tokenizer.token_stream(text);
+
+            while let Some(token) = token_stream.next() {
+                let overlaps = offsets
+                    .last()
+                    .is_some_and(|&(_, end)| token.offset_from < end);
+                if !overlaps && terms.contains(&token.text) {
+                    offsets.push((token.offset_from, token.offset_to));
+                }
+            }
 
-    // Generate snippets for requested fields
-    let mut snippets_map: HashMap<String, rustler::Term> = HashMap::new();
+            if !offsets.is_empty() {
+                offsets_map.insert(name, offsets);
+            }
+        }
 
-    for (field_name, generator) in snippet_generators {
-        let snippet = generator.snippet_from_doc(doc);
-        let snippet_html = snippet.to_html();
-        snippets_map.insert(field_name.clone(), snippet_html.encode(env));
+        offsets_map.encode(env)
     }
+}
 
-    let snippets_elixir_map = snippets_map.encode(env);
+/// Escapes text for HTML the same way Tantivy's snippets do
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Converts a Tantivy document to an Elixir hit map with snippets
+/// With `fragment_scorers`, the hit gets a `"best_snippet"` for the field whose best
+/// fragment scores highest, the first listed on ties, instead of the `"snippets"` map
+fn document_to_hit_map_with_snippets<'a>(
+    env: rustler::Env<'a>,
+    schema: &tantivy::schema::Schema,
+    doc: &TantivyDocument,
+    score: f32,
+    id_field: Option<Field>,
+    returned_fields: Option<&[Field]>,
+    multi_fields: &[Field],
+    snippet_generators: &HashMap<String, SnippetGenerator>,
+    full_text_highlighters: &HashMap<String, FullTextHighlighter>,
+    word_window_highlighters: &HashMap<String, WordWindowHighlighter>,
+    fragment_scorers: Option<&[(String, FragmentScorer)]>,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::types::map;
+    use rustler::Encoder;
+
+    let (doc_map, unsupported_fields) =
+        stored_fields_to_term(env, schema, doc, returned_fields, multi_fields);
+
+    // Short values are returned whole instead of as a single best fragment
+    let snippet_html = |field_name: &str, snippet: Snippet| {
+        full_text_highlighters
+            .get(field_name)
+            .and_then(|highlighter| highlighter.highlight(doc))
+            .unwrap_or_else(|| match word_window_highlighters.get(field_name) {
+                Some(highlighter) => highlighter.highlight(doc),
+                None => snippet.to_html(),
+            })
+    };
 
     // Create the hit map with score, doc, and snippets
-    map::map_new(env)
-        .map_put("score".encode(env), score.encode(env))
+    let hit_map = map::map_new(env)
+        .map_put("score".encode(env), score_to_f64(score).encode(env))
         .ok()
         .unwrap()
         .map_put("doc".encode(env), doc_map)
         .ok()
-        .unwrap()
-        .map_put("snippets".encode(env), snippets_elixir_map)
-        .ok()
-        .unwrap()
+        .unwrap();
+
+    let hit_map = match fragment_scorers {
+        Some(fragment_scorers) => {
+            // Only the winning field is highlighted
+            let mut best: Option<(&str, Snippet, Score)> = None;
+            for (field_name, scorer) in fragment_scorers {
+                let Some(generator) = snippet_generators.get(field_name) else {
+                    continue;
+                };
+                let snippet = generator.snippet_from_doc(doc);
+                let fragment_score = scorer.score(snippet.fragment());
+                let better = match &best {
+                    Some((_, _, best_score)) => fragment_score > *best_score,
+                    None => fragment_score > 0.0,
+                };
+                if better {
+                    best = Some((field_name, snippet, fragment_score));
+                }
+            }
+
+            let best_snippet = best.map(|(field_name, snippet, _)| {
+                HashMap::from([
+                    ("field", field_name.to_string()),
+                    ("snippet", snippet_html(field_name, snippet)),
+                ])
+            });
+
+            hit_map
+                .map_put("best_snippet".encode(env), best_snippet.encode(env))
+                .ok()
+                .unwrap()
+        }
+        None => {
+            // Generate snippets for requested fields
+            let snippets_map: HashMap<&str, String> = snippet_generators
+                .iter()
+                .map(|(field_name, generator)| {
+                    let snippet = generator.snippet_from_doc(doc);
+                    (field_name.as_str(), snippet_html(field_name, snippet))
+                })
+                .collect();
+
+            hit_map
+                .map_put("snippets".encode(env), snippets_map.encode(env))
+                .ok()
+                .unwrap()
+        }
+    };
+    let hit_map = put_unsupported_fields(env, hit_map, unsupported_fields);
+
+    put_hit_id(env, hit_map, schema, doc, id_field)
 }
 
 pub fn load(env: Env) -> bool {
@@ -0,0 +1,201 @@
+use rustler::types::map;
+use rustler::{Encoder, ResourceArc};
+use std::cmp::Reverse;
+use tantivy::query::EnableScoring;
+use tantivy::schema::FieldType;
+use tantivy::{DocAddress, Score, SegmentOrdinal};
+
+use crate::aliases::resolve_alias;
+use crate::error::MuninnError;
+use crate::grouping::KeyColumn;
+use crate::searcher::{document_to_hit_map, fetch_documents, parse_query, SearcherResource};
+
+/// Sort key of a hit: whether it has a value, then the value, negated for ascending
+/// order, so the best hits always have the largest key
+type SortKey = (bool, i128);
+
+/// Performs a query and returns the hits with the largest or smallest values of a fast
+/// field, instead of the most relevant ones
+/// Segments are searched from the one holding the best value, and a segment whose best
+/// value cannot beat the worst hit already collected is skipped. Documents are usually
+/// added in time order, so "latest N" queries on a timestamp only search the newest
+/// segments instead of every match
+pub fn searcher_search_sorted<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    sort_field: String,
+    order: String,
+    limit: usize,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let sort_field = resolve_alias(&aliases, &sort_field);
+    let schema = searcher.index().schema();
+
+    let field = schema
+        .get_field(&sort_field)
+        .map_err(|_| MuninnError::field_not_found(&sort_field))?;
+    let field_entry = schema.get_field_entry(field);
+
+    if !KeyColumn::supports(field_entry) {
+        return Err(MuninnError::invalid_argument(format!(
+            "Sort field '{}' must be a u64, i64 or bool fast field",
+            sort_field
+        )));
+    }
+
+    let descending = match order.as_str() {
+        "asc" => false,
+        "desc" => true,
+        _ => {
+            return Err(MuninnError::invalid_argument(format!(
+                "Invalid sort order '{}': expected asc or desc",
+                order
+            )))
+        }
+    };
+
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    let search_error =
+        |e: tantivy::TantivyError| MuninnError::search_error(format!("Search failed: {}", e));
+
+    let weight = query
+        .weight(EnableScoring::disabled_from_searcher(searcher))
+        .map_err(search_error)?;
+
+    let sort_key = |value: i128| -> SortKey {
+        if descending {
+            (true, value)
+        } else {
+            (true, -value)
+        }
+    };
+
+    // Open every segment's column and order the segments by the best value they hold
+    let mut segments = Vec::new();
+    for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+        let column = KeyColumn::open(segment_reader, &sort_field, field_entry.field_type())
+            .map_err(search_error)?;
+        let best_key = match column.bounds() {
+            Some((min_value, max_value)) => {
+                sort_key(if descending { max_value } else { min_value })
+            }
+            None => (false, 0),
+        };
+        segments.push((best_key, segment_ord as SegmentOrdinal, column));
+    }
+    segments.sort_by_key(|(best_key, segment_ord, _)| (Reverse(*best_key), *segment_ord));
+
+    let num_segments = segments.len();
+    let mut segments_searched = 0;
+    let mut hits: Vec<(SortKey, DocAddress)> = Vec::new();
+
+    for (best_key, segment_ord, column) in segments {
+        // Segments are ordered by their best key, so no later segment can do better
+        if hits.len() >= limit && hits.last().is_some_and(|(worst, _)| best_key < *worst) {
+            break;
+        }
+        segments_searched += 1;
+
+        let segment_reader = searcher.segment_reader(segment_ord);
+        let alive_bitset = segment_reader.alive_bitset();
+
+        weight
+            .for_each_no_score(segment_reader, &mut |docs| {
+                for &doc_id in docs {
+                    if alive_bitset.is_some_and(|alive_bitset| !alive_bitset.is_alive(doc_id)) {
+                        continue;
+                    }
+
+                    let key = match column.first_key(doc_id) {
+                        Some(value) => sort_key(value),
+                        // Documents without a value come after every document with one
+                        None => (false, 0),
+                    };
+                    hits.push((key, DocAddress::new(segment_ord, doc_id)));
+
+                    // Trim in batches rather than on every hit
+                    if hits.len() >= limit.max(1) * 2 {
+                        keep_top_hits(&mut hits, limit);
+                    }
+                }
+            })
+            .map_err(search_error)?;
+
+        keep_top_hits(&mut hits, limit);
+    }
+
+    let scored_hits: Vec<(Score, DocAddress)> = hits
+        .iter()
+        .map(|(_, doc_address)| (1.0, *doc_address))
+        .collect();
+    let docs = fetch_documents(searcher, &scored_hits)?;
+    let returned_fields = searcher_res.returned_fields()?;
+
+    let mut hit_terms = Vec::new();
+    for (((has_value, key), _), doc) in hits.iter().zip(docs) {
+        let value = if descending { *key } else { -*key };
+        let sort_value = match field_entry.field_type() {
+            _ if !has_value => None::<bool>.encode(env),
+            FieldType::U64(_) => (value as u64).encode(env),
+            FieldType::I64(_) => (value as i64).encode(env),
+            _ => (value != 0).encode(env),
+        };
+
+        let hit_map = document_to_hit_map(
+            env,
+            &schema,
+            &doc,
+            1.0,
+            searcher_res.id_field,
+            returned_fields.as_deref(),
+            &searcher_res.multi_fields,
+        )?
+        .map_put("sort_value".encode(env), sort_value)
+        .ok()
+        .unwrap();
+        hit_terms.push(hit_map);
+    }
+
+    let result_map = map::map_new(env)
+        .map_put("total_hits".encode(env), hits.len().encode(env))
+        .ok()
+        .unwrap()
+        .map_put("hits".encode(env), hit_terms.encode(env))
+        .ok()
+        .unwrap()
+        .map_put(
+            "segments_searched".encode(env),
+            segments_searched.encode(env),
+        )
+        .ok()
+        .unwrap()
+        .map_put(
+            "segments_skipped".encode(env),
+            (num_segments - segments_searched).encode(env),
+        )
+        .ok()
+        .unwrap();
+
+    Ok(result_map)
+}
+
+/// Sorts hits by descending key, then by address for stable ties, and keeps the first `n`
+fn keep_top_hits(hits: &mut Vec<(SortKey, DocAddress)>, n: usize) {
+    hits.sort_by(|(key_a, address_a), (key_b, address_b)| {
+        key_b.cmp(key_a).then_with(|| address_a.cmp(address_b))
+    });
+    hits.truncate(n);
+}
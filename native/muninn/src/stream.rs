@@ -0,0 +1,118 @@
+use rustler::{Env, ResourceArc};
+use std::panic::RefUnwindSafe;
+use std::sync::Mutex;
+use tantivy::collector::TopDocs;
+use tantivy::schema::Field;
+use tantivy::{DocAddress, Searcher};
+
+use crate::error::MuninnError;
+use crate::searcher::{document_to_hit_map, fetch_documents, parse_query, SearcherResource};
+
+/// Resource wrapper for a streaming search cursor
+/// Holds every matching doc address up front and hands out stored documents
+/// one batch at a time, so large result sets never have to be encoded at once
+pub struct StreamCursorResource {
+    pub searcher: Searcher,
+    pub hits: Vec<(f32, DocAddress)>,
+    pub batch_size: usize,
+    pub position: Mutex<usize>,
+    /// Field whose value is returned as "_id" in every hit
+    pub id_field: Option<Field>,
+    /// Numeric fields whose values are returned as a list in every hit
+    pub multi_fields: Vec<Field>,
+    /// Stored fields returned in every hit, as set on the searcher when the stream started
+    pub returned_fields: Option<Vec<Field>>,
+}
+
+unsafe impl Send for StreamCursorResource {}
+unsafe impl Sync for StreamCursorResource {}
+impl RefUnwindSafe for StreamCursorResource {}
+
+/// Runs the query and returns a cursor over all of its matches
+/// A batch is capped by the index's maximum limit, like a single search
+pub fn searcher_search_stream(
+    searcher_res: ResourceArc<SearcherResource>,
+    query_string: String,
+    default_fields: Vec<String>,
+    batch_size: usize,
+) -> Result<ResourceArc<StreamCursorResource>, MuninnError> {
+    if batch_size == 0 {
+        return Err(MuninnError::invalid_argument(
+            "Batch size must be greater than 0",
+        ));
+    }
+    searcher_res.check_limit(batch_size)?;
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let query = parse_query(
+        searcher,
+        &searcher_res.synonyms,
+        &aliases,
+        &query_string,
+        &default_fields,
+        &[],
+    )?;
+
+    // Collect every match, ordered by score like the regular search functions
+    let num_docs = searcher.num_docs() as usize;
+    let hits = searcher
+        .search(&*query, &TopDocs::with_limit(num_docs.max(1)))
+        .map_err(|e| MuninnError::search_error(format!("Search failed: {}", e)))?;
+
+    Ok(ResourceArc::new(StreamCursorResource {
+        searcher: searcher.clone(),
+        hits,
+        batch_size,
+        position: Mutex::new(0),
+        id_field: searcher_res.id_field,
+        multi_fields: searcher_res.multi_fields.clone(),
+        returned_fields: searcher_res.returned_fields()?,
+    }))
+}
+
+/// Returns the next batch of hits from the cursor, or an empty list once exhausted
+pub fn searcher_stream_next<'a>(
+    env: rustler::Env<'a>,
+    cursor: ResourceArc<StreamCursorResource>,
+) -> Result<rustler::Term<'a>, MuninnError> {
+    use rustler::Encoder;
+
+    let mut position = cursor
+        .position
+        .lock()
+        .map_err(|_| MuninnError::search_error("Failed to acquire cursor lock"))?;
+
+    let start = *position;
+    let end = start
+        .saturating_add(cursor.batch_size)
+        .min(cursor.hits.len());
+    let schema = cursor.searcher.schema();
+    let batch = &cursor.hits[start.min(end)..end];
+    let docs = fetch_documents(&cursor.searcher, batch)?;
+
+    let hits = batch
+        .iter()
+        .zip(docs)
+        .map(|((score, _), doc)| {
+            document_to_hit_map(
+                env,
+                schema,
+                &doc,
+                *score,
+                cursor.id_field,
+                cursor.returned_fields.as_deref(),
+                &cursor.multi_fields,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    *position = end;
+
+    Ok(hits.encode(env))
+}
+
+pub fn load(env: Env) -> bool {
+    rustler::resource!(StreamCursorResource, env);
+    true
+}
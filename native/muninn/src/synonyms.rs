@@ -0,0 +1,112 @@
+use rustler::ResourceArc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tantivy::query_grammar::{Delimiter, Occur, UserInputAst, UserInputLeaf, UserInputLiteral};
+
+use crate::error::MuninnError;
+use crate::index::IndexResource;
+
+/// Synonyms of a term or phrase, keyed by its lowercased text
+pub type Synonyms = HashMap<String, Vec<String>>;
+
+/// Synonym map shared by an index and the readers and searchers created from it
+pub type SharedSynonyms = Arc<Mutex<Synonyms>>;
+
+/// Replaces the synonyms used when parsing query strings on the index
+/// Synonyms are not persisted with the index and must be set again after opening it
+pub fn index_set_synonyms(
+    index_res: ResourceArc<IndexResource>,
+    synonyms: Synonyms,
+) -> Result<(), MuninnError> {
+    let mut normalized = Synonyms::new();
+
+    for (text, expansions) in synonyms {
+        let text = normalize(&text);
+        if text.is_empty() {
+            return Err(MuninnError::invalid_argument(
+                "Synonym keys cannot be empty",
+            ));
+        }
+
+        let expansions: Vec<String> = expansions
+            .iter()
+            .map(|expansion| expansion.trim().to_string())
+            .filter(|expansion| !expansion.is_empty() && normalize(expansion) != text)
+            .collect();
+        normalized.entry(text).or_default().extend(expansions);
+    }
+
+    *index_res
+        .synonyms
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire synonyms lock"))? = normalized;
+
+    Ok(())
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Expands every plain word or phrase of a parsed query that has synonyms into a
+/// disjunction of the original and its synonyms, searched in the same field
+/// Multi-word synonyms are searched as phrases. Fuzzy, prefix and slop literals are
+/// left alone since their text is not a whole term
+pub fn expand_synonyms(ast: UserInputAst, synonyms: &Synonyms) -> UserInputAst {
+    match ast {
+        UserInputAst::Clause(clauses) => UserInputAst::Clause(
+            clauses
+                .into_iter()
+                .map(|(occur, clause)| (occur, expand_synonyms(clause, synonyms)))
+                .collect(),
+        ),
+        UserInputAst::Boost(ast, boost) => {
+            UserInputAst::Boost(Box::new(expand_synonyms(*ast, synonyms)), boost)
+        }
+        UserInputAst::Leaf(leaf) => match *leaf {
+            UserInputLeaf::Literal(literal) => expand_literal(literal, synonyms),
+            leaf => UserInputAst::Leaf(Box::new(leaf)),
+        },
+    }
+}
+
+fn expand_literal(literal: UserInputLiteral, synonyms: &Synonyms) -> UserInputAst {
+    let expansions = if literal.slop == 0 && !literal.prefix {
+        synonyms.get(&normalize(&literal.phrase))
+    } else {
+        None
+    };
+
+    let Some(expansions) = expansions.filter(|expansions| !expansions.is_empty()) else {
+        return literal_ast(literal);
+    };
+
+    let synonym_literals: Vec<UserInputLiteral> = expansions
+        .iter()
+        .map(|expansion| UserInputLiteral {
+            field_name: literal.field_name.clone(),
+            phrase: expansion.clone(),
+            delimiter: if expansion.contains(char::is_whitespace) {
+                Delimiter::DoubleQuotes
+            } else {
+                Delimiter::None
+            },
+            slop: 0,
+            prefix: false,
+        })
+        .collect();
+
+    let clauses = std::iter::once(literal)
+        .chain(synonym_literals)
+        .map(|literal| (Some(Occur::Should), literal_ast(literal)))
+        .collect();
+
+    UserInputAst::Clause(clauses)
+}
+
+fn literal_ast(literal: UserInputLiteral) -> UserInputAst {
+    UserInputAst::Leaf(Box::new(UserInputLeaf::Literal(literal)))
+}
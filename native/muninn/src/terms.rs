@@ -0,0 +1,167 @@
+use rustler::types::map;
+use rustler::{Encoder, ResourceArc};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use tantivy::postings::Postings;
+use tantivy::schema::{FieldType, IndexRecordOption};
+use tantivy::DocSet;
+
+use crate::aliases::resolve_alias;
+use crate::error::MuninnError;
+use crate::searcher::{live_doc_address, SearcherResource};
+
+/// Returns the `top_n` most frequent terms of a text field within one document, as
+/// `{"term", "freq"}` maps ordered by descending frequency, then by term
+/// Every term of the field in the document's segment is looked up in the postings, so
+/// the cost grows with the number of distinct terms of the field, not of the document.
+/// The field must index term frequencies, which keyword fields do not
+pub fn searcher_doc_term_freqs<'a>(
+    env: rustler::Env<'a>,
+    searcher_res: ResourceArc<SearcherResource>,
+    doc_address: (u32, u32),
+    field_name: String,
+    top_n: usize,
+) -> Result<Vec<rustler::Term<'a>>, MuninnError> {
+    if top_n == 0 {
+        return Err(MuninnError::invalid_argument(
+            "Number of terms must be greater than 0",
+        ));
+    }
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let field_name = resolve_alias(&aliases, &field_name);
+    let schema = searcher.schema();
+
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+    let has_freqs = match schema.get_field_entry(field).field_type() {
+        FieldType::Str(options) => options
+            .get_indexing_options()
+            .is_some_and(|indexing| indexing.index_option().has_freq()),
+        _ => false,
+    };
+    if !has_freqs {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' must be a text field indexing term frequencies",
+            field_name
+        )));
+    }
+
+    let doc_address = live_doc_address(searcher, doc_address)?;
+    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+
+    let read_error =
+        |e: std::io::Error| MuninnError::search_error(format!("Failed to read terms: {}", e));
+
+    let inverted_index = segment_reader
+        .inverted_index(field)
+        .map_err(|e| MuninnError::search_error(format!("Failed to read terms: {}", e)))?;
+    let mut stream = inverted_index.terms().stream().map_err(read_error)?;
+
+    let mut term_freqs = Vec::new();
+    while stream.advance() {
+        let mut postings = inverted_index
+            .read_postings_from_terminfo(stream.value(), IndexRecordOption::WithFreqs)
+            .map_err(read_error)?;
+
+        if postings.seek(doc_address.doc_id) != doc_address.doc_id {
+            continue;
+        }
+
+        if let Ok(term_text) = std::str::from_utf8(stream.key()) {
+            term_freqs.push((term_text.to_string(), postings.term_freq()));
+        }
+    }
+
+    term_freqs.sort_by(|(term_a, freq_a), (term_b, freq_b)| {
+        Reverse(freq_a)
+            .cmp(&Reverse(freq_b))
+            .then_with(|| term_a.cmp(term_b))
+    });
+    term_freqs.truncate(top_n);
+
+    Ok(term_freqs
+        .into_iter()
+        .map(|(term_text, freq)| {
+            map::map_new(env)
+                .map_put("term".encode(env), term_text.encode(env))
+                .ok()
+                .unwrap()
+                .map_put("freq".encode(env), freq.encode(env))
+                .ok()
+                .unwrap()
+        })
+        .collect())
+}
+
+/// Returns the terms of a text field starting with `prefix`, in term order, with the
+/// number of documents holding each, as `{term, doc_freq}` pairs, at most `limit` of them
+/// Document frequencies are summed over the segments and, like the statistics used for
+/// scoring, still count deleted documents until their segment is merged. A term among
+/// the first `limit` overall is among the first `limit` of every segment holding it, so
+/// each segment's dictionary is only read up to `limit` terms past the prefix
+pub fn searcher_term_dict(
+    searcher_res: ResourceArc<SearcherResource>,
+    field_name: String,
+    prefix: String,
+    limit: usize,
+) -> Result<Vec<(String, u64)>, MuninnError> {
+    searcher_res.check_limit(limit)?;
+
+    if limit == 0 {
+        return Err(MuninnError::invalid_argument(
+            "Number of terms must be greater than 0",
+        ));
+    }
+
+    let searcher = &searcher_res.searcher;
+    let aliases = searcher_res.field_aliases()?;
+    let field_name = resolve_alias(&aliases, &field_name);
+    let schema = searcher.schema();
+
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+    let field_entry = schema.get_field_entry(field);
+    if !matches!(field_entry.field_type(), FieldType::Str(_)) || !field_entry.is_indexed() {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not an indexed text or keyword field",
+            field_name
+        )));
+    }
+
+    let read_error =
+        |e: std::io::Error| MuninnError::search_error(format!("Failed to read terms: {}", e));
+
+    let mut doc_freqs: BTreeMap<String, u64> = BTreeMap::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader
+            .inverted_index(field)
+            .map_err(|e| MuninnError::search_error(format!("Failed to read terms: {}", e)))?;
+        let mut stream = inverted_index
+            .terms()
+            .range()
+            .ge(prefix.as_bytes())
+            .into_stream()
+            .map_err(read_error)?;
+
+        let mut read = 0;
+        while read < limit && stream.advance() {
+            if !stream.key().starts_with(prefix.as_bytes()) {
+                break;
+            }
+            read += 1;
+
+            if let Ok(term_text) = std::str::from_utf8(stream.key()) {
+                *doc_freqs.entry(term_text.to_string()).or_default() +=
+                    u64::from(stream.value().doc_freq);
+            }
+        }
+    }
+
+    Ok(doc_freqs.into_iter().take(limit).collect())
+}
@@ -0,0 +1,105 @@
+use tantivy::collector::{Collector, ScoreSegmentTweaker, ScoreTweaker, TopDocs};
+use tantivy::schema::{FieldType, Schema};
+use tantivy::{DocAddress, DocId, Score, SegmentReader};
+
+use crate::error::MuninnError;
+use crate::grouping::KeyColumn;
+
+/// Score of a hit followed by its tiebreak key, compared in that order
+pub(crate) type TiebreakScore = (Score, i128);
+
+/// Orders hits with equal scores by the value of a fast field instead of by doc
+/// address, which changes when segments merge
+#[derive(Clone)]
+pub(crate) struct Tiebreak {
+    field_name: String,
+    field_type: FieldType,
+    descending: bool,
+}
+
+pub(crate) struct TiebreakSegment {
+    column: KeyColumn,
+    descending: bool,
+}
+
+impl Tiebreak {
+    /// Checks that the field is a u64, i64 or bool fast field and that the order is
+    /// "asc" or "desc"
+    pub(crate) fn new(
+        schema: &Schema,
+        field_name: String,
+        order: &str,
+    ) -> Result<Self, MuninnError> {
+        let field = schema
+            .get_field(&field_name)
+            .map_err(|_| MuninnError::field_not_found(&field_name))?;
+        let field_entry = schema.get_field_entry(field);
+
+        if !KeyColumn::supports(field_entry) {
+            return Err(MuninnError::invalid_argument(format!(
+                "Tiebreak field '{}' must be a u64, i64 or bool fast field",
+                field_name
+            )));
+        }
+
+        let descending = match order {
+            "asc" => false,
+            "desc" => true,
+            _ => {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Invalid tiebreak order '{}': expected asc or desc",
+                    order
+                )))
+            }
+        };
+
+        Ok(Tiebreak {
+            field_name,
+            field_type: field_entry.field_type().clone(),
+            descending,
+        })
+    }
+
+    /// Collects the top hits by score, then by the tiebreak field
+    pub(crate) fn top_docs(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> impl Collector<Fruit = Vec<(TiebreakScore, DocAddress)>> {
+        TopDocs::with_limit(limit)
+            .and_offset(offset)
+            .tweak_score(self.clone())
+    }
+}
+
+impl ScoreTweaker<TiebreakScore> for Tiebreak {
+    type Child = TiebreakSegment;
+
+    fn segment_tweaker(&self, segment_reader: &SegmentReader) -> tantivy::Result<TiebreakSegment> {
+        Ok(TiebreakSegment {
+            column: KeyColumn::open(segment_reader, &self.field_name, &self.field_type)?,
+            descending: self.descending,
+        })
+    }
+}
+
+impl ScoreSegmentTweaker<TiebreakScore> for TiebreakSegment {
+    fn score(&mut self, doc: DocId, score: Score) -> TiebreakScore {
+        // Documents without a value rank after every document with one
+        let key = match self.column.first_key(doc) {
+            Some(key) if self.descending => key,
+            Some(key) => -key,
+            None => i128::MIN,
+        };
+        (score, key)
+    }
+}
+
+/// Drops the tiebreak keys from collected hits, keeping their order
+pub(crate) fn strip_tiebreak_keys(
+    hits: Vec<(TiebreakScore, DocAddress)>,
+) -> Vec<(Score, DocAddress)> {
+    hits.into_iter()
+        .map(|((score, _), doc_address)| (score, doc_address))
+        .collect()
+}
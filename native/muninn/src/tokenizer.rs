@@ -0,0 +1,357 @@
+use rustler::{Encoder, Env, ResourceArc, Term};
+use std::collections::BTreeMap;
+use tantivy::schema::FieldType;
+use tantivy::tokenizer::{
+    LowerCaser, RawTokenizer, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+use tantivy::Index;
+
+use crate::error::MuninnError;
+use crate::index::IndexResource;
+
+/// Tokenizer indexing a whole value as a single lowercased term, for keyword fields
+/// matched exactly but regardless of case
+pub const RAW_LOWERCASE_TOKENIZER: &str = "raw_lowercase";
+
+/// Name text fields use for the tokenizer registered by `index_register_cjk_tokenizer`
+/// with its default name
+pub const CJK_TOKENIZER: &str = "cjk";
+
+/// Components of the analyzer registered by `index_register_cjk_tokenizer`
+const CJK_COMPONENTS: [&str; 3] = ["CjkTokenizer", "RemoveLongFilter(40)", "LowerCaser"];
+
+/// Returns the tokenizers registered on every index, by Tantivy or
+/// `register_builtin_tokenizers`, with the components of their analyzers
+/// Tantivy's analyzers cannot describe themselves, so the components are listed here
+pub fn builtin_tokenizers() -> BTreeMap<String, Vec<String>> {
+    let tokenizers: [(&str, &[&str]); 5] = [
+        (
+            "default",
+            &["SimpleTokenizer", "RemoveLongFilter(40)", "LowerCaser"],
+        ),
+        (
+            "en_stem",
+            &[
+                "SimpleTokenizer",
+                "RemoveLongFilter(40)",
+                "LowerCaser",
+                "Stemmer(English)",
+            ],
+        ),
+        ("raw", &["RawTokenizer"]),
+        (RAW_LOWERCASE_TOKENIZER, &["RawTokenizer", "LowerCaser"]),
+        ("whitespace", &["WhitespaceTokenizer"]),
+    ];
+
+    tokenizers
+        .into_iter()
+        .map(|(name, components)| (name.to_string(), to_strings(components)))
+        .collect()
+}
+
+/// Returns the sorted tokenizer names a text field may be built with
+/// The built-in tokenizers are registered on every index, while the CJK tokenizer must
+/// be registered on the index before documents are added or searched. Checking names
+/// when the schema is built catches a misspelled tokenizer before any document is added
+pub fn field_tokenizer_names() -> Vec<String> {
+    let mut names: Vec<String> = builtin_tokenizers().into_keys().collect();
+    names.push(CJK_TOKENIZER.to_string());
+    names.sort();
+    names
+}
+
+fn to_strings(components: &[&str]) -> Vec<String> {
+    components
+        .iter()
+        .map(|component| component.to_string())
+        .collect()
+}
+
+/// Registers the tokenizers Muninn adds to every index next to Tantivy's own
+/// Tokenizers are not persisted, so this runs whenever an index is created or opened
+pub fn register_builtin_tokenizers(index: &Index) {
+    index.tokenizers().register(
+        RAW_LOWERCASE_TOKENIZER,
+        TextAnalyzer::builder(RawTokenizer::default())
+            .filter(LowerCaser)
+            .build(),
+    );
+}
+
+/// Tokenizer for Chinese, Japanese and Korean text
+/// CJK runs have no word separators, so they are split into overlapping character
+/// bigrams ("北京大学" -> "北京", "京大", "大学"). A CJK run of a single character
+/// becomes a unigram. Any other alphanumeric run is emitted as a whole word, like
+/// the default tokenizer
+#[derive(Clone, Default)]
+pub struct CjkTokenizer;
+
+/// Token stream over the tokens produced by `CjkTokenizer`
+pub struct CjkTokenStream {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Tokenizer for CjkTokenizer {
+    type TokenStream<'a> = CjkTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CjkTokenStream {
+        CjkTokenStream {
+            tokens: tokenize_cjk(text),
+            index: 0,
+        }
+    }
+}
+
+impl TokenStream for CjkTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.index < self.tokens.len() {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index - 1]
+    }
+}
+
+/// Returns true for Han ideographs, kana and hangul
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}'     // Hangul Jamo
+        | '\u{3040}'..='\u{309F}'   // Hiragana
+        | '\u{30A0}'..='\u{30FF}'   // Katakana
+        | '\u{3130}'..='\u{318F}'   // Hangul Compatibility Jamo
+        | '\u{31F0}'..='\u{31FF}'   // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}'   // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'   // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FA1F}' // CJK Unified Ideographs Extension B and later
+    )
+}
+
+fn push_token(tokens: &mut Vec<Token>, text: &str, offset_from: usize, offset_to: usize) {
+    tokens.push(Token {
+        offset_from,
+        offset_to,
+        position: tokens.len(),
+        text: text[offset_from..offset_to].to_string(),
+        position_length: 1,
+    });
+}
+
+/// Splits text into CJK bigrams and alphanumeric words
+fn tokenize_cjk(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    // (byte offset, char) of the CJK run currently being read
+    let mut cjk_run: Vec<(usize, char)> = Vec::new();
+    // Start offset of the alphanumeric word currently being read
+    let mut word_start: Option<usize> = None;
+
+    let flush_cjk = |tokens: &mut Vec<Token>, run: &mut Vec<(usize, char)>| {
+        match run.len() {
+            0 => {}
+            1 => {
+                let (offset, c) = run[0];
+                push_token(tokens, text, offset, offset + c.len_utf8());
+            }
+            _ => {
+                for pair in run.windows(2) {
+                    let (start, _) = pair[0];
+                    let (offset, c) = pair[1];
+                    push_token(tokens, text, start, offset + c.len_utf8());
+                }
+            }
+        }
+        run.clear();
+    };
+
+    for (offset, c) in text.char_indices() {
+        if is_cjk(c) {
+            if let Some(start) = word_start.take() {
+                push_token(&mut tokens, text, start, offset);
+            }
+            cjk_run.push((offset, c));
+        } else if c.is_alphanumeric() {
+            flush_cjk(&mut tokens, &mut cjk_run);
+            word_start.get_or_insert(offset);
+        } else {
+            flush_cjk(&mut tokens, &mut cjk_run);
+            if let Some(start) = word_start.take() {
+                push_token(&mut tokens, text, start, offset);
+            }
+        }
+    }
+
+    flush_cjk(&mut tokens, &mut cjk_run);
+    if let Some(start) = word_start {
+        push_token(&mut tokens, text, start, text.len());
+    }
+
+    tokens
+}
+
+/// Builds the analyzer registered by `index_register_cjk_tokenizer`
+/// Mirrors the default analyzer: long tokens are dropped and words are lowercased
+fn cjk_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(CjkTokenizer)
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .build()
+}
+
+/// Registers the CJK tokenizer under `CJK_TOKENIZER`, the name fields reference it by
+/// Tokenizers are not persisted with the index and must be registered again after opening it
+pub fn index_register_cjk_tokenizer(
+    index_res: ResourceArc<IndexResource>,
+) -> Result<(), MuninnError> {
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    index.tokenizers().register(CJK_TOKENIZER, cjk_analyzer());
+
+    index_res
+        .tokenizers
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire tokenizers lock"))?
+        .insert(CJK_TOKENIZER.to_string(), to_strings(&CJK_COMPONENTS));
+
+    Ok(())
+}
+
+/// Returns the sorted names of the tokenizers registered on the index
+/// Covers Tantivy's built-in tokenizers and those registered through Muninn
+pub fn index_list_tokenizers(
+    index_res: ResourceArc<IndexResource>,
+) -> Result<Vec<String>, MuninnError> {
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let registered = index_res
+        .tokenizers
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire tokenizers lock"))?;
+
+    let tokenizers = index.tokenizers();
+    Ok(registered
+        .keys()
+        .filter(|name| tokenizers.get(name).is_some())
+        .cloned()
+        .collect())
+}
+
+/// The tokens a field's analyzer produces for a text, and which analyzer that is
+pub struct FieldTokens {
+    pub tokenizer: String,
+    /// The analyzer's tokenizer and filters, in order
+    pub components: Vec<String>,
+    pub tokens: Vec<Token>,
+}
+
+impl Encoder for FieldTokens {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let tokens: Vec<Term<'a>> = self
+            .tokens
+            .iter()
+            .map(|token| {
+                rustler::types::map::map_new(env)
+                    .map_put("text".encode(env), token.text.encode(env))
+                    .ok()
+                    .unwrap()
+                    .map_put("position".encode(env), token.position.encode(env))
+                    .ok()
+                    .unwrap()
+                    .map_put("offset_from".encode(env), token.offset_from.encode(env))
+                    .ok()
+                    .unwrap()
+                    .map_put("offset_to".encode(env), token.offset_to.encode(env))
+                    .ok()
+                    .unwrap()
+            })
+            .collect();
+
+        rustler::types::map::map_new(env)
+            .map_put("tokenizer".encode(env), self.tokenizer.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("components".encode(env), self.components.encode(env))
+            .ok()
+            .unwrap()
+            .map_put("tokens".encode(env), tokens.encode(env))
+            .ok()
+            .unwrap()
+    }
+}
+
+/// Runs a text through the analyzer of an indexed text field, as when indexing it
+/// Returns the tokenizer name the field resolved to, the analyzer's components and
+/// the tokens, for checking that a schema's tokenizer setting took effect
+pub fn index_tokenize(
+    index_res: ResourceArc<IndexResource>,
+    field_name: String,
+    text: String,
+) -> Result<FieldTokens, MuninnError> {
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?;
+
+    let schema = index.schema();
+    let field = schema
+        .get_field(&field_name)
+        .map_err(|_| MuninnError::field_not_found(&field_name))?;
+
+    let FieldType::Str(text_options) = schema.get_field_entry(field).field_type() else {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not a text field",
+            field_name
+        )));
+    };
+    let Some(indexing) = text_options.get_indexing_options() else {
+        return Err(MuninnError::invalid_argument(format!(
+            "Field '{}' is not indexed",
+            field_name
+        )));
+    };
+
+    let tokenizer = indexing.tokenizer().to_string();
+    let mut analyzer = index.tokenizers().get(&tokenizer).ok_or_else(|| {
+        MuninnError::invalid_argument(format!(
+            "Tokenizer '{}' of field '{}' is not registered on the index",
+            tokenizer, field_name
+        ))
+    })?;
+
+    let components = index_res
+        .tokenizers
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire tokenizers lock"))?
+        .get(&tokenizer)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut tokens = Vec::new();
+    let mut token_stream = analyzer.token_stream(&text);
+    while let Some(token) = token_stream.next() {
+        tokens.push(token.clone());
+    }
+
+    Ok(FieldTokens {
+        tokenizer,
+        components,
+        tokens,
+    })
+}
@@ -1,133 +1,1071 @@
-use rustler::{Env, ResourceArc, Term};
+use rustler::{Encoder, Env, ResourceArc, Term};
 use std::collections::HashMap;
-use tantivy::schema::FieldType;
-use tantivy::TantivyDocument;
+use std::io::Write;
+use std::sync::{Arc, MutexGuard};
+use tantivy::directory::error::LockError;
+use tantivy::directory::{Directory, MmapDirectory, TerminatingWrite};
+use tantivy::index::SegmentId;
+use tantivy::indexer::{merge_filtered_segments, LogMergePolicy, NoMergePolicy};
+use tantivy::schema::{Field, FieldType, OwnedValue, Schema, Value};
+use tantivy::{
+    DocAddress, Index, IndexWriter, Segment, SegmentMeta, TantivyDocument, TantivyError,
+};
 
+use crate::error::MuninnError;
 use crate::index::IndexResource;
+use crate::schema::{ValueLimit, SOURCE_FIELD};
+use crate::searcher::term_for_value;
+
+/// Auto-commit settings and the number of documents added and deletes queued since the
+/// last commit
+#[derive(Debug, Default)]
+pub struct AutoCommit {
+    /// Commit after this many added documents, 0 when disabled
+    pub every_n_docs: usize,
+    pub pending_docs: usize,
+    pub pending_deletes: usize,
+}
+
+impl AutoCommit {
+    /// Forgets the pending operations once they are committed or rolled back
+    pub fn clear_pending(&mut self) {
+        self.pending_docs = 0;
+        self.pending_deletes = 0;
+    }
+}
+
+/// Rejects writes on an index opened read-only
+fn ensure_writable(index_res: &IndexResource) -> Result<(), MuninnError> {
+    if index_res.read_only {
+        return Err(MuninnError::read_only(
+            "Index was opened read-only and cannot be written to",
+        ));
+    }
+    Ok(())
+}
+
+/// Creates the index writer, which takes Tantivy's lock on the index directory
+/// A busy lock means another writer, possibly in another process, is active and is
+/// reported as `index_locked` so callers can back off and retry
+fn create_writer(index: &Index) -> Result<IndexWriter<TantivyDocument>, MuninnError> {
+    index.writer(50_000_000).map_err(|e| match e {
+        TantivyError::LockFailure(LockError::LockBusy, _) => {
+            MuninnError::index_locked(format!("Failed to create writer: {}", e))
+        }
+        _ => MuninnError::index_error(format!("Failed to create writer: {}", e)),
+    })
+}
+
+/// The index writer, locked
+type WriterGuard<'a> = MutexGuard<'a, Option<IndexWriter<TantivyDocument>>>;
+
+/// Locks the writer, then returns a copy of the index it writes to
+/// Every write takes the writer lock before the index lock, like `index_swap`, and holds
+/// the index lock only while copying the index. Opening a reader never waits for a
+/// write or a commit that way, and the index cannot be swapped while the writer lock
+/// is held, so the schema settings read meanwhile match the copied index
+fn lock_writer(index_res: &IndexResource) -> Result<(WriterGuard<'_>, Index), MuninnError> {
+    let writer_lock = index_res
+        .writer
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire writer lock"))?;
+
+    let index = index_res
+        .index
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire index lock"))?
+        .clone();
+
+    Ok((writer_lock, index))
+}
 
 /// Adds a document to the index
+/// Returns the commit opstamp when the add reached the auto-commit threshold, and the
+/// names of the fields whose values were truncated to their max length
 pub fn writer_add_document(
     index_res: ResourceArc<IndexResource>,
     document: Term,
-) -> Result<(), String> {
-    // Decode the document map from Elixir
-    let doc_map: HashMap<String, Term> = document
-        .decode()
-        .map_err(|_| "Failed to decode document: expected a map".to_string())?;
+) -> Result<(Option<u64>, Vec<String>), MuninnError> {
+    ensure_writable(&index_res)?;
 
-    let index = index_res
-        .index
-        .lock()
-        .map_err(|_| "Failed to acquire index lock".to_string())?;
+    let mut doc_map = decode_document(document)?;
+
+    let (mut writer_lock, index) = lock_writer(&index_res)?;
+
+    // Read under the writer lock, so the settings match the schema after an index swap
+    let schema_settings = index_res.schema_settings();
+    let truncated = enforce_value_limits(&schema_settings.value_limits, &mut doc_map)?;
 
     let schema = index.schema();
+    let mut tantivy_doc = build_document(&schema, doc_map)?;
+    copy_field_values(&schema_settings.copy_fields, &mut tantivy_doc);
+
+    check_required_fields(&schema_settings.required_fields, &schema, &tantivy_doc)?;
+    add_source(&schema, &mut tantivy_doc, document)?;
+
+    // Initialize writer if it doesn't exist
+    if writer_lock.is_none() {
+        *writer_lock = Some(create_writer(&index)?);
+    }
+
+    let writer = writer_lock.as_mut().unwrap();
+
+    writer
+        .add_document(tantivy_doc)
+        .map_err(|e| MuninnError::index_error(format!("Failed to add document: {}", e)))?;
+
+    let mut autocommit = lock_autocommit(&index_res)?;
+    autocommit.pending_docs += 1;
+
+    if autocommit.every_n_docs == 0 || autocommit.pending_docs < autocommit.every_n_docs {
+        return Ok((None, truncated));
+    }
+
+    let opstamp = writer
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+    autocommit.clear_pending();
+
+    Ok((Some(opstamp), truncated))
+}
+
+/// Decodes the document map from Elixir
+pub(crate) fn decode_document(document: Term) -> Result<HashMap<String, Term>, MuninnError> {
+    document
+        .decode()
+        .map_err(|_| MuninnError::invalid_argument("Failed to decode document: expected a map"))
+}
+
+/// Converts a decoded Elixir document map to a Tantivy document
+/// Unknown fields, nil values and text values that are not strings are skipped, so a
+/// field is only added when it genuinely has a value. A numeric or bool value that does
+/// not fit the field's type is rejected rather than dropped, since a missing value and
+/// a value such as 0 mean different things to range and exists queries
+pub(crate) fn build_document(
+    schema: &Schema,
+    doc_map: HashMap<String, Term>,
+) -> Result<TantivyDocument, MuninnError> {
     let mut tantivy_doc = TantivyDocument::default();
 
     // Convert Elixir map to Tantivy document
     for (field_name, value) in doc_map {
-        if let Ok(field) = schema.get_field(&field_name) {
-            let field_entry = schema.get_field_entry(field);
-
-            match field_entry.field_type() {
-                FieldType::Str(_) => {
-                    if let Ok(string_val) = value.decode::<String>() {
-                        tantivy_doc.add_text(field, &string_val);
-                    }
+        // The source field is filled from the whole document by `add_source`
+        if field_name == SOURCE_FIELD {
+            continue;
+        }
+
+        let Ok(field) = schema.get_field(&field_name) else {
+            continue;
+        };
+        let field_entry = schema.get_field_entry(field);
+
+        let invalid = |type_name: &str| {
+            MuninnError::invalid_argument(format!(
+                "Value of field '{}' is not a valid {}",
+                field_name, type_name
+            ))
+        };
+
+        match field_entry.field_type() {
+            FieldType::Str(_) => {
+                if let Ok(string_val) = value.decode::<String>() {
+                    tantivy_doc.add_text(field, &string_val);
                 }
-                FieldType::U64(_) => {
-                    // Try u64 first, then i64 (if positive)
-                    if let Ok(int_val) = value.decode::<u64>() {
-                        tantivy_doc.add_u64(field, int_val);
-                    } else if let Ok(int_val) = value.decode::<i64>() {
-                        if int_val >= 0 {
-                            tantivy_doc.add_u64(field, int_val as u64);
-                        }
-                    }
+            }
+            FieldType::U64(_) => {
+                for value in numeric_values(value).map_err(|_| invalid("u64 list"))? {
+                    let int_val = value.decode::<u64>().map_err(|_| invalid("u64"))?;
+                    tantivy_doc.add_u64(field, int_val);
                 }
-                FieldType::I64(_) => {
-                    if let Ok(int_val) = value.decode::<i64>() {
-                        tantivy_doc.add_i64(field, int_val);
-                    } else if let Ok(int_val) = value.decode::<u64>() {
-                        tantivy_doc.add_i64(field, int_val as i64);
-                    }
+            }
+            FieldType::I64(_) => {
+                for value in numeric_values(value).map_err(|_| invalid("i64 list"))? {
+                    let int_val = value.decode::<i64>().map_err(|_| invalid("i64"))?;
+                    tantivy_doc.add_i64(field, int_val);
                 }
-                FieldType::F64(_) => {
+            }
+            FieldType::F64(_) => {
+                for value in numeric_values(value).map_err(|_| invalid("f64 list"))? {
                     // Try f64, then fall back to integers
-                    if let Ok(float_val) = value.decode::<f64>() {
-                        tantivy_doc.add_f64(field, float_val);
+                    let float_val = if let Ok(float_val) = value.decode::<f64>() {
+                        float_val
                     } else if let Ok(int_val) = value.decode::<i64>() {
-                        tantivy_doc.add_f64(field, int_val as f64);
+                        int_val as f64
                     } else if let Ok(int_val) = value.decode::<u64>() {
-                        tantivy_doc.add_f64(field, int_val as f64);
-                    }
-                }
-                FieldType::Bool(_) => {
-                    if let Ok(bool_val) = value.decode::<bool>() {
-                        tantivy_doc.add_bool(field, bool_val);
-                    }
-                }
-                _ => {
-                    // Unsupported field type, skip
+                        int_val as f64
+                    } else {
+                        return Err(invalid("f64"));
+                    };
+                    tantivy_doc.add_f64(field, float_val);
                 }
             }
+            FieldType::Bool(_) if !is_nil(value) => {
+                let bool_val = value.decode::<bool>().map_err(|_| invalid("bool"))?;
+                tantivy_doc.add_bool(field, bool_val);
+            }
+            _ => {
+                // Unsupported field type or nil bool, skip
+            }
+        }
+    }
+
+    Ok(tantivy_doc)
+}
+
+/// Stores the original document as JSON when the schema has a source field
+fn add_source(
+    schema: &Schema,
+    tantivy_doc: &mut TantivyDocument,
+    document: Term,
+) -> Result<(), MuninnError> {
+    let Ok(source_field) = schema.get_field(SOURCE_FIELD) else {
+        return Ok(());
+    };
+
+    let json = term_to_json(document)?;
+    tantivy_doc.add_text(source_field, json.to_string());
+
+    Ok(())
+}
+
+/// Converts an Elixir term to JSON the way Jason encodes it
+/// nil, true and false become null and booleans, other atoms become strings. Map keys
+/// must be strings or atoms. Tuples, pids and other terms have no JSON representation
+fn term_to_json(term: Term) -> Result<serde_json::Value, MuninnError> {
+    use rustler::TermType;
+    use serde_json::Value as Json;
+
+    let unsupported =
+        || MuninnError::invalid_argument("Failed to encode document source: unsupported value");
+
+    let json = match term.get_type() {
+        TermType::Atom => match term.atom_to_string().map_err(|_| unsupported())?.as_str() {
+            "nil" => Json::Null,
+            "true" => Json::Bool(true),
+            "false" => Json::Bool(false),
+            atom => Json::String(atom.to_string()),
+        },
+        TermType::Binary => Json::String(term.decode().map_err(|_| unsupported())?),
+        TermType::Integer => match term.decode::<i64>() {
+            Ok(int_val) => Json::from(int_val),
+            Err(_) => Json::from(term.decode::<u64>().map_err(|_| unsupported())?),
+        },
+        TermType::Float => {
+            let float_val: f64 = term.decode().map_err(|_| unsupported())?;
+            serde_json::Number::from_f64(float_val)
+                .map(Json::Number)
+                .ok_or_else(unsupported)?
+        }
+        TermType::List => {
+            let elements: Vec<Term> = term.decode().map_err(|_| unsupported())?;
+            Json::Array(
+                elements
+                    .into_iter()
+                    .map(term_to_json)
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        TermType::Map => {
+            let mut object = serde_json::Map::new();
+            for (key, value) in
+                rustler::types::map::MapIterator::new(term).ok_or_else(unsupported)?
+            {
+                let key = match key.get_type() {
+                    TermType::Binary => key.decode().map_err(|_| unsupported())?,
+                    TermType::Atom => key.atom_to_string().map_err(|_| unsupported())?,
+                    _ => return Err(unsupported()),
+                };
+                object.insert(key, term_to_json(value)?);
+            }
+            Json::Object(object)
+        }
+        _ => return Err(unsupported()),
+    };
+
+    Ok(json)
+}
+
+/// Applies the index's value limits to a decoded document, truncating or rejecting
+/// text values longer than their field's max length
+/// Returns the names of the truncated fields. Truncation keeps whole UTF-8 characters,
+/// so a truncated value may be a few bytes shorter than the limit
+fn enforce_value_limits(
+    value_limits: &[ValueLimit],
+    doc_map: &mut HashMap<String, Term>,
+) -> Result<Vec<String>, MuninnError> {
+    let mut truncated = Vec::new();
+
+    for limit in value_limits {
+        let Some(value) = doc_map.get_mut(&limit.field_name) else {
+            continue;
+        };
+
+        let Ok(text) = value.decode::<&str>() else {
+            continue;
+        };
+
+        if text.len() <= limit.max_length {
+            continue;
+        }
+
+        if !limit.truncate {
+            return Err(MuninnError::invalid_argument(format!(
+                "Value of field '{}' is {} bytes, above the maximum of {}",
+                limit.field_name,
+                text.len(),
+                limit.max_length
+            )));
+        }
+
+        let mut end = limit.max_length;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        *value = text[..end].encode(value.get_env());
+        truncated.push(limit.field_name.clone());
+    }
+
+    Ok(truncated)
+}
+
+/// Adds the text values of every copied field to its target field, such as the exact
+/// companion of a stemmed field. Runs after value limits, so truncated values are copied
+pub(crate) fn copy_field_values(copy_fields: &[(Field, Field)], doc: &mut TantivyDocument) {
+    for (source, target) in copy_fields {
+        let values: Vec<String> = doc
+            .get_all(*source)
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect();
+
+        for value in values {
+            doc.add_text(*target, value);
         }
     }
+}
+
+/// Rejects a document without a value for every required field
+/// Values that could not be converted to the field's type count as missing
+fn check_required_fields(
+    required_fields: &[Field],
+    schema: &Schema,
+    doc: &TantivyDocument,
+) -> Result<(), MuninnError> {
+    let missing: Vec<&str> = required_fields
+        .iter()
+        .filter(|field| doc.get_first(**field).is_none())
+        .map(|field| schema.get_field_name(*field))
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(MuninnError::invalid_argument(format!(
+        "Document is missing required fields: {}",
+        missing.join(", ")
+    )))
+}
+
+/// Returns the elements of a list value, or the value itself, leaving out nil
+/// Numeric fields accept a list to store several values in one document. An improper
+/// list fails to decode rather than leaving the field without values
+fn numeric_values(value: Term) -> rustler::NifResult<Vec<Term>> {
+    let values = if value.is_list() {
+        value.decode()?
+    } else {
+        vec![value]
+    };
+
+    Ok(values.into_iter().filter(|value| !is_nil(*value)).collect())
+}
+
+fn is_nil(value: Term) -> bool {
+    value.is_atom() && value.atom_to_string().is_ok_and(|atom| atom == "nil")
+}
+
+fn lock_autocommit(
+    index_res: &IndexResource,
+) -> Result<std::sync::MutexGuard<'_, AutoCommit>, MuninnError> {
+    index_res
+        .autocommit
+        .lock()
+        .map_err(|_| MuninnError::index_error("Failed to acquire autocommit lock"))
+}
+
+/// Commits automatically once `every_n_docs` documents have been added, 0 disables it
+/// Documents already pending count towards the new threshold on the next add
+pub fn writer_set_autocommit(
+    index_res: ResourceArc<IndexResource>,
+    every_n_docs: usize,
+) -> Result<(), MuninnError> {
+    ensure_writable(&index_res)?;
+
+    lock_autocommit(&index_res)?.every_n_docs = every_n_docs;
+    Ok(())
+}
+
+/// Returns the number of documents added since the last commit or rollback
+pub fn writer_pending_docs(index_res: ResourceArc<IndexResource>) -> Result<usize, MuninnError> {
+    Ok(lock_autocommit(&index_res)?.pending_docs)
+}
+
+/// Commits all pending changes to the index
+pub fn writer_commit(index_res: ResourceArc<IndexResource>) -> Result<(), MuninnError> {
+    ensure_writable(&index_res)?;
 
-    // Get or create the persistent writer
     let mut writer_lock = index_res
         .writer
         .lock()
-        .map_err(|_| "Failed to acquire writer lock".to_string())?;
+        .map_err(|_| MuninnError::index_error("Failed to acquire writer lock"))?;
+
+    if let Some(writer) = writer_lock.as_mut() {
+        writer
+            .commit()
+            .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+    }
+
+    lock_autocommit(&index_res)?.clear_pending();
+
+    Ok(())
+}
+
+/// Commits the pending changes of several indexes together, rolling all of them back
+/// when any commit fails. An index listed twice is committed once
+/// The writers are locked in a fixed order, so concurrent calls cannot deadlock, and a
+/// commit is prepared on each of them, flushing its pending documents, before the first
+/// one is committed. Tantivy commits each index on its own, so this narrows but does
+/// not close the window in which one index is committed and another is not: a failure
+/// while committing, after preparing succeeded, leaves the indexes committed before it
+pub fn writer_multi_commit(indexes: Vec<ResourceArc<IndexResource>>) -> Result<(), MuninnError> {
+    for index_res in &indexes {
+        ensure_writable(index_res)?;
+    }
+
+    let mut index_refs: Vec<&IndexResource> =
+        indexes.iter().map(|index_res| &**index_res).collect();
+    index_refs.sort_by_key(|index_res| *index_res as *const IndexResource);
+    index_refs.dedup_by(|a, b| std::ptr::eq(*a, *b));
+
+    let mut writer_locks = index_refs
+        .iter()
+        .map(|index_res| {
+            index_res
+                .writer
+                .lock()
+                .map_err(|_| MuninnError::index_error("Failed to acquire writer lock"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Indexes without a writer have nothing to commit
+    let mut writers: Vec<&mut IndexWriter<TantivyDocument>> = writer_locks
+        .iter_mut()
+        .filter_map(|writer_lock| writer_lock.as_mut())
+        .collect();
+
+    let result = prepare_and_commit_all(&mut writers).map_err(|e| {
+        let mut rollback_failed = false;
+        for writer in writers.iter_mut() {
+            rollback_failed |= writer.rollback().is_err();
+        }
+
+        if rollback_failed {
+            MuninnError::index_error(format!("Failed to commit: {}, and to roll back", e))
+        } else {
+            MuninnError::index_error(format!("Failed to commit: {}, rolled back", e))
+        }
+    });
+
+    for index_res in index_refs {
+        lock_autocommit(index_res)?.clear_pending();
+    }
+
+    result
+}
+
+/// Prepares a commit on every writer, then commits them one after the other
+fn prepare_and_commit_all(
+    writers: &mut [&mut IndexWriter<TantivyDocument>],
+) -> tantivy::Result<()> {
+    let prepared_commits = writers
+        .iter_mut()
+        .map(|writer| writer.prepare_commit())
+        .collect::<tantivy::Result<Vec<_>>>()?;
+
+    for prepared_commit in prepared_commits {
+        prepared_commit.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Commits all pending changes, then merges the segments of the index down to at most
+/// `max_segments`, for a bulk load that should leave a compact index behind
+/// Background merges are waited for first, and the merge policy is paused while the
+/// segments are merged, so no other merge can claim them. Segments are grouped so the
+/// groups hold about the same number of documents. Returns the number of segments left
+pub fn writer_commit_and_merge(
+    index_res: ResourceArc<IndexResource>,
+    max_segments: usize,
+) -> Result<usize, MuninnError> {
+    ensure_writable(&index_res)?;
+
+    if max_segments == 0 {
+        return Err(MuninnError::invalid_argument(
+            "Max segments must be greater than 0",
+        ));
+    }
+
+    commit_and_merge(&index_res, max_segments, false)
+}
+
+/// Commits all pending changes, then merges the whole index into a single segment
+/// Unlike `writer_commit_and_merge`, an index already down to one segment is rewritten
+/// when it holds deleted documents, so every deleted document is purged and the
+/// fieldnorms and term statistics only count live documents
+pub fn writer_optimize(index_res: ResourceArc<IndexResource>) -> Result<(), MuninnError> {
+    ensure_writable(&index_res)?;
+
+    commit_and_merge(&index_res, 1, true)?;
+
+    Ok(())
+}
+
+/// Commits, then merges the segments down to at most `max_segments`, also rewriting
+/// segments that hold deleted documents when `purge_deletes` is set. Returns the number
+/// of segments left
+fn commit_and_merge(
+    index_res: &IndexResource,
+    max_segments: usize,
+    purge_deletes: bool,
+) -> Result<usize, MuninnError> {
+    let (mut writer_lock, index) = lock_writer(index_res)?;
+
+    let merge_error = |e: TantivyError| MuninnError::index_error(format!("Failed to merge: {}", e));
+
+    if let Some(mut writer) = writer_lock.take() {
+        writer
+            .commit()
+            .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+        writer.wait_merging_threads().map_err(merge_error)?;
+    }
+    lock_autocommit(index_res)?.clear_pending();
+
+    let mut segment_metas = index.searchable_segment_metas().map_err(merge_error)?;
+    let has_deletes = |segment_metas: &[SegmentMeta]| {
+        purge_deletes && segment_metas.iter().any(|meta| meta.has_deletes())
+    };
+
+    if segment_metas.len() > max_segments || has_deletes(&segment_metas) {
+        // Largest segments first, each into the group holding the fewest documents
+        segment_metas.sort_by_key(|meta| std::cmp::Reverse(meta.num_docs()));
+        let mut groups: Vec<(u32, Vec<SegmentMeta>)> = vec![(0, Vec::new()); max_segments];
+        for meta in &segment_metas {
+            let group = groups
+                .iter_mut()
+                .min_by_key(|(num_docs, _)| *num_docs)
+                .unwrap();
+            group.0 += meta.num_docs();
+            group.1.push(meta.clone());
+        }
+
+        let mut writer = create_writer(&index)?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+
+        let merges: Vec<_> = groups
+            .iter()
+            .filter(|(_, metas)| metas.len() > 1 || has_deletes(metas))
+            .map(|(_, metas)| {
+                let segment_ids: Vec<SegmentId> = metas.iter().map(|meta| meta.id()).collect();
+                writer.merge(&segment_ids)
+            })
+            .collect();
+        for merge in merges {
+            merge.wait().map_err(merge_error)?;
+        }
+
+        writer.garbage_collect_files().wait().map_err(merge_error)?;
+        writer.set_merge_policy(Box::new(LogMergePolicy::default()));
+        *writer_lock = Some(writer);
+    }
+
+    let num_segments = index.searchable_segment_ids().map_err(merge_error)?.len();
+
+    Ok(num_segments)
+}
+
+/// Commits all pending changes and stores an opaque payload in the commit metadata
+/// The writer is created if needed so a payload can be stamped even without pending documents
+pub fn writer_commit_with_payload(
+    index_res: ResourceArc<IndexResource>,
+    payload: String,
+) -> Result<(), MuninnError> {
+    ensure_writable(&index_res)?;
+
+    let (mut writer_lock, index) = lock_writer(&index_res)?;
 
-    // Initialize writer if it doesn't exist
     if writer_lock.is_none() {
-        let new_writer = index
-            .writer(50_000_000)
-            .map_err(|e| format!("Failed to create writer: {}", e))?;
-        *writer_lock = Some(new_writer);
+        *writer_lock = Some(create_writer(&index)?);
+    }
+
+    let writer = writer_lock.as_mut().unwrap();
+
+    let mut prepared_commit = writer
+        .prepare_commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to prepare commit: {}", e)))?;
+    prepared_commit.set_payload(&payload);
+    prepared_commit
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+
+    lock_autocommit(&index_res)?.clear_pending();
+
+    Ok(())
+}
+
+/// Adds a document and commits while holding the writer lock, returning the opstamp
+/// The commit also covers documents added earlier and not yet committed
+pub fn writer_add_and_commit(
+    index_res: ResourceArc<IndexResource>,
+    document: Term,
+) -> Result<u64, MuninnError> {
+    ensure_writable(&index_res)?;
+
+    let mut doc_map = decode_document(document)?;
+
+    let (mut writer_lock, index) = lock_writer(&index_res)?;
+
+    // Truncated values are not reported here, the opstamp is the only result
+    let schema_settings = index_res.schema_settings();
+    enforce_value_limits(&schema_settings.value_limits, &mut doc_map)?;
+
+    let schema = index.schema();
+    let mut tantivy_doc = build_document(&schema, doc_map)?;
+    copy_field_values(&schema_settings.copy_fields, &mut tantivy_doc);
+
+    check_required_fields(&schema_settings.required_fields, &schema, &tantivy_doc)?;
+    add_source(&schema, &mut tantivy_doc, document)?;
+
+    if writer_lock.is_none() {
+        *writer_lock = Some(create_writer(&index)?);
     }
 
     let writer = writer_lock.as_mut().unwrap();
 
     writer
         .add_document(tantivy_doc)
-        .map_err(|e| format!("Failed to add document: {}", e))?;
+        .map_err(|e| MuninnError::index_error(format!("Failed to add document: {}", e)))?;
 
-    Ok(())
+    let opstamp = writer
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+
+    lock_autocommit(&index_res)?.clear_pending();
+
+    Ok(opstamp)
 }
 
-/// Commits all pending changes to the index
-pub fn writer_commit(index_res: ResourceArc<IndexResource>) -> Result<(), String> {
+/// Deletes every document holding a term and commits while holding the writer lock,
+/// returning the opstamp. Text values are matched verbatim as a single term, so this
+/// is meant for keyword and numeric id fields
+/// The commit also covers documents added earlier and not yet committed
+pub fn writer_delete_and_commit(
+    index_res: ResourceArc<IndexResource>,
+    field_name: String,
+    value: Term,
+) -> Result<u64, MuninnError> {
+    ensure_writable(&index_res)?;
+
+    let (mut writer_lock, index) = lock_writer(&index_res)?;
+
+    let term = term_for_value(&index.schema(), &field_name, value)?;
+
+    if writer_lock.is_none() {
+        *writer_lock = Some(create_writer(&index)?);
+    }
+
+    let writer = writer_lock.as_mut().unwrap();
+
+    writer.delete_term(term);
+
+    let opstamp = writer
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+
+    lock_autocommit(&index_res)?.clear_pending();
+
+    Ok(opstamp)
+}
+
+/// Deletes every document holding any of the values in a field, returning the number
+/// of values processed. All values are converted before the first delete, so a value
+/// that does not match the field type rejects the whole batch
+/// The deletes are queued under a single writer lock and take effect on the next commit
+pub fn writer_delete_terms(
+    index_res: ResourceArc<IndexResource>,
+    field_name: String,
+    values: Vec<Term>,
+) -> Result<usize, MuninnError> {
+    ensure_writable(&index_res)?;
+
+    let (mut writer_lock, index) = lock_writer(&index_res)?;
+
+    let schema = index.schema();
+    let terms = values
+        .into_iter()
+        .map(|value| term_for_value(&schema, &field_name, value))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if writer_lock.is_none() {
+        *writer_lock = Some(create_writer(&index)?);
+    }
+
+    let writer = writer_lock.as_mut().unwrap();
+
+    let count = terms.len();
+    for term in terms {
+        writer.delete_term(term);
+    }
+
+    lock_autocommit(&index_res)?.pending_deletes += count;
+
+    Ok(count)
+}
+
+/// Rolls back all uncommitted changes
+pub fn writer_rollback(index_res: ResourceArc<IndexResource>) -> Result<(), MuninnError> {
+    ensure_writable(&index_res)?;
+
     let mut writer_lock = index_res
         .writer
         .lock()
-        .map_err(|_| "Failed to acquire writer lock".to_string())?;
+        .map_err(|_| MuninnError::index_error("Failed to acquire writer lock"))?;
 
     if let Some(writer) = writer_lock.as_mut() {
         writer
-            .commit()
-            .map_err(|e| format!("Failed to commit: {}", e))?;
+            .rollback()
+            .map_err(|e| MuninnError::index_error(format!("Failed to rollback: {}", e)))?;
     }
 
+    lock_autocommit(&index_res)?.clear_pending();
+
     Ok(())
 }
 
-/// Rolls back all uncommitted changes
-pub fn writer_rollback(index_res: ResourceArc<IndexResource>) -> Result<(), String> {
+/// Commits or rolls back pending changes, then drops the writer
+/// Dropping the writer releases Tantivy's lock on the index directory right away instead
+/// of whenever the index resource is garbage collected. The next write creates a new writer
+pub fn writer_close(
+    index_res: ResourceArc<IndexResource>,
+    commit: bool,
+) -> Result<(), MuninnError> {
+    ensure_writable(&index_res)?;
+
     let mut writer_lock = index_res
         .writer
         .lock()
-        .map_err(|_| "Failed to acquire writer lock".to_string())?;
+        .map_err(|_| MuninnError::index_error("Failed to acquire writer lock"))?;
 
-    if let Some(writer) = writer_lock.as_mut() {
+    if let Some(mut writer) = writer_lock.take() {
+        if commit {
+            writer
+                .commit()
+                .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+        } else {
+            writer
+                .rollback()
+                .map_err(|e| MuninnError::index_error(format!("Failed to rollback: {}", e)))?;
+        }
+
+        // Waits for merges to finish, then drops the writer and with it the directory lock
         writer
-            .rollback()
-            .map_err(|e| format!("Failed to rollback: {}", e))?;
+            .wait_merging_threads()
+            .map_err(|e| MuninnError::index_error(format!("Failed to close writer: {}", e)))?;
     }
 
+    lock_autocommit(&index_res)?.clear_pending();
+
     Ok(())
 }
 
+/// Copies every document of the source index into the destination index
+/// Documents are rebuilt from their stored values, so every source field must be stored,
+/// except fields copied from another field, which the destination fills from its own
+/// copied fields. Source fields missing from the destination schema are dropped
+/// The destination writer commits after each batch of `batch_size` documents
+/// Returns the number of documents copied
+pub fn index_reindex(
+    source_res: ResourceArc<IndexResource>,
+    dest_res: ResourceArc<IndexResource>,
+    batch_size: usize,
+) -> Result<u64, MuninnError> {
+    ensure_writable(&dest_res)?;
+
+    if batch_size == 0 {
+        return Err(MuninnError::invalid_argument(
+            "Batch size must be greater than 0",
+        ));
+    }
+
+    if Arc::ptr_eq(&source_res.index, &dest_res.index) {
+        return Err(MuninnError::invalid_argument(
+            "Source and destination must be different indexes",
+        ));
+    }
+
+    // Take a snapshot of the source, then release its lock before locking the destination
+    let (searcher, source_copy_fields) = {
+        let source_index = source_res
+            .index
+            .lock()
+            .map_err(|_| MuninnError::index_error("Failed to acquire source index lock"))?;
+
+        let searcher = source_index
+            .reader()
+            .map_err(|e| MuninnError::index_error(format!("Failed to create reader: {}", e)))?
+            .searcher();
+        (searcher, source_res.schema_settings().copy_fields)
+    };
+
+    let (mut writer_lock, dest_index) = lock_writer(&dest_res)?;
+
+    let copied_fields: Vec<Field> = source_copy_fields
+        .iter()
+        .map(|(_, target)| *target)
+        .collect();
+    let field_map = reindex_field_map(searcher.schema(), &dest_index.schema(), &copied_fields)?;
+    let dest_copy_fields = dest_res.schema_settings().copy_fields;
+
+    if writer_lock.is_none() {
+        *writer_lock = Some(create_writer(&dest_index)?);
+    }
+
+    let writer = writer_lock.as_mut().unwrap();
+    let mut copied = 0u64;
+    let mut batch_docs = 0;
+
+    for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc_address = DocAddress::new(segment_ord as u32, doc_id);
+            let source_doc: TantivyDocument = searcher.doc(doc_address).map_err(|e| {
+                MuninnError::index_error(format!("Failed to retrieve document: {}", e))
+            })?;
+
+            let mut dest_doc = TantivyDocument::default();
+            for (field, value) in source_doc.field_values() {
+                if let Some(dest_field) = field_map[field.field_id() as usize] {
+                    let owned_value: OwnedValue = value.into();
+                    dest_doc.add_field_value(dest_field, &owned_value);
+                }
+            }
+            copy_field_values(&dest_copy_fields, &mut dest_doc);
+
+            writer
+                .add_document(dest_doc)
+                .map_err(|e| MuninnError::index_error(format!("Failed to add document: {}", e)))?;
+            copied += 1;
+            batch_docs += 1;
+
+            // Counted as pending, so a batch left uncommitted by an error blocks a swap
+            lock_autocommit(&dest_res)?.pending_docs += 1;
+
+            if batch_docs == batch_size {
+                writer
+                    .commit()
+                    .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+                lock_autocommit(&dest_res)?.clear_pending();
+                batch_docs = 0;
+            }
+        }
+    }
+
+    writer
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+
+    lock_autocommit(&dest_res)?.clear_pending();
+
+    Ok(copied)
+}
+
+/// Adds the documents of other index directories to an index, for consolidating shards
+/// The live documents of every source are merged into one new segment, built in a
+/// temporary directory and then copied into the index, so nothing is re-analyzed and
+/// fields need not be stored. Every source must have the same schema as the index.
+/// The commit also covers documents added earlier and not yet committed. Returns the
+/// number of documents added
+pub fn index_merge_from(
+    dest_res: ResourceArc<IndexResource>,
+    source_paths: Vec<String>,
+) -> Result<u64, MuninnError> {
+    ensure_writable(&dest_res)?;
+
+    let (mut writer_lock, dest_index) = lock_writer(&dest_res)?;
+    let schema = dest_index.schema();
+    let dest_path = dest_res.path();
+
+    let mut segments = Vec::new();
+    for source_path in &source_paths {
+        let canonical_path = std::fs::canonicalize(source_path).ok();
+        if canonical_path.is_some() && canonical_path == dest_path {
+            return Err(MuninnError::invalid_argument(
+                "An index cannot be merged into itself",
+            ));
+        }
+
+        let source = Index::open_in_dir(source_path).map_err(|e| {
+            MuninnError::index_error(format!(
+                "Failed to open source index '{}': {}",
+                source_path, e
+            ))
+        })?;
+
+        if source.schema() != schema {
+            return Err(MuninnError::invalid_argument(format!(
+                "Source index '{}' has a different schema",
+                source_path
+            )));
+        }
+
+        segments.extend(source.searchable_segments().map_err(|e| {
+            MuninnError::index_error(format!(
+                "Failed to read source index '{}': {}",
+                source_path, e
+            ))
+        })?);
+    }
+
+    let num_docs: u32 = segments
+        .iter()
+        .map(|segment| segment.meta().num_docs())
+        .sum();
+    if num_docs == 0 {
+        return Ok(0);
+    }
+
+    if writer_lock.is_none() {
+        *writer_lock = Some(create_writer(&dest_index)?);
+    }
+
+    let writer = writer_lock.as_mut().unwrap();
+
+    let merge_path = std::env::temp_dir().join(format!(
+        "muninn-merge-{}",
+        SegmentId::generate_random().uuid_string()
+    ));
+    let merged = merge_segments_into(&dest_index, &segments, &merge_path);
+    let _ = std::fs::remove_dir_all(&merge_path);
+    let segment_meta = merged?;
+
+    writer
+        .add_segment(segment_meta)
+        .map_err(|e| MuninnError::index_error(format!("Failed to add segment: {}", e)))?;
+    writer
+        .commit()
+        .map_err(|e| MuninnError::index_error(format!("Failed to commit: {}", e)))?;
+
+    lock_autocommit(&dest_res)?.clear_pending();
+
+    Ok(num_docs as u64)
+}
+
+/// Merges segments into a single segment in a new index at `merge_path`, then copies
+/// its files into the destination index. Returns the segment's meta, which keeps the
+/// copied files from being garbage collected until it is added to the writer
+fn merge_segments_into(
+    dest_index: &Index,
+    segments: &[Segment],
+    merge_path: &std::path::Path,
+) -> Result<SegmentMeta, MuninnError> {
+    let merge_error =
+        |e: TantivyError| MuninnError::index_error(format!("Failed to merge indexes: {}", e));
+    let copy_error = |e: std::io::Error| {
+        MuninnError::index_error(format!("Failed to copy merged segment: {}", e))
+    };
+
+    std::fs::create_dir_all(merge_path).map_err(copy_error)?;
+    let merge_directory = MmapDirectory::open(merge_path)
+        .map_err(|e| MuninnError::index_error(format!("Failed to merge indexes: {}", e)))?;
+
+    let merged_index = merge_filtered_segments(
+        segments,
+        dest_index.settings().clone(),
+        segments.iter().map(|_| None).collect(),
+        merge_directory,
+    )
+    .map_err(merge_error)?;
+
+    let merged_segment = merged_index
+        .searchable_segments()
+        .map_err(merge_error)?
+        .pop()
+        .ok_or_else(|| MuninnError::index_error("Failed to merge indexes: no segment written"))?;
+
+    let segment_meta =
+        dest_index.new_segment_meta(merged_segment.id(), merged_segment.meta().max_doc());
+
+    // The listed files include components the segment may not have, such as deletes
+    for file in merged_segment.meta().list_files() {
+        if !merged_index.directory().exists(&file).unwrap_or(false) {
+            continue;
+        }
+
+        let bytes = merged_index
+            .directory()
+            .open_read(&file)
+            .map_err(|e| MuninnError::index_error(format!("Failed to read merged segment: {}", e)))?
+            .read_bytes()
+            .map_err(copy_error)?;
+
+        let mut write = dest_index.directory().open_write(&file).map_err(|e| {
+            MuninnError::index_error(format!("Failed to copy merged segment: {}", e))
+        })?;
+        write.write_all(bytes.as_slice()).map_err(copy_error)?;
+        write.terminate().map_err(copy_error)?;
+    }
+
+    Ok(segment_meta)
+}
+
+/// Maps each source field, by id, to the destination field of the same name
+/// Copied fields are skipped. Fails if another source field is not stored or its value
+/// type differs in the destination
+fn reindex_field_map(
+    source_schema: &Schema,
+    dest_schema: &Schema,
+    copied_fields: &[Field],
+) -> Result<Vec<Option<tantivy::schema::Field>>, MuninnError> {
+    source_schema
+        .fields()
+        .map(|(field, entry)| {
+            if copied_fields.contains(&field) {
+                return Ok(None);
+            }
+
+            if !entry.is_stored() {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Field '{}' is not stored and cannot be reindexed",
+                    entry.name()
+                )));
+            }
+
+            let Ok(dest_field) = dest_schema.get_field(entry.name()) else {
+                return Ok(None);
+            };
+
+            let dest_type = dest_schema.get_field_entry(dest_field).field_type();
+            if dest_type.value_type() != entry.field_type().value_type() {
+                return Err(MuninnError::invalid_argument(format!(
+                    "Field '{}' has a different type in the destination index",
+                    entry.name()
+                )));
+            }
+
+            Ok(Some(dest_field))
+        })
+        .collect()
+}
+
 pub fn load(_env: Env) -> bool {
     true
 }
@@ -0,0 +1,131 @@
+defmodule Muninn.ConcurrencyTest do
+  use ExUnit.Case, async: true
+
+  alias Muninn.{Index, IndexReader, IndexWriter, Schema, Searcher}
+
+  # Generous, so a slow machine passes while a deadlock still fails the test
+  @timeout 60_000
+
+  setup do
+    test_path = "/tmp/muninn_concurrency_#{:erlang.unique_integer([:positive])}"
+
+    on_exit(fn ->
+      Muninn.TestHelpers.safe_rm_rf(test_path)
+    end)
+
+    schema =
+      Schema.new()
+      |> Schema.add_text_field("title", stored: true)
+      |> Schema.add_u64_field("batch", stored: true, indexed: true)
+
+    {:ok, index} = Index.create(test_path, schema)
+
+    {:ok, index: index}
+  end
+
+  describe "readers during writes" do
+    test "see every commit whole", %{index: index} do
+      batch_size = 25
+      batches = 20
+
+      writer =
+        Task.async(fn ->
+          for batch <- 1..batches do
+            for n <- 1..batch_size do
+              :ok = IndexWriter.add_document(index, %{"title" => "doc #{n}", "batch" => batch})
+            end
+
+            :ok = IndexWriter.commit(index)
+          end
+        end)
+
+      readers =
+        for _ <- 1..4 do
+          Task.async(fn ->
+            for _ <- 1..30 do
+              {:ok, reader} = IndexReader.new(index)
+              {:ok, searcher} = Searcher.new(reader)
+              {:ok, results} = Searcher.search_query(searcher, "*", [], limit: 1_000)
+
+              # A commit is visible with all of its documents or none of them
+              assert rem(results["total_hits"], batch_size) == 0
+
+              Enum.each(results["hits"], fn hit ->
+                assert %{"title" => "doc " <> _, "batch" => batch} = hit["doc"]
+                assert batch in 1..batches
+              end)
+            end
+          end)
+        end
+
+      Task.await_many([writer | readers], @timeout)
+
+      assert hit_count(index) == batch_size * batches
+    end
+
+    test "do not stall behind several writing processes", %{index: index} do
+      writers =
+        for writer <- 1..4 do
+          Task.async(fn ->
+            for n <- 1..25 do
+              :ok = IndexWriter.add_document(index, %{"title" => "doc #{n}", "batch" => writer})
+
+              if rem(n, 5) == 0 do
+                :ok = IndexWriter.commit(index)
+              end
+            end
+          end)
+        end
+
+      readers =
+        for _ <- 1..4 do
+          Task.async(fn ->
+            for _ <- 1..25 do
+              {:ok, reader} = IndexReader.new(index)
+              {:ok, searcher} = Searcher.new(reader)
+              assert {:ok, _} = Searcher.search_query(searcher, "title:doc", ["title"])
+            end
+          end)
+        end
+
+      Task.await_many(writers ++ readers, @timeout)
+
+      assert hit_count(index) == 100
+    end
+
+    test "open while documents are added and committed in one call", %{index: index} do
+      writers =
+        for writer <- 1..2 do
+          Task.async(fn ->
+            for n <- 1..20 do
+              {:ok, _opstamp} =
+                IndexWriter.add_and_commit(index, %{"title" => "doc #{n}", "batch" => writer})
+            end
+          end)
+        end
+
+      readers =
+        for _ <- 1..4 do
+          Task.async(fn ->
+            for _ <- 1..20 do
+              {:ok, reader} = IndexReader.new(index)
+              {:ok, searcher} = Searcher.new(reader)
+              {:ok, results} = Searcher.search_query(searcher, "*", [], limit: 100)
+              assert results["total_hits"] <= 40
+            end
+          end)
+        end
+
+      Task.await_many(writers ++ readers, @timeout)
+
+      assert hit_count(index) == 40
+    end
+  end
+
+  defp hit_count(index) do
+    {:ok, reader} = IndexReader.new(index)
+    {:ok, searcher} = Searcher.new(reader)
+    {:ok, results} = Searcher.search_query(searcher, "*", [], limit: 1_000)
+    results["total_hits"]
+  end
+end
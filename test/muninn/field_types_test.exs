@@ -1,7 +1,7 @@
 defmodule Muninn.FieldTypesTest do
   use ExUnit.Case, async: true
 
-  alias Muninn.{Index, Schema}
+  alias Muninn.{Index, IndexReader, IndexWriter, Query, Schema, Searcher}
 
   setup do
     test_path = "/tmp/muninn_field_types_#{:erlang.unique_integer([:positive])}"
@@ -24,6 +24,27 @@ defmodule Muninn.FieldTypesTest do
       assert {:ok, _index} = Index.create(test_path, schema)
       assert {:ok, _index} = Index.open(test_path)
     end
+
+    test "accepts the built-in tokenizers", %{test_path: test_path} do
+      tokenizers = ~w(default en_stem raw raw_lowercase whitespace cjk)
+
+      schema =
+        Enum.reduce(tokenizers, Schema.new(), fn tokenizer, schema ->
+          Schema.add_text_field(schema, "body_#{tokenizer}", tokenizer: tokenizer)
+        end)
+
+      assert {:ok, index} = Index.create(test_path, schema)
+      assert {:ok, %{"tokenizer" => "whitespace"}} = Index.tokenize(index, "body_whitespace", "a")
+    end
+
+    test "rejects unknown tokenizers when the index is created", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("body", tokenizer: "en-stem")
+
+      assert {:error, :invalid_schema, reason} = Index.create(test_path, schema)
+      assert reason =~ "unknown tokenizer 'en-stem'"
+      assert reason =~ "cjk, default, en_stem, raw, raw_lowercase, whitespace"
+      refute File.exists?(Path.join(test_path, "meta.json"))
+    end
   end
 
   describe "numeric fields - u64" do
@@ -108,6 +129,103 @@ defmodule Muninn.FieldTypesTest do
       assert field.type == :bool
       assert field.name == "published"
     end
+
+    test "matches field:true and field:false in query strings", %{test_path: test_path} do
+      searcher = bool_searcher(test_path)
+
+      assert {:ok, %{"hits" => hits}} =
+               Searcher.search_query(searcher, "in_stock:true", ["name"])
+
+      assert names(hits) == ["laptop", "mouse"]
+
+      assert {:ok, %{"hits" => [%{"doc" => %{"name" => "keyboard"}}]}} =
+               Searcher.search_query(searcher, "(mouse OR keyboard) AND in_stock:false", ["name"])
+
+      assert {:ok, %{"hits" => [%{"doc" => %{"name" => "laptop"}}]}} =
+               Searcher.search_query(searcher, "-name:mouse AND in_stock:true", ["name"])
+
+      assert {:error, :parse_error, message} =
+               Searcher.search_query(searcher, "in_stock:yes", ["name"])
+
+      assert message =~ "only match true or false"
+    end
+
+    test "matches bool term queries", %{test_path: test_path} do
+      searcher = bool_searcher(test_path)
+
+      assert {:ok, %{"hits" => hits}} =
+               Searcher.search(searcher, Query.term("in_stock", true))
+
+      assert names(hits) == ["laptop", "mouse"]
+
+      assert {:ok, %{"hits" => [%{"doc" => %{"name" => "keyboard"}}]}} =
+               Searcher.search(searcher, Query.term("in_stock", "false"))
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search(searcher, Query.term("in_stock", "yes"))
+    end
+  end
+
+  describe "keyword fields" do
+    test "creates index with keyword fields", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, indexed: true)
+        |> Schema.add_keyword_field("status", stored: false, indexed: true)
+
+      assert {:ok, _index} = Index.create(test_path, schema)
+      assert {:ok, _index} = Index.open(test_path)
+    end
+
+    test "keyword field has correct type" do
+      schema = Schema.new() |> Schema.add_keyword_field("sku")
+      field = hd(schema.fields)
+
+      assert field.type == :keyword
+      assert field.name == "sku"
+    end
+
+    test "case_insensitive matches whole values regardless of case", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("brand", stored: true, case_insensitive: true)
+        |> Schema.add_text_field("title", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.add_document(index, %{"brand" => "Apple Pie", "title" => "first"})
+      :ok = IndexWriter.add_document(index, %{"brand" => "apple tart", "title" => "second"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      for query <- [~s(brand:"apple pie"), ~s(brand:"APPLE PIE")] do
+        {:ok, results} = Searcher.search_query(searcher, query, ["title"])
+        assert [%{"doc" => %{"brand" => "Apple Pie"}}] = results["hits"]
+      end
+
+      {:ok, results} = Searcher.search_query(searcher, "brand:APPLE", ["title"])
+      assert results["total_hits"] == 0
+
+      assert {:ok, true} = Searcher.doc_exists(searcher, "brand", "APPLE TART")
+
+      {:ok, results} =
+        Searcher.search_query(searcher, "*", ["title"], exclude_terms: [{"brand", "APPLE PIE"}])
+
+      assert [%{"doc" => %{"title" => "second"}}] = results["hits"]
+
+      {:ok, results} = Searcher.search_prefix(searcher, "brand", "Apple")
+      assert results["total_hits"] == 2
+
+      assert {:ok, 1} = IndexWriter.delete_terms(index, "brand", ["APPLE TART"])
+    end
+
+    test "rejects tokenizers other than raw and raw_lowercase", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("brand", tokenizer: "default")
+
+      assert {:error, :invalid_schema, reason} = Index.create(test_path, schema)
+      assert reason =~ "only raw or raw_lowercase"
+    end
   end
 
   describe "mixed field types" do
@@ -223,4 +341,28 @@ defmodule Muninn.FieldTypesTest do
       assert {:ok, _index} = Index.open(test_path)
     end
   end
+
+  defp bool_searcher(test_path) do
+    schema =
+      Schema.new()
+      |> Schema.add_text_field("name", stored: true)
+      |> Schema.add_bool_field("in_stock", stored: true, indexed: true)
+
+    {:ok, index} = Index.create(test_path, schema)
+
+    :ok =
+      IndexWriter.add_documents(index, [
+        %{"name" => "laptop", "in_stock" => true},
+        %{"name" => "mouse", "in_stock" => true},
+        %{"name" => "keyboard", "in_stock" => false}
+      ])
+
+    :ok = IndexWriter.commit(index)
+
+    {:ok, reader} = IndexReader.new(index)
+    {:ok, searcher} = Searcher.new(reader)
+    searcher
+  end
+
+  defp names(hits), do: hits |> Enum.map(& &1["doc"]["name"]) |> Enum.sort()
 end
@@ -28,6 +28,26 @@ defmodule Muninn.FuzzySearchTest do
       assert List.first(results["hits"])["doc"]["title"] == "Elixir"
     end
 
+    test "distance=0 behaves like an exact term match", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Elixir"})
+      IndexWriter.add_document(index, %{"title" => "Elixr"})
+      IndexWriter.add_document(index, %{"title" => "Phoenix"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, fuzzy} = Searcher.search_fuzzy(searcher, "title", "elixir", distance: 0)
+      {:ok, exact} = Searcher.search(searcher, Muninn.Query.term("title", "elixir"))
+
+      assert fuzzy["total_hits"] == exact["total_hits"]
+      assert Enum.map(fuzzy["hits"], & &1["doc"]) == Enum.map(exact["hits"], & &1["doc"])
+      assert Enum.map(fuzzy["hits"], & &1["doc"]["title"]) == ["Elixir"]
+    end
+
     test "single character substitution (elixr → elixir)", %{test_path: test_path} do
       schema = Schema.new() |> Schema.add_text_field("title", stored: true, indexed: true)
       {:ok, index} = Index.create(test_path, schema)
@@ -281,9 +301,65 @@ defmodule Muninn.FuzzySearchTest do
       hit = List.first(results["hits"])
       snippet = hit["snippets"]["content"]
 
-      # Snippet should contain <b> tags (if highlighting works)
-      # Note: Tantivy might not highlight fuzzy matches, so we just check it exists
-      assert is_binary(snippet)
+      assert snippet =~ "<b>Elixir</b>"
+    end
+
+    test "highlights every snippet field", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, indexed: true)
+        |> Schema.add_text_field("content", stored: true, indexed: true)
+        |> Schema.add_text_field("author", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "title" => "Elixir Guide",
+        "content" => "Learn Elixir programming with this comprehensive guide",
+        "author" => "Jane Doe"
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} =
+        Searcher.search_fuzzy_with_snippets(
+          searcher,
+          "title",
+          "elixr",
+          ["title", "content", "author"],
+          distance: 1
+        )
+
+      assert [%{"snippets" => snippets}] = results["hits"]
+      assert snippets["title"] == "<b>Elixir</b> Guide"
+      assert snippets["content"] =~ "Learn <b>Elixir</b> programming"
+      assert snippets["author"] == ""
+    end
+
+    test "respects max_snippet_chars", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("content", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "content" => "Elixir " <> String.duplicate("filler words around the match ", 20)
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} =
+        Searcher.search_fuzzy_with_snippets(searcher, "content", "elixr", ["content"],
+          max_snippet_chars: 30
+        )
+
+      snippet = hd(results["hits"])["snippets"]["content"]
+      assert snippet =~ "<b>Elixir</b>"
+      assert String.length(String.replace(snippet, ~r/<\/?b>/, "")) <= 30
     end
   end
 
@@ -296,11 +372,43 @@ defmodule Muninn.FuzzySearchTest do
       {:ok, reader} = IndexReader.new(index)
       {:ok, searcher} = Searcher.new(reader)
 
-      {:error, reason} = Searcher.search_fuzzy(searcher, "title", "test", distance: 3)
+      {:error, :invalid_argument, reason} =
+        Searcher.search_fuzzy(searcher, "title", "test", distance: 3)
 
       assert reason == "Distance must be between 0 and 2"
     end
 
+    test "native layer rejects oversized distances", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      for distance <- [3, 255, 1_000] do
+        assert {:error, :invalid_argument, "Distance must be between 0 and 2"} =
+                 Muninn.Native.searcher_search_fuzzy(
+                   searcher,
+                   "title",
+                   "test",
+                   distance,
+                   true,
+                   10
+                 )
+
+        assert {:error, :invalid_argument, "Distance must be between 0 and 2"} =
+                 Muninn.Native.searcher_search_fuzzy_prefix(
+                   searcher,
+                   "title",
+                   "test",
+                   distance,
+                   true,
+                   10
+                 )
+      end
+    end
+
     test "returns error for non-text field", %{test_path: test_path} do
       schema = Schema.new() |> Schema.add_u64_field("count", stored: true, indexed: true)
       {:ok, index} = Index.create(test_path, schema)
@@ -309,7 +417,8 @@ defmodule Muninn.FuzzySearchTest do
       {:ok, reader} = IndexReader.new(index)
       {:ok, searcher} = Searcher.new(reader)
 
-      {:error, reason} = Searcher.search_fuzzy(searcher, "count", "test", distance: 1)
+      {:error, :invalid_argument, reason} =
+        Searcher.search_fuzzy(searcher, "count", "test", distance: 1)
 
       assert String.contains?(reason, "must be a text field")
     end
@@ -322,7 +431,8 @@ defmodule Muninn.FuzzySearchTest do
       {:ok, reader} = IndexReader.new(index)
       {:ok, searcher} = Searcher.new(reader)
 
-      {:error, reason} = Searcher.search_fuzzy(searcher, "nonexistent", "test", distance: 1)
+      {:error, :not_found, reason} =
+        Searcher.search_fuzzy(searcher, "nonexistent", "test", distance: 1)
 
       assert String.contains?(reason, "not found")
     end
@@ -1,7 +1,7 @@
 defmodule Muninn.IndexTest do
   use ExUnit.Case, async: true
 
-  alias Muninn.{Index, Schema}
+  alias Muninn.{Index, IndexReader, IndexWriter, Schema, Searcher}
 
   @test_index_path "/tmp/muninn_test_#{:erlang.unique_integer([:positive])}"
 
@@ -16,7 +16,7 @@ defmodule Muninn.IndexTest do
     :ok
   end
 
-  describe "create/2" do
+  describe "create/3" do
     test "creates a new index with valid schema" do
       schema =
         Schema.new()
@@ -45,9 +45,74 @@ defmodule Muninn.IndexTest do
 
       assert {:error, :duplicate_field_names} = Index.create(@test_index_path, schema)
     end
+
+    test "returns already_exists when the directory holds an index" do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, _index} = Index.create(@test_index_path, schema)
+
+      assert {:error, :already_exists, message} = Index.create(@test_index_path, schema)
+      assert message =~ "already holds an index"
+    end
+
+    test "replaces an existing index with force" do
+      old_schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, old_index} = Index.create(@test_index_path, old_schema)
+      {:ok, _opstamp} = IndexWriter.add_and_commit(old_index, %{"title" => "old document"})
+      :ok = IndexWriter.close(old_index)
+
+      notes_path = Path.join(@test_index_path, "notes.txt")
+      File.write!(notes_path, "not part of the index")
+
+      assert {:ok, index} = Index.create(@test_index_path, old_schema, force: true)
+
+      assert {:ok, %{"hits" => []}} =
+               Searcher.search_query(searcher_for(index), "document", ["title"])
+
+      assert File.read!(notes_path) == "not part of the index"
+    end
+
+    test "refuses to replace an index whose writer is open" do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+      {:ok, _opstamp} = IndexWriter.add_and_commit(index, %{"title" => "kept document"})
+
+      assert {:error, :index_locked, _} = Index.create(@test_index_path, schema, force: true)
+
+      assert {:ok, %{"hits" => [_]}} =
+               Searcher.search_query(searcher_for(index), "document", ["title"])
+    end
+
+    test "keeps the existing index when the forced schema is invalid" do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, _index} = Index.create(@test_index_path, schema)
+
+      bad_schema = Schema.new() |> Schema.add_u64_field("views", max_length: 5)
+      assert {:error, :invalid_schema, _} =
+               Index.create(@test_index_path, bad_schema, force: true)
+
+      assert {:ok, index} = Index.open(@test_index_path)
+      assert {:ok, []} = Index.check_schema(index, schema)
+    end
+
+    test "rejects a field copied from a missing or numeric field" do
+      missing_source =
+        Schema.new()
+        |> Schema.add_text_field("title")
+        |> Schema.add_text_field("title_exact", copy_from: "heading")
+
+      assert {:error, :invalid_schema, reason} = Index.create(@test_index_path, missing_source)
+      assert reason =~ "not in the schema"
+
+      numeric_source =
+        Schema.new()
+        |> Schema.add_u64_field("views")
+        |> Schema.add_text_field("views_text", copy_from: "views")
+
+      assert {:error, :invalid_schema, _} = Index.create(@test_index_path, numeric_source)
+    end
   end
 
-  describe "open/1" do
+  describe "open/2" do
     test "opens an existing index" do
       # First create an index
       schema =
@@ -64,5 +129,762 @@ defmodule Muninn.IndexTest do
     test "returns error for non-existent index" do
       assert {:error, _reason} = Index.open("/tmp/nonexistent_index_#{:erlang.unique_integer()}")
     end
+
+    test "resolves a relative path against the base directory" do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      base_dir = Path.dirname(@test_index_path)
+      name = Path.basename(@test_index_path)
+
+      {:ok, _index} = Index.create(name, schema, base_dir: base_dir)
+
+      assert File.dir?(@test_index_path)
+      assert {:ok, _index} = Index.open(name, base_dir: base_dir)
+    end
+
+    test "reports the resolved path when opening fails" do
+      assert {:error, reason} = Index.open("missing_index", base_dir: @test_index_path)
+      assert reason =~ Path.join(@test_index_path, "missing_index")
+    end
+  end
+
+  describe "read_only option" do
+    setup do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+      :ok = IndexWriter.add_document(index, %{"title" => "elixir"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, writer_index: index}
+    end
+
+    test "searches an index while another resource holds the writer" do
+      {:ok, index} = Index.open(@test_index_path, read_only: true)
+
+      assert {:ok, %{"total_hits" => 1}} =
+               Searcher.search_query(searcher_for(index), "elixir", ["title"])
+    end
+
+    test "rejects every write operation" do
+      {:ok, index} = Index.open(@test_index_path, read_only: true)
+
+      assert {:error, :read_only, _} = IndexWriter.add_document(index, %{"title" => "rust"})
+      assert {:error, :read_only, _} = IndexWriter.commit(index)
+      assert {:error, :read_only, _} = IndexWriter.commit_with_payload(index, "v1")
+      assert {:error, :read_only, _} = IndexWriter.rollback(index)
+    end
+
+    test "sees commits made through the writer", %{writer_index: writer_index} do
+      {:ok, index} = Index.open(@test_index_path, read_only: true)
+
+      :ok = IndexWriter.add_document(writer_index, %{"title" => "elixir again"})
+      :ok = IndexWriter.commit(writer_index)
+
+      assert {:ok, %{"total_hits" => 2}} =
+               Searcher.search_query(searcher_for(index), "elixir", ["title"])
+    end
+  end
+
+  describe "max_limit option" do
+    setup do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+      :ok = IndexWriter.add_document(index, %{"title" => "elixir"})
+      :ok = IndexWriter.commit(index)
+
+      :ok
+    end
+
+    test "rejects limits above the default maximum" do
+      {:ok, index} = Index.open(@test_index_path)
+      searcher = searcher_for(index)
+
+      assert {:ok, %{"total_hits" => 1}} =
+               Searcher.search_query(searcher, "elixir", ["title"], limit: 100_000)
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.search_query(searcher, "elixir", ["title"], limit: 5_000_000)
+
+      assert message =~ "exceeds the maximum of 100000"
+    end
+
+    test "applies a configured maximum to every search function" do
+      {:ok, index} = Index.open(@test_index_path, max_limit: 5)
+      searcher = searcher_for(index)
+
+      assert {:ok, _} = Searcher.search_query(searcher, "elixir", ["title"], limit: 5)
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "elixir", ["title"], limit: 6)
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search(searcher, Muninn.Query.term("title", "elixir"), limit: 6)
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_prefix(searcher, "title", "eli", limit: 6)
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_fuzzy(searcher, "title", "elixr", limit: 6)
+    end
+
+    test "rejects a maximum of zero" do
+      assert {:error, reason} = Index.open(@test_index_path, max_limit: 0)
+      assert reason =~ "greater than 0"
+    end
+  end
+
+  describe "positions option" do
+    setup do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("message", stored: true)
+        |> Schema.add_text_field("title", stored: true, positions: true)
+
+      {:ok, index} = Index.create(@test_index_path, schema, positions: false)
+
+      :ok =
+        IndexWriter.add_document(index, %{
+          "message" => "disk error rate high",
+          "title" => "disk error rate"
+        })
+
+      :ok = IndexWriter.commit(index)
+
+      %{searcher: searcher_for(index)}
+    end
+
+    test "still matches terms on fields without positions", %{searcher: searcher} do
+      assert {:ok, %{"total_hits" => 1}} =
+               Searcher.search_query(searcher, "error AND rate", ["message"])
+    end
+
+    test "rejects phrase queries on fields without positions", %{searcher: searcher} do
+      assert {:error, :parse_error, message} =
+               Searcher.search_query(searcher, ~s("error rate"), ["message"])
+
+      assert message =~ "positions"
+    end
+
+    test "keeps positions on fields that opt in", %{searcher: searcher} do
+      assert {:ok, %{"total_hits" => 1}} =
+               Searcher.search_query(searcher, ~s("error rate"), ["title"])
+    end
+  end
+
+  describe "register_cjk_tokenizer/1" do
+    setup do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("body", stored: true, tokenizer: "cjk")
+
+      {:ok, index} = Index.create(@test_index_path, schema)
+      :ok = Index.register_cjk_tokenizer(index)
+
+      {:ok, index: index}
+    end
+
+    test "indexes and retrieves a Chinese phrase", %{index: index} do
+      :ok =
+        IndexWriter.add_documents(index, [
+          %{"body" => "北京大学是中国著名的大学"},
+          %{"body" => "上海交通大学位于上海"}
+        ])
+
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:ok, %{"total_hits" => 1, "hits" => [hit]}} =
+               Searcher.search_query(searcher, "北京大学", ["body"])
+
+      assert hit["doc"]["body"] == "北京大学是中国著名的大学"
+
+      assert {:ok, %{"total_hits" => 2}} =
+               Searcher.search_query(searcher, "大学", ["body"])
+
+      assert {:ok, %{"total_hits" => 0}} =
+               Searcher.search_query(searcher, "大学北京", ["body"])
+    end
+
+    test "keeps latin words searchable in mixed text", %{index: index} do
+      :ok = IndexWriter.add_document(index, %{"body" => "我喜欢Elixir编程"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:ok, %{"total_hits" => 1}} =
+               Searcher.search_query(searcher, "elixir", ["body"])
+
+      assert {:ok, %{"total_hits" => 1}} =
+               Searcher.search_query(searcher, "编程", ["body"])
+    end
+  end
+
+  describe "list_tokenizers/1" do
+    test "lists built-in and registered tokenizers" do
+      schema = Schema.new() |> Schema.add_text_field("body", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+
+      assert {:ok, ["default", "en_stem", "raw", "raw_lowercase", "whitespace"]} =
+               Index.list_tokenizers(index)
+
+      :ok = Index.register_cjk_tokenizer(index)
+
+      assert {:ok, ["cjk", "default", "en_stem", "raw", "raw_lowercase", "whitespace"]} =
+               Index.list_tokenizers(index)
+    end
+
+    test "does not carry registrations over to a reopened index" do
+      schema = Schema.new() |> Schema.add_text_field("body", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+      :ok = Index.register_cjk_tokenizer(index)
+
+      {:ok, reopened} = Index.open(@test_index_path)
+
+      assert {:ok, names} = Index.list_tokenizers(reopened)
+      refute "cjk" in names
+      assert "raw_lowercase" in names
+    end
+  end
+
+  describe "tokenize/3" do
+    setup do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", tokenizer: "en_stem")
+        |> Schema.add_text_field("cjk_body", tokenizer: "cjk")
+        |> Schema.add_keyword_field("brand", case_insensitive: true)
+        |> Schema.add_text_field("image_url", stored: true, indexed: false)
+        |> Schema.add_u64_field("views")
+
+      {:ok, index} = Index.create(@test_index_path, schema)
+
+      {:ok, index: index}
+    end
+
+    test "returns the tokens and the analyzer of the field", %{index: index} do
+      assert {:ok, result} = Index.tokenize(index, "body", "Running fast")
+
+      assert result["tokenizer"] == "en_stem"
+
+      assert result["components"] == [
+               "SimpleTokenizer",
+               "RemoveLongFilter(40)",
+               "LowerCaser",
+               "Stemmer(English)"
+             ]
+
+      assert result["tokens"] == [
+               %{"text" => "run", "position" => 0, "offset_from" => 0, "offset_to" => 7},
+               %{"text" => "fast", "position" => 1, "offset_from" => 8, "offset_to" => 12}
+             ]
+    end
+
+    test "resolves fields without a tokenizer to the default one", %{index: index} do
+      assert {:ok, %{"tokenizer" => "default", "tokens" => tokens}} =
+               Index.tokenize(index, "title", "Hello World")
+
+      assert Enum.map(tokens, & &1["text"]) == ["hello", "world"]
+    end
+
+    test "describes keyword and registered tokenizers", %{index: index} do
+      assert {:ok, %{"tokenizer" => "raw_lowercase", "tokens" => [%{"text" => "apple pie"}]}} =
+               Index.tokenize(index, "brand", "Apple Pie")
+
+      :ok = Index.register_cjk_tokenizer(index)
+
+      assert {:ok, result} = Index.tokenize(index, "cjk_body", "北京大学")
+      assert result["components"] == ["CjkTokenizer", "RemoveLongFilter(40)", "LowerCaser"]
+      assert Enum.map(result["tokens"], & &1["text"]) == ["北京", "京大", "大学"]
+    end
+
+    test "rejects fields that cannot be analyzed", %{index: index} do
+      assert {:error, :invalid_argument, message} = Index.tokenize(index, "cjk_body", "北京")
+      assert message =~ "not registered"
+
+      assert {:error, :invalid_argument, _} = Index.tokenize(index, "image_url", "x")
+      assert {:error, :invalid_argument, _} = Index.tokenize(index, "views", "1")
+      assert {:error, :not_found, _} = Index.tokenize(index, "missing", "x")
+    end
+  end
+
+  describe "info/1" do
+    test "reports an on-disk index and its canonical path" do
+      schema = Schema.new() |> Schema.add_text_field("title")
+      dotted_path = Path.join([@test_index_path, "..", Path.basename(@test_index_path)])
+
+      {:ok, index} = Index.create(dotted_path, schema)
+
+      assert %{"directory" => "mmap", "path" => path} = Index.info(index)
+      assert Path.type(path) == :absolute
+      refute path =~ ".."
+      assert Path.basename(path) == Path.basename(@test_index_path)
+
+      {:ok, reopened} = Index.open(@test_index_path)
+      assert Index.info(reopened) == Index.info(index)
+    end
+  end
+
+  describe "health_check/1" do
+    test "reports the committed documents and latency" do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+
+      assert {:ok, %{"num_docs" => 0, "latency_ms" => latency_ms}} = Index.health_check(index)
+      assert is_float(latency_ms) and latency_ms >= 0
+
+      IndexWriter.add_document(index, %{"title" => "one"})
+      IndexWriter.add_document(index, %{"title" => "two"})
+      assert {:ok, %{"num_docs" => 0}} = Index.health_check(index)
+
+      IndexWriter.commit(index)
+      assert {:ok, %{"num_docs" => 2}} = Index.health_check(index)
+    end
+
+    test "fails when a segment file is missing" do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+      IndexWriter.add_document(index, %{"title" => "one"})
+      IndexWriter.commit(index)
+
+      @test_index_path
+      |> Path.join("*.store")
+      |> Path.wildcard()
+      |> Enum.each(&File.rm!/1)
+
+      assert {:error, :index_error, message} = Index.health_check(index)
+      assert message =~ "not readable"
+    end
+  end
+
+  describe "format_info/2" do
+    test "reports the versions that wrote the index" do
+      schema = Schema.new() |> Schema.add_text_field("title")
+      {:ok, index} = Index.create(@test_index_path, schema)
+
+      assert {:ok, nil} = Index.format_info(@test_index_path)
+
+      :ok = IndexWriter.add_document(index, %{"title" => "First"})
+      :ok = IndexWriter.commit(index)
+
+      assert {:ok, info} = Index.format_info(@test_index_path)
+      assert info == Muninn.info()
+      assert info["compatible"]
+      assert info["tantivy_version"] =~ ~r/^\d+\.\d+\.\d+$/
+    end
+
+    test "returns not_found without an index" do
+      assert {:error, :not_found, _} = Index.format_info(@test_index_path)
+    end
+  end
+
+  describe "check_schema/2" do
+    setup do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true, fast: true)
+
+      {:ok, _} = Index.create(@test_index_path, schema)
+      {:ok, index} = Index.open(@test_index_path)
+
+      {:ok, index: index, schema: schema}
+    end
+
+    test "returns no differences for the creating schema", %{index: index, schema: schema} do
+      assert {:ok, []} = Index.check_schema(index, schema)
+    end
+
+    test "reports missing, unexpected and changed fields", %{index: index} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_text_field("title", tokenizer: "en_stem")
+        |> Schema.add_i64_field("views", stored: true)
+        |> Schema.add_bool_field("published")
+
+      assert {:ok, differences} = Index.check_schema(index, schema)
+
+      assert Enum.sort_by(differences, &{&1["field"], &1["difference"]}) == [
+               %{
+                 "field" => "published",
+                 "difference" => "missing",
+                 "expected" => "bool",
+                 "actual" => nil
+               },
+               %{
+                 "field" => "title",
+                 "difference" => "stored",
+                 "expected" => "false",
+                 "actual" => "true"
+               },
+               %{
+                 "field" => "title",
+                 "difference" => "tokenizer",
+                 "expected" => "en_stem",
+                 "actual" => "default"
+               },
+               %{
+                 "field" => "views",
+                 "difference" => "type",
+                 "expected" => "i64",
+                 "actual" => "u64"
+               }
+             ]
+    end
+
+    test "reports fields and id designations the schema lacks", %{index: index} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true)
+
+      assert {:ok, differences} = Index.check_schema(index, schema)
+
+      assert Enum.map(differences, &{&1["field"], &1["difference"]}) == [
+               {"title", "unexpected"},
+               {"views", "unexpected"},
+               {"sku", "id"}
+             ]
+    end
+  end
+
+  describe "reindex/3" do
+    setup do
+      dest_path = @test_index_path <> "_dest"
+      File.rm_rf!(dest_path)
+      on_exit(fn -> File.rm_rf!(dest_path) end)
+
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true)
+
+      {:ok, source} = Index.create(@test_index_path, schema)
+
+      for n <- 1..5 do
+        :ok = IndexWriter.add_document(source, %{"title" => "Post #{n}", "views" => n})
+      end
+
+      :ok = IndexWriter.commit(source)
+
+      {:ok, source: source, dest_path: dest_path}
+    end
+
+    test "copies every document with the destination's analysis", ctx do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true)
+
+      {:ok, dest} = Index.create(ctx.dest_path, schema)
+
+      assert {:ok, 5} = Index.reindex(ctx.source, dest, batch_size: 2)
+
+      searcher = searcher_for(dest)
+      {:ok, results} = Searcher.search_query(searcher, ~s(title:"Post 3"), [])
+      assert [%{"doc" => %{"title" => "Post 3", "views" => 3}}] = results["hits"]
+
+      {:ok, results} = Searcher.search_query(searcher, "views:[1 TO 5]", [])
+      assert results["total_hits"] == 5
+    end
+
+    test "drops fields missing from the destination", ctx do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, dest} = Index.create(ctx.dest_path, schema)
+
+      assert {:ok, 5} = Index.reindex(ctx.source, dest)
+
+      {:ok, results} = Searcher.search_query(searcher_for(dest), "post", ["title"])
+      assert Enum.all?(results["hits"], &(Map.keys(&1["doc"]) == ["title"]))
+    end
+
+    test "fills exact companions from their source fields", ctx do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, tokenizer: "en_stem", exact: true)
+
+      {:ok, dest} = Index.create(ctx.dest_path, schema)
+      assert {:ok, 5} = Index.reindex(ctx.source, dest)
+
+      {:ok, results} = Searcher.search_query(searcher_for(dest), "title_exact:post", [])
+      assert results["total_hits"] == 5
+
+      other_path = ctx.dest_path <> "_other"
+      on_exit(fn -> File.rm_rf!(other_path) end)
+      {:ok, other} = Index.create(other_path, schema)
+
+      assert {:ok, 5} = Index.reindex(dest, other)
+    end
+
+    test "rejects an unstored source field", ctx do
+      schema = Schema.new() |> Schema.add_text_field("body")
+      {:ok, source} = Index.create(ctx.dest_path <> "_unstored", schema)
+      on_exit(fn -> File.rm_rf!(ctx.dest_path <> "_unstored") end)
+
+      assert {:error, :invalid_argument, message} = Index.reindex(source, ctx.source)
+      assert message =~ "body"
+    end
+
+    test "rejects a field whose type changed", ctx do
+      schema = Schema.new() |> Schema.add_i64_field("views", stored: true)
+      {:ok, dest} = Index.create(ctx.dest_path, schema)
+
+      assert {:error, :invalid_argument, message} = Index.reindex(ctx.source, dest)
+      assert message =~ "views"
+    end
+
+    test "rejects reindexing an index into itself", ctx do
+      assert {:error, :invalid_argument, _} = Index.reindex(ctx.source, ctx.source)
+    end
+  end
+
+  describe "swap/3" do
+    setup do
+      next_path = @test_index_path <> "_next"
+      File.rm_rf!(next_path)
+      on_exit(fn -> File.rm_rf!(next_path) end)
+
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, live} = Index.create(@test_index_path, schema)
+      :ok = IndexWriter.add_document(live, %{"title" => "Running shoes"})
+      :ok = IndexWriter.commit(live)
+
+      {:ok, live: live, next_path: next_path}
+    end
+
+    test "moves searches and writes to the new directory", ctx do
+      old_searcher = searcher_for(ctx.live)
+
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, tokenizer: "en_stem")
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+
+      {:ok, next} = Index.create(ctx.next_path, schema)
+      {:ok, 1} = Index.reindex(ctx.live, next)
+      :ok = IndexWriter.close(next)
+
+      assert {:ok, reader} = Index.swap(ctx.live, ctx.next_path)
+      {:ok, searcher} = Searcher.new(reader)
+
+      # The new directory stems "running", the old searcher still serves the old one
+      {:ok, results} = Searcher.search_query(searcher, "run", ["title"])
+      assert results["total_hits"] == 1
+      {:ok, results} = Searcher.search_query(old_searcher, "run", ["title"])
+      assert results["total_hits"] == 0
+
+      :ok = IndexWriter.add_document(ctx.live, %{"title" => "Trail runs", "sku" => "t-1"})
+      :ok = IndexWriter.commit(ctx.live)
+
+      {:ok, results} = Searcher.search_query(searcher_for(ctx.live), "trail", ["title"])
+      assert [%{"_id" => "t-1"}] = results["hits"]
+      assert Index.info(ctx.live)["path"] =~ ~r/_next$/
+    end
+
+    test "refuses while documents are pending", ctx do
+      {:ok, next} = Index.create(ctx.next_path, Schema.new() |> Schema.add_text_field("title"))
+      :ok = IndexWriter.close(next)
+      :ok = IndexWriter.add_document(ctx.live, %{"title" => "Uncommitted"})
+
+      assert {:error, :invalid_argument, message} = Index.swap(ctx.live, ctx.next_path)
+      assert message =~ "pending"
+    end
+
+    test "refuses while deletes are pending", ctx do
+      {:ok, next} = Index.create(ctx.next_path, Schema.new() |> Schema.add_text_field("title"))
+      :ok = IndexWriter.close(next)
+      {:ok, 1} = IndexWriter.delete_terms(ctx.live, "title", ["running"])
+
+      assert {:error, :invalid_argument, message} = Index.swap(ctx.live, ctx.next_path)
+      assert message =~ "1 pending deletes"
+
+      :ok = IndexWriter.commit(ctx.live)
+      assert {:ok, _reader} = Index.swap(ctx.live, ctx.next_path)
+    end
+
+    test "rejects the index's own directory and missing directories", ctx do
+      assert {:error, :invalid_argument, _} = Index.swap(ctx.live, @test_index_path)
+      assert {:error, :index_error, _} = Index.swap(ctx.live, ctx.next_path)
+    end
+  end
+
+  describe "set_durability/2" do
+    setup do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, schema: schema}
+    end
+
+    test "commits in fast mode are searchable and kept after switching back", ctx do
+      {:ok, index} = Index.create(@test_index_path, ctx.schema, durability: :fast)
+
+      for n <- 1..3 do
+        :ok = IndexWriter.add_document(index, %{"title" => "Bulk post #{n}"})
+        :ok = IndexWriter.commit(index)
+      end
+
+      assert :ok = Index.set_durability(index, :durable)
+      :ok = IndexWriter.add_document(index, %{"title" => "Durable post"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, results} = Searcher.search_query(searcher_for(index), "post", ["title"])
+      assert results["total_hits"] == 4
+    end
+
+    test "an index loaded in fast mode opens again", ctx do
+      {:ok, index} = Index.create(@test_index_path, ctx.schema)
+      assert :ok = Index.set_durability(index, :fast)
+      :ok = IndexWriter.add_document(index, %{"title" => "Fast post"})
+      :ok = IndexWriter.commit(index)
+      :ok = IndexWriter.close(index)
+
+      {:ok, reopened} = Index.open(@test_index_path, durability: :fast)
+      {:ok, results} = Searcher.search_query(searcher_for(reopened), "fast", ["title"])
+      assert results["total_hits"] == 1
+    end
+  end
+
+  describe "merge_from/3" do
+    setup do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body")
+
+      source_paths =
+        for shard <- 1..2 do
+          path = @test_index_path <> "_shard_#{shard}"
+          File.rm_rf!(path)
+          on_exit(fn -> File.rm_rf!(path) end)
+
+          {:ok, source} = Index.create(path, schema)
+
+          for n <- 1..3 do
+            :ok =
+              IndexWriter.add_document(source, %{
+                "title" => "Shard #{shard} post #{n}",
+                "body" => "unstored body #{shard}"
+              })
+          end
+
+          :ok = IndexWriter.commit(source)
+          {:ok, 1} = IndexWriter.delete_terms(source, "title", ["3"])
+          :ok = IndexWriter.commit(source)
+          path
+        end
+
+      {:ok, schema: schema, source_paths: source_paths}
+    end
+
+    test "adds the live documents of every source", ctx do
+      {:ok, index} = Index.create(@test_index_path, ctx.schema)
+      :ok = IndexWriter.add_document(index, %{"title" => "Existing post", "body" => "kept"})
+      :ok = IndexWriter.commit(index)
+
+      assert {:ok, 4} = Index.merge_from(index, ctx.source_paths)
+
+      searcher = searcher_for(index)
+      {:ok, results} = Searcher.search_query(searcher, "post", ["title"])
+      assert results["total_hits"] == 5
+
+      # Unstored fields are merged too, since nothing is re-analyzed
+      {:ok, results} = Searcher.search_query(searcher, "body:2", [])
+      titles = Enum.map(results["hits"], & &1["doc"]["title"])
+      assert Enum.sort(titles) == ["Shard 2 post 1", "Shard 2 post 2"]
+    end
+
+    test "resolves relative source paths against the base directory", ctx do
+      {:ok, index} = Index.create(@test_index_path, ctx.schema)
+      base_dir = Path.dirname(@test_index_path)
+      relative_paths = Enum.map(ctx.source_paths, &Path.basename/1)
+
+      assert {:ok, 4} = Index.merge_from(index, relative_paths, base_dir: base_dir)
+
+      assert {:error, :invalid_argument, _} =
+               Index.merge_from(index, [Path.basename(@test_index_path)], base_dir: base_dir)
+    end
+
+    test "rejects sources with a different schema", ctx do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(@test_index_path, schema)
+
+      assert {:error, :invalid_argument, message} = Index.merge_from(index, ctx.source_paths)
+      assert message =~ "different schema"
+    end
+
+    test "rejects merging an index into itself", ctx do
+      [path | _] = ctx.source_paths
+      {:ok, index} = Index.open(path)
+
+      assert {:error, :invalid_argument, _} = Index.merge_from(index, [path])
+    end
+
+    test "fails on a missing source", ctx do
+      {:ok, index} = Index.create(@test_index_path, ctx.schema)
+
+      assert {:error, :index_error, _} =
+               Index.merge_from(index, [@test_index_path <> "_missing"])
+    end
+  end
+
+  describe "percolate/4" do
+    setup do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body")
+        |> Schema.add_u64_field("severity", fast: true)
+
+      {:ok, schema: schema}
+    end
+
+    test "returns the ids of the matching queries in order", %{schema: schema} do
+      document = %{"title" => "Disk failure", "body" => "error in datacenter 3", "severity" => 5}
+
+      queries = [
+        {:outage, "error AND datacenter"},
+        {"billing", "invoice OR refund"},
+        {3, "title:disk AND severity:[4 TO 10]"},
+        {:phrase, ~s("in datacenter")}
+      ]
+
+      assert {:ok, [:outage, 3, :phrase]} = Index.percolate(schema, document, queries)
+    end
+
+    test "accepts a map of queries and explicit default fields", %{schema: schema} do
+      queries = %{title_match: "failure", body_match: "error"}
+
+      assert {:ok, [:title_match]} =
+               Index.percolate(
+                 schema,
+                 %{"title" => "Disk failure", "body" => "failure and error"},
+                 queries,
+                 default_fields: ["title"]
+               )
+    end
+
+    test "names the query that failed to parse", %{schema: schema} do
+      queries = [{:disk, "error"}, {:broken, "missing:value"}]
+
+      assert {:error, :parse_error, message} =
+               Index.percolate(schema, %{"body" => "error"}, queries)
+
+      assert message =~ "position 1"
+    end
+
+    test "rejects tokenizers registered on an index" do
+      schema = Schema.new() |> Schema.add_text_field("body", tokenizer: "cjk")
+
+      assert {:error, :invalid_argument, message} =
+               Index.percolate(schema, %{"body" => "中文"}, [{:a, "中文"}])
+
+      assert message =~ "cjk"
+    end
+  end
+
+  defp searcher_for(index) do
+    {:ok, reader} = IndexReader.new(index)
+    {:ok, searcher} = Searcher.new(reader)
+    searcher
   end
 end
@@ -1,7 +1,7 @@
 defmodule Muninn.IndexWriterTest do
   use ExUnit.Case, async: true
 
-  alias Muninn.{Index, IndexWriter, Schema}
+  alias Muninn.{Index, IndexReader, IndexWriter, Schema, Searcher}
 
   setup do
     test_path = "/tmp/muninn_writer_#{:erlang.unique_integer([:positive])}"
@@ -108,6 +108,155 @@ defmodule Muninn.IndexWriterTest do
       doc = %{"title" => "Test", "unknown_field" => "ignored"}
       assert :ok = IndexWriter.add_document(index, doc)
     end
+
+    test "returns a tagged error for documents that are not maps", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:error, :invalid_argument, reason} =
+               Muninn.Native.writer_add_document(index, "not a map")
+
+      assert reason =~ "expected a map"
+    end
+
+    test "rejects documents missing required fields", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, required: true)
+        |> Schema.add_u64_field("views", stored: true, required: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:error, :invalid_argument, reason} =
+               IndexWriter.add_document(index, %{"body" => "No title"})
+
+      assert reason == "Document is missing required fields: title, views"
+
+      assert {:error, :invalid_argument, "Document is missing required fields: views"} =
+               IndexWriter.add_document(index, %{"title" => "No views", "views" => nil})
+
+      assert {:error, :invalid_argument, "Value of field 'views' is not a valid u64"} =
+               IndexWriter.add_document(index, %{"title" => "Bad views", "views" => "many"})
+
+      assert :ok = IndexWriter.add_document(index, %{"title" => "Complete", "views" => 1})
+    end
+
+    test "rejects improper lists for multi-valued numeric fields", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_u64_field("cat_ids", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:error, :invalid_argument, "Value of field 'cat_ids' is not a valid u64 list"} =
+               IndexWriter.add_document(index, %{"cat_ids" => [1 | 2]})
+
+      assert {:error, :invalid_argument, _} =
+               IndexWriter.add_document(index, %{"cat_ids" => [1, "two"]})
+    end
+
+    test "keeps required fields after reopening the index", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true, required: true)
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.close(index)
+
+      {:ok, reopened} = Index.open(test_path)
+
+      assert {:error, :invalid_argument, _} = IndexWriter.add_document(reopened, %{})
+      assert :ok = IndexWriter.add_document(reopened, %{"sku" => "sku-1"})
+    end
+
+    test "rejects values above a field's max length", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true, max_length: 5)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert :ok = IndexWriter.add_document(index, %{"sku" => "sku-1"})
+
+      assert {:error, :invalid_argument, reason} =
+               IndexWriter.add_document(index, %{"sku" => "sku-12"})
+
+      assert reason == "Value of field 'sku' is 6 bytes, above the maximum of 5"
+    end
+
+    test "truncates values and reports the truncated fields", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true)
+        |> Schema.add_text_field("body", stored: true, max_length: 2, on_too_long: :truncate)
+
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.close(index)
+
+      # Limits are restored when the index is reopened
+      {:ok, index} = Index.open(test_path)
+
+      assert {:ok, %{truncated: ["body"], opstamp: nil}} =
+               IndexWriter.add_document_detailed(index, %{"sku" => "a", "body" => "héllo world"})
+
+      assert {:ok, %{truncated: [], opstamp: nil}} =
+               IndexWriter.add_document_detailed(index, %{"sku" => "b", "body" => "ok"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+      {:ok, results} = Searcher.search_query(searcher, "sku:a", [])
+
+      # "é" takes bytes 2 and 3, so the cut keeps only "h"
+      assert [%{"doc" => %{"body" => "h"}}] = results["hits"]
+    end
+
+    test "rejects max lengths on other field types", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_u64_field("views", max_length: 5)
+
+      assert {:error, :invalid_schema, reason} = Index.create(test_path, schema)
+      assert reason =~ "only supported on text and keyword fields"
+    end
+  end
+
+  describe "add_document/2 value types" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_u64_field("views", stored: true)
+        |> Schema.add_i64_field("offset", stored: true)
+        |> Schema.add_f64_field("price", stored: true)
+        |> Schema.add_bool_field("active", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      {:ok, index: index}
+    end
+
+    test "rejects values that do not fit the field type", %{index: index} do
+      for {field, value} <- [
+            {"views", "12"},
+            {"views", -1},
+            {"views", 1.5},
+            {"offset", 9_223_372_036_854_775_808},
+            {"price", "cheap"},
+            {"active", "yes"}
+          ] do
+        assert {:error, :invalid_argument, reason} =
+                 IndexWriter.add_document(index, %{field => value})
+
+        assert reason =~ field
+      end
+
+      assert {:error, :invalid_argument, _} =
+               IndexWriter.add_document(index, %{"views" => [1, "2"]})
+    end
+
+    test "leaves nil values out of the document", %{index: index} do
+      doc = %{"views" => nil, "offset" => [nil, 3], "price" => 2, "active" => nil}
+      assert :ok = IndexWriter.add_document(index, doc)
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:ok, %{"hits" => [%{"doc" => stored}]}} =
+               Searcher.search_query(searcher, "offset:3", [])
+
+      assert stored == %{"offset" => 3, "price" => 2.0}
+    end
   end
 
   describe "add_documents/2 batch operations" do
@@ -205,6 +354,383 @@ defmodule Muninn.IndexWriterTest do
     end
   end
 
+  describe "multi_commit/1" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, main} = Index.create(test_path <> "_main", schema)
+      {:ok, suggest} = Index.create(test_path <> "_suggest", schema)
+
+      on_exit(fn ->
+        File.rm_rf(test_path <> "_main")
+        File.rm_rf(test_path <> "_suggest")
+      end)
+
+      {:ok, main: main, suggest: suggest, main_path: test_path <> "_main"}
+    end
+
+    test "commits every index", %{main: main, suggest: suggest} do
+      :ok = IndexWriter.add_document(main, %{"title" => "Elixir in Action"})
+      :ok = IndexWriter.add_document(suggest, %{"title" => "elixir"})
+
+      assert :ok = IndexWriter.multi_commit([main, suggest])
+
+      assert hit_count(main) == 1
+      assert hit_count(suggest) == 1
+      assert {:ok, 0} = IndexWriter.pending_docs(main)
+      assert {:ok, 0} = IndexWriter.pending_docs(suggest)
+    end
+
+    test "commits an index listed twice once", %{main: main, suggest: suggest} do
+      :ok = IndexWriter.add_document(main, %{"title" => "First"})
+
+      assert :ok = IndexWriter.multi_commit([main, suggest, main])
+      assert hit_count(main) == 1
+      assert hit_count(suggest) == 0
+    end
+
+    test "commits nothing when an index is read-only", %{main: main, main_path: main_path} do
+      :ok = IndexWriter.add_document(main, %{"title" => "Pending"})
+      {:ok, read_only} = Index.open(main_path, read_only: true)
+
+      assert {:error, :read_only, _} = IndexWriter.multi_commit([main, read_only])
+      assert hit_count(main) == 0
+      assert {:ok, 1} = IndexWriter.pending_docs(main)
+    end
+
+    test "accepts an empty list" do
+      assert :ok = IndexWriter.multi_commit([])
+    end
+  end
+
+  describe "commit_and_merge/2" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      # Every commit writes its own segment
+      for n <- 1..5 do
+        :ok = IndexWriter.add_document(index, %{"title" => "Post #{n}"})
+        :ok = IndexWriter.commit(index)
+      end
+
+      {:ok, index: index}
+    end
+
+    test "merges the segments down to the target", %{index: index} do
+      :ok = IndexWriter.add_document(index, %{"title" => "Post 6"})
+
+      assert {:ok, 2} = IndexWriter.commit_and_merge(index, 2)
+      assert {:ok, 0} = IndexWriter.pending_docs(index)
+      assert {:ok, 1} = IndexWriter.commit_and_merge(index, 1)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+      {:ok, results} = Searcher.search_query(searcher, "post", ["title"], limit: 10)
+      assert results["total_hits"] == 6
+    end
+
+    test "leaves fewer segments than the target alone", %{index: index} do
+      assert {:ok, 5} = IndexWriter.commit_and_merge(index, 10)
+    end
+
+    test "keeps writing after the merge", %{index: index} do
+      {:ok, 1} = IndexWriter.commit_and_merge(index, 1)
+
+      :ok = IndexWriter.add_document(index, %{"title" => "Post 6"})
+      :ok = IndexWriter.commit(index)
+
+      assert {:ok, 1} = IndexWriter.commit_and_merge(index, 1)
+    end
+  end
+
+  describe "optimize/1" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true)
+        |> Schema.add_text_field("title", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      {:ok, index: index}
+    end
+
+    test "merges every segment into one", %{index: index} do
+      for n <- 1..4 do
+        :ok = IndexWriter.add_document(index, %{"sku" => "#{n}", "title" => "Post #{n}"})
+        :ok = IndexWriter.commit(index)
+      end
+
+      :ok = IndexWriter.add_document(index, %{"sku" => "5", "title" => "Post 5"})
+
+      assert :ok = IndexWriter.optimize(index)
+      assert {:ok, 0} = IndexWriter.pending_docs(index)
+      assert {:ok, 1} = IndexWriter.commit_and_merge(index, 10)
+      assert skus(index) == ["1", "2", "3", "4", "5"]
+
+      # The writer keeps working afterwards
+      :ok = IndexWriter.add_document(index, %{"sku" => "6", "title" => "Post 6"})
+      assert :ok = IndexWriter.optimize(index)
+      assert skus(index) == ["1", "2", "3", "4", "5", "6"]
+    end
+
+    test "purges deleted documents from a single segment", %{index: index} do
+      :ok =
+        IndexWriter.add_documents(index, [
+          %{"sku" => "1", "title" => "apple pie"},
+          %{"sku" => "2", "title" => "apple tart"},
+          %{"sku" => "3", "title" => "plum jam"}
+        ])
+
+      :ok = IndexWriter.commit(index)
+
+      {:ok, 1} = IndexWriter.delete_terms(index, "sku", ["2"])
+      :ok = IndexWriter.commit(index)
+
+      # The deleted document still counts in the term statistics of its segment
+      before = apple_score(index)
+      assert {:ok, 1} = IndexWriter.commit_and_merge(index, 1)
+      assert apple_score(index) == before
+
+      assert :ok = IndexWriter.optimize(index)
+      assert apple_score(index) > before
+      assert skus(index) == ["1", "3"]
+    end
+
+    test "leaves an empty index alone", %{index: index} do
+      assert :ok = IndexWriter.optimize(index)
+      assert skus(index) == []
+    end
+  end
+
+  describe "commit_with_payload/2" do
+    test "stores the payload in the commit metadata", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:ok, nil} = Index.commit_payload(index)
+
+      :ok = IndexWriter.add_document(index, %{"title" => "First"})
+      assert :ok = IndexWriter.commit_with_payload(index, "lsn:42")
+      assert {:ok, "lsn:42"} = Index.commit_payload(index)
+
+      {:ok, reopened} = Index.open(test_path)
+      assert {:ok, "lsn:42"} = Index.commit_payload(reopened)
+    end
+
+    test "stamps a payload without pending documents", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert :ok = IndexWriter.commit_with_payload(index, "v1")
+      assert {:ok, "v1"} = Index.commit_payload(index)
+    end
+
+    test "is replaced by later commits", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.commit_with_payload(index, "v1")
+      :ok = IndexWriter.commit_with_payload(index, "v2")
+      assert {:ok, "v2"} = Index.commit_payload(index)
+
+      :ok = IndexWriter.add_document(index, %{"title" => "Second"})
+      :ok = IndexWriter.commit(index)
+      assert {:ok, nil} = Index.commit_payload(index)
+    end
+  end
+
+  describe "add_and_commit/2 and delete_and_commit/3" do
+    test "write and commit in one call", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true)
+        |> Schema.add_u64_field("version", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:ok, added} = IndexWriter.add_and_commit(index, %{"sku" => "a", "version" => 1})
+      assert {:ok, _} = IndexWriter.add_and_commit(index, %{"sku" => "b", "version" => 2})
+      assert skus(index) == ["a", "b"]
+
+      assert {:ok, deleted} = IndexWriter.delete_and_commit(index, "sku", "a")
+      assert deleted > added
+      assert skus(index) == ["b"]
+
+      assert {:ok, _} = IndexWriter.delete_and_commit(index, "version", 2)
+      assert skus(index) == []
+    end
+
+    test "return errors without committing", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true, required: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:error, :invalid_argument, _} = IndexWriter.add_and_commit(index, %{})
+      assert {:error, :not_found, _} = IndexWriter.delete_and_commit(index, "missing", "a")
+      assert {:error, :invalid_argument, _} = IndexWriter.delete_and_commit(index, "sku", 1)
+    end
+  end
+
+  describe "delete_terms/3" do
+    test "deletes documents holding any of the values", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.add_documents(index, Enum.map(~w(a b c d), &%{"sku" => &1}))
+      :ok = IndexWriter.commit(index)
+
+      assert {:ok, 3} = IndexWriter.delete_terms(index, "sku", ["a", "c", "missing"])
+      assert skus(index) == ["a", "b", "c", "d"]
+
+      :ok = IndexWriter.commit(index)
+      assert skus(index) == ["b", "d"]
+    end
+
+    test "rejects the whole batch when a value has the wrong type", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.add_documents(index, [%{"sku" => "a"}, %{"sku" => "b"}])
+      :ok = IndexWriter.commit(index)
+
+      assert {:error, :invalid_argument, _} = IndexWriter.delete_terms(index, "sku", ["a", 1])
+      assert {:error, :not_found, _} = IndexWriter.delete_terms(index, "missing", ["a"])
+
+      :ok = IndexWriter.commit(index)
+      assert skus(index) == ["a", "b"]
+    end
+  end
+
+  describe "set_autocommit/2" do
+    test "commits every N added documents", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.set_autocommit(index, 2)
+
+      assert :ok = IndexWriter.add_document(index, %{"title" => "one"})
+
+      assert {:ok, %{opstamp: opstamp, truncated: []}} =
+               IndexWriter.add_document_detailed(index, %{"title" => "two"})
+
+      assert is_integer(opstamp)
+      assert :ok = IndexWriter.add_document(index, %{"title" => "three"})
+
+      # Only the first two documents were committed
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+      assert {:ok, %{"total_hits" => 2}} = Searcher.search_query(searcher, "*", [])
+    end
+
+    test "explicit commits reset the count and 0 disables it", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.set_autocommit(index, 2)
+      :ok = IndexWriter.add_document(index, %{"title" => "one"})
+      :ok = IndexWriter.commit(index)
+      assert :ok = IndexWriter.add_document(index, %{"title" => "two"})
+
+      :ok = IndexWriter.set_autocommit(index, 0)
+      assert :ok = IndexWriter.add_document(index, %{"title" => "three"})
+      assert :ok = IndexWriter.add_document(index, %{"title" => "four"})
+    end
+
+    test "add_documents/2 keeps returning :ok across automatic commits", %{
+      test_path: test_path
+    } do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.set_autocommit(index, 1)
+
+      assert :ok =
+               IndexWriter.add_documents(index, [%{"title" => "one"}, %{"title" => "two"}])
+    end
+  end
+
+  describe "pending_docs/1" do
+    test "counts documents added since the last commit or rollback", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:ok, 0} = IndexWriter.pending_docs(index)
+
+      :ok = IndexWriter.add_documents(index, [%{"title" => "one"}, %{"title" => "two"}])
+      assert {:ok, 2} = IndexWriter.pending_docs(index)
+
+      :ok = IndexWriter.commit(index)
+      assert {:ok, 0} = IndexWriter.pending_docs(index)
+
+      :ok = IndexWriter.add_document(index, %{"title" => "three"})
+      :ok = IndexWriter.rollback(index)
+      assert {:ok, 0} = IndexWriter.pending_docs(index)
+    end
+
+    test "resets on automatic commits", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok = IndexWriter.set_autocommit(index, 2)
+      :ok = IndexWriter.add_documents(index, [%{"title" => "one"}, %{"title" => "two"}])
+      :ok = IndexWriter.add_document(index, %{"title" => "three"})
+
+      assert {:ok, 1} = IndexWriter.pending_docs(index)
+    end
+  end
+
+  describe "writer lock" do
+    test "returns :index_locked when another writer holds the lock", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.add_document(index, %{"title" => "First"})
+
+      {:ok, other} = Index.open(test_path)
+
+      assert {:error, :index_locked, _} = IndexWriter.add_document(other, %{"title" => "Second"})
+      assert {:error, :index_locked, _} = IndexWriter.commit_with_payload(other, "v1")
+    end
+  end
+
+  describe "close/2" do
+    test "commits pending documents and releases the lock", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.add_document(index, %{"title" => "First"})
+
+      assert :ok = IndexWriter.close(index)
+
+      {:ok, other} = Index.open(test_path)
+      assert :ok = IndexWriter.add_document(other, %{"title" => "Second"})
+      assert :ok = IndexWriter.commit(other)
+
+      {:ok, reader} = IndexReader.new(other)
+      {:ok, searcher} = Searcher.new(reader)
+      assert {:ok, %{"total_hits" => 2}} = Searcher.search_query(searcher, "*", [])
+    end
+
+    test "rolls back pending documents with commit: false", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.add_document(index, %{"title" => "Discarded"})
+
+      assert :ok = IndexWriter.close(index, commit: false)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+      assert {:ok, %{"total_hits" => 0}} = Searcher.search_query(searcher, "*", [])
+    end
+
+    test "reopens a writer on the next write", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert :ok = IndexWriter.close(index)
+      assert :ok = IndexWriter.add_document(index, %{"title" => "Again"})
+      assert :ok = IndexWriter.commit(index)
+    end
+  end
+
   describe "rollback/1" do
     test "rolls back uncommitted documents", %{test_path: test_path} do
       schema = Schema.new() |> Schema.add_text_field("title", stored: true)
@@ -367,4 +893,25 @@ defmodule Muninn.IndexWriterTest do
       :ok = IndexWriter.commit(index)
     end
   end
+
+  defp apple_score(index) do
+    {:ok, reader} = IndexReader.new(index)
+    {:ok, searcher} = Searcher.new(reader)
+    {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "apple", ["title"])
+    hit["score"]
+  end
+
+  defp hit_count(index) do
+    {:ok, reader} = IndexReader.new(index)
+    {:ok, searcher} = Searcher.new(reader)
+    {:ok, results} = Searcher.search_query(searcher, "*", [])
+    results["total_hits"]
+  end
+
+  defp skus(index) do
+    {:ok, reader} = IndexReader.new(index)
+    {:ok, searcher} = Searcher.new(reader)
+    {:ok, results} = Searcher.search_query(searcher, "*", [])
+    results["hits"] |> Enum.map(& &1["doc"]["sku"]) |> Enum.sort()
+  end
 end
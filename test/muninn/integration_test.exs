@@ -114,7 +114,7 @@ defmodule Muninn.IntegrationTest do
         Schema.new()
         |> Schema.add_text_field("field")
 
-      assert {:error, reason} = Index.create(readonly_path, schema)
+      assert {:error, :io_error, reason} = Index.create(readonly_path, schema)
       assert is_binary(reason)
     end
   end
@@ -51,7 +51,7 @@ defmodule Muninn.NativeTest do
     end
   end
 
-  describe "index_create/2" do
+  describe "index_create/4" do
     test "creates index with schema" do
       path = "/tmp/muninn_native_test_#{:erlang.unique_integer([:positive])}"
 
@@ -59,12 +59,76 @@ defmodule Muninn.NativeTest do
 
       fields = [{"title", "text", true, true}]
 
-      assert {:ok, index} = Native.index_create(path, fields)
+      assert {:ok, index} = Native.index_create(path, fields, nil, false)
       assert is_reference(index)
     end
   end
 
-  describe "index_open/1" do
+  describe "index_create_with_schema/4" do
+    setup do
+      paths =
+        for name <- ["a", "b"] do
+          "/tmp/muninn_native_shared_#{name}_#{:erlang.unique_integer([:positive])}"
+        end
+
+      on_exit(fn -> Enum.each(paths, &File.rm_rf!/1) end)
+
+      {:ok, paths: paths}
+    end
+
+    test "creates indexes sharing one schema and its settings", %{paths: [path_a, path_b]} do
+      fields =
+        Muninn.Schema.new()
+        |> Muninn.Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Muninn.Schema.add_text_field("title", stored: true, required: true)
+        |> Map.fetch!(:fields)
+        |> Enum.map(&Muninn.Schema.Field.to_native/1)
+
+      schema = Native.schema_build(fields)
+      assert Native.schema_num_fields(schema) == 2
+
+      for path <- [path_a, path_b] do
+        assert {:ok, index} = Native.index_create_with_schema(path, schema, nil, false)
+        assert Native.index_num_fields(index) == 2
+
+        assert {:error, :invalid_argument, _} =
+                 Muninn.IndexWriter.add_document(index, %{"sku" => "t-1"})
+
+        :ok = Muninn.IndexWriter.add_document(index, %{"sku" => "t-1", "title" => "Tea"})
+        # Closing commits and releases the writer lock, so the index can be replaced below
+        :ok = Muninn.IndexWriter.close(index)
+
+        {:ok, reader} = Muninn.IndexReader.new(index)
+        {:ok, searcher} = Muninn.Searcher.new(reader)
+        {:ok, results} = Muninn.Searcher.search_query(searcher, "tea", ["title"])
+        assert [%{"_id" => "t-1"}] = results["hits"]
+      end
+
+      # The settings are saved with each index
+      {:ok, reopened} = Native.index_open(path_b, nil)
+      {:ok, reopened_schema} = Native.index_schema(reopened)
+
+      assert {:error, :already_exists, _} =
+               Native.index_create_with_schema(path_a, reopened_schema, nil, false)
+
+      assert {:ok, index} = Native.index_create_with_schema(path_a, reopened_schema, nil, true)
+
+      assert {:error, :invalid_argument, _} =
+               Muninn.IndexWriter.add_document(index, %{"sku" => "t-2"})
+    end
+
+    test "rejects an invalid max limit", %{paths: [path, _]} do
+      schema = Native.schema_build([{"title", "text", true, true}])
+
+      assert {:error, :invalid_argument, reason} =
+               Native.index_create_with_schema(path, schema, 0, false)
+
+      assert reason =~ "Max limit"
+      refute File.exists?(Path.join(path, "meta.json"))
+    end
+  end
+
+  describe "index_open/2" do
     test "opens existing index" do
       path = "/tmp/muninn_native_open_#{:erlang.unique_integer([:positive])}"
 
@@ -72,18 +136,34 @@ defmodule Muninn.NativeTest do
 
       # Create first
       fields = [{"field", "text", true, true}]
-      {:ok, _} = Native.index_create(path, fields)
+      {:ok, _} = Native.index_create(path, fields, nil, false)
 
       # Then open
-      assert {:ok, index} = Native.index_open(path)
+      assert {:ok, index} = Native.index_open(path, nil)
       assert is_reference(index)
     end
 
     test "returns error for non-existent index" do
       path = "/tmp/muninn_nonexist_#{:erlang.unique_integer([:positive])}"
 
-      assert {:error, reason} = Native.index_open(path)
+      assert {:error, reason} = Native.index_open(path, nil)
       assert is_binary(reason)
     end
   end
+
+  describe "index_schema/1 and index_num_fields/1" do
+    test "introspect the schema of an opened index" do
+      path = "/tmp/muninn_native_schema_#{:erlang.unique_integer([:positive])}"
+
+      on_exit(fn -> File.rm_rf!(path) end)
+
+      fields = [{"title", "text", true, true}, {"views", "u64", true, true}]
+      {:ok, _} = Native.index_create(path, fields, nil, false)
+      {:ok, index} = Native.index_open(path, nil)
+
+      assert Native.index_num_fields(index) == 2
+      assert {:ok, schema} = Native.index_schema(index)
+      assert Native.schema_num_fields(schema) == 2
+    end
+  end
 end
@@ -516,6 +516,41 @@ defmodule Muninn.QueryParserTest do
       assert results["total_hits"] == 1
       assert List.first(results["hits"])["doc"]["title"] == "elixir guide"
     end
+
+    test "allows empty default fields for fully qualified queries", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, indexed: true)
+        |> Schema.add_text_field("author", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "rust book", "author" => "steve"})
+      IndexWriter.add_document(index, %{"title" => "rust guide", "author" => "carol"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "title:rust AND author:steve", [])
+
+      assert results["total_hits"] == 1
+      assert List.first(results["hits"])["doc"]["title"] == "rust book"
+    end
+
+    test "rejects unqualified terms when no default fields are given", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:error, :parse_error, reason} = Searcher.search_query(searcher, "title:rust AND steve", [])
+
+      assert String.contains?(reason, "default field must be provided")
+    end
   end
 
   describe "error handling" do
@@ -530,7 +565,7 @@ defmodule Muninn.QueryParserTest do
       {:ok, reader} = IndexReader.new(index)
       {:ok, searcher} = Searcher.new(reader)
 
-      {:error, reason} =
+      {:error, :not_found, reason} =
         Searcher.search_query(searcher, "test", ["nonexistent_field"])
 
       assert String.contains?(reason, "not found in schema")
@@ -548,7 +583,7 @@ defmodule Muninn.QueryParserTest do
       {:ok, searcher} = Searcher.new(reader)
 
       # Unclosed quote is a parse error
-      {:error, reason} = Searcher.search_query(searcher, ~s("unclosed), ["title"])
+      {:error, :parse_error, reason} = Searcher.search_query(searcher, ~s("unclosed), ["title"])
 
       assert String.contains?(reason, "Failed to parse query")
     end
@@ -578,4 +613,1062 @@ defmodule Muninn.QueryParserTest do
       assert results["total_hits"] == 1
     end
   end
+
+  describe "scoring option" do
+    test "const scoring gives every match the same score", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("content", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"content" => "elixir"})
+      IndexWriter.add_document(index, %{"content" => "elixir elixir elixir and more words"})
+      IndexWriter.add_document(index, %{"content" => "rust"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, bm25} = Searcher.search_query(searcher, "elixir", ["content"])
+      {:ok, const} = Searcher.search_query(searcher, "elixir", ["content"], scoring: :const)
+
+      assert bm25["total_hits"] == const["total_hits"]
+      assert bm25["hits"] |> Enum.map(& &1["score"]) |> Enum.uniq() |> length() == 2
+      assert Enum.map(const["hits"], & &1["score"]) == [1.0, 1.0]
+    end
+
+    test "const scoring applies to term search and snippets", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("content", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"content" => "elixir is fun"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} =
+        Searcher.search(searcher, Muninn.Query.term("content", "elixir"), scoring: :const)
+
+      assert [%{"score" => 1.0}] = results["hits"]
+
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "elixir", ["content"], ["content"],
+          scoring: :const
+        )
+
+      assert [%{"score" => 1.0, "snippets" => %{"content" => snippet}}] = results["hits"]
+      assert snippet =~ "<b>elixir</b>"
+    end
+
+    test "returns error for unknown scoring mode", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("content", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:error, :invalid_argument, reason} =
+        Searcher.search_query(searcher, "elixir", ["content"], scoring: :tfidf)
+
+      assert String.contains?(reason, "Unknown scoring mode")
+    end
+  end
+
+  describe "matched_terms option" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, indexed: true)
+        |> Schema.add_text_field("tags", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Elixir guide", "tags" => "phoenix web"})
+      IndexWriter.add_document(index, %{"title" => "Rust guide", "tags" => "systems"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "lists the query terms found in each hit", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir phoenix", ["title", "tags"],
+          matched_terms: true
+        )
+
+      assert [hit] = results["hits"]
+
+      assert Enum.sort_by(hit["matched_terms"], & &1["field"]) == [
+               %{"field" => "tags", "term" => "phoenix"},
+               %{"field" => "title", "term" => "elixir"}
+             ]
+    end
+
+    test "reports terms per document", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_query(searcher, "guide OR phoenix", ["title", "tags"],
+          matched_terms: true
+        )
+
+      matched =
+        Map.new(results["hits"], fn hit ->
+          {hit["doc"]["title"], Enum.map(hit["matched_terms"], & &1["term"]) |> Enum.sort()}
+        end)
+
+      assert matched == %{
+               "Elixir guide" => ["guide", "phoenix"],
+               "Rust guide" => ["guide"]
+             }
+    end
+
+    test "is omitted by default", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["title"])
+
+      assert [hit] = results["hits"]
+      refute Map.has_key?(hit, "matched_terms")
+    end
+  end
+
+  describe "segment_ords option" do
+    test "reports the segment of each hit", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      # Each commit writes a new segment
+      IndexWriter.add_document(index, %{"title" => "elixir one"})
+      IndexWriter.commit(index)
+      IndexWriter.add_document(index, %{"title" => "elixir two"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["title"], segment_ords: true)
+
+      assert results["hits"] |> Enum.map(& &1["segment_ord"]) |> Enum.sort() == [0, 1]
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["title"])
+      refute Enum.any?(results["hits"], &Map.has_key?(&1, "segment_ord"))
+    end
+  end
+
+  describe "normalize_scores option" do
+    test "scales scores so the top hit is 1.0", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir elixir elixir"},
+        %{"title" => "elixir and erlang"},
+        %{"title" => "erlang only"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, raw} = Searcher.search_query(searcher, "elixir", ["title"])
+
+      {:ok, normalized} =
+        Searcher.search_query(searcher, "elixir", ["title"], normalize_scores: true)
+
+      [top, second] = normalized["hits"]
+      assert top["score"] == 1.0
+      assert second["score"] > 0.0 and second["score"] < 1.0
+
+      assert Enum.map(normalized["hits"], & &1["raw_score"]) ==
+               Enum.map(raw["hits"], & &1["score"])
+
+      assert_in_delta second["score"], second["raw_score"] / top["raw_score"], 1.0e-6
+      refute Map.has_key?(hd(raw["hits"]), "raw_score")
+    end
+  end
+
+  describe "tiebreak option" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("published_at", stored: true, fast: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir news", "published_at" => 20},
+        %{"title" => "elixir news", "published_at" => 30},
+        %{"title" => "elixir news"},
+        %{"title" => "elixir news", "published_at" => 10},
+        %{"title" => "elixir elixir elixir", "published_at" => 1}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "orders equal scores by the field", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir", ["title"], tiebreak: {"published_at", :desc})
+
+      assert Enum.map(results["hits"], & &1["doc"]["published_at"]) == [1, 30, 20, 10, nil]
+
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir", ["title"],
+          scoring: :const,
+          tiebreak: {"published_at", :asc}
+        )
+
+      assert Enum.map(results["hits"], & &1["doc"]["published_at"]) == [1, 10, 20, 30, nil]
+    end
+
+    test "keeps pages in tiebreak order", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_paged(searcher, "news", ["title"],
+          limit: 2,
+          offset: 1,
+          tiebreak: {"published_at", :desc}
+        )
+
+      assert results["total"] == 4
+      assert Enum.map(results["hits"], & &1["doc"]["published_at"]) == [20, 10]
+    end
+
+    test "rejects fields that are not integer fast fields", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "elixir", ["title"], tiebreak: {"title", :asc})
+
+      assert {:error, :not_found, _} =
+               Searcher.search_query(searcher, "elixir", ["title"], tiebreak: {"missing", :asc})
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "elixir", ["title"],
+                 tiebreak: {"published_at", :up}
+               )
+    end
+  end
+
+  describe "match_offsets option" do
+    test "reports where query terms occur in each text field", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "title" => "Elixir in Action",
+        "body" => "Learn Elixir, then more ELIXIR. Phoenix is next."
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} =
+        Searcher.search_query(searcher, "elixir", ["title", "body"], match_offsets: true)
+
+      assert hit["match_offsets"] == %{"title" => [{0, 6}], "body" => [{6, 12}, {24, 30}]}
+
+      [{start, stop} | _] = hit["match_offsets"]["body"]
+      assert binary_part(hit["doc"]["body"], start, stop - start) == "Elixir"
+    end
+
+    test "leaves out fields without a match", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"title" => "Phoenix", "body" => "elixir web"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} =
+        Searcher.search_query(searcher, "elixir", ["title", "body"], match_offsets: true)
+
+      assert hit["match_offsets"] == %{"body" => [{0, 6}]}
+
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "elixir", ["body"])
+      refute Map.has_key?(hit, "match_offsets")
+    end
+  end
+
+  describe "source option" do
+    test "returns the original document as JSON", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_source_field()
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      doc = %{
+        "title" => "Elixir guide",
+        "tags" => ["beam", "otp"],
+        "meta" => %{"pages" => 120, "rating" => 4.5, "draft" => false, "editor" => nil}
+      }
+
+      :ok = IndexWriter.add_document(index, doc)
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["title"], source: true)
+      [hit] = results["hits"]
+
+      assert Jason.decode!(hit["_source"]) == doc
+      assert hit["doc"] == %{"title" => "Elixir guide"}
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["title"])
+      refute Map.has_key?(hd(results["hits"]), "_source")
+    end
+
+    test "rejects documents that cannot be encoded", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_source_field()
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:error, :invalid_argument, _} =
+               IndexWriter.add_document(index, %{"title" => "Bad", "pair" => {1, 2}})
+    end
+
+    test "requires an index storing sources", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :invalid_argument, reason} =
+               Searcher.search_query(searcher, "elixir", ["title"], source: true)
+
+      assert reason =~ "does not store document sources"
+    end
+  end
+
+  describe "doc_bytes option" do
+    test "reports the stored size of each document", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "short", "body" => "tiny"},
+        %{"title" => "long", "body" => String.duplicate("bloated ", 500)}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "*", [], doc_bytes: true)
+      sizes = Map.new(results["hits"], &{&1["doc"]["title"], &1["_doc_bytes"]})
+
+      assert sizes["short"] < 100
+      assert sizes["long"] > 4_000
+
+      # Fields left out of "doc" still count
+      {:ok, results} =
+        Searcher.search_query(searcher, "title:long", [], doc_bytes: true, fields: ["title"])
+
+      assert [%{"doc" => doc, "_doc_bytes" => bytes}] = results["hits"]
+      assert doc == %{"title" => "long"}
+      assert bytes == sizes["long"]
+
+      {:ok, results} = Searcher.search_query(searcher, "title:long", [])
+      refute Map.has_key?(hd(results["hits"]), "_doc_bytes")
+    end
+  end
+
+  describe "exclude_terms option" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, indexed: true)
+        |> Schema.add_keyword_field("status", stored: true)
+        |> Schema.add_u64_field("year", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "Elixir basics", "status" => "published", "year" => 2024},
+        %{"title" => "Elixir internals", "status" => "archived", "year" => 2019},
+        %{"title" => "Elixir at scale", "status" => "draft", "year" => 2024}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "drops documents matching an excluded keyword", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir", ["title"],
+          exclude_terms: [{"status", "archived"}]
+        )
+
+      assert titles(results) == ["Elixir at scale", "Elixir basics"]
+    end
+
+    test "combines several exclusions across field types", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir", ["title"],
+          exclude_terms: [{"status", "draft"}, {"year", 2019}]
+        )
+
+      assert titles(results) == ["Elixir basics"]
+    end
+
+    test "returns errors for unknown fields and mistyped values", %{searcher: searcher} do
+      assert {:error, :not_found, _} =
+               Searcher.search_query(searcher, "elixir", ["title"],
+                 exclude_terms: [{"missing", "x"}]
+               )
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "elixir", ["title"],
+                 exclude_terms: [{"year", "2019"}]
+               )
+    end
+  end
+
+  describe "filters option" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_keyword_field("tenant", stored: true)
+        |> Schema.add_u64_field("year", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir elixir elixir", "tenant" => "acme", "year" => 2024},
+        %{"title" => "elixir", "tenant" => "acme", "year" => 2019},
+        %{"title" => "elixir elixir", "tenant" => "globex", "year" => 2024}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "restricts hits without changing their scores", %{searcher: searcher} do
+      {:ok, unfiltered} = Searcher.search_query(searcher, "elixir", ["title"])
+
+      {:ok, filtered} =
+        Searcher.search_query(searcher, "elixir", ["title"], filters: ["tenant:acme"])
+
+      assert ranked_titles(filtered) == ["elixir elixir elixir", "elixir"]
+
+      scores = Map.new(unfiltered["hits"], &{&1["doc"]["title"], &1["score"]})
+      assert Enum.all?(filtered["hits"], &(&1["score"] == scores[&1["doc"]["title"]]))
+    end
+
+    test "requires every filter to match", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir", ["title"],
+          filters: ["tenant:acme", "year:[2020 TO *]"]
+        )
+
+      assert ranked_titles(results) == ["elixir elixir elixir"]
+    end
+
+    test "returns parse errors for invalid filters", %{searcher: searcher} do
+      assert {:error, :parse_error, _} =
+               Searcher.search_query(searcher, "elixir", ["title"], filters: ["tenant:(acme"])
+    end
+  end
+
+  describe "boosts (^)" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, indexed: true)
+        |> Schema.add_text_field("body", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "rust guide", "body" => "search engines"},
+        %{"title" => "search guide", "body" => "rust programming"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "term boosts reorder otherwise equal hits", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "title:rust^2 title:search^0.5", [])
+      assert ranked_titles(results) == ["rust guide", "search guide"]
+
+      {:ok, results} = Searcher.search_query(searcher, "title:rust^0.5 title:search^2", [])
+      assert ranked_titles(results) == ["search guide", "rust guide"]
+    end
+
+    test "field boosts scale the score of that field's clause", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "title:rust^3 body:rust", [])
+      assert ranked_titles(results) == ["rust guide", "search guide"]
+
+      [boosted, plain] = results["hits"]
+      assert_in_delta boosted["score"], plain["score"] * 3, 1.0e-4
+
+      {:ok, results} = Searcher.search_query(searcher, "(body:rust)^3 title:rust", [])
+      assert ranked_titles(results) == ["search guide", "rust guide"]
+    end
+
+    test "boosts apply to unqualified terms across default fields", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "rust^2 engines", ["title", "body"])
+      assert ranked_titles(results) == ["rust guide", "search guide"]
+    end
+
+    test "returns a clear parse error for malformed boosts", %{searcher: searcher} do
+      for {query, boost} <- [{"rust^abc", "^abc"}, {"rust^-1", "^-1"}, {"rust ^2", "^2"}] do
+        assert {:error, :parse_error, message} = Searcher.search_query(searcher, query, ["title"])
+        assert message =~ "Invalid boost '#{boost}'"
+      end
+    end
+  end
+
+  describe "exists queries (field:*)" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("rating", stored: true, fast: true)
+        |> Schema.add_i64_field("offset", stored: true)
+        |> Schema.add_keyword_field("tag")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "rated zero", "rating" => 0, "offset" => 0},
+        %{"title" => "rated five", "rating" => 5},
+        %{"title" => "unrated", "rating" => nil, "offset" => -3}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "matches documents with a value, including 0", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "rating:*", [])
+      assert titles(results) == ["rated five", "rated zero"]
+
+      {:ok, results} = Searcher.search_query(searcher, "offset:*", [])
+      assert titles(results) == ["rated zero", "unrated"]
+    end
+
+    test "finds documents without a value", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "* -rating:*", [])
+      assert titles(results) == ["unrated"]
+
+      {:ok, results} = Searcher.search_query(searcher, "rated", ["title"], filters: ["rating:*"])
+      assert titles(results) == ["rated five", "rated zero"]
+
+      assert {:error, :parse_error, _} = Searcher.search_query(searcher, "-rating:*", [])
+    end
+
+    test "combines with other clauses", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "title:rated AND offset:*", [])
+      assert titles(results) == ["rated zero"]
+
+      {:ok, results} = Searcher.search_query(searcher, "(rating:* OR offset:*)^2", [])
+      assert results["total_hits"] == 3
+    end
+
+    test "rejects fields that cannot be checked", %{searcher: searcher} do
+      assert {:error, :parse_error, reason} = Searcher.search_query(searcher, "tag:*", [])
+      assert reason =~ "fast field"
+
+      assert {:error, :parse_error, _} = Searcher.search_query(searcher, "missing:*", [])
+    end
+  end
+
+  describe "set_query_defaults/2" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_keyword_field("tag")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir phoenix", "tag" => "web"},
+        %{"title" => "elixir otp", "tag" => "web"},
+        %{"title" => "phoenix liveview", "tag" => "ui"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "sets the default operator", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "elixir phoenix", ["title"])
+      assert results["total_hits"] == 3
+
+      assert :ok = Searcher.set_query_defaults(searcher, default_operator: :and)
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir phoenix", ["title"])
+      assert titles(results) == ["elixir phoenix"]
+
+      {:ok, results} =
+        Searcher.search_query(searcher, "elixir phoenix", ["title"], default_operator: :or)
+
+      assert results["total_hits"] == 3
+    end
+
+    test "applies the default operator to filters", %{searcher: searcher} do
+      query = "elixir OR phoenix"
+      filters = ["title:elixir title:phoenix"]
+
+      :ok = Searcher.set_query_defaults(searcher, default_operator: :and)
+
+      {:ok, results} = Searcher.search_query(searcher, query, ["title"], filters: filters)
+      assert titles(results) == ["elixir phoenix"]
+
+      {:ok, results} =
+        Searcher.search_query(searcher, query, ["title"],
+          filters: filters,
+          default_operator: :or
+        )
+
+      assert results["total_hits"] == 3
+    end
+
+    test "sets lenient parsing", %{searcher: searcher} do
+      query = "title:otp OR missing:otp"
+
+      assert {:error, :parse_error, _} = Searcher.search_query(searcher, query, [])
+
+      assert :ok = Searcher.set_query_defaults(searcher, lenient: true)
+
+      {:ok, results} = Searcher.search_query(searcher, query, [])
+      assert titles(results) == ["elixir otp"]
+
+      assert {:error, :parse_error, _} =
+               Searcher.search_query(searcher, query, [], lenient: false)
+    end
+
+    test "sets the fuzzy distance of text fields", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "elixr", ["title"])
+      assert results["total_hits"] == 0
+
+      assert :ok = Searcher.set_query_defaults(searcher, fuzzy_distance: 1)
+
+      {:ok, results} = Searcher.search_query(searcher, "elixr", ["title"])
+      assert titles(results) == ["elixir otp", "elixir phoenix"]
+
+      # Keyword values stay exact
+      {:ok, results} = Searcher.search_query(searcher, "tag:wbe", [])
+      assert results["total_hits"] == 0
+
+      {:ok, results} = Searcher.search_query(searcher, "elixr", ["title"], fuzzy_distance: 0)
+      assert results["total_hits"] == 0
+    end
+
+    test "omitted options go back to their defaults", %{searcher: searcher} do
+      :ok = Searcher.set_query_defaults(searcher, default_operator: :and, fuzzy_distance: 2)
+      :ok = Searcher.set_query_defaults(searcher)
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir phoenix", ["title"])
+      assert results["total_hits"] == 3
+
+      {:ok, results} = Searcher.search_query(searcher, "elixr", ["title"])
+      assert results["total_hits"] == 0
+    end
+
+    test "rejects invalid settings", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} =
+               Searcher.set_query_defaults(searcher, fuzzy_distance: 3)
+
+      assert {:error, :invalid_argument, reason} =
+               Searcher.set_query_defaults(searcher, default_operator: :xor)
+
+      assert reason =~ "xor"
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "elixir", ["title"], default_operator: :xor)
+    end
+  end
+
+  describe "query_tokenizers option" do
+    test "tokenizes the query differently from the indexed text", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, tokenizer: "en_stem")
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"title" => "running shoes"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      # The stemmed query term "run" matches the stemmed index
+      assert {:ok, %{"total_hits" => 1}} = Searcher.search_query(searcher, "running", ["title"])
+
+      # Without stemming at query time, only the indexed form matches
+      opts = [query_tokenizers: %{"title" => "default"}]
+
+      assert {:ok, %{"total_hits" => 0}} =
+               Searcher.search_query(searcher, "running", ["title"], opts)
+
+      assert {:ok, %{"total_hits" => 1}} = Searcher.search_query(searcher, "run", ["title"], opts)
+    end
+
+    test "looks up whole terms in an n-gram field", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("name", stored: true, tokenizer: "cjk")
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = Index.register_cjk_tokenizer(index)
+
+      IndexWriter.add_documents(index, [%{"name" => "北京大学"}, %{"name" => "东京大学"}])
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      # Bigram query tokenization also matches the "京大" bigram of the second name
+      {:ok, results} = Searcher.search_query(searcher, "北京大", ["name"])
+      assert results["total_hits"] == 2
+
+      {:ok, results} =
+        Searcher.search_query(searcher, "北京", ["name"], query_tokenizers: %{"name" => "raw"})
+
+      assert [%{"doc" => %{"name" => "北京大学"}}] = results["hits"]
+    end
+
+    test "returns errors for bad fields and tokenizers", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :not_found, _} =
+               Searcher.search_query(searcher, "x", ["title"],
+                 query_tokenizers: %{"missing" => "raw"}
+               )
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "x", ["title"],
+                 query_tokenizers: %{"title" => "ngram"}
+               )
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "x", ["title"],
+                 query_tokenizers: %{"views" => "raw"}
+               )
+    end
+  end
+
+  describe "search_multi_field/4" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir guide", "body" => "rust is fast"},
+        %{"title" => "rust guide", "body" => "elixir is functional"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "ranks matches by field boost", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_multi_field(searcher, "elixir", %{"title" => 3, "body" => 1})
+
+      assert ranked_titles(results) == ["elixir guide", "rust guide"]
+
+      {:ok, results} =
+        Searcher.search_multi_field(searcher, "elixir", %{"title" => 1, "body" => 2.5})
+
+      assert ranked_titles(results) == ["rust guide", "elixir guide"]
+    end
+
+    test "only searches the given fields by default", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_multi_field(searcher, "functional", %{"title" => 1})
+      assert results["total_hits"] == 0
+
+      {:ok, results} =
+        Searcher.search_multi_field(searcher, "body:functional", %{"title" => 1})
+
+      assert ranked_titles(results) == ["rust guide"]
+    end
+
+    test "rejects invalid field boosts", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} = Searcher.search_multi_field(searcher, "elixir", %{})
+
+      assert {:error, :invalid_argument, reason} =
+               Searcher.search_multi_field(searcher, "elixir", %{"title" => -1})
+
+      assert reason =~ "non-negative"
+
+      assert {:error, :not_found, _} =
+               Searcher.search_multi_field(searcher, "elixir", %{"missing" => 1})
+    end
+  end
+
+  describe "search_min_should_match/5" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true, tokenizer: "en_stem")
+        |> Schema.add_u64_field("views", indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir phoenix liveview", "body" => "all three"},
+        %{"title" => "elixir phoenix", "body" => "two of them"},
+        %{"title" => "elixir", "body" => "running phoenix apps"},
+        %{"title" => "elixir rust", "body" => "only elixir here"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "matches documents holding enough of the terms", %{searcher: searcher} do
+      terms = ["elixir", "phoenix", "liveview"]
+
+      {:ok, results} = Searcher.search_min_should_match(searcher, ["title"], terms, 2)
+      assert titles(results) == ["elixir phoenix", "elixir phoenix liveview"]
+      assert hd(results["hits"])["doc"]["title"] == "elixir phoenix liveview"
+
+      {:ok, results} = Searcher.search_min_should_match(searcher, ["title"], terms, 3)
+      assert titles(results) == ["elixir phoenix liveview"]
+
+      {:ok, results} = Searcher.search_min_should_match(searcher, ["title"], terms, 1)
+      assert results["total_hits"] == 4
+    end
+
+    test "counts a term once across fields", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_min_should_match(searcher, ["title", "body"], ["elixir", "phoenix"], 2)
+
+      # "elixir rust" holds "elixir" in both fields, which still counts once
+      assert titles(results) == ["elixir", "elixir phoenix", "elixir phoenix liveview"]
+    end
+
+    test "analyzes terms with each field's tokenizer", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_min_should_match(searcher, ["body"], ["Running", "Apps"], 2)
+
+      assert titles(results) == ["elixir"]
+    end
+
+    test "rejects invalid arguments", %{searcher: searcher} do
+      assert {:error, :invalid_argument, reason} =
+               Searcher.search_min_should_match(searcher, ["title"], ["elixir"], 2)
+
+      assert reason =~ "between 1 and the number of terms"
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_min_should_match(searcher, [], ["elixir"], 1)
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_min_should_match(searcher, ["views"], ["elixir"], 1)
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_min_should_match(searcher, ["title"], ["two words"], 1)
+
+      assert {:error, :not_found, _} =
+               Searcher.search_min_should_match(searcher, ["missing"], ["elixir"], 1)
+    end
+  end
+
+  describe "search_exact_boosted/4" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, tokenizer: "en_stem", exact: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "run fast", "body" => "stemmed"},
+        %{"title" => "running fast", "body" => "exact"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "stemmed matches score the same without the exact field", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "running", ["title"])
+
+      assert results["total_hits"] == 2
+      assert [%{"score" => score}, %{"score" => score}] = results["hits"]
+    end
+
+    test "ranks exact-form matches above stemmed ones", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_exact_boosted(searcher, "running", ["title"])
+
+      assert ranked_titles(results) == ["running fast", "run fast"]
+
+      {:ok, results} = Searcher.search_exact_boosted(searcher, "run", ["title"])
+
+      assert ranked_titles(results) == ["run fast", "running fast"]
+    end
+
+    test "copies values into the exact field", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "title_exact:running", [])
+
+      assert ranked_titles(results) == ["running fast"]
+      refute Map.has_key?(hd(results["hits"])["doc"], "title_exact")
+    end
+
+    test "keeps the pairing when the index is reopened", %{test_path: test_path} do
+      path = test_path <> "_reopened"
+      on_exit(fn -> Muninn.TestHelpers.safe_rm_rf(path) end)
+
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true, exact: true)
+      {:ok, _index} = Index.create(path, schema)
+      {:ok, index} = Index.open(path)
+
+      IndexWriter.add_document(index, %{"title" => "running late"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "title_exact:late", [])
+      assert ranked_titles(results) == ["running late"]
+    end
+
+    test "fails for a field without an exact companion", %{searcher: searcher} do
+      assert {:error, :not_found, _} =
+               Searcher.search_exact_boosted(searcher, "exact", ["body"])
+    end
+  end
+
+  describe "synonyms" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "Pizza in New York City"},
+        %{"title" => "NYC bagels"},
+        %{"title" => "City of York, New England"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, index: index, searcher: searcher}
+    end
+
+    test "expands a term into its synonym phrases", %{index: index, searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "nyc", ["title"])
+      assert titles(results) == ["NYC bagels"]
+
+      :ok = Index.set_synonyms(index, %{"NYC" => ["new york city"]})
+
+      {:ok, results} = Searcher.search_query(searcher, "nyc", ["title"])
+      assert titles(results) == ["NYC bagels", "Pizza in New York City"]
+
+      {:ok, results} = Searcher.search_query(searcher, "title:nyc AND pizza", [])
+      assert titles(results) == ["Pizza in New York City"]
+    end
+
+    test "expands quoted phrases and only in the given direction", %{
+      index: index,
+      searcher: searcher
+    } do
+      :ok = Index.set_synonyms(index, %{"new york city" => ["nyc"]})
+
+      {:ok, results} = Searcher.search_query(searcher, ~s("New York City"), ["title"])
+      assert titles(results) == ["NYC bagels", "Pizza in New York City"]
+
+      {:ok, results} = Searcher.search_query(searcher, "nyc", ["title"])
+      assert titles(results) == ["NYC bagels"]
+    end
+
+    test "an empty map removes the synonyms", %{index: index, searcher: searcher} do
+      :ok = Index.set_synonyms(index, %{"nyc" => ["new york city"]})
+      :ok = Index.set_synonyms(index, %{})
+
+      {:ok, results} = Searcher.search_query(searcher, "nyc", ["title"])
+      assert titles(results) == ["NYC bagels"]
+    end
+
+    test "rejects empty keys", %{index: index} do
+      assert {:error, :invalid_argument, _} = Index.set_synonyms(index, %{" " => ["x"]})
+    end
+  end
+
+  defp ranked_titles(results), do: Enum.map(results["hits"], & &1["doc"]["title"])
+
+  defp titles(results), do: results["hits"] |> Enum.map(& &1["doc"]["title"]) |> Enum.sort()
 end
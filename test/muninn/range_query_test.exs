@@ -81,6 +81,49 @@ defmodule Muninn.RangeQueryTest do
       assert 10 in temps
     end
 
+    test "i64 range bounds include negative endpoints", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_i64_field("age", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      for age <- [-20, -10, -3, 0, 10, 11] do
+        IndexWriter.add_document(index, %{"age" => age})
+      end
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert ages(searcher, "age:[-10 TO 10]") == [-10, -3, 0, 10]
+      assert ages(searcher, "age:{-10 TO 10}") == [-3, 0]
+      assert ages(searcher, "age:[-20 TO -3]") == [-20, -10, -3]
+      assert ages(searcher, "age:[* TO -3]") == [-20, -10, -3]
+      assert ages(searcher, "age:<=-10") == [-20, -10]
+      assert ages(searcher, "age:>-5") == [-3, 0, 10, 11]
+      assert ages(searcher, "age:-10") == [-10]
+    end
+
+    test "f64 range bounds keep their fractional part", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_f64_field("price", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      for price <- [-5.5, 0.5, 1.5, 2.25, 99.99, 100.0] do
+        IndexWriter.add_document(index, %{"price" => price})
+      end
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert prices(searcher, "price:[1.5 TO 99.99]") == [1.5, 2.25, 99.99]
+      assert prices(searcher, "price:{1.5 TO 99.99}") == [2.25]
+      assert prices(searcher, "price:[-5.5 TO 0.5]") == [-5.5, 0.5]
+      assert prices(searcher, "price:>=-1.0") == [0.5, 1.5, 2.25, 99.99, 100.0]
+      assert prices(searcher, "price:[-6 TO 1]") == [-5.5, 0.5]
+      assert prices(searcher, "price:-5.5") == [-5.5]
+    end
+
     test "open-ended range with wildcard", %{test_path: test_path} do
       schema = Schema.new() |> Schema.add_u64_field("views", stored: true, indexed: true)
       {:ok, index} = Index.create(test_path, schema)
@@ -129,6 +172,15 @@ defmodule Muninn.RangeQueryTest do
     end
   end
 
+  defp ages(searcher, query), do: sorted_values(searcher, query, "age")
+
+  defp prices(searcher, query), do: sorted_values(searcher, query, "price")
+
+  defp sorted_values(searcher, query, field) do
+    {:ok, results} = Searcher.search_query(searcher, query, [field], limit: 100)
+    results["hits"] |> Enum.map(& &1["doc"][field]) |> Enum.sort()
+  end
+
   describe "search_range_u64/5" do
     test "inclusive both bounds", %{test_path: test_path} do
       schema = Schema.new() |> Schema.add_u64_field("value", stored: true, indexed: true)
@@ -197,7 +249,7 @@ defmodule Muninn.RangeQueryTest do
       {:ok, reader} = IndexReader.new(index)
       {:ok, searcher} = Searcher.new(reader)
 
-      {:error, reason} = Searcher.search_range_u64(searcher, "text", 1, 10)
+      {:error, :invalid_argument, reason} = Searcher.search_range_u64(searcher, "text", 1, 10)
 
       assert String.contains?(reason, "not a u64 field")
     end
@@ -287,6 +339,51 @@ defmodule Muninn.RangeQueryTest do
     end
   end
 
+  describe "search_range_text/5" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true)
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("count", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      for sku <- ["a000", "a500", "a999", "b000", "A100"] do
+        IndexWriter.add_document(index, %{"sku" => sku, "title" => "item #{sku}"})
+      end
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "matches keyword values in lexicographic order", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_range_text(searcher, "sku", "a000", "a999")
+
+      skus = results["hits"] |> Enum.map(& &1["doc"]["sku"]) |> Enum.sort()
+      assert skus == ["a000", "a500", "a999"]
+    end
+
+    test "respects bound inclusivity", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_range_text(searcher, "sku", "a000", "a999", inclusive: :neither)
+
+      assert Enum.map(results["hits"], & &1["doc"]["sku"]) == ["a500"]
+    end
+
+    test "rejects non-text fields", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_range_text(searcher, "count", "1", "9")
+
+      assert {:error, :not_found, _} =
+               Searcher.search_range_text(searcher, "missing", "a", "b")
+    end
+  end
+
   describe "limit parameter" do
     test "respects limit in range queries", %{test_path: test_path} do
       schema = Schema.new() |> Schema.add_u64_field("value", stored: true, indexed: true)
@@ -11,6 +11,13 @@ defmodule Muninn.Schema.FieldTest do
       assert field.name == "title"
       assert field.stored == false
       assert field.indexed == true
+      assert field.fast == false
+    end
+
+    test "creates a field with fast option" do
+      field = Field.new(:u64, "id", fast: true)
+
+      assert field.fast == true
     end
 
     test "creates a field with stored option" do
@@ -70,4 +77,54 @@ defmodule Muninn.Schema.FieldTest do
       assert map.indexed === false
     end
   end
+
+  describe "to_native/1" do
+    test "converts field to NIF tuple with options map" do
+      field = Field.new(:u64, "id", stored: true, fast: true)
+
+      assert Field.to_native(field) ==
+               {"id", "u64", true, true,
+                %{
+                  "fast" => true,
+                  "tokenizer" => nil,
+                  "id" => false,
+                  "required" => false,
+                  "positions" => nil,
+                  "max_length" => nil,
+                  "on_too_long" => "reject",
+                  "copy_from" => nil,
+                  "multi" => false
+                }}
+    end
+
+    test "includes the tokenizer name" do
+      field = Field.new(:text, "body", tokenizer: "cjk")
+
+      assert {"body", "text", false, true, %{"tokenizer" => "cjk"}} = Field.to_native(field)
+    end
+
+    test "includes the id flag" do
+      field = Field.new(:keyword, "sku", stored: true, id: true)
+
+      assert {"sku", "keyword", true, true, %{"id" => true}} = Field.to_native(field)
+    end
+
+    test "includes the required flag" do
+      field = Field.new(:text, "title", required: true)
+
+      assert {"title", "text", false, true, %{"required" => true}} = Field.to_native(field)
+    end
+
+    test "includes the multi flag" do
+      field = Field.new(:u64, "cat_ids", stored: true, multi: true)
+
+      assert {"cat_ids", "u64", true, true, %{"multi" => true}} = Field.to_native(field)
+    end
+
+    test "includes the positions flag" do
+      field = Field.new(:text, "message", positions: false)
+
+      assert {"message", "text", false, true, %{"positions" => false}} = Field.to_native(field)
+    end
+  end
 end
@@ -54,6 +54,19 @@ defmodule Muninn.SchemaTest do
       assert hd(schema.fields).stored == false
       assert hd(schema.fields).indexed == true
     end
+
+    test "adds an unstored exact companion with the exact option" do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true, tokenizer: "en_stem", exact: true)
+
+      assert [title, exact] = schema.fields
+      assert title.tokenizer == "en_stem"
+      assert exact.name == "title_exact"
+      assert exact.copy_from == "title"
+      assert exact.stored == false
+      assert exact.tokenizer == nil
+    end
   end
 
   describe "validate/1" do
@@ -79,6 +92,15 @@ defmodule Muninn.SchemaTest do
 
       assert Schema.validate(schema) == {:error, :duplicate_field_names}
     end
+
+    test "returns error for more than one id field" do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_u64_field("legacy_id", stored: true, id: true)
+
+      assert Schema.validate(schema) == {:error, :multiple_id_fields}
+    end
   end
 
   describe "to_map/1" do
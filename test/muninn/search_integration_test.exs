@@ -298,7 +298,7 @@ defmodule Muninn.SearchIntegrationTest do
       {:ok, searcher} = Searcher.new(reader)
 
       query = Query.term("nonexistent_field", "test")
-      {:error, reason} = Searcher.search(searcher, query, limit: 10)
+      {:error, :not_found, reason} = Searcher.search(searcher, query, limit: 10)
 
       assert reason =~ "Field 'nonexistent_field' not found"
     end
@@ -48,6 +48,69 @@ defmodule Muninn.SearcherTest do
 
       assert is_reference(reader)
     end
+
+    test "max_result_window caps offset plus limit in paginated searches", %{
+      test_path: test_path
+    } do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true, id: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, Enum.map(1..5, &%{"sku" => "sku-#{&1}"}))
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index, max_result_window: 4)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:ok, %{"total" => 5}} =
+               Searcher.search_paged(searcher, "*", [], limit: 2, offset: 2)
+
+      assert {:error, :result_window_exceeded, message} =
+               Searcher.search_paged(searcher, "*", [], limit: 2, offset: 3)
+
+      assert message =~ "maximum result window of 4"
+
+      assert {:error, :result_window_exceeded, _} =
+               Searcher.search_ids(searcher, "*", [], limit: 5)
+    end
+
+    test "rejects a max_result_window of zero", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title")
+      {:ok, index} = Index.create(test_path, schema)
+
+      assert {:error, :invalid_argument, reason} = IndexReader.new(index, max_result_window: 0)
+      assert reason =~ "greater than 0"
+    end
+
+    test "stays on its commit with manual reloading", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      {:ok, first} = IndexWriter.add_and_commit(index, %{"title" => "elixir one"})
+
+      {:ok, reader} = IndexReader.new(index, reload: :manual)
+      assert {:ok, ^first} = IndexReader.opstamp(reader)
+
+      {:ok, second} = IndexWriter.add_and_commit(index, %{"title" => "elixir two"})
+      Process.sleep(200)
+
+      {:ok, searcher} = Searcher.new(reader)
+      assert {:ok, %{"total_hits" => 1}} = Searcher.search_query(searcher, "elixir", ["title"])
+
+      assert {:ok, ^second} = IndexReader.reload(reader)
+      assert {:ok, ^second} = IndexReader.opstamp(reader)
+
+      {:ok, reloaded} = Searcher.new(reader)
+      assert {:ok, %{"total_hits" => 2}} = Searcher.search_query(reloaded, "elixir", ["title"])
+      assert {:ok, %{"total_hits" => 1}} = Searcher.search_query(searcher, "elixir", ["title"])
+    end
+
+    test "only reloads manual readers", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title")
+      {:ok, index} = Index.create(test_path, schema)
+      {:ok, reader} = IndexReader.new(index)
+
+      assert {:error, :invalid_argument, _} = IndexReader.reload(reader)
+      assert {:error, :invalid_argument, _} = IndexReader.opstamp(reader)
+    end
   end
 
   describe "Searcher" do
@@ -160,6 +223,31 @@ defmodule Muninn.SearcherTest do
 
       assert results["total_hits"] == 1
     end
+
+    test "analyzes the term with the field's tokenizer", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("body", stored: true, tokenizer: "en_stem")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"body" => "The runners were running"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      # "running" is indexed as "run", so the raw term would never match
+      for value <- ["running", "Running", "runs"] do
+        {:ok, results} = Searcher.search(searcher, Query.term("body", value))
+        assert results["total_hits"] == 1
+      end
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.search(searcher, Query.term("body", "running fast"))
+
+      assert message =~ "analyzed into 2 terms"
+    end
   end
 
   describe "search result format" do
@@ -226,6 +314,228 @@ defmodule Muninn.SearcherTest do
         assert hit["score"] > 0.0
       end
     end
+
+    test "scores keep the short decimal form of their f32 value", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"text" => "searchterm"},
+        %{"text" => "searchterm with several other words"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} =
+        Searcher.search_query(searcher, "searchterm", ["text"], normalize_scores: true)
+
+      # A widened f32 such as 0.30000001192092896 shows 17 significant digits
+      for hit <- results["hits"], score <- [hit["score"], hit["raw_score"]] do
+        digits =
+          score
+          |> Float.to_string()
+          |> String.split("e")
+          |> hd()
+          |> String.replace(~r/\D/, "")
+          |> String.trim_leading("0")
+
+        assert String.length(digits) <= 9
+      end
+    end
+
+    test "ordered_fields lists the doc fields in schema order", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true)
+        |> Schema.add_text_field("author", stored: true)
+        |> Schema.add_text_field("body")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "body" => "searchterm",
+        "author" => "ada",
+        "title" => "Notes",
+        "views" => 7
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} =
+        Searcher.search_query(searcher, "searchterm", ["body"], ordered_fields: true)
+
+      assert hit["fields"] == [{"title", "Notes"}, {"views", 7}, {"author", "ada"}]
+      assert Map.new(hit["fields"]) == hit["doc"]
+
+      {:ok, %{"hits" => [hit]}} =
+        Searcher.search_query(searcher, "searchterm", ["body"],
+          ordered_fields: true,
+          fields: ["author", "title"]
+        )
+
+      assert hit["fields"] == [{"title", "Notes"}, {"author", "ada"}]
+
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "searchterm", ["body"])
+      refute Map.has_key?(hit, "fields")
+    end
+  end
+
+  describe "multi-valued numeric fields" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("cat_ids", stored: true, indexed: true)
+        |> Schema.add_i64_field("offsets", stored: true)
+        |> Schema.add_f64_field("ratings", stored: true)
+        |> Schema.add_u64_field("sizes", stored: true, multi: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      :ok =
+        IndexWriter.add_document(index, %{
+          "title" => "shirt",
+          "cat_ids" => [1, 5, 9],
+          "offsets" => [-3, 0, 3],
+          "ratings" => [4.5, 3],
+          "sizes" => [38, 40]
+        })
+
+      :ok =
+        IndexWriter.add_document(index, %{"title" => "hat", "cat_ids" => [7], "sizes" => [56]})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "round-trips every value in order", %{searcher: searcher} do
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "shirt", ["title"])
+
+      assert hit["doc"]["cat_ids"] == [1, 5, 9]
+      assert hit["doc"]["offsets"] == [-3, 0, 3]
+      assert hit["doc"]["ratings"] == [4.5, 3.0]
+    end
+
+    test "matches on any of the values", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "cat_ids:5", [])
+      assert [%{"doc" => %{"title" => "shirt"}}] = results["hits"]
+
+      {:ok, results} = Searcher.search_range_u64(searcher, "cat_ids", 8, 10)
+      assert [%{"doc" => %{"title" => "shirt"}}] = results["hits"]
+    end
+
+    test "returns a single value as a scalar", %{searcher: searcher} do
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "hat", ["title"])
+
+      assert hit["doc"]["cat_ids"] == 7
+    end
+
+    test "returns multi fields as a list whatever the number of values", %{
+      searcher: searcher
+    } do
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "hat", ["title"])
+      assert hit["doc"]["sizes"] == [56]
+
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "shirt", ["title"])
+      assert hit["doc"]["sizes"] == [38, 40]
+    end
+
+    test "keeps multi fields after reopening the index", %{test_path: test_path} do
+      {:ok, index} = Index.open(test_path, read_only: true)
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "hat", ["title"])
+      assert hit["doc"]["sizes"] == [56]
+    end
+
+    test "rejects multi on a non-numeric field", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("tags", stored: true, multi: true)
+
+      assert {:error, :invalid_schema, message} =
+               Index.create(Path.join(test_path, "text_multi"), schema)
+
+      assert message =~ "must be a u64, i64 or f64 field to be multi-valued"
+    end
+  end
+
+  describe "stored-only fields" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("image_url", stored: true, indexed: false)
+        |> Schema.add_keyword_field("html", stored: true, indexed: false)
+        |> Schema.add_u64_field("width", stored: true, indexed: false)
+        |> Schema.add_i64_field("offset", stored: true, indexed: false)
+        |> Schema.add_f64_field("ratio", stored: true, indexed: false)
+        |> Schema.add_bool_field("retina", stored: true, indexed: false)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "title" => "Elixir logo",
+        "image_url" => "https://example.com/elixir logo.png",
+        "html" => "<img alt=\"elixir\">",
+        "width" => 640,
+        "offset" => -12,
+        "ratio" => 1.5,
+        "retina" => true
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "round-trip every type in the hit map", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["title"])
+      [hit] = results["hits"]
+
+      assert hit["doc"] == %{
+               "title" => "Elixir logo",
+               "image_url" => "https://example.com/elixir logo.png",
+               "html" => "<img alt=\"elixir\">",
+               "width" => 640,
+               "offset" => -12,
+               "ratio" => 1.5,
+               "retina" => true
+             }
+    end
+
+    test "never match queries", %{searcher: searcher} do
+      for query <- ["image_url:example", "width:640", "retina:true"] do
+        assert {:error, :parse_error, reason} = Searcher.search_query(searcher, query, [])
+        assert reason =~ "not declared as indexed"
+      end
+
+      assert {:error, :invalid_argument, reason} =
+               Searcher.search_query(searcher, "logo", ["title", "image_url"])
+
+      assert reason =~ "not indexed"
+    end
+
+    test "cannot be given a tokenizer", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("image_url", stored: true, indexed: false, tokenizer: "raw")
+
+      assert {:error, :invalid_schema, reason} = Index.create(test_path, schema)
+      assert reason =~ "not indexed and cannot have a tokenizer"
+    end
   end
 
   describe "Query.Term" do
@@ -282,4 +592,1676 @@ defmodule Muninn.SearcherTest do
       assert is_list(results["hits"])
     end
   end
+
+  describe "search_stream/4" do
+    test "streams every match across batches", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      for i <- 1..25 do
+        IndexWriter.add_document(index, %{"text" => "doc number#{i}"})
+      end
+
+      IndexWriter.add_document(index, %{"text" => "unrelated"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, stream} = Searcher.search_stream(searcher, "doc", ["text"], batch_size: 4)
+      hits = Enum.to_list(stream)
+
+      assert length(hits) == 25
+      assert Enum.all?(hits, &is_float(&1["score"]))
+
+      texts = hits |> Enum.map(& &1["doc"]["text"]) |> Enum.uniq()
+      assert length(texts) == 25
+    end
+
+    test "stream is lazy and can be partially consumed", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      for i <- 1..10 do
+        IndexWriter.add_document(index, %{"text" => "item #{i}"})
+      end
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, stream} = Searcher.search_stream(searcher, "item", ["text"], batch_size: 3)
+      assert length(Enum.take(stream, 5)) == 5
+    end
+
+    test "cursor returns empty batches once exhausted", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"text" => "alpha"})
+      IndexWriter.add_document(index, %{"text" => "alpha beta"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, cursor} = Muninn.Native.searcher_search_stream(searcher, "alpha", ["text"], 5)
+      assert {:ok, [_, _]} = Muninn.Native.searcher_stream_next(cursor)
+      assert {:ok, []} = Muninn.Native.searcher_stream_next(cursor)
+      assert {:ok, []} = Muninn.Native.searcher_stream_next(cursor)
+    end
+
+    test "handles a batch size as large as the index allows", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true, indexed: true)
+      huge = 2 ** 64 - 1
+      {:ok, index} = Index.create(test_path, schema, max_limit: huge)
+
+      IndexWriter.add_document(index, %{"text" => "alpha"})
+      IndexWriter.add_document(index, %{"text" => "alpha beta"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, cursor} = Muninn.Native.searcher_search_stream(searcher, "alpha", ["text"], huge)
+      assert {:ok, [_, _]} = Muninn.Native.searcher_stream_next(cursor)
+      assert {:ok, []} = Muninn.Native.searcher_stream_next(cursor)
+    end
+
+    test "returns error for invalid query or batch size", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :parse_error, _} =
+               Searcher.search_stream(searcher, "missing:value", ["text"])
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_stream(searcher, "text", ["text"], batch_size: 0)
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.search_stream(searcher, "text", ["text"], batch_size: 2 ** 64 - 1)
+
+      assert message =~ "exceeds the maximum"
+    end
+  end
+
+  describe "search_fast_fields/5" do
+    test "returns fast field values without stored documents", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("id", fast: true)
+        |> Schema.add_i64_field("offset", fast: true)
+        |> Schema.add_f64_field("rating", fast: true)
+        |> Schema.add_bool_field("active", fast: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "title" => "Elixir guide",
+        "id" => 42,
+        "offset" => -3,
+        "rating" => 4.5,
+        "active" => true
+      })
+
+      IndexWriter.add_document(index, %{"title" => "Rust guide", "id" => 7})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} =
+        Searcher.search_fast_fields(searcher, "elixir", ["title"], [
+          "id",
+          "offset",
+          "rating",
+          "active"
+        ])
+
+      assert results["total_hits"] == 1
+      [hit] = results["hits"]
+      assert hit["fields"] == %{"id" => 42, "offset" => -3, "rating" => 4.5, "active" => true}
+      refute Map.has_key?(hit, "doc")
+
+      {:ok, results} = Searcher.search_fast_fields(searcher, "rust", ["title"], ["id", "rating"])
+      [hit] = results["hits"]
+      assert hit["fields"] == %{"id" => 7}
+    end
+
+    test "returns error for fields that are not fast", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :invalid_argument, reason} =
+               Searcher.search_fast_fields(searcher, "elixir", ["title"], ["views"])
+
+      assert reason =~ "not a fast field"
+
+      assert {:error, :not_found, reason} =
+               Searcher.search_fast_fields(searcher, "elixir", ["title"], ["missing"])
+
+      assert reason =~ "not found"
+    end
+  end
+
+  describe "get_doc/2" do
+    test "fetches a hit again by its doc address", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_text_field("title", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"sku" => "a", "title" => "elixir guide"})
+      IndexWriter.commit(index)
+      IndexWriter.add_document(index, %{"sku" => "b", "title" => "rust guide"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} =
+        Searcher.search_query(searcher, "rust", ["title"], doc_addresses: true)
+
+      assert {segment_ord, doc_id} = hit["doc_address"]
+      assert is_integer(segment_ord) and is_integer(doc_id)
+
+      assert {:ok, fetched} = Searcher.get_doc(searcher, hit["doc_address"])
+      assert fetched == Map.drop(hit, ["score", "doc_address"])
+    end
+
+    test "returns not_found for an address without a document", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"title" => "elixir"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:ok, %{"doc" => %{"title" => "elixir"}}} = Searcher.get_doc(searcher, {0, 0})
+      assert {:error, :not_found, _} = Searcher.get_doc(searcher, {0, 1})
+      assert {:error, :not_found, _} = Searcher.get_doc(searcher, {5, 0})
+    end
+  end
+
+  describe "doc_term_freqs/4" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body")
+        |> Schema.add_keyword_field("tag", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "elixir", "body" => "Elixir runs on the BEAM, elixir is fun", "tag" => "a"},
+        %{"title" => "rust", "body" => "rust rust rust", "tag" => "b"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} =
+        Searcher.search_query(searcher, "elixir", ["title"], doc_addresses: true)
+
+      {:ok, searcher: searcher, address: hit["doc_address"]}
+    end
+
+    test "returns the document's terms by frequency", %{searcher: searcher, address: address} do
+      assert {:ok, [%{"term" => "elixir", "freq" => 2} | rest]} =
+               Searcher.doc_term_freqs(searcher, address, "body")
+
+      assert Enum.map(rest, & &1["term"]) == ["beam", "fun", "is", "on", "runs", "the"]
+      assert Enum.all?(rest, &(&1["freq"] == 1))
+    end
+
+    test "limits the number of terms", %{searcher: searcher, address: address} do
+      assert {:ok, terms} = Searcher.doc_term_freqs(searcher, address, "body", top_n: 2)
+      assert Enum.map(terms, & &1["term"]) == ["elixir", "beam"]
+    end
+
+    test "rejects fields without frequencies and missing documents", ctx do
+      assert {:error, :invalid_argument, _} =
+               Searcher.doc_term_freqs(ctx.searcher, ctx.address, "tag")
+
+      assert {:error, :not_found, _} =
+               Searcher.doc_term_freqs(ctx.searcher, ctx.address, "missing")
+
+      assert {:error, :not_found, _} = Searcher.doc_term_freqs(ctx.searcher, {5, 0}, "body")
+    end
+  end
+
+  describe "term_dict/3" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("body")
+        |> Schema.add_keyword_field("tag")
+        |> Schema.add_u64_field("views", indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"body" => "Elixir and Erlang", "tag" => "elixir", "views" => 1},
+        %{"body" => "Rust and Elixir", "tag" => "rust", "views" => 2}
+      ])
+
+      IndexWriter.commit(index)
+
+      IndexWriter.add_document(index, %{"body" => "Elm", "tag" => "elm", "views" => 3})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "sums document frequencies across segments", %{searcher: searcher} do
+      assert {:ok, terms} = Searcher.term_dict(searcher, "body")
+
+      assert terms == [
+               {"and", 2},
+               {"elixir", 2},
+               {"elm", 1},
+               {"erlang", 1},
+               {"rust", 1}
+             ]
+    end
+
+    test "filters by prefix and limits the terms", %{searcher: searcher} do
+      assert {:ok, [{"elixir", 2}, {"elm", 1}, {"erlang", 1}]} =
+               Searcher.term_dict(searcher, "body", prefix: "e")
+
+      assert {:ok, [{"elixir", 1}, {"elm", 1}]} =
+               Searcher.term_dict(searcher, "tag", prefix: "el", limit: 2)
+
+      assert {:ok, []} = Searcher.term_dict(searcher, "body", prefix: "zig")
+    end
+
+    test "rejects non-string fields, missing fields and bad limits", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} = Searcher.term_dict(searcher, "views")
+      assert {:error, :not_found, _} = Searcher.term_dict(searcher, "missing")
+      assert {:error, :invalid_argument, _} = Searcher.term_dict(searcher, "body", limit: 0)
+    end
+  end
+
+  describe "score_docs/5" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      # Two commits, so the documents span two segments
+      IndexWriter.add_documents(index, [%{"title" => "elixir phoenix"}, %{"title" => "rust"}])
+      IndexWriter.commit(index)
+      IndexWriter.add_documents(index, [%{"title" => "elixir"}, %{"title" => "go"}])
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => hits}} = Searcher.search_query(searcher, "*", [], doc_addresses: true)
+      addresses = Map.new(hits, &{&1["doc"]["title"], &1["doc_address"]})
+
+      {:ok, searcher: searcher, addresses: addresses}
+    end
+
+    test "scores each document like a search, in the order given", ctx do
+      labeled = Enum.map(["go", "elixir", "rust", "elixir phoenix"], &ctx.addresses[&1])
+
+      assert {:ok, results} =
+               Searcher.score_docs(ctx.searcher, "elixir phoenix", ["title"], labeled)
+
+      assert Enum.map(results, & &1["doc_address"]) == labeled
+
+      {:ok, %{"hits" => hits}} = Searcher.search_query(ctx.searcher, "elixir phoenix", ["title"])
+      search_scores = Map.new(hits, &{&1["doc"]["title"], &1["score"]})
+
+      assert Enum.map(results, & &1["score"]) == [
+               0.0,
+               search_scores["elixir"],
+               0.0,
+               search_scores["elixir phoenix"]
+             ]
+
+      refute Enum.any?(results, &Map.has_key?(&1, "explanation"))
+    end
+
+    test "explains the scores of matching documents", ctx do
+      labeled = [ctx.addresses["elixir"], ctx.addresses["rust"]]
+
+      assert {:ok, [matching, other]} =
+               Searcher.score_docs(ctx.searcher, "elixir", ["title"], labeled, explain: true)
+
+      assert %{"value" => value, "description" => _, "details" => [_ | _]} =
+               matching["explanation"]
+
+      assert_in_delta value, matching["score"], 1.0e-5
+      assert other["explanation"] == nil
+    end
+
+    test "rejects addresses without a live document", ctx do
+      assert {:error, :not_found, _} =
+               Searcher.score_docs(ctx.searcher, "elixir", ["title"], [{5, 0}])
+
+      assert {:ok, []} = Searcher.score_docs(ctx.searcher, "elixir", ["title"], [])
+    end
+  end
+
+  describe "compile_regex/2 and run_regex/3" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"title" => "error err42 in node", "views" => 1})
+      IndexWriter.add_document(index, %{"title" => "err7 warning", "views" => 2})
+      IndexWriter.add_document(index, %{"title" => "all clear", "views" => 3})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "matches whole terms against the compiled regex", %{searcher: searcher} do
+      assert {:ok, compiled} = Searcher.compile_regex("err[0-9]+", "title")
+
+      assert {:ok, %{"total_hits" => 2, "hits" => hits}} = Searcher.run_regex(searcher, compiled)
+      assert hits |> Enum.map(& &1["doc"]["views"]) |> Enum.sort() == [1, 2]
+
+      assert {:ok, %{"total_hits" => 1}} = Searcher.run_regex(searcher, compiled, limit: 1)
+
+      {:ok, substring} = Searcher.compile_regex("rr", "title")
+      assert {:ok, %{"total_hits" => 0}} = Searcher.run_regex(searcher, substring)
+    end
+
+    test "rejects invalid patterns when compiling" do
+      assert {:error, :invalid_argument, _} = Searcher.compile_regex("err[", "title")
+    end
+
+    test "checks the field when running", %{searcher: searcher} do
+      {:ok, missing} = Searcher.compile_regex("a.*", "missing")
+      assert {:error, :not_found, _} = Searcher.run_regex(searcher, missing)
+
+      {:ok, numeric} = Searcher.compile_regex("1", "views")
+      assert {:error, :invalid_argument, _} = Searcher.run_regex(searcher, numeric)
+    end
+  end
+
+  describe "set_default_fields/2" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true)
+        |> Schema.add_u64_field("views", stored: true, fast: true)
+        |> Schema.add_text_field("notes")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "sku" => "a",
+        "title" => "elixir guide",
+        "body" => String.duplicate("long body ", 100),
+        "views" => 7
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "restricts the doc map of every search", %{searcher: searcher} do
+      assert :ok = Searcher.set_default_fields(searcher, ["title"])
+
+      assert {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "elixir", ["title"])
+      assert hit["doc"] == %{"title" => "elixir guide"}
+      assert hit["_id"] == "a"
+
+      assert {:ok, %{"hits" => [%{"doc" => doc}]}} =
+               Searcher.search_range_u64(searcher, "views", 0, 10)
+
+      assert doc == %{"title" => "elixir guide"}
+      assert {:ok, %{"doc" => ^doc}} = Searcher.get_doc(searcher, {0, 0})
+    end
+
+    test "is overridden by the fields option", %{searcher: searcher} do
+      :ok = Searcher.set_default_fields(searcher, ["title"])
+
+      assert {:ok, %{"hits" => [%{"doc" => doc}]}} =
+               Searcher.search_query(searcher, "elixir", ["title"], fields: ["sku", "views"])
+
+      assert doc == %{"sku" => "a", "views" => 7}
+    end
+
+    test "returns every stored field again after nil", %{searcher: searcher} do
+      :ok = Searcher.set_default_fields(searcher, ["title"])
+      assert :ok = Searcher.set_default_fields(searcher, nil)
+
+      assert {:ok, %{"hits" => [%{"doc" => doc}]}} =
+               Searcher.search_query(searcher, "elixir", ["title"])
+
+      assert Map.keys(doc) == ["body", "sku", "title", "views"]
+    end
+
+    test "rejects missing and unstored fields", %{searcher: searcher} do
+      assert {:error, :not_found, _} = Searcher.set_default_fields(searcher, ["missing"])
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.set_default_fields(searcher, ["notes"])
+
+      assert message =~ "not stored"
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_query(searcher, "elixir", ["title"], fields: ["notes"])
+    end
+  end
+
+  describe "set_field_aliases/2" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("views", stored: true, indexed: true, fast: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"title" => "elixir guide", "views" => 7})
+      IndexWriter.add_document(index, %{"title" => "rust guide", "views" => 3})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      :ok = Searcher.set_field_aliases(searcher, %{"name" => "title", "hits" => "views"})
+
+      {:ok, searcher: searcher}
+    end
+
+    test "rewrites aliases in query strings", %{searcher: searcher} do
+      assert {:ok, %{"hits" => [hit]}} =
+               Searcher.search_query(searcher, "name:elixir AND hits:[5 TO 10]", [])
+
+      assert hit["doc"] == %{"title" => "elixir guide", "views" => 7}
+
+      assert {:ok, %{"hits" => [_]}} =
+               Searcher.search_query(searcher, "elixir", ["name"], filters: ["hits:>5"])
+    end
+
+    test "rewrites aliases in field arguments", %{searcher: searcher} do
+      assert {:ok, %{"hits" => [%{"fields" => %{"views" => 7}}]}} =
+               Searcher.search_fast_fields(searcher, "elixir", ["name"], ["hits"])
+
+      assert {:ok, %{"hits" => [%{"snippets" => %{"title" => snippet}}]}} =
+               Searcher.search_with_snippets(searcher, "rust", ["name"], ["name"])
+
+      assert snippet =~ "<b>rust</b>"
+
+      assert {:ok, %{"hits" => [_]}} = Searcher.search_range_u64(searcher, "hits", 5, 10)
+      assert {:ok, %{"hits" => [_, _]}} = Searcher.search_prefix(searcher, "name", "gui")
+
+      assert {:ok, %{"total" => %{"value" => 10.0}}} =
+               Searcher.aggregate(searcher, "*", [], %{
+                 "total" => %{"sum" => %{"field" => "hits"}}
+               })
+    end
+
+    test "passes unknown names through", %{searcher: searcher} do
+      assert {:error, :parse_error, _} = Searcher.search_query(searcher, "titel:elixir", [])
+      assert {:error, :not_found, _} = Searcher.search_query(searcher, "elixir", ["titel"])
+    end
+
+    test "can be replaced and removed", %{searcher: searcher} do
+      :ok = Searcher.set_field_aliases(searcher, %{"heading" => "title"})
+
+      assert {:ok, %{"hits" => [_]}} = Searcher.search_query(searcher, "heading:elixir", [])
+      assert {:error, :parse_error, _} = Searcher.search_query(searcher, "name:elixir", [])
+
+      :ok = Searcher.set_field_aliases(searcher, %{})
+      assert {:error, :parse_error, _} = Searcher.search_query(searcher, "heading:elixir", [])
+    end
+
+    test "rejects invalid aliases", %{searcher: searcher} do
+      assert {:error, :not_found, _} = Searcher.set_field_aliases(searcher, %{"a" => "missing"})
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.set_field_aliases(searcher, %{"views" => "title"})
+
+      assert message =~ "already a field"
+    end
+  end
+
+  describe "compile_query/3 and run/3" do
+    test "runs a compiled query with later searchers", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"title" => "disk error on node one"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+      assert {:ok, compiled} = Searcher.compile_query(searcher, "error -ignored", ["title"])
+
+      assert {:ok, %{"total_hits" => 1, "hits" => [hit]}} = Searcher.run(searcher, compiled)
+
+      {:ok, expected} = Searcher.search_query(searcher, "error -ignored", ["title"])
+      assert expected["hits"] == [hit]
+
+      IndexWriter.add_document(index, %{"title" => "network error on node two"})
+      IndexWriter.add_document(index, %{"title" => "ignored error"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:ok, %{"total_hits" => 2}} = Searcher.run(searcher, compiled)
+      assert {:ok, %{"total_hits" => 1}} = Searcher.run(searcher, compiled, limit: 1)
+    end
+
+    test "reports parse errors when compiling", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :parse_error, _} = Searcher.compile_query(searcher, "title:(", ["title"])
+      assert {:error, :not_found, _} = Searcher.compile_query(searcher, "x", ["missing"])
+    end
+
+    test "rejects a searcher of an index with another schema", %{test_path: test_path} do
+      {:ok, index} =
+        Index.create(test_path, Schema.new() |> Schema.add_text_field("title", stored: true))
+
+      {:ok, other_index} =
+        Index.create(test_path <> "_other", Schema.new() |> Schema.add_text_field("body"))
+
+      on_exit(fn -> Muninn.TestHelpers.safe_rm_rf(test_path <> "_other") end)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+      {:ok, compiled} = Searcher.compile_query(searcher, "elixir", ["title"])
+
+      {:ok, other_reader} = IndexReader.new(other_index)
+      {:ok, other_searcher} = Searcher.new(other_reader)
+
+      assert {:error, :invalid_argument, message} = Searcher.run(other_searcher, compiled)
+      assert message =~ "different schema"
+    end
+  end
+
+  describe "search_paged/4" do
+    test "returns a page of hits with the total number of matches", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("rank", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      docs =
+        for rank <- 1..5 do
+          %{"title" => String.duplicate("elixir ", 6 - rank) <> "guide", "rank" => rank}
+        end
+
+      IndexWriter.add_documents(index, docs ++ [%{"title" => "rust guide", "rank" => 99}])
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_paged(searcher, "elixir", ["title"], limit: 2, offset: 1)
+
+      assert results["total"] == 5
+      assert Enum.map(results["hits"], & &1["doc"]["rank"]) == [2, 3]
+
+      {:ok, results} = Searcher.search_paged(searcher, "elixir", ["title"], offset: 10)
+      assert results == %{"total" => 5, "hits" => []}
+    end
+  end
+
+  describe "search_grouped/5" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("category", stored: true, fast: true)
+        |> Schema.add_f64_field("price", fast: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"title" => "laptop laptop laptop", "category" => 1},
+        %{"title" => "laptop laptop", "category" => 1},
+        %{"title" => "laptop", "category" => 1},
+        %{"title" => "laptop laptop bag", "category" => 2},
+        %{"title" => "laptop stand with a very long title", "category" => 3},
+        %{"title" => "laptop without category"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "returns the top hits of each group", %{searcher: searcher} do
+      {:ok, %{"groups" => groups}} =
+        Searcher.search_grouped(searcher, "laptop", ["title"], "category", hits_per_group: 2)
+
+      assert Enum.map(groups, & &1["value"]) == [1, 2, 3]
+
+      assert Enum.map(groups, fn group -> Enum.map(group["hits"], & &1["doc"]["title"]) end) ==
+               [
+                 ["laptop laptop laptop", "laptop laptop"],
+                 ["laptop laptop bag"],
+                 ["laptop stand with a very long title"]
+               ]
+    end
+
+    test "limits the number of groups", %{searcher: searcher} do
+      {:ok, %{"groups" => groups}} =
+        Searcher.search_grouped(searcher, "laptop", ["title"], "category", num_groups: 1)
+
+      assert [%{"value" => 1, "hits" => hits}] = groups
+      assert length(hits) == 3
+    end
+
+    test "rejects group fields that are not integer or bool fast fields", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_grouped(searcher, "laptop", ["title"], "price")
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_grouped(searcher, "laptop", ["title"], "title")
+
+      assert {:error, :not_found, _} =
+               Searcher.search_grouped(searcher, "laptop", ["title"], "missing")
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_grouped(searcher, "laptop", ["title"], "category",
+                 hits_per_group: 0
+               )
+    end
+  end
+
+  describe "search_sorted/5" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("created_at", stored: true, fast: true)
+        |> Schema.add_f64_field("price", fast: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      # One commit per batch, so each segment holds a distinct range of times
+      for batch <- 0..2 do
+        IndexWriter.add_documents(
+          index,
+          for i <- 1..3 do
+            %{"title" => "post #{batch * 3 + i}", "created_at" => batch * 100 + i}
+          end
+        )
+
+        IndexWriter.commit(index)
+      end
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "returns the latest hits and skips older segments", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_sorted(searcher, "post", ["title"], "created_at", limit: 2)
+
+      assert Enum.map(results["hits"], & &1["sort_value"]) == [203, 202]
+      assert Enum.map(results["hits"], & &1["doc"]["title"]) == ["post 9", "post 8"]
+      assert results["total_hits"] == 2
+      assert results["segments_searched"] == 1
+      assert results["segments_skipped"] == 2
+    end
+
+    test "orders hits by ascending value", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_sorted(searcher, "post", ["title"], "created_at", order: :asc, limit: 4)
+
+      assert Enum.map(results["hits"], & &1["sort_value"]) == [1, 2, 3, 101]
+      assert results["segments_skipped"] == 1
+    end
+
+    test "rejects invalid sort fields and orders", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_sorted(searcher, "post", ["title"], "price")
+
+      assert {:error, :not_found, _} =
+               Searcher.search_sorted(searcher, "post", ["title"], "missing")
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.search_sorted(searcher, "post", ["title"], "created_at", order: :up)
+    end
+  end
+
+  describe "search_with_decay/6" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_i64_field("published_at", stored: true, fast: true)
+        |> Schema.add_u64_field("views", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      for {sku, published_at} <- [{"old", 500}, {"fresh", 1000}, {"day", 900}] do
+        IndexWriter.add_document(index, %{
+          "sku" => sku,
+          "title" => "elixir news",
+          "published_at" => published_at
+        })
+      end
+
+      IndexWriter.add_document(index, %{"sku" => "undated", "title" => "elixir news"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "halves scores every scale before the origin", %{searcher: searcher} do
+      assert {:ok, %{"total_hits" => 4, "hits" => hits}} =
+               Searcher.search_with_decay(searcher, "elixir", ["title"], "published_at", 100,
+                 origin: 1000
+               )
+
+      assert Enum.map(hits, & &1["_id"]) == ["fresh", "day", "old", "undated"]
+
+      [fresh, day, old, undated] = Enum.map(hits, & &1["score"])
+      assert_in_delta day / fresh, 0.5, 1.0e-6
+      assert_in_delta old / fresh, 1 / 32, 1.0e-6
+      assert undated == 0.0
+    end
+
+    test "decays linearly to zero at twice the scale", %{searcher: searcher} do
+      {:ok, %{"hits" => hits}} =
+        Searcher.search_with_decay(searcher, "elixir", ["title"], "published_at", 100.0,
+          origin: 1000,
+          function: :linear,
+          limit: 2
+        )
+
+      assert [%{"_id" => "fresh", "score" => fresh}, %{"_id" => "day", "score" => day}] = hits
+      assert_in_delta day / fresh, 0.5, 1.0e-6
+    end
+
+    test "does not decay values after the origin", %{searcher: searcher} do
+      {:ok, %{"hits" => hits}} =
+        Searcher.search_with_decay(searcher, "elixir", ["title"], "published_at", 100,
+          origin: 900
+        )
+
+      assert [score, score | _] = Enum.map(hits, & &1["score"])
+    end
+
+    test "rejects invalid fields, scales and functions", %{searcher: searcher} do
+      decay = &Searcher.search_with_decay(searcher, "elixir", ["title"], &1, &2, &3)
+
+      assert {:error, :not_found, _} = decay.("missing", 100, [])
+      assert {:error, :invalid_argument, _} = decay.("views", 100, [])
+      assert {:error, :invalid_argument, _} = decay.("published_at", 0, [])
+      assert {:error, :invalid_argument, _} = decay.("published_at", 100, function: :gauss)
+    end
+  end
+
+  describe "search_bbox/6" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("name", stored: true, id: true)
+        |> Schema.add_text_field("kind", stored: true)
+        |> Schema.add_f64_field("lat", stored: true, fast: true)
+        |> Schema.add_f64_field("lon", stored: true, fast: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      places = [
+        {"soho", "coffee", 51.513, -0.136},
+        {"shoreditch", "coffee", 51.526, -0.078},
+        {"paris", "coffee", 48.857, 2.352},
+        {"fiji", "coffee", -17.713, 178.065},
+        {"samoa", "coffee", -13.759, -172.105},
+        {"covent", "books", 51.512, -0.123}
+      ]
+
+      for {name, kind, lat, lon} <- places do
+        IndexWriter.add_document(index, %{
+          "name" => name,
+          "kind" => kind,
+          "lat" => lat,
+          "lon" => lon
+        })
+      end
+
+      IndexWriter.add_document(index, %{"name" => "nowhere", "kind" => "coffee"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "combines the query with the box", %{searcher: searcher} do
+      london = %{min_lat: 51.49, max_lat: 51.53, min_lon: -0.16, max_lon: -0.07}
+
+      assert bbox_names(searcher, "coffee", london) == ["shoreditch", "soho"]
+      assert bbox_names(searcher, "*", london) == ["covent", "shoreditch", "soho"]
+    end
+
+    test "accepts integer bounds and boxes crossing the antimeridian", %{searcher: searcher} do
+      pacific = %{min_lat: -20, max_lat: -10, min_lon: 170, max_lon: -170}
+
+      assert bbox_names(searcher, "coffee", pacific) == ["fiji", "samoa"]
+    end
+
+    test "rejects invalid bounds and fields", %{searcher: searcher} do
+      valid = %{min_lat: 0, max_lat: 10, min_lon: 0, max_lon: 10}
+
+      for bbox <- [%{valid | min_lat: 20}, %{valid | max_lat: 91}, %{valid | min_lon: -181}] do
+        assert {:error, :invalid_argument, _} =
+                 Searcher.search_bbox(searcher, "*", [], {"lat", "lon"}, bbox)
+      end
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.search_bbox(searcher, "*", [], {"lat", "kind"}, valid)
+
+      assert message =~ "f64 fast field"
+    end
+  end
+
+  describe "search_ids/4" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_u64_field("post_id", fast: true)
+        |> Schema.add_text_field("title")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_documents(index, [
+        %{"sku" => "a-1", "post_id" => 1, "title" => "elixir elixir elixir"},
+        %{"sku" => "b-2", "post_id" => 2, "title" => "elixir elixir"},
+        %{"sku" => "c-3", "post_id" => 3, "title" => "elixir and rust"}
+      ])
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "returns the schema's id field in score order", %{searcher: searcher} do
+      {:ok, results} = Searcher.search_ids(searcher, "elixir", ["title"])
+
+      assert results["total_hits"] == 3
+      assert Enum.map(results["hits"], & &1["_id"]) == ["a-1", "b-2", "c-3"]
+      assert Enum.all?(results["hits"], &(Map.keys(&1) == ["_id", "score"]))
+    end
+
+    test "reads a fast id field and pages with offset", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_ids(searcher, "elixir", ["title"], id_field: "post_id", offset: 1)
+
+      assert Enum.map(results["hits"], & &1["_id"]) == [2, 3]
+
+      {:ok, results} =
+        Searcher.search_ids(searcher, "elixir", ["title"], id_field: "post_id", limit: 1)
+
+      assert Enum.map(results["hits"], & &1["_id"]) == [1]
+    end
+
+    test "rejects id fields that cannot be read", %{searcher: searcher} do
+      assert {:error, :invalid_argument, reason} =
+               Searcher.search_ids(searcher, "elixir", ["title"], id_field: "title")
+
+      assert reason =~ "must be stored"
+
+      assert {:error, :not_found, _} =
+               Searcher.search_ids(searcher, "elixir", ["title"], id_field: "missing")
+    end
+  end
+
+  describe "search_prefix/4" do
+    test "lowercases the prefix for lowercasing tokenizers", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Elixir Guide"})
+      IndexWriter.add_document(index, %{"title" => "Erlang Guide"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_prefix(searcher, "title", "ELI")
+      assert Enum.map(results["hits"], & &1["doc"]["title"]) == ["Elixir Guide"]
+    end
+
+    test "lowercase: false matches the prefix verbatim", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Elixir Guide"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      # The default tokenizer indexed "elixir", so a verbatim "Eli" cannot match
+      {:ok, results} = Searcher.search_prefix(searcher, "title", "Eli", lowercase: false)
+      assert results["total_hits"] == 0
+
+      {:ok, results} = Searcher.search_prefix(searcher, "title", "eli", lowercase: false)
+      assert results["total_hits"] == 1
+    end
+
+    test "returns error for empty prefix", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :invalid_argument, "Prefix cannot be empty"} =
+               Searcher.search_prefix(searcher, "title", "")
+    end
+  end
+
+  describe "search_wildcard/4" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_keyword_field("sku", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Elixir Guide", "sku" => "AB-12-7x"})
+      IndexWriter.add_document(index, %{"title" => "Elision rules", "sku" => "AB-345-7"})
+      IndexWriter.add_document(index, %{"title" => "TypeScript basics", "sku" => "a.b"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "matches * and ? against whole terms", %{searcher: searcher} do
+      assert wildcard_titles(searcher, "title", "ELI*") == ["Elision rules", "Elixir Guide"]
+      assert wildcard_titles(searcher, "title", "eli?ir") == ["Elixir Guide"]
+      assert wildcard_titles(searcher, "title", "lix*") == []
+    end
+
+    test "matches other characters literally on keyword fields", %{searcher: searcher} do
+      assert wildcard_titles(searcher, "sku", "AB-??-7*") == ["Elixir Guide"]
+      assert wildcard_titles(searcher, "sku", "ab-*") == []
+      assert wildcard_titles(searcher, "sku", "a.b") == ["TypeScript basics"]
+      assert wildcard_titles(searcher, "sku", "a?b") == ["TypeScript basics"]
+    end
+
+    test "rejects leading wildcards unless allowed", %{searcher: searcher} do
+      assert {:error, :invalid_argument, message} =
+               Searcher.search_wildcard(searcher, "title", "*script")
+
+      assert message =~ "allow_leading_wildcard"
+
+      assert wildcard_titles(searcher, "title", "*script", allow_leading_wildcard: true) ==
+               ["TypeScript basics"]
+    end
+
+    test "rejects patterns that cannot match a single term", %{searcher: searcher} do
+      for pattern <- ["", "*", "??", "elixir guide"] do
+        assert {:error, :invalid_argument, _} =
+                 Searcher.search_wildcard(searcher, "title", pattern,
+                   allow_leading_wildcard: true
+                 )
+      end
+    end
+  end
+
+  describe "document retrieval order" do
+    test "hits keep score order across segments", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("text", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      # Separate commits produce several segments
+      for n <- [1, 5, 2, 4, 3] do
+        words = List.duplicate("elixir", n) ++ List.duplicate("filler", 6 - n)
+        IndexWriter.add_document(index, %{"text" => Enum.join(words, " ")})
+        IndexWriter.commit(index)
+      end
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "elixir", ["text"])
+
+      scores = Enum.map(results["hits"], & &1["score"])
+      assert scores == Enum.sort(scores, :desc)
+
+      counts =
+        Enum.map(results["hits"], fn hit ->
+          hit["doc"]["text"] |> String.split() |> Enum.count(&(&1 == "elixir"))
+        end)
+
+      assert counts == [5, 4, 3, 2, 1]
+    end
+  end
+
+  describe "keyword fields" do
+    test "match the whole value exactly", %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_keyword_field("sku", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Widget", "sku" => "PROD-123"})
+      IndexWriter.add_document(index, %{"title" => "Gadget", "sku" => "PROD-1234"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search(searcher, Query.term("sku", "PROD-123"))
+      assert Enum.map(results["hits"], & &1["doc"]["sku"]) == ["PROD-123"]
+
+      # Keywords are case-sensitive and not split into tokens
+      {:ok, results} = Searcher.search(searcher, Query.term("sku", "prod-123"))
+      assert results["total_hits"] == 0
+
+      {:ok, results} = Searcher.search(searcher, Query.term("sku", "PROD"))
+      assert results["total_hits"] == 0
+
+      {:ok, results} = Searcher.search_query(searcher, ~s(sku:"PROD-1234"), ["title"])
+      assert Enum.map(results["hits"], & &1["doc"]["title"]) == ["Gadget"]
+    end
+
+    test "prefix search keeps the prefix case", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"sku" => "PROD-123"})
+      IndexWriter.add_document(index, %{"sku" => "prod-456"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_prefix(searcher, "sku", "PROD-")
+      assert Enum.map(results["hits"], & &1["doc"]["sku"]) == ["PROD-123"]
+    end
+  end
+
+  describe "search_with_snippets/5 full_text_threshold" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true, indexed: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{"title" => "Elixir & Phoenix: why elixir scales"})
+
+      IndexWriter.add_document(index, %{
+        "title" => String.duplicate("filler words ", 20) <> "about elixir at the very end"
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "returns short fields whole with every match highlighted", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "elixir", ["title"], ["title"],
+          max_snippet_chars: 20,
+          full_text_threshold: 100
+        )
+
+      snippets = Enum.map(results["hits"], & &1["snippets"]["title"])
+
+      assert "<b>Elixir</b> &amp; Phoenix: why <b>elixir</b> scales" in snippets
+    end
+
+    test "falls back to a fragment for longer fields", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "elixir", ["title"], ["title"],
+          max_snippet_chars: 40,
+          full_text_threshold: 100
+        )
+
+      long_hit = Enum.find(results["hits"], &String.starts_with?(&1["doc"]["title"], "filler"))
+      snippet = long_hit["snippets"]["title"]
+
+      assert snippet =~ "<b>elixir</b>"
+      assert String.length(snippet) < String.length(long_hit["doc"]["title"])
+    end
+
+    test "is disabled by default", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "elixir", ["title"], ["title"],
+          max_snippet_chars: 20
+        )
+
+      short_hit = Enum.find(results["hits"], &String.starts_with?(&1["doc"]["title"], "Elixir"))
+
+      refute short_hit["snippets"]["title"] ==
+               "<b>Elixir</b> &amp; Phoenix: why <b>elixir</b> scales"
+    end
+  end
+
+  describe "search_with_snippets/5 context_words" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("body", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "body" => "one two three four, five elixir six. seven eight nine ten"
+      })
+
+      IndexWriter.add_document(index, %{
+        "body" =>
+          "phoenix first " <>
+            String.duplicate("filler ", 20) <> "then elixir and phoenix together at last"
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "returns whole words on each side of the match", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "six", ["body"], ["body"], context_words: 2)
+
+      assert [%{"snippets" => %{"body" => snippet}}] = results["hits"]
+      assert snippet == "five elixir <b>six</b>. seven eight"
+    end
+
+    test "centers on the window with the most query terms", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "body:phoenix AND body:elixir", [], ["body"],
+          context_words: 2
+        )
+
+      # The lone "phoenix" at the start is skipped for the window holding both terms
+      assert [%{"snippets" => %{"body" => snippet}}] = results["hits"]
+      assert snippet == "filler then <b>elixir</b> and <b>phoenix</b>"
+    end
+
+    test "prefers full text for short values", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "six", ["body"], ["body"],
+          context_words: 1,
+          full_text_threshold: 100
+        )
+
+      assert [%{"snippets" => %{"body" => snippet}}] = results["hits"]
+      assert snippet == "one two three four, five elixir <b>six</b>. seven eight nine ten"
+    end
+  end
+
+  describe "search_with_snippets/5 snippet_tokenizers" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("body", stored: true, tokenizer: "en_stem")
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"body" => "run fast, runs faster, keep running"})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "highlights with the field's tokenizer by default", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "run", ["body"], ["body"],
+          full_text_threshold: 100
+        )
+
+      assert [%{"snippets" => %{"body" => snippet}}] = results["hits"]
+      assert snippet == "<b>run</b> fast, <b>runs</b> faster, keep <b>running</b>"
+    end
+
+    test "highlights with the override tokenizer", %{searcher: searcher} do
+      for threshold <- [0, 100] do
+        {:ok, results} =
+          Searcher.search_with_snippets(searcher, "run", ["body"], ["body"],
+            full_text_threshold: threshold,
+            snippet_tokenizers: %{"body" => "default"}
+          )
+
+        assert [%{"snippets" => %{"body" => snippet}}] = results["hits"]
+        assert snippet == "<b>run</b> fast, runs faster, keep running"
+      end
+    end
+
+    test "rejects unknown tokenizers and fields", %{searcher: searcher} do
+      assert {:error, :invalid_argument, message} =
+               Searcher.search_with_snippets(searcher, "run", ["body"], ["body"],
+                 snippet_tokenizers: %{"body" => "missing"}
+               )
+
+      assert message =~ "missing"
+
+      assert {:error, :not_found, _} =
+               Searcher.search_with_snippets(searcher, "run", ["body"], ["body"],
+                 snippet_tokenizers: %{"nope" => "default"}
+               )
+    end
+  end
+
+  describe "search_with_snippets/5 best_snippet" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_text_field("body", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      IndexWriter.add_document(index, %{
+        "title" => "Elixir guide",
+        "body" => "a book about phoenix and elixir"
+      })
+
+      IndexWriter.add_document(index, %{
+        "title" => "Phoenix in action",
+        "body" => "nothing relevant here"
+      })
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "returns the highest scoring snippet and its field", %{searcher: searcher} do
+      fields = ["title", "body"]
+
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "phoenix elixir", fields, fields,
+          best_snippet: true
+        )
+
+      best = Map.new(results["hits"], &{&1["doc"]["title"], &1["best_snippet"]})
+
+      assert best["Elixir guide"] == %{
+               "field" => "body",
+               "snippet" => "a book about <b>phoenix</b> and <b>elixir</b>"
+             }
+
+      assert best["Phoenix in action"] == %{
+               "field" => "title",
+               "snippet" => "<b>Phoenix</b> in action"
+             }
+
+      refute Enum.any?(results["hits"], &Map.has_key?(&1, "snippets"))
+    end
+
+    test "prefers the field listed first on ties", %{searcher: searcher} do
+      for {snippet_fields, field} <- [{~w(title body), "title"}, {~w(body title), "body"}] do
+        {:ok, results} =
+          Searcher.search_with_snippets(searcher, "elixir", ["title", "body"], snippet_fields,
+            best_snippet: true
+          )
+
+        assert [%{"best_snippet" => %{"field" => ^field}}] = results["hits"]
+      end
+    end
+
+    test "is nil when no snippet field matches", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "title:action", [], ["body"], best_snippet: true)
+
+      assert [%{"best_snippet" => nil}] = results["hits"]
+    end
+
+    test "keeps full_text_threshold for the winning field", %{searcher: searcher} do
+      {:ok, results} =
+        Searcher.search_with_snippets(searcher, "title:action", [], ["body", "title"],
+          best_snippet: true,
+          max_snippet_chars: 5,
+          full_text_threshold: 100
+        )
+
+      assert [%{"best_snippet" => %{"snippet" => "Phoenix in <b>action</b>"}}] = results["hits"]
+    end
+  end
+
+  describe "aggregate/4" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_text_field("title", stored: true)
+        |> Schema.add_u64_field("brand_id", fast: true)
+        |> Schema.add_f64_field("price", fast: true)
+        |> Schema.add_u64_field("stock")
+
+      {:ok, index} = Index.create(test_path, schema)
+
+      for {title, brand_id, price} <- [
+            {"red laptop", 1, 1000.0},
+            {"blue laptop", 1, 1200.0},
+            {"green laptop", 2, 800.0},
+            {"red phone", 3, 500.0}
+          ] do
+        IndexWriter.add_document(index, %{
+          "title" => title,
+          "brand_id" => brand_id,
+          "price" => price,
+          "stock" => 1
+        })
+      end
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "runs several aggregations over the matches", %{searcher: searcher} do
+      assert {:ok, results} =
+               Searcher.aggregate(searcher, "laptop", ["title"], %{
+                 "brands" => %{"terms" => %{"field" => "brand_id"}},
+                 "price" => %{"stats" => %{"field" => "price"}},
+                 "cheapest" => %{min: %{field: "price"}}
+               })
+
+      assert Enum.map(results["brands"]["buckets"], &{&1["key"], &1["doc_count"]}) ==
+               [{1, 2}, {2, 1}]
+
+      assert %{"count" => 3, "min" => 800.0, "max" => 1200.0, "avg" => 1000.0} =
+               results["price"]
+
+      assert results["cheapest"] == %{"value" => 800.0}
+    end
+
+    test "computes sub-aggregations for each bucket", %{searcher: searcher} do
+      assert {:ok, %{"brands" => %{"buckets" => buckets}}} =
+               Searcher.aggregate(searcher, "*", [], %{
+                 "brands" => %{
+                   "terms" => %{"field" => "brand_id", "size" => 2},
+                   "aggs" => %{
+                     "avg_price" => %{"avg" => %{"field" => "price"}},
+                     "prices" => %{
+                       "histogram" => %{"field" => "price", "interval" => 1000},
+                       "aggs" => %{"max_price" => %{"max" => %{"field" => "price"}}}
+                     }
+                   }
+                 }
+               })
+
+      assert [%{"key" => 1, "doc_count" => 2, "avg_price" => %{"value" => 1100.0}} = top, _] =
+               buckets
+
+      assert [
+               %{"key" => 1000.0, "doc_count" => 2, "max_price" => %{"value" => 1200.0}}
+             ] = top["prices"]["buckets"]
+    end
+
+    test "rejects sub-aggregations under metric aggregations", %{searcher: searcher} do
+      assert {:error, :invalid_argument, message} =
+               Searcher.aggregate(searcher, "*", [], %{
+                 "avg_price" => %{
+                   "avg" => %{"field" => "price"},
+                   "aggs" => %{"max_price" => %{"max" => %{"field" => "price"}}}
+                 }
+               })
+
+      assert message =~ "metric aggregation"
+    end
+
+    test "validates the fields of sub-aggregations", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} =
+               Searcher.aggregate(searcher, "*", [], %{
+                 "brands" => %{
+                   "terms" => %{"field" => "brand_id"},
+                   "aggs" => %{"stock" => %{"sum" => %{"field" => "stock"}}}
+                 }
+               })
+    end
+
+    test "cardinality/4 counts the distinct values of the matches", %{searcher: searcher} do
+      assert {:ok, 2} = Searcher.cardinality(searcher, "laptop", ["title"], "brand_id")
+      assert {:ok, 3} = Searcher.cardinality(searcher, "*", [], "brand_id")
+      assert {:ok, 0} = Searcher.cardinality(searcher, "tablet", ["title"], "brand_id")
+
+      assert {:error, :not_found, _} = Searcher.cardinality(searcher, "*", [], "nope")
+      assert {:error, :invalid_argument, _} = Searcher.cardinality(searcher, "*", [], "stock")
+    end
+
+    test "aggregates every document with *", %{searcher: searcher} do
+      assert {:ok, %{"count" => %{"value" => 4.0}}} =
+               Searcher.aggregate(searcher, "*", [], %{
+                 "count" => %{"value_count" => %{"field" => "price"}}
+               })
+    end
+
+    test "rejects malformed requests", %{searcher: searcher} do
+      assert {:error, :invalid_argument, _} = Searcher.aggregate(searcher, "*", [], %{})
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.aggregate(searcher, "*", [], %{"x" => %{"median" => %{}}})
+
+      assert message =~ "Invalid aggregation request"
+
+      assert {:error, :invalid_argument, _} =
+               Searcher.aggregate(searcher, "*", [], %{"x" => {:avg, "price"}})
+    end
+
+    test "rejects missing and non-fast fields", %{searcher: searcher} do
+      assert {:error, :not_found, _} =
+               Searcher.aggregate(searcher, "*", [], %{"x" => %{"avg" => %{"field" => "nope"}}})
+
+      assert {:error, :invalid_argument, message} =
+               Searcher.aggregate(searcher, "*", [], %{"x" => %{"avg" => %{"field" => "stock"}}})
+
+      assert message =~ "not a fast field"
+    end
+  end
+
+  describe "doc_exists/3" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("id", stored: true)
+        |> Schema.add_u64_field("version", stored: true, indexed: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      IndexWriter.add_document(index, %{"id" => "post-42", "version" => 3})
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "reports whether a key exists", %{searcher: searcher} do
+      assert {:ok, true} = Searcher.doc_exists(searcher, "id", "post-42")
+      assert {:ok, false} = Searcher.doc_exists(searcher, "id", "post-43")
+      assert {:ok, true} = Searcher.doc_exists(searcher, "version", 3)
+      assert {:ok, false} = Searcher.doc_exists(searcher, "version", 4)
+    end
+
+    test "returns errors for unknown fields and mistyped values", %{searcher: searcher} do
+      assert {:error, :not_found, _} = Searcher.doc_exists(searcher, "missing", "x")
+      assert {:error, :invalid_argument, _} = Searcher.doc_exists(searcher, "version", "3")
+    end
+  end
+
+  describe "count_multi/3" do
+    setup %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path, schema)
+
+      for title <- ["red shirt", "blue shirt", "red shoes", "green hat"] do
+        IndexWriter.add_document(index, %{"title" => title})
+      end
+
+      IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, searcher: searcher}
+    end
+
+    test "counts the matches of each query", %{searcher: searcher} do
+      assert {:ok, counts} =
+               Searcher.count_multi(searcher, ["shirt", "red OR shirt", "purple"], ["title"])
+
+      assert counts == %{"shirt" => 2, "red OR shirt" => 3, "purple" => 0}
+    end
+
+    test "fails when a query does not parse", %{searcher: searcher} do
+      assert {:error, :parse_error, _} =
+               Searcher.count_multi(searcher, ["shirt", "missing:x"], ["title"])
+    end
+
+    test "estimate_hits/3 approximates the count", %{searcher: searcher} do
+      assert {:ok, 2} = Searcher.estimate_hits(searcher, "shirt", ["title"])
+      assert {:ok, 0} = Searcher.estimate_hits(searcher, "purple", ["title"])
+
+      # Small results are counted exactly
+      assert {:ok, 3} = Searcher.estimate_hits(searcher, "red OR shirt", ["title"])
+      assert {:ok, 1} = Searcher.estimate_hits(searcher, "red AND shirt", ["title"])
+
+      assert {:error, :parse_error, _} = Searcher.estimate_hits(searcher, "missing:x", [])
+    end
+  end
+
+  describe "id field" do
+    setup %{test_path: test_path} do
+      schema =
+        Schema.new()
+        |> Schema.add_keyword_field("sku", stored: true, id: true)
+        |> Schema.add_text_field("title", stored: true)
+
+      {:ok, index} = Index.create(test_path, schema)
+      :ok = IndexWriter.add_document(index, %{"sku" => "sku-1", "title" => "red shirt"})
+      :ok = IndexWriter.add_document(index, %{"sku" => "sku-2", "title" => "blue shirt"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, index: index}
+    end
+
+    test "includes the id field value as _id in every hit", %{index: index} do
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "shirt", ["title"])
+      assert results["hits"] |> Enum.map(& &1["_id"]) |> Enum.sort() == ["sku-1", "sku-2"]
+
+      {:ok, results} = Searcher.search_with_snippets(searcher, "red", ["title"], ["title"])
+      assert [%{"_id" => "sku-1", "snippets" => %{"title" => _}}] = results["hits"]
+    end
+
+    test "keeps the id field after reopening the index", %{test_path: test_path} do
+      {:ok, reopened} = Index.open(test_path, read_only: true)
+      {:ok, reader} = IndexReader.new(reopened)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, results} = Searcher.search_query(searcher, "blue", ["title"])
+      assert [%{"_id" => "sku-2"}] = results["hits"]
+    end
+
+    test "returns an error when a hit has no id value", %{index: index} do
+      :ok = IndexWriter.add_document(index, %{"title" => "green shirt"})
+      :ok = IndexWriter.commit(index)
+
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      assert {:error, :not_found, message} = Searcher.search_query(searcher, "green", ["title"])
+      assert message =~ "sku"
+    end
+
+    test "leaves hits without _id when no field is designated", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_text_field("title", stored: true)
+      {:ok, index} = Index.create(test_path <> "_plain", schema)
+      on_exit(fn -> Muninn.TestHelpers.safe_rm_rf(test_path <> "_plain") end)
+
+      :ok = IndexWriter.add_document(index, %{"title" => "shirt"})
+      :ok = IndexWriter.commit(index)
+      {:ok, reader} = IndexReader.new(index)
+      {:ok, searcher} = Searcher.new(reader)
+
+      {:ok, %{"hits" => [hit]}} = Searcher.search_query(searcher, "shirt", ["title"])
+      refute Map.has_key?(hit, "_id")
+    end
+
+    test "rejects an id field that is not stored", %{test_path: test_path} do
+      schema = Schema.new() |> Schema.add_keyword_field("sku", id: true)
+
+      assert {:error, :invalid_schema, message} = Index.create(test_path <> "_unstored", schema)
+      assert message =~ "must be stored"
+    end
+  end
+
+  defp bbox_names(searcher, query, bbox) do
+    {:ok, results} = Searcher.search_bbox(searcher, query, ["kind"], {"lat", "lon"}, bbox)
+    results["hits"] |> Enum.map(& &1["_id"]) |> Enum.sort()
+  end
+
+  defp wildcard_titles(searcher, field, pattern, opts \\ []) do
+    {:ok, results} = Searcher.search_wildcard(searcher, field, pattern, opts)
+    results["hits"] |> Enum.map(& &1["doc"]["title"]) |> Enum.sort()
+  end
 end
@@ -5,4 +5,9 @@ defmodule MuninnTest do
   test "greets the world" do
     assert Muninn.hello() == :world
   end
+
+  test "info/0 reports the tantivy and index format versions" do
+    assert %{"tantivy_version" => "0.25." <> _, "index_format_version" => format} = Muninn.info()
+    assert is_integer(format)
+  end
 end